@@ -9,6 +9,7 @@
 	federation::{self, FederationCommand},
 	media::{self, MediaCommand},
 	query::{self, QueryCommand},
+	reports::{self, ReportsCommand},
 	room::{self, RoomCommand},
 	server::{self, ServerCommand},
 	token::{self, TokenCommand},
@@ -72,6 +73,10 @@ pub(super) enum AdminCommand {
 	#[command(subcommand)]
 	/// - Commands for managing registration tokens
 	Token(TokenCommand),
+
+	#[command(subcommand)]
+	/// - Commands for triaging user reports
+	Reports(ReportsCommand),
 }
 
 #[tracing::instrument(skip_all, name = "command")]
@@ -88,5 +93,6 @@ pub(super) async fn process(command: AdminCommand, context: &Context<'_>) -> Res
 		| Debug(command) => debug::process(command, context).await,
 		| Query(command) => query::process(command, context).await,
 		| Token(command) => token::process(command, context).await,
+		| Reports(command) => reports::process(command, context).await,
 	}
 }
@@ -0,0 +1,12 @@
+use tuwunel_core::Result;
+
+use crate::{admin_command, utils::parse_local_user_id};
+
+#[admin_command]
+pub(super) async fn clear_quota(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	self.services.media.clear_quota_override(&user_id);
+
+	write!(self, "Restored the configured media quota of {user_id}").await
+}
@@ -7,6 +7,7 @@
 pub(super) async fn get_file_info(&self, mxc: OwnedMxcUri) -> Result {
 	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
 	let metadata = self.services.media.get_metadata(&mxc).await;
+	let scan = self.services.media.scan_verdict(&mxc).await;
 
-	write!(self, "```\n{metadata:#?}\n```").await
+	write!(self, "```\n{metadata:#?}\nscan: {scan:#?}\n```").await
 }
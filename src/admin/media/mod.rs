@@ -1,4 +1,5 @@
 #![expect(rustdoc::broken_intra_doc_links)]
+mod clear_quota;
 mod delete;
 mod delete_all_from_server;
 mod delete_all_from_user;
@@ -9,9 +10,16 @@
 mod get_remote_file;
 mod get_remote_thumbnail;
 mod preview;
+mod protect;
+mod quarantine;
+mod quota;
+mod set_quota;
+mod tier;
+mod unprotect;
+mod unquarantine;
 
 use clap::{ArgGroup, Subcommand};
-use ruma::{OwnedEventId, OwnedMxcUri, OwnedServerName};
+use ruma::{OwnedEventId, OwnedMxcUri, OwnedRoomId, OwnedServerName};
 use tuwunel_core::Result;
 use url::Url;
 
@@ -120,4 +128,86 @@ pub(super) enum MediaCommand {
 		#[arg(short, long)]
 		no_cache: bool,
 	},
+
+	/// - Quarantines a single MXC URL, every media referenced in a room or
+	///   every media uploaded by a local user. Quarantined media is refused on
+	///   download and never fetched again; protected media is skipped.
+	#[command(group(
+		ArgGroup::new("target")
+			.required(true)
+			.args(["mxc", "room_id", "user"]),
+	))]
+	Quarantine {
+		/// The MXC URL to quarantine
+		mxc: Option<OwnedMxcUri>,
+
+		/// - Quarantine all media referenced in this room
+		#[arg(long)]
+		room_id: Option<OwnedRoomId>,
+
+		/// - Quarantine all media uploaded by this local user
+		#[arg(long)]
+		user: Option<String>,
+	},
+
+	/// - Releases a single MXC URL, a room's media or a local user's media from
+	///   quarantine
+	#[command(group(
+		ArgGroup::new("target")
+			.required(true)
+			.args(["mxc", "room_id", "user"]),
+	))]
+	Unquarantine {
+		/// The MXC URL to release
+		mxc: Option<OwnedMxcUri>,
+
+		/// - Release all media referenced in this room
+		#[arg(long)]
+		room_id: Option<OwnedRoomId>,
+
+		/// - Release all media uploaded by this local user
+		#[arg(long)]
+		user: Option<String>,
+	},
+
+	/// - Protects media from quarantine and from date and size purges
+	Protect {
+		/// The MXC URL to protect
+		mxc: OwnedMxcUri,
+	},
+
+	/// - Removes the protection from media
+	Unprotect {
+		/// The MXC URL to unprotect
+		mxc: OwnedMxcUri,
+	},
+
+	/// - Show a local user's media usage and quota
+	Quota {
+		user_id: String,
+	},
+
+	/// - Override a local user's media quota
+	///
+	/// A limit of 0 disables it.
+	SetQuota {
+		user_id: String,
+
+		/// Total size of media, e.g. "1 GiB"
+		max_bytes: String,
+
+		/// Number of media files
+		max_files: u64,
+	},
+
+	/// - Return a local user to the configured media quota
+	ClearQuota {
+		user_id: String,
+	},
+
+	/// - Move the media due by the tiering policy to `media_tiering_provider`
+	///   now
+	///
+	/// Progress is shown by `!admin server list-tasks`.
+	Tier,
 }
@@ -0,0 +1,46 @@
+use ruma::{Mxc, OwnedMxcUri, OwnedRoomId, UserId};
+use tuwunel_core::Result;
+use tuwunel_service::Services;
+
+use crate::{admin_command, utils::parse_local_user_id};
+
+#[admin_command]
+pub(super) async fn quarantine(
+	&self,
+	mxc: Option<OwnedMxcUri>,
+	room_id: Option<OwnedRoomId>,
+	user: Option<String>,
+) -> Result {
+	let by = &self.services.globals.server_user;
+	let count = set_quarantined(self.services, mxc, room_id, user, Some(by)).await?;
+
+	write!(self, "Quarantined {count} media.").await
+}
+
+/// Applies the quarantine change to the media the arguments select: one MXC,
+/// a room's media or a local user's uploads.
+pub(super) async fn set_quarantined(
+	services: &Services,
+	mxc: Option<OwnedMxcUri>,
+	room_id: Option<OwnedRoomId>,
+	user: Option<String>,
+	by: Option<&UserId>,
+) -> Result<usize> {
+	let media = &services.media;
+	let count = match (mxc, room_id, user) {
+		| (Some(mxc), ..) => {
+			let mxc: Mxc<'_> = mxc.as_str().try_into()?;
+
+			media.set_quarantined(&mxc, by).await.into()
+		},
+		| (_, Some(room_id), _) => media.set_quarantined_room(&room_id, by).await,
+		| (.., Some(user)) => {
+			let user_id = parse_local_user_id(services, &user)?;
+
+			media.set_quarantined_user(&user_id, by).await
+		},
+		| _ => 0,
+	};
+
+	Ok(count)
+}
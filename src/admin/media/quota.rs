@@ -0,0 +1,29 @@
+use tuwunel_core::{Result, utils::bytes::pretty};
+use tuwunel_service::media::{Quota, Usage};
+
+use crate::{admin_command, utils::parse_local_user_id};
+
+#[admin_command]
+pub(super) async fn quota(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let media = &self.services.media;
+
+	let Usage { bytes, files } = media.usage(&user_id).await;
+	let Quota { max_bytes, max_files } = media.quota(&user_id).await;
+	let source = match media.get_quota_override(&user_id).await {
+		| Some(_) => "overridden",
+		| None => "configured",
+	};
+
+	let size = |bytes: u64| pretty(usize::try_from(bytes).unwrap_or(usize::MAX));
+	let max_bytes = max_bytes.map_or_else(|| "unlimited".to_owned(), size);
+	let max_files = max_files.map_or_else(|| "unlimited".to_owned(), |max| max.to_string());
+
+	write!(
+		self,
+		"{user_id} uses {} in {files} files of a {source} quota of {max_bytes} in {max_files} \
+		 files",
+		size(bytes),
+	)
+	.await
+}
@@ -0,0 +1,21 @@
+use tuwunel_core::{Result, utils::bytes};
+use tuwunel_service::media::QuotaOverride;
+
+use crate::{admin_command, utils::parse_local_user_id};
+
+#[admin_command]
+pub(super) async fn set_quota(
+	&self,
+	user_id: String,
+	max_bytes: String,
+	max_files: u64,
+) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let max_bytes = u64::try_from(bytes::from_str(&max_bytes)?)?;
+
+	self.services
+		.media
+		.set_quota_override(&user_id, QuotaOverride { max_bytes, max_files });
+
+	write!(self, "Overrode the media quota of {user_id}").await
+}
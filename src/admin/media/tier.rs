@@ -0,0 +1,14 @@
+use tuwunel_core::Result;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn tier(&self) -> Result {
+	let id = self.services.media.start_tiering()?;
+
+	write!(
+		self,
+		"Started media tiering task {id}. Follow it with `!admin server list-tasks`."
+	)
+	.await
+}
@@ -0,0 +1,19 @@
+use ruma::{Mxc, OwnedMxcUri};
+use tuwunel_core::Result;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn unprotect(&self, mxc: OwnedMxcUri) -> Result {
+	let mxc: Mxc<'_> = mxc.as_str().try_into()?;
+	if !self
+		.services
+		.media
+		.set_protected(&mxc, false)
+		.await
+	{
+		return write!(self, "{mxc} is already unprotected.").await;
+	}
+
+	write!(self, "Unprotected {mxc}.").await
+}
@@ -0,0 +1,17 @@
+use ruma::{OwnedMxcUri, OwnedRoomId};
+use tuwunel_core::Result;
+
+use super::quarantine::set_quarantined;
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn unquarantine(
+	&self,
+	mxc: Option<OwnedMxcUri>,
+	room_id: Option<OwnedRoomId>,
+	user: Option<String>,
+) -> Result {
+	let count = set_quarantined(self.services, mxc, room_id, user, None).await?;
+
+	write!(self, "Released {count} media from quarantine.").await
+}
@@ -0,0 +1,10 @@
+use tuwunel_core::Result;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn delete(&self, report_id: u64) -> Result {
+	self.services.reports.delete(report_id).await?;
+
+	write!(self, "Deleted report {report_id}.").await
+}
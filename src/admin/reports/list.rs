@@ -0,0 +1,60 @@
+use futures::StreamExt;
+use ruma::{OwnedRoomId, OwnedUserId};
+use tuwunel_core::{Result, utils::time::rfc2822_from_seconds};
+use tuwunel_service::reports::Filter;
+
+use super::{ReportKind, ReportState};
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn list(
+	&self,
+	room: Option<OwnedRoomId>,
+	reporter: Option<OwnedUserId>,
+	state: Option<ReportState>,
+	kind: Option<ReportKind>,
+	limit: usize,
+) -> Result {
+	let filter = Filter {
+		kind: kind.map(Into::into),
+		room_id: room,
+		reporter,
+		state: state.map(Into::into),
+		..Filter::default()
+	};
+
+	let reports: Vec<_> = self
+		.services
+		.reports
+		.reports(&filter, true)
+		.take(limit)
+		.collect()
+		.await;
+
+	writeln!(self, "| ID | Received | Kind | Reporter | Subject | State | Reason |").await?;
+	writeln!(self, "| --- | --- | --- | --- | --- | --- | --- |").await?;
+	for (id, report) in reports {
+		let received =
+			rfc2822_from_seconds(i64::try_from(report.received_ts / 1000).unwrap_or(i64::MAX));
+
+		let subject = report
+			.event_id
+			.as_ref()
+			.map(ToString::to_string)
+			.or_else(|| report.room_id.as_ref().map(ToString::to_string))
+			.or_else(|| report.user_id.as_ref().map(ToString::to_string))
+			.unwrap_or_default();
+
+		writeln!(
+			self,
+			"| {id} | {received} | {:?} | {} | {subject} | {:?} | {} |",
+			report.kind,
+			report.reporter,
+			report.state(),
+			report.reason.as_deref().unwrap_or_default(),
+		)
+		.await?;
+	}
+
+	Ok(())
+}
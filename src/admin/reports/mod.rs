@@ -0,0 +1,84 @@
+mod delete;
+mod list;
+mod resolve;
+mod show;
+
+use clap::{Subcommand, ValueEnum};
+use ruma::{OwnedRoomId, OwnedUserId};
+use tuwunel_core::Result;
+use tuwunel_service::reports::{Kind, State};
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum ReportsCommand {
+	/// - List reports, newest first
+	List {
+		/// Only reports about this room.
+		#[arg(long)]
+		room: Option<OwnedRoomId>,
+
+		/// Only reports filed by this user.
+		#[arg(long)]
+		reporter: Option<OwnedUserId>,
+
+		/// Only open or only resolved reports.
+		#[arg(long, value_enum)]
+		state: Option<ReportState>,
+
+		/// Only reports of this kind.
+		#[arg(long, value_enum)]
+		kind: Option<ReportKind>,
+
+		#[arg(long, default_value = "50")]
+		limit: usize,
+	},
+
+	/// - Show a report in full
+	Show {
+		report_id: u64,
+	},
+
+	/// - Mark a report as handled
+	Resolve {
+		report_id: u64,
+	},
+
+	/// - Delete a report
+	Delete {
+		report_id: u64,
+	},
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub(super) enum ReportState {
+	Open,
+	Resolved,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub(super) enum ReportKind {
+	Event,
+	Room,
+	User,
+}
+
+impl From<ReportState> for State {
+	fn from(state: ReportState) -> Self {
+		match state {
+			| ReportState::Open => Self::Open,
+			| ReportState::Resolved => Self::Resolved,
+		}
+	}
+}
+
+impl From<ReportKind> for Kind {
+	fn from(kind: ReportKind) -> Self {
+		match kind {
+			| ReportKind::Event => Self::Event,
+			| ReportKind::Room => Self::Room,
+			| ReportKind::User => Self::User,
+		}
+	}
+}
@@ -0,0 +1,13 @@
+use tuwunel_core::Result;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn resolve(&self, report_id: u64) -> Result {
+	self.services
+		.reports
+		.resolve(report_id, self.sender)
+		.await?;
+
+	write!(self, "Resolved report {report_id}.").await
+}
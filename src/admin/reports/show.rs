@@ -0,0 +1,11 @@
+use tuwunel_core::Result;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn show(&self, report_id: u64) -> Result {
+	let report = self.services.reports.get(report_id).await?;
+	let report = serde_json::to_string_pretty(&report)?;
+
+	write!(self, "```json\n{report}\n```").await
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use ruma::OwnedRoomOrAliasId;
+use tuwunel_core::Result;
+use tuwunel_service::rooms::archive::Exported;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn room_export(
+	&self,
+	room_id: OwnedRoomOrAliasId,
+	path: PathBuf,
+	media: bool,
+) -> Result {
+	let room_id = self
+		.services
+		.alias
+		.maybe_resolve(&room_id)
+		.await?;
+
+	let Exported {
+		pdus,
+		outliers,
+		snapshots,
+		media: files,
+		media_failed,
+	} = self
+		.services
+		.archive
+		.export(&room_id, &path, media)
+		.await?;
+
+	writeln!(self, "Exported {room_id} to {}:", path.display()).await?;
+	writeln!(self, "| Exported | Count | Failed |").await?;
+	writeln!(self, "| --- | --- | --- |").await?;
+	writeln!(self, "| Timeline events | {pdus} | |").await?;
+	writeln!(self, "| Auth and state events | {outliers} | |").await?;
+	writeln!(self, "| State snapshots | {snapshots} | |").await?;
+	writeln!(self, "| Media | {files} | {media_failed} |").await
+}
@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use tuwunel_core::Result;
+use tuwunel_service::rooms::archive::Imported;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn room_import(&self, path: PathBuf) -> Result {
+	let Imported {
+		room_id,
+		outliers,
+		pdus,
+		pdus_failed,
+		media,
+		media_failed,
+	} = self.services.archive.import(&path).await?;
+
+	writeln!(self, "Imported {room_id} from {}:", path.display()).await?;
+	writeln!(self, "| Imported | Count | Failed |").await?;
+	writeln!(self, "| --- | --- | --- |").await?;
+	writeln!(self, "| Timeline events | {pdus} | {pdus_failed} |").await?;
+	writeln!(self, "| Auth and state events | {outliers} | |").await?;
+	writeln!(self, "| Media | {media} | {media_failed} |").await
+}
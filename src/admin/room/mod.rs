@@ -3,6 +3,8 @@
 mod delete;
 mod directory;
 mod exists;
+mod export;
+mod import;
 mod info;
 mod list;
 mod list_extremities;
@@ -12,6 +14,8 @@
 mod prune_extremities;
 mod purge_user;
 
+use std::path::PathBuf;
+
 use clap::Subcommand;
 use ruma::{OwnedRoomId, OwnedRoomOrAliasId};
 use tuwunel_core::Result;
@@ -88,6 +92,31 @@ pub(super) enum RoomCommand {
 		room_id: OwnedRoomOrAliasId,
 	},
 
+	/// - Export a room to a portable archive
+	///
+	/// Writes the room's timeline, its auth chain and the state at its forward
+	/// extremities to a new file, for legal hold or to move the room to
+	/// another server.
+	Export {
+		/// Room ID or alias
+		room_id: OwnedRoomOrAliasId,
+
+		/// File to write; an existing file is not overwritten
+		path: PathBuf,
+
+		/// Include the files of this server's media which the room refers to
+		#[arg(long)]
+		media: bool,
+	},
+
+	/// - Import a room from an archive written by `export`
+	///
+	/// The room must not exist on this server yet. Its events are checked
+	/// like events received over federation.
+	Import {
+		path: PathBuf,
+	},
+
 	/// - Delete room
 	Delete {
 		room_id: OwnedRoomId,
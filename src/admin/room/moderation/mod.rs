@@ -1,6 +1,8 @@
 mod ban_list_of_rooms;
 mod ban_room;
 mod list_banned_rooms;
+mod policy_match;
+mod policy_rejections;
 mod unban_room;
 
 use clap::Subcommand;
@@ -44,6 +46,18 @@ pub(crate) enum RoomModerationCommand {
 		/// information
 		no_details: bool,
 	},
+
+	/// - Show the policy list rule banning a user, room or server
+	///
+	/// Rules come from the rooms in `policy_list_rooms`.
+	PolicyMatch {
+		/// A user ID, room ID, room alias or server name
+		entity: String,
+	},
+
+	/// - List recent actions refused by policy lists, with the rule refusing
+	///   each
+	PolicyRejections,
 }
 
 async fn do_ban_room(services: &Services, room_id: &RoomId) {
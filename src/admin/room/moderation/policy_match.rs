@@ -0,0 +1,29 @@
+use tuwunel_core::Result;
+use tuwunel_service::policy_lists::Kind;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn policy_match(&self, entity: String) -> Result {
+	let kind = match entity.chars().next() {
+		| Some('@') => Kind::User,
+		| Some('!' | '#') => Kind::Room,
+		| _ => Kind::Server,
+	};
+
+	let Some(rule) = self
+		.services
+		.policy_lists
+		.matching_rule(kind, &entity)
+		.await
+	else {
+		return write!(self, "No policy list rule matches the {kind} {entity}.").await;
+	};
+
+	write!(
+		self,
+		"The {kind} {entity} is banned by the rule `{}` of {}: {}",
+		rule.entity, rule.policy_room, rule.reason,
+	)
+	.await
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use tuwunel_core::{
+	Err, Result,
+	utils::time::{now_millis, pretty},
+};
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn policy_rejections(&self) -> Result {
+	let rejections = self.services.policy_lists.rejections();
+	if rejections.is_empty() {
+		return Err!("No actions were refused by policy lists.");
+	}
+
+	let now = now_millis();
+	writeln!(self, "| When | Action | Entity | Policy room | Rule | Reason |").await?;
+	writeln!(self, "| --- | --- | --- | --- | --- | --- |").await?;
+	for rejection in rejections {
+		let age = pretty(Duration::from_millis(now.saturating_sub(rejection.rejected_ts)));
+		let rule = &rejection.rule;
+
+		writeln!(
+			self,
+			"| {age} ago | {} | {} | {} | {} {} | {} |",
+			rejection.action,
+			rejection.entity,
+			rule.policy_room,
+			rule.kind,
+			rule.entity,
+			rule.reason,
+		)
+		.await?;
+	}
+
+	Ok(())
+}
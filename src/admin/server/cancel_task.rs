@@ -0,0 +1,10 @@
+use tuwunel_core::Result;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn cancel_task(&self, task_id: String) -> Result {
+	self.services.tasks.cancel(&task_id)?;
+
+	write!(self, "Cancelled task {task_id}.").await
+}
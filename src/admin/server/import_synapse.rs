@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use tuwunel_core::Result;
+use tuwunel_service::synapse::{Import, Report};
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn import_synapse(
+	&self,
+	database: PathBuf,
+	media_store: Option<PathBuf>,
+	rejoin: bool,
+) -> Result {
+	let import = Import { database, media_store, rejoin };
+	let Report {
+		users,
+		skipped_users,
+		users_failed,
+		devices,
+		access_tokens,
+		account_data,
+		push_rules_failed,
+		key_backups,
+		room_keys,
+		media,
+		media_failed,
+		rooms_joined,
+		rooms_failed,
+	} = self.services.synapse.import(&import).await?;
+
+	writeln!(
+		self,
+		"Imported {users} users; skipped {skipped_users} guests or existing users."
+	)
+	.await?;
+	writeln!(self, "| Imported | Count | Failed |").await?;
+	writeln!(self, "| --- | --- | --- |").await?;
+	writeln!(self, "| Devices | {devices} | |").await?;
+	writeln!(self, "| Access tokens | {access_tokens} | |").await?;
+	writeln!(self, "| Account data | {account_data} | |").await?;
+	writeln!(self, "| Key backups | {key_backups} | |").await?;
+	writeln!(self, "| Room keys | {room_keys} | |").await?;
+	writeln!(self, "| Media | {media} | {media_failed} |").await?;
+	writeln!(self, "| Rooms rejoined | {rooms_joined} | {rooms_failed} |").await?;
+
+	if users_failed > 0 {
+		writeln!(self, "{users_failed} users could not be fully imported; see the log.").await?;
+	}
+
+	if push_rules_failed > 0 {
+		writeln!(self, "{push_rules_failed} push rules could not be imported; see the log.")
+			.await?;
+	}
+
+	Ok(())
+}
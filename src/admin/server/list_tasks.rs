@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use tuwunel_core::{
+	Result,
+	utils::time::{now_millis, pretty},
+};
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn list_tasks(&self, action: Option<String>, unfinished: bool) -> Result {
+	let mut tasks = self.services.tasks.list();
+	tasks.retain(|task| {
+		action
+			.as_deref()
+			.is_none_or(|action| task.action == action)
+			&& (!unfinished || !task.status.is_terminal())
+	});
+	tasks.sort_unstable_by_key(|task| task.timestamp_ms);
+
+	let now = now_millis();
+	writeln!(self, "| ID | Action | Resource | Status | Started | Progress | Error |").await?;
+	writeln!(self, "| --- | --- | --- | --- | --- | --- | --- |").await?;
+	for task in tasks {
+		let age = pretty(Duration::from_millis(now.saturating_sub(task.timestamp_ms)));
+		let progress = task
+			.progress
+			.map(|progress| progress.to_string())
+			.unwrap_or_default();
+
+		writeln!(
+			self,
+			"| {} | {} | {} | {} | {age} ago | {progress} | {} |",
+			task.id,
+			task.action,
+			task.resource_id,
+			task.status.as_str(),
+			task.error.unwrap_or_default(),
+		)
+		.await?;
+	}
+
+	Ok(())
+}
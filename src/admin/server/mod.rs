@@ -1,15 +1,19 @@
 mod admin_notice;
 mod backup_database;
+mod cancel_task;
 mod clear_caches;
 mod delete_backups;
+mod import_synapse;
 mod list_backups;
 mod list_features;
+mod list_tasks;
 mod memory_usage;
 mod regenerate_config;
 mod reload_config;
 mod reload_mods;
 #[cfg(unix)]
 mod restart;
+mod rotate_signing_key;
 mod show_config;
 mod shutdown;
 mod uptime;
@@ -101,6 +105,44 @@ pub(super) enum ServerCommand {
 		keep: usize,
 	},
 
+	/// - List background tasks started through the admin API, oldest first
+	ListTasks {
+		/// Only tasks of this action, e.g. `purge_history`.
+		#[arg(short, long)]
+		action: Option<String>,
+
+		/// Only tasks that are scheduled or active.
+		#[arg(short, long)]
+		unfinished: bool,
+	},
+
+	/// - Cancel a scheduled or active background task
+	CancelTask {
+		task_id: String,
+	},
+
+	/// - Import the local users of a Synapse homeserver from its SQLite
+	///   database, keeping their logins, keys and uploads
+	ImportSynapse {
+		/// Path of the Synapse database, usually `homeserver.db`.
+		database: PathBuf,
+
+		/// Synapse's `media_store_path`, to import uploads from.
+		#[arg(long)]
+		media_store: Option<PathBuf>,
+
+		/// Rejoin the users to their rooms over federation.
+		#[arg(long)]
+		rejoin: bool,
+	},
+
+	/// - Generate a new server signing key and make it active
+	///
+	/// The previous key stays published as expired, so events it signed still
+	/// verify. Use this when the key may be compromised. Rooms naming this
+	/// server's key in `m.room.policy` must be updated with the new one.
+	RotateSigningKey,
+
 	/// - Send a message to the admin room.
 	AdminNotice {
 		message: Vec<String>,
@@ -0,0 +1,15 @@
+use tuwunel_core::Result;
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn rotate_signing_key(&self) -> Result {
+	let old_key_id = self.services.server_keys.active_key_id();
+	let new_key_id = self.services.server_keys.rotate_keypair().await?;
+
+	write!(
+		self,
+		"Signing key {new_key_id} is now active; {old_key_id} is published as expired."
+	)
+	.await
+}
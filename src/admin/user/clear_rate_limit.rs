@@ -0,0 +1,12 @@
+use tuwunel_core::Result;
+
+use crate::{admin_command, utils::parse_local_user_id};
+
+#[admin_command]
+pub(super) async fn clear_rate_limit(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	self.services.ratelimit.clear_override(&user_id);
+
+	write!(self, "Restored the configured rate limits of {user_id}").await
+}
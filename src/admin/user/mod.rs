@@ -1,4 +1,5 @@
 mod add_email;
+mod clear_rate_limit;
 mod create_user;
 mod deactivate;
 mod deactivate_all;
@@ -18,10 +19,12 @@
 mod list_users;
 mod make_user_admin;
 mod put_room_tag;
+mod rate_limit;
 mod redact_event;
 mod reject_invites;
 mod reset_password;
 mod set_profile_key;
+mod set_rate_limit;
 mod unerase;
 
 use clap::{ArgGroup, Subcommand, ValueEnum};
@@ -138,6 +141,29 @@ pub(super) enum UserCommand {
 		user_id: String,
 	},
 
+	/// - Show the rate-limit override of a local user, if any
+	RateLimit {
+		user_id: String,
+	},
+
+	/// - Override a local user's rate limits in every endpoint class
+	///
+	/// A rate or burst of 0 exempts the user from rate limiting.
+	SetRateLimit {
+		user_id: String,
+
+		/// Refill rate in requests per second; may be fractional
+		per_second: f64,
+
+		/// Number of requests allowed in a burst
+		burst_count: u32,
+	},
+
+	/// - Return a local user to the configured rate limits
+	ClearRateLimit {
+		user_id: String,
+	},
+
 	/// - Deletes a user's device.
 	DeleteDevice {
 		user_id: OwnedUserId,
@@ -0,0 +1,26 @@
+use tuwunel_core::Result;
+
+use crate::{admin_command, utils::parse_local_user_id};
+
+#[admin_command]
+pub(super) async fn rate_limit(&self, user_id: String) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	match self
+		.services
+		.ratelimit
+		.get_override(&user_id)
+		.await
+	{
+		| Some(rates) if rates.per_second <= 0.0 || rates.burst_count == 0 =>
+			write!(self, "{user_id} is exempt from rate limiting").await,
+		| Some(rates) =>
+			write!(
+				self,
+				"{user_id} is limited to {} requests per second with a burst of {}",
+				rates.per_second, rates.burst_count
+			)
+			.await,
+		| None => write!(self, "{user_id} has the configured rate limits").await,
+	}
+}
@@ -0,0 +1,24 @@
+use tuwunel_core::{Err, Result};
+use tuwunel_service::ratelimit::Override;
+
+use crate::{admin_command, utils::parse_local_user_id};
+
+#[admin_command]
+pub(super) async fn set_rate_limit(
+	&self,
+	user_id: String,
+	per_second: f64,
+	burst_count: u32,
+) -> Result {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if !per_second.is_finite() || per_second < 0.0 {
+		return Err!("The rate must be a non-negative number.");
+	}
+
+	self.services
+		.ratelimit
+		.set_override(&user_id, Override { per_second, burst_count });
+
+	write!(self, "Overrode the rate limits of {user_id}").await
+}
@@ -57,8 +57,8 @@ fn into_user_media(entry: UserMediaEntry) -> UserMedia {
 		created_ts: UInt::try_from(entry.created_ts).unwrap_or(UInt::MAX),
 		url_cache: None,
 		last_access_ts: UInt::from(0_u32),
-		quarantined_by: None,
-		safe_from_quarantine: false,
+		quarantined_by: entry.quarantined_by.map(Into::into),
+		safe_from_quarantine: entry.safe_from_quarantine,
 		user_id: entry.user_id,
 		authenticated: None,
 		sha256: None,
@@ -1,12 +1,21 @@
 //! Synapse admin API: media endpoints.
+//!
+//! synapse-admin-api does not cover quarantine and protection, so their
+//! request and response types are declared alongside the routes.
 
 mod delete_media;
 mod delete_media_by_date_size;
 mod delete_user_media;
 mod list_room_media;
 mod list_user_media;
+mod protect_media;
 mod purge_media_cache;
+mod quarantine_media;
+mod quarantine_room_media;
+mod quarantine_user_media;
 mod query_media;
+mod unprotect_media;
+mod unquarantine_media;
 mod user_media_statistics;
 
 use ruma::{
@@ -21,7 +30,12 @@
 	delete_media_by_date_size::admin_delete_media_by_date_size_route,
 	delete_user_media::admin_delete_user_media_route,
 	list_room_media::admin_list_room_media_route, list_user_media::admin_list_user_media_route,
-	purge_media_cache::admin_purge_media_cache_route, query_media::admin_query_media_route,
+	protect_media::admin_protect_media_route, purge_media_cache::admin_purge_media_cache_route,
+	quarantine_media::admin_quarantine_media_route,
+	quarantine_room_media::admin_quarantine_room_media_route,
+	quarantine_user_media::admin_quarantine_user_media_route,
+	query_media::admin_query_media_route, unprotect_media::admin_unprotect_media_route,
+	unquarantine_media::admin_unquarantine_media_route,
 	user_media_statistics::admin_user_media_statistics_route,
 };
 
@@ -134,6 +148,8 @@ fn entry(media_id: &str, created_ts: u64, media_length: Option<u64>) -> UserMedi
 			media_length,
 			created_ts,
 			user_id: Some(user_id!("@alice:example.org").to_owned()),
+			quarantined_by: None,
+			safe_from_quarantine: false,
 		}
 	}
 
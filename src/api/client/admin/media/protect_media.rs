@@ -0,0 +1,50 @@
+use axum::extract::State;
+use ruma::{
+	Mxc,
+	api::{auth_scheme::AccessToken, metadata, request, response},
+};
+use tuwunel_core::{Result, err};
+
+use crate::{Ruma, client::admin::require_admin};
+
+metadata! {
+	method: POST,
+	rate_limited: false,
+	authentication: AccessToken,
+	path: "/_synapse/admin/v1/media/protect/{media_id}",
+}
+
+#[request]
+pub(crate) struct Request {
+	#[ruma_api(path)]
+	pub media_id: String,
+}
+
+#[response]
+pub(crate) struct Response {}
+
+/// # `POST /_synapse/admin/v1/media/protect/{media_id}`
+///
+/// Protects a local media item from quarantine and from the date and size
+/// purge.
+pub(crate) async fn admin_protect_media_route(
+	State(services): State<crate::State>,
+	body: Ruma<Request>,
+) -> Result<Response> {
+	require_admin(&services, body.sender_user()).await?;
+
+	let mxc = Mxc {
+		server_name: services.globals.server_name(),
+		media_id: &body.media_id,
+	};
+
+	services
+		.media
+		.get_metadata(&mxc)
+		.await
+		.ok_or_else(|| err!(Request(NotFound("Unknown media"))))?;
+
+	services.media.set_protected(&mxc, true).await;
+
+	Ok(Response {})
+}
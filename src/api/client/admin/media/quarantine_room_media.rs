@@ -0,0 +1,47 @@
+use axum::extract::State;
+use ruma::{
+	OwnedRoomId, UInt,
+	api::{auth_scheme::AccessToken, metadata, request, response},
+};
+use tuwunel_core::Result;
+
+use crate::{Ruma, client::admin::require_admin};
+
+metadata! {
+	method: POST,
+	rate_limited: false,
+	authentication: AccessToken,
+	path: "/_synapse/admin/v1/room/{room_id}/media/quarantine",
+}
+
+#[request]
+pub(crate) struct Request {
+	#[ruma_api(path)]
+	pub room_id: OwnedRoomId,
+}
+
+#[response]
+pub(crate) struct Response {
+	pub num_quarantined: UInt,
+}
+
+/// # `POST /_synapse/admin/v1/room/{room_id}/media/quarantine`
+///
+/// Quarantines every media item the room's events reference, local or
+/// remote, counting those newly quarantined.
+pub(crate) async fn admin_quarantine_room_media_route(
+	State(services): State<crate::State>,
+	body: Ruma<Request>,
+) -> Result<Response> {
+	let sender_user = body.sender_user();
+	require_admin(&services, sender_user).await?;
+
+	let num_quarantined = services
+		.media
+		.set_quarantined_room(&body.room_id, Some(sender_user))
+		.await;
+
+	Ok(Response {
+		num_quarantined: UInt::try_from(num_quarantined).unwrap_or(UInt::MAX),
+	})
+}
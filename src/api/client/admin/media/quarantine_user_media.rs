@@ -0,0 +1,54 @@
+use axum::extract::State;
+use ruma::{
+	OwnedUserId, UInt,
+	api::{auth_scheme::AccessToken, metadata, request, response},
+};
+use tuwunel_core::{Err, Result};
+
+use crate::{Ruma, client::admin::require_admin};
+
+metadata! {
+	method: POST,
+	rate_limited: false,
+	authentication: AccessToken,
+	path: "/_synapse/admin/v1/user/{user_id}/media/quarantine",
+}
+
+#[request]
+pub(crate) struct Request {
+	#[ruma_api(path)]
+	pub user_id: OwnedUserId,
+}
+
+#[response]
+pub(crate) struct Response {
+	pub num_quarantined: UInt,
+}
+
+/// # `POST /_synapse/admin/v1/user/{user_id}/media/quarantine`
+///
+/// Quarantines every media item the local user uploaded, counting those
+/// newly quarantined.
+pub(crate) async fn admin_quarantine_user_media_route(
+	State(services): State<crate::State>,
+	body: Ruma<Request>,
+) -> Result<Response> {
+	let sender_user = body.sender_user();
+	require_admin(&services, sender_user).await?;
+
+	if !services
+		.globals
+		.server_is_ours(body.user_id.server_name())
+	{
+		return Err!(Request(InvalidParam("Can only look up local users")));
+	}
+
+	let num_quarantined = services
+		.media
+		.set_quarantined_user(&body.user_id, Some(sender_user))
+		.await;
+
+	Ok(Response {
+		num_quarantined: UInt::try_from(num_quarantined).unwrap_or(UInt::MAX),
+	})
+}
@@ -49,9 +49,9 @@ fn into_media_info(local: bool, server_name: &ServerName, entry: UserMediaEntry)
 		filesystem_id: None,
 		url_cache: None,
 		last_access_ts: None,
-		quarantined_by: None,
+		quarantined_by: entry.quarantined_by.map(Into::into),
 		authenticated: None,
-		safe_from_quarantine: local.then_some(false),
+		safe_from_quarantine: local.then_some(entry.safe_from_quarantine),
 		sha256: None,
 	}
 }
@@ -76,6 +76,8 @@ fn entry(
 			media_length,
 			created_ts,
 			user_id: user.then(|| user_id!("@alice:example.org").to_owned()),
+			quarantined_by: None,
+			safe_from_quarantine: false,
 		}
 	}
 
@@ -116,6 +118,21 @@ fn remote_media_sets_origin_and_drops_uploader() {
 		assert_eq!(info.safe_from_quarantine, None);
 	}
 
+	#[test]
+	fn quarantine_flags_are_reported() {
+		let entry = UserMediaEntry {
+			quarantined_by: Some(user_id!("@admin:example.org").to_owned()),
+			safe_from_quarantine: true,
+			..entry(true, Some("image/png"), None, None, 0)
+		};
+
+		let info = into_media_info(true, server_name!("example.org"), entry);
+
+		let quarantined_by = info.quarantined_by.map(|by| by.to_string());
+		assert_eq!(quarantined_by.as_deref(), Some("@admin:example.org"));
+		assert_eq!(info.safe_from_quarantine, Some(true));
+	}
+
 	#[test]
 	fn absent_derivables_stay_null() {
 		let info =
@@ -0,0 +1,44 @@
+use axum::extract::State;
+use ruma::{
+	Mxc, OwnedServerName,
+	api::{auth_scheme::AccessToken, metadata, request, response},
+};
+use tuwunel_core::Result;
+
+use crate::{Ruma, client::admin::require_admin};
+
+metadata! {
+	method: POST,
+	rate_limited: false,
+	authentication: AccessToken,
+	path: "/_synapse/admin/v1/media/unquarantine/{server_name}/{media_id}",
+}
+
+#[request]
+pub(crate) struct Request {
+	#[ruma_api(path)]
+	pub server_name: OwnedServerName,
+
+	#[ruma_api(path)]
+	pub media_id: String,
+}
+
+#[response]
+pub(crate) struct Response {}
+
+/// # `POST /_synapse/admin/v1/media/unquarantine/{server_name}/{media_id}`
+pub(crate) async fn admin_unquarantine_media_route(
+	State(services): State<crate::State>,
+	body: Ruma<Request>,
+) -> Result<Response> {
+	require_admin(&services, body.sender_user()).await?;
+
+	let mxc = Mxc {
+		server_name: &body.server_name,
+		media_id: &body.media_id,
+	};
+
+	services.media.set_quarantined(&mxc, None).await;
+
+	Ok(Response {})
+}
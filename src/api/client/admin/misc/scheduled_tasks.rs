@@ -51,7 +51,7 @@ fn matches_filters(task: &TaskInfo, req: &Request) -> bool {
 fn scheduled_task(task: TaskInfo) -> ScheduledTask {
 	ScheduledTask {
 		id: task.id.to_string(),
-		action: task.action.to_owned(),
+		action: task.action,
 		status: task_status(task.status),
 		timestamp_ms: MilliSecondsSinceUnixEpoch(
 			UInt::try_from(task.timestamp_ms).unwrap_or_default(),
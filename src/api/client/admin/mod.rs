@@ -10,6 +10,7 @@
 pub(crate) mod federation;
 pub(crate) mod media;
 pub(crate) mod misc;
+pub(crate) mod reports;
 pub(crate) mod rooms;
 pub(crate) mod tokens;
 pub(crate) mod users;
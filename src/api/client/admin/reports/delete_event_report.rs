@@ -0,0 +1,50 @@
+use axum::extract::State;
+use ruma::{
+	UInt,
+	api::{auth_scheme::AccessToken, metadata, request, response},
+};
+use tuwunel_core::{Err, Result};
+use tuwunel_service::reports::Kind;
+
+use crate::{Ruma, client::admin::require_admin};
+
+metadata! {
+	method: DELETE,
+	rate_limited: false,
+	authentication: AccessToken,
+	path: "/_synapse/admin/v1/event_reports/{report_id}",
+}
+
+#[request]
+pub(crate) struct Request {
+	#[ruma_api(path)]
+	pub report_id: UInt,
+}
+
+#[response]
+pub(crate) struct Response {}
+
+/// # `DELETE /_synapse/admin/v1/event_reports/{report_id}`
+///
+/// Deletes an event report.
+pub(crate) async fn admin_delete_event_report_route(
+	State(services): State<crate::State>,
+	body: Ruma<Request>,
+) -> Result<Response> {
+	require_admin(&services, body.sender_user()).await?;
+
+	let id = u64::from(body.report_id);
+	let is_event_report = services
+		.reports
+		.get(id)
+		.await
+		.is_ok_and(|report| report.kind == Kind::Event);
+
+	if !is_event_report {
+		return Err!(Request(NotFound("Event report not found")));
+	}
+
+	services.reports.delete(id).await?;
+
+	Ok(Response {})
+}
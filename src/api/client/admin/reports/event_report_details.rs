@@ -0,0 +1,63 @@
+use axum::extract::State;
+use ruma::{
+	UInt,
+	api::{auth_scheme::AccessToken, metadata, request, response},
+};
+use serde_json::value::RawValue as RawJsonValue;
+use tuwunel_core::{Result, err};
+
+use super::{EventReport, event_report};
+use crate::{Ruma, client::admin::require_admin};
+
+metadata! {
+	method: GET,
+	rate_limited: false,
+	authentication: AccessToken,
+	path: "/_synapse/admin/v1/event_reports/{report_id}",
+}
+
+#[request]
+pub(crate) struct Request {
+	#[ruma_api(path)]
+	pub report_id: UInt,
+}
+
+#[response]
+pub(crate) struct Response {
+	#[serde(flatten)]
+	pub report: EventReport,
+
+	/// The reported event, absent once it is purged.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub event_json: Option<Box<RawJsonValue>>,
+}
+
+/// # `GET /_synapse/admin/v1/event_reports/{report_id}`
+///
+/// Returns one event report with the reported event.
+pub(crate) async fn admin_event_report_details_route(
+	State(services): State<crate::State>,
+	body: Ruma<Request>,
+) -> Result<Response> {
+	require_admin(&services, body.sender_user()).await?;
+
+	let id = u64::from(body.report_id);
+	let report = services
+		.reports
+		.get(id)
+		.await
+		.map_err(|_| err!(Request(NotFound("Event report not found"))))?;
+
+	let report = event_report(&services, id, report)
+		.await
+		.ok_or_else(|| err!(Request(NotFound("Event report not found"))))?;
+
+	let event_json = services
+		.timeline
+		.get_pdu_json(&report.event_id)
+		.await
+		.ok()
+		.and_then(|event| serde_json::value::to_raw_value(&event).ok());
+
+	Ok(Response { report, event_json })
+}
@@ -0,0 +1,133 @@
+use axum::extract::State;
+use futures::StreamExt;
+use ruma::{
+	OwnedUserId, UInt,
+	api::{Direction, auth_scheme::AccessToken, metadata, request, response},
+};
+use tuwunel_core::{
+	Result,
+	utils::{IterStream, ReadyExt},
+};
+use tuwunel_service::reports::{Filter, Kind, Report};
+
+use super::{EventReport, event_report};
+use crate::{Ruma, client::admin::require_admin};
+
+/// Synapse's page size when `limit` is omitted.
+const LIMIT_DEFAULT: usize = 100;
+
+metadata! {
+	method: GET,
+	rate_limited: false,
+	authentication: AccessToken,
+	path: "/_synapse/admin/v1/event_reports",
+}
+
+#[request]
+pub(crate) struct Request {
+	/// Offset into the list to start from.
+	#[ruma_api(query)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub from: Option<UInt>,
+
+	#[ruma_api(query)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub limit: Option<UInt>,
+
+	/// Newest first unless `f`.
+	#[ruma_api(query)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub dir: Option<Direction>,
+
+	/// Only reports filed by users whose ID contains this.
+	#[ruma_api(query)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub user_id: Option<String>,
+
+	/// Only reports in rooms whose ID contains this.
+	#[ruma_api(query)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub room_id: Option<String>,
+
+	/// Only reports of events sent by this user.
+	#[ruma_api(query)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub event_sender_user_id: Option<OwnedUserId>,
+}
+
+#[response]
+pub(crate) struct Response {
+	pub event_reports: Vec<EventReport>,
+
+	/// Offset of the next page, absent on the last one.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub next_token: Option<UInt>,
+
+	/// Reports matching the filters across every page.
+	pub total: UInt,
+}
+
+/// # `GET /_synapse/admin/v1/event_reports`
+///
+/// Lists stored event reports, newest first by default, filtered by reporter,
+/// room and event sender.
+pub(crate) async fn admin_list_event_reports_route(
+	State(services): State<crate::State>,
+	body: Ruma<Request>,
+) -> Result<Response> {
+	require_admin(&services, body.sender_user()).await?;
+
+	let filter = Filter {
+		kind: Some(Kind::Event),
+		user_id: body.event_sender_user_id.clone(),
+		..Filter::default()
+	};
+
+	let newest_first = !matches!(body.dir, Some(Direction::Forward));
+	let matched: Vec<(u64, Report)> = services
+		.reports
+		.reports(&filter, newest_first)
+		.ready_filter(|(_, report)| matches_query(report, &body))
+		.collect()
+		.await;
+
+	let from = body
+		.from
+		.and_then(|from| usize::try_from(from).ok())
+		.unwrap_or(0);
+
+	let limit = body
+		.limit
+		.and_then(|limit| usize::try_from(limit).ok())
+		.unwrap_or(LIMIT_DEFAULT);
+
+	let total = matched.len();
+	let next = from.saturating_add(limit);
+
+	let event_reports = matched
+		.into_iter()
+		.skip(from)
+		.take(limit)
+		.stream()
+		.filter_map(async |(id, report)| event_report(&services, id, report).await)
+		.collect()
+		.await;
+
+	Ok(Response {
+		event_reports,
+		next_token: (next < total).then(|| UInt::try_from(next).unwrap_or(UInt::MAX)),
+		total: UInt::try_from(total).unwrap_or(UInt::MAX),
+	})
+}
+
+fn matches_query(report: &Report, req: &Request) -> bool {
+	req.user_id
+		.as_deref()
+		.is_none_or(|user_id| report.reporter.as_str().contains(user_id))
+		&& req.room_id.as_deref().is_none_or(|room_id| {
+			report
+				.room_id
+				.as_ref()
+				.is_some_and(|id| id.as_str().contains(room_id))
+		})
+}
@@ -0,0 +1,70 @@
+//! Synapse admin API: event reports.
+//!
+//! synapse-admin-api does not cover these endpoints, so their request and
+//! response types are declared alongside the routes.
+
+mod delete_event_report;
+mod event_report_details;
+mod list_event_reports;
+
+use futures::future::join;
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomAliasId, OwnedRoomId, OwnedUserId, UInt,
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_service::reports::Report;
+
+pub(crate) use self::{
+	delete_event_report::admin_delete_event_report_route,
+	event_report_details::admin_event_report_details_route,
+	list_event_reports::admin_list_event_reports_route,
+};
+
+/// An event report as listed by Synapse.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct EventReport {
+	pub id: UInt,
+	pub received_ts: MilliSecondsSinceUnixEpoch,
+	pub room_id: OwnedRoomId,
+	pub name: Option<String>,
+	pub event_id: OwnedEventId,
+
+	/// The reporting user.
+	pub user_id: OwnedUserId,
+
+	pub reason: Option<String>,
+
+	/// The sender of the reported event.
+	pub sender: OwnedUserId,
+
+	pub canonical_alias: Option<OwnedRoomAliasId>,
+}
+
+/// The Synapse view of a stored report, or `None` unless it reports an event.
+async fn event_report(services: &crate::State, id: u64, report: Report) -> Option<EventReport> {
+	let (Some(room_id), Some(event_id), Some(sender)) =
+		(report.room_id, report.event_id, report.user_id)
+	else {
+		return None;
+	};
+
+	let (name, canonical_alias) = join(
+		services.state_accessor.get_name(&room_id),
+		services
+			.state_accessor
+			.get_canonical_alias(&room_id),
+	)
+	.await;
+
+	Some(EventReport {
+		id: UInt::new_saturating(id),
+		received_ts: MilliSecondsSinceUnixEpoch(UInt::new_saturating(report.received_ts)),
+		room_id,
+		name: name.ok(),
+		event_id,
+		user_id: report.reporter,
+		reason: report.reason,
+		sender,
+		canonical_alias: canonical_alias.ok(),
+	})
+}
@@ -5,7 +5,7 @@
 	v2::{Request as V2Request, Response as V2Response},
 };
 use tuwunel_core::Result;
-use tuwunel_service::rooms::delete::ShutdownRoom as Summary;
+use tuwunel_service::{rooms::delete::ShutdownRoom as Summary, tasks::Params};
 
 use crate::{Ruma, client::admin::require_admin};
 
@@ -30,26 +30,22 @@ pub(crate) async fn admin_delete_room_v1_route(
 /// # `DELETE /_synapse/admin/v2/rooms/{room_id}`
 ///
 /// Schedules the same shutdown as a background task and returns its id at once;
-/// the outcome is retrieved from the delete-status endpoints.
+/// the outcome is retrieved from the delete-status endpoints. The shutdown
+/// resumes after a restart.
 pub(crate) async fn admin_delete_room_v2_route(
 	State(services): State<crate::State>,
 	body: Ruma<V2Request>,
 ) -> Result<V2Response> {
 	require_admin(&services, body.sender_user()).await?;
 
-	let room_id = body.room_id.clone();
-	let sender = body.sender_user().to_owned();
-	let (block, purge) = (body.block, body.purge);
-
-	let work = async move {
-		let summary = run_shutdown(&services, &room_id, &sender, block, purge).await;
-
-		Ok(serde_json::to_value(summary)?)
-	};
-
 	let delete_id = services
 		.tasks
-		.spawn(super::DELETE_ROOM_ACTION, body.room_id.to_string(), work)
+		.spawn_resumable(Params::ShutdownRoom {
+			room_id: body.room_id.clone(),
+			requester: body.sender_user().to_owned(),
+			block: body.block,
+			purge: body.purge,
+		})
 		.to_string();
 
 	Ok(V2Response { delete_id })
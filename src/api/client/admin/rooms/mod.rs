@@ -33,7 +33,7 @@
 };
 use synapse_admin_api::rooms::list_rooms::v1::RoomDetails;
 use tuwunel_core::{Result, matrix::Event, utils::TryFutureExtExt};
-use tuwunel_service::Services;
+use tuwunel_service::{Services, tasks};
 
 pub(crate) use self::{
 	block::{admin_get_room_block_route, admin_set_room_block_route},
@@ -58,13 +58,13 @@
 	timestamp_to_event::admin_room_timestamp_to_event_route,
 };
 
-/// Action name recorded for the async room-shutdown task, mirroring Synapse's
-/// `SHUTDOWN_AND_PURGE_ROOM`. The delete-status endpoints filter on it.
-const DELETE_ROOM_ACTION: &str = "shutdown_and_purge_room";
+/// Action name recorded for the async room-shutdown task. The delete-status
+/// endpoints filter on it.
+const DELETE_ROOM_ACTION: &str = tasks::SHUTDOWN_AND_PURGE_ROOM;
 
 /// Action name recorded for the history-purge task, filtered on by the
 /// purge-status endpoint.
-const PURGE_HISTORY_ACTION: &str = "purge_history";
+const PURGE_HISTORY_ACTION: &str = tasks::PURGE_HISTORY;
 
 /// Assembles the shared per-room summary row returned by both the room-list and
 /// room-details endpoints.
@@ -11,7 +11,7 @@
 	status::v1::{PurgeStatus, Request as StatusRequest, Response as StatusResponse},
 };
 use tuwunel_core::{Err, Result, err, matrix::pdu::PduCount};
-use tuwunel_service::tasks::Status;
+use tuwunel_service::tasks::{Params, Status};
 
 use crate::{Ruma, client::admin::require_admin};
 
@@ -34,7 +34,7 @@ pub(crate) async fn admin_purge_history_route(
 	.await?;
 
 	let purge_id =
-		schedule_purge(services, body.room_id.clone(), boundary, body.delete_local_events);
+		schedule_purge(&services, body.room_id.clone(), boundary, body.delete_local_events);
 
 	Ok(PurgeResponse { purge_id })
 }
@@ -51,7 +51,7 @@ pub(crate) async fn admin_purge_history_by_event_route(
 	let boundary = resolve_boundary(&services, &body.room_id, Some(&body.event_id), None).await?;
 
 	let purge_id =
-		schedule_purge(services, body.room_id.clone(), boundary, body.delete_local_events);
+		schedule_purge(&services, body.room_id.clone(), boundary, body.delete_local_events);
 
 	Ok(PurgeByEventResponse { purge_id })
 }
@@ -125,28 +125,21 @@ async fn resolve_boundary(
 		.ok_or_else(|| err!(Request(NotFound("No event found before the given timestamp"))))
 }
 
-/// Spawns the purge on the tasks service, returning its id. Takes `services` by
-/// value (it is `Copy`) so the detached task owns a `'static` handle.
+/// Spawns the purge on the tasks service, returning its id. The purge resumes
+/// after a restart.
 fn schedule_purge(
-	services: crate::State,
+	services: &crate::State,
 	room_id: OwnedRoomId,
 	boundary: PduCount,
 	delete_local_events: bool,
 ) -> String {
-	let resource_id = room_id.to_string();
-
-	let work = async move {
-		let purged = services
-			.timeline
-			.purge_history(&room_id, boundary, delete_local_events)
-			.await?;
-
-		Ok(serde_json::json!({ "purged": purged }))
-	};
-
 	services
 		.tasks
-		.spawn(super::PURGE_HISTORY_ACTION, resource_id, work)
+		.spawn_resumable(Params::PurgeHistory {
+			room_id,
+			until: boundary.into_signed(),
+			delete_local_events,
+		})
 		.to_string()
 }
 
@@ -33,6 +33,16 @@ pub(crate) async fn create_alias_route(
 		return Err!(Request(Forbidden("Room alias is forbidden.")));
 	}
 
+	services
+		.policy_lists
+		.check_room("create alias", body.room_alias.as_str())
+		.await?;
+
+	services
+		.policy_lists
+		.check_room("create alias", body.room_id.as_str())
+		.await?;
+
 	if services
 		.alias
 		.resolve_local_alias(&body.room_alias)
@@ -0,0 +1,225 @@
+use std::time::Duration;
+
+use axum::{
+	extract::{FromRequestParts, State},
+	response::{IntoResponse, Response},
+};
+use futures::FutureExt;
+use http::request::Parts;
+use ruma::{
+	EventId, RoomId, UserId,
+	api::client::{
+		delayed_events::{
+			delayed_message_event, delayed_state_event,
+			update_delayed_event::{self, unstable::UpdateAction},
+		},
+		message::send_message_event,
+		state::send_state_event,
+	},
+	events::MessageLikeEventType,
+};
+use serde::Deserialize;
+use serde_json::value::RawValue as RawJsonValue;
+use tuwunel_core::{Err, Error, Result, err, utils};
+use tuwunel_service::Services;
+
+use super::{send_message_event_route, send_state_event_for_key_route, state};
+use crate::{Ruma, RumaResponse};
+
+/// The MSC4140 query parameter which turns a send into a delayed event.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Delay {
+	/// Milliseconds to wait before the event is sent.
+	#[serde(rename = "org.matrix.msc4140.delay")]
+	delay: Option<u64>,
+}
+
+impl FromRequestParts<crate::State> for Delay {
+	type Rejection = Error;
+
+	async fn from_request_parts(
+		parts: &mut Parts,
+		_services: &crate::State,
+	) -> Result<Self, Self::Rejection> {
+		let query = parts.uri.query().unwrap_or_default();
+
+		serde_html_form::from_str(query)
+			.map_err(|e| err!(Request(InvalidParam("Invalid delay parameter: {e}"))))
+	}
+}
+
+/// # `PUT /_matrix/client/*/rooms/{roomId}/send/{eventType}/{txnId}`
+///
+/// Sends a message event into the room. With `org.matrix.msc4140.delay` the
+/// event is instead scheduled as a delayed event and its `delay_id` returned.
+///
+/// The delayed form shares the path and request of an ordinary send but not
+/// its response, so the route is registered outside of the Ruma router.
+pub(crate) async fn send_message_or_delayed_event_route(
+	State(services): State<crate::State>,
+	Delay { delay }: Delay,
+	body: Ruma<send_message_event::v3::Request>,
+) -> Result<Response> {
+	let Some(delay) = delay else {
+		return send_message_event_route(State(services), body)
+			.boxed()
+			.await
+			.map(RumaResponse)
+			.map(IntoResponse::into_response);
+	};
+
+	let sender_user = body.sender_user();
+	let sender_device = body.sender_device.as_deref();
+
+	if body.event_type == MessageLikeEventType::RoomEncrypted && !services.config.allow_encryption
+	{
+		return Err!(Request(Forbidden("Encryption has been disabled")));
+	}
+
+	if services.users.is_suspended(sender_user).await {
+		return Err!(Request(UserSuspended("Cannot schedule events while suspended.")));
+	}
+
+	// A retried request answers with the delay_id the first one scheduled.
+	if let Ok(response) = services
+		.transaction_ids
+		.existing_txnid(sender_user, sender_device, &body.txn_id)
+		.await
+	{
+		let delay_id = utils::string_from_bytes(&response)
+			.ok()
+			.filter(|delay_id| <&EventId>::try_from(delay_id.as_str()).is_err())
+			.ok_or_else(|| {
+				err!(Request(InvalidParam(
+					"Tried to use txn_id already used for an incompatible endpoint."
+				)))
+			})?;
+
+		return Ok(RumaResponse(delayed_message_event::unstable::Response::new(delay_id))
+			.into_response());
+	}
+
+	check_joined(&services, sender_user, &body.room_id).await?;
+
+	let content: Box<RawJsonValue> = serde_json::from_str(body.body.body.json().get())
+		.map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))?;
+
+	let delay_id = services.delayed_events.schedule(
+		sender_user,
+		&body.room_id,
+		&body.event_type.to_string(),
+		None,
+		content,
+		Duration::from_millis(delay),
+	)?;
+
+	services.transaction_ids.add_txnid(
+		sender_user,
+		sender_device,
+		&body.txn_id,
+		delay_id.as_bytes(),
+	);
+
+	Ok(RumaResponse(delayed_message_event::unstable::Response::new(delay_id)).into_response())
+}
+
+/// # `PUT /_matrix/client/*/rooms/{roomId}/state/{eventType}/{stateKey}`
+///
+/// Sends a state event into the room. With `org.matrix.msc4140.delay` the
+/// event is instead scheduled as a delayed event and its `delay_id` returned.
+/// Also serves the path without a state key.
+pub(crate) async fn send_state_or_delayed_event_route(
+	State(services): State<crate::State>,
+	Delay { delay }: Delay,
+	body: Ruma<send_state_event::v3::Request>,
+) -> Result<Response> {
+	let Some(delay) = delay else {
+		return send_state_event_for_key_route(State(services), body)
+			.boxed()
+			.await
+			.map(RumaResponse)
+			.map(IntoResponse::into_response);
+	};
+
+	let sender_user = body.sender_user();
+
+	state::allowed_to_send_state_event(
+		&services,
+		sender_user,
+		&body.room_id,
+		&body.event_type,
+		&body.state_key,
+		&body.body.body,
+	)
+	.await?;
+
+	check_joined(&services, sender_user, &body.room_id).await?;
+
+	let content: Box<RawJsonValue> = serde_json::from_str(body.body.body.json().get())
+		.map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))?;
+
+	let delay_id = services.delayed_events.schedule(
+		sender_user,
+		&body.room_id,
+		&body.event_type.to_string(),
+		Some(&body.state_key),
+		content,
+		Duration::from_millis(delay),
+	)?;
+
+	Ok(RumaResponse(delayed_state_event::unstable::Response::new(delay_id)).into_response())
+}
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc4140/delayed_events/{delayId}`
+///
+/// Restarts the delay of one of the user's delayed events, cancels it, or
+/// sends it at once.
+pub(crate) async fn update_delayed_event_route(
+	State(services): State<crate::State>,
+	body: Ruma<update_delayed_event::unstable::Request>,
+) -> Result<update_delayed_event::unstable::Response> {
+	let event = services
+		.delayed_events
+		.get(&body.delay_id)
+		.await?;
+
+	if event.sender != body.sender_user() {
+		return Err!(Request(NotFound("No delayed event with this delay_id.")));
+	}
+
+	match body.action {
+		| UpdateAction::Restart =>
+			services
+				.delayed_events
+				.restart(&body.delay_id)
+				.await?,
+		| UpdateAction::Cancel =>
+			services
+				.delayed_events
+				.cancel(&body.delay_id)
+				.await?,
+		| UpdateAction::Send => {
+			services
+				.delayed_events
+				.send(&body.delay_id)
+				.await?;
+		},
+		| _ => return Err!(Request(InvalidParam("Unknown delayed event action."))),
+	}
+
+	Ok(update_delayed_event::unstable::Response::new())
+}
+
+/// Scheduling is refused for rooms the user is not joined to; anything else is
+/// left to the auth rules when the event is finally sent.
+async fn check_joined(services: &Services, sender_user: &UserId, room_id: &RoomId) -> Result {
+	if !services
+		.state_cache
+		.is_joined(sender_user, room_id)
+		.await
+	{
+		return Err!(Request(Forbidden("You are not joined to this room.")));
+	}
+
+	Ok(())
+}
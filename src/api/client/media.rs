@@ -1,9 +1,10 @@
 use std::time::Duration;
 
 use axum::extract::State;
+use futures::future::join;
 use reqwest::Url;
 use ruma::{
-	MilliSecondsSinceUnixEpoch, Mxc, UserId,
+	MilliSecondsSinceUnixEpoch, Mxc, UInt, UserId,
 	api::client::{
 		authenticated_media::{
 			get_content, get_content_as_filename, get_content_thumbnail, get_media_config,
@@ -21,7 +22,7 @@
 };
 use tuwunel_service::{
 	Services,
-	media::{CACHE_CONTROL_IMMUTABLE, CORP_CROSS_ORIGIN, Dim, MXC_LENGTH, Media},
+	media::{CACHE_CONTROL_IMMUTABLE, CORP_CROSS_ORIGIN, Dim, MXC_LENGTH, Media, Quota, Usage},
 };
 
 use crate::{ClientIp, Ruma};
@@ -36,6 +37,70 @@ pub(crate) async fn get_media_config_route(
 	})
 }
 
+/// Media usage (MSC4034); ruma does not cover it, so its request and response
+/// types are declared here.
+pub(crate) mod get_media_usage {
+	use ruma::{
+		UInt,
+		api::{auth_scheme::AccessToken, metadata, request, response},
+	};
+
+	metadata! {
+		method: GET,
+		rate_limited: false,
+		authentication: AccessToken,
+		path: "/_matrix/client/v1/media/usage",
+	}
+
+	#[request]
+	pub(crate) struct Request {}
+
+	#[response]
+	pub(crate) struct Response {
+		/// Bytes of media the user has uploaded.
+		#[serde(rename = "m.storage.used")]
+		pub used: UInt,
+
+		/// Number of media files the user has uploaded.
+		#[serde(rename = "m.storage.files")]
+		pub files: UInt,
+
+		/// The user's byte quota, absent when unlimited.
+		#[serde(
+			rename = "m.storage.size",
+			skip_serializing_if = "Option::is_none"
+		)]
+		pub size: Option<UInt>,
+
+		/// The user's file quota, absent when unlimited.
+		#[serde(
+			rename = "m.storage.max_files",
+			skip_serializing_if = "Option::is_none"
+		)]
+		pub max_files: Option<UInt>,
+	}
+}
+
+/// # `GET /_matrix/client/v1/media/usage`
+///
+/// Returns the user's media storage usage alongside their quota. Also served
+/// at `/_matrix/client/unstable/org.matrix.msc4034/media/usage`.
+pub(crate) async fn get_media_usage_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_media_usage::Request>,
+) -> Result<get_media_usage::Response> {
+	let sender_user = body.sender_user();
+	let (Usage { bytes, files }, Quota { max_bytes, max_files }) =
+		join(services.media.usage(sender_user), services.media.quota(sender_user)).await;
+
+	Ok(get_media_usage::Response {
+		used: UInt::new_saturating(bytes),
+		files: UInt::new_saturating(files),
+		size: max_bytes.map(UInt::new_saturating),
+		max_files: max_files.map(UInt::new_saturating),
+	})
+}
+
 /// # `POST /_matrix/media/v3/upload`
 ///
 /// Permanently save media in the server.
@@ -162,7 +227,8 @@ pub(crate) async fn get_content_thumbnail_route(
 ) -> Result<get_content_thumbnail::v1::Response> {
 	let user = body.sender_user();
 
-	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?;
+	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?
+		.with_animated(body.animated);
 	let mxc = Mxc {
 		server_name: &body.server_name,
 		media_id: &body.media_id,
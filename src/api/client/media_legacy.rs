@@ -266,7 +266,8 @@ pub(crate) async fn get_content_thumbnail_legacy_route(
 		media_id: &body.media_id,
 	};
 
-	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?;
+	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?
+		.with_animated(body.animated);
 
 	if body.allow_redirect
 		&& services.globals.server_is_ours(&body.server_name)
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use axum::extract::State;
 use futures::{FutureExt, StreamExt, pin_mut};
 use ruma::{
@@ -24,6 +26,9 @@
 
 use crate::Ruma;
 
+/// How long a member list request waits for a partial-state room to resync.
+const PARTIAL_STATE_WAIT: Duration = Duration::from_secs(10);
+
 /// # `GET /_matrix/client/r0/rooms/{roomId}/members`
 ///
 /// Lists all joined users in a room (TODO: at a specific point in time, with a
@@ -44,6 +49,13 @@ pub(crate) async fn get_member_events_route(
 		)));
 	}
 
+	// MSC3706: the member list is incomplete until a faster join resyncs; give
+	// the resync a moment before answering with the members known so far.
+	services
+		.partial_state
+		.wait_full_state(&body.room_id, PARTIAL_STATE_WAIT)
+		.await;
+
 	let membership = body.membership.as_ref();
 	let not_membership = body.not_membership.as_ref();
 	let membership_filter = |content: &RoomMemberEventContent| {
@@ -96,6 +108,11 @@ pub(crate) async fn joined_members_route(
 		return Err!(Request(Forbidden("You aren't a member of the room.")));
 	}
 
+	services
+		.partial_state
+		.wait_full_state(&body.room_id, PARTIAL_STATE_WAIT)
+		.await;
+
 	Ok(joined_members::v3::Response {
 		joined: services
 			.state_accessor
@@ -0,0 +1,251 @@
+use std::{collections::BTreeMap, ffi::CStr, sync::atomic::Ordering};
+
+use axum::{
+	extract::State,
+	response::{IntoResponse, Response},
+};
+use axum_extra::{
+	TypedHeader,
+	headers::{Authorization, authorization::Bearer},
+};
+use http::header::CONTENT_TYPE;
+use subtle::ConstantTimeEq;
+use tuwunel_core::{
+	Err, Result,
+	metrics::openmetrics::{self, Exposition, Kind},
+	utils::time::now_secs,
+};
+use tuwunel_service::{Services, sending::Destination};
+
+const ESTIMATE_NUM_KEYS: &CStr = c"rocksdb.estimate-num-keys";
+const TOTAL_SST_FILES_SIZE: &CStr = c"rocksdb.total-sst-files-size";
+
+/// # `GET /_tuwunel/metrics`
+///
+/// Tuwunel-specific API exposing server, database, federation and sync
+/// statistics in the OpenMetrics text format. Requires the configured
+/// `metrics_token` as bearer token; without one the endpoint is absent.
+pub(crate) async fn tuwunel_metrics_route(
+	State(services): State<crate::State>,
+	bearer: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<Response> {
+	let Some(secret) = services
+		.config
+		.metrics_token
+		.as_deref()
+		.filter(|secret| !secret.is_empty())
+	else {
+		return Err!(Request(NotFound("Metrics are not enabled on this server.")));
+	};
+
+	let authorized = bearer
+		.as_ref()
+		.is_some_and(|TypedHeader(Authorization(bearer))| {
+			bearer
+				.token()
+				.as_bytes()
+				.ct_eq(secret.as_bytes())
+				.into()
+		});
+
+	if !authorized {
+		return Err!(Request(Unauthorized("Invalid metrics token.")));
+	}
+
+	let mut out = Exposition::new();
+	write_server(&services, &mut out);
+	write_database(&services, &mut out)?;
+	write_sending(&services, &mut out).await;
+	write_federation(&services, &mut out).await;
+	write_sync(&services, &mut out).await;
+
+	Ok(([(CONTENT_TYPE, openmetrics::CONTENT_TYPE)], out.finish()).into_response())
+}
+
+fn write_server(services: &Services, out: &mut Exposition) {
+	let metrics = &services.server.metrics;
+
+	out.family("tuwunel_build_info", Kind::Gauge, "Server version.")
+		.gauge("tuwunel_build_info", &[("version", tuwunel_core::version::version())], 1);
+
+	if let Some(runtime) = metrics.runtime_metrics() {
+		out.family("tuwunel_runtime_workers", Kind::Gauge, "Tokio worker threads.")
+			.gauge("tuwunel_runtime_workers", &[], runtime.num_workers())
+			.family("tuwunel_runtime_alive_tasks", Kind::Gauge, "Tokio tasks alive.")
+			.gauge("tuwunel_runtime_alive_tasks", &[], runtime.num_alive_tasks())
+			.family(
+				"tuwunel_runtime_global_queue_depth",
+				Kind::Gauge,
+				"Tasks waiting in the Tokio global queue.",
+			)
+			.gauge("tuwunel_runtime_global_queue_depth", &[], runtime.global_queue_depth());
+	}
+
+	out.family(
+		"tuwunel_request_panics",
+		Kind::Counter,
+		"Request handlers terminated by a panic.",
+	)
+	.counter("tuwunel_request_panics", &[], metrics.requests_panic.load(Ordering::Relaxed));
+
+	out.family(
+		"tuwunel_request_duration_seconds",
+		Kind::Histogram,
+		"Latency of requests by route.",
+	);
+	for ((method, route), snapshot) in metrics.requests_latency.snapshot() {
+		out.histogram(
+			"tuwunel_request_duration_seconds",
+			&[("method", &method), ("route", &route)],
+			&snapshot,
+		);
+	}
+}
+
+fn write_database(services: &Services, out: &mut Exposition) -> Result {
+	let stats = services.db.engine.memory_stats()?;
+
+	out.family("tuwunel_database_memory_bytes", Kind::Gauge, "Database engine memory use.")
+		.gauge("tuwunel_database_memory_bytes", &[("kind", "memtables")], stats.mem_table_total)
+		.gauge(
+			"tuwunel_database_memory_bytes",
+			&[("kind", "memtables_unflushed")],
+			stats.mem_table_unflushed,
+		)
+		.gauge(
+			"tuwunel_database_memory_bytes",
+			&[("kind", "table_readers")],
+			stats.table_readers,
+		)
+		.gauge("tuwunel_database_memory_bytes", &[("kind", "row_cache")], stats.row_cache_usage)
+		.family("tuwunel_database_row_cache_capacity_bytes", Kind::Gauge, "Row cache capacity.")
+		.gauge("tuwunel_database_row_cache_capacity_bytes", &[], stats.row_cache_capacity);
+
+	let maps = || services.db.iter().map(|(_, map)| map);
+
+	out.family(
+		"tuwunel_database_cache_hits",
+		Kind::Counter,
+		"Point reads answered from cache without blocking.",
+	);
+	for map in maps() {
+		let (hits, _) = map.cache_stats();
+		out.counter("tuwunel_database_cache_hits", &[("map", map.name())], hits);
+	}
+
+	out.family(
+		"tuwunel_database_cache_misses",
+		Kind::Counter,
+		"Point reads deferred to the blocking pool after missing the cache.",
+	);
+	for map in maps() {
+		let (_, misses) = map.cache_stats();
+		out.counter("tuwunel_database_cache_misses", &[("map", map.name())], misses);
+	}
+
+	out.family("tuwunel_database_keys", Kind::Gauge, "Estimated number of keys.");
+	for map in maps() {
+		if let Ok(keys) = map.property_integer(ESTIMATE_NUM_KEYS) {
+			out.gauge("tuwunel_database_keys", &[("map", map.name())], keys);
+		}
+	}
+
+	out.family("tuwunel_database_sst_bytes", Kind::Gauge, "Size of all SST files.");
+	for map in maps() {
+		if let Ok(bytes) = map.property_integer(TOTAL_SST_FILES_SIZE) {
+			out.gauge("tuwunel_database_sst_bytes", &[("map", map.name())], bytes);
+		}
+	}
+
+	Ok(())
+}
+
+async fn write_sending(services: &Services, out: &mut Exposition) {
+	let queued = services.sending.db.queued_depths().await;
+	let active = services.sending.db.active_depths().await;
+
+	out.family(
+		"tuwunel_sending_queued",
+		Kind::Gauge,
+		"Outgoing items waiting by destination kind.",
+	);
+	for (kind, depth) in depths_by_kind(&queued) {
+		out.gauge("tuwunel_sending_queued", &[("kind", kind)], depth);
+	}
+
+	out.family(
+		"tuwunel_sending_active",
+		Kind::Gauge,
+		"Outgoing items in flight by destination kind.",
+	);
+	for (kind, depth) in depths_by_kind(&active) {
+		out.gauge("tuwunel_sending_active", &[("kind", kind)], depth);
+	}
+
+	out.family(
+		"tuwunel_federation_queue_depth",
+		Kind::Gauge,
+		"Items waiting for each remote server.",
+	);
+	for (dest, depth) in &queued {
+		if let Destination::Federation(server) = dest {
+			out.gauge("tuwunel_federation_queue_depth", &[("server", server.as_str())], depth);
+		}
+	}
+}
+
+async fn write_federation(services: &Services, out: &mut Exposition) {
+	let backoffs = services.federation.peer_backoffs().await;
+	let now = now_secs();
+
+	out.family(
+		"tuwunel_federation_peer_backoff_seconds",
+		Kind::Gauge,
+		"Remaining backoff before a failing peer is retried.",
+	);
+	for (server, backoff) in &backoffs {
+		let remaining = backoff
+			.anchor_secs
+			.saturating_add(backoff.delay_secs)
+			.saturating_sub(now);
+
+		out.gauge(
+			"tuwunel_federation_peer_backoff_seconds",
+			&[("server", server.as_str())],
+			remaining,
+		);
+	}
+
+	out.family(
+		"tuwunel_federation_peers_failing",
+		Kind::Gauge,
+		"Peers with a current failure streak.",
+	)
+	.gauge("tuwunel_federation_peers_failing", &[], backoffs.len());
+}
+
+async fn write_sync(services: &Services, out: &mut Exposition) {
+	let connections = services.sync.loaded_connection_count().await;
+
+	out.family(
+		"tuwunel_sync_connections",
+		Kind::Gauge,
+		"Sliding sync connections held in memory.",
+	)
+	.gauge("tuwunel_sync_connections", &[], connections);
+}
+
+fn depths_by_kind(depths: &BTreeMap<Destination, usize>) -> [(&'static str, usize); 3] {
+	let mut kinds = [("appservice", 0), ("federation", 0), ("push", 0)];
+	for (dest, depth) in depths {
+		let slot = match dest {
+			| Destination::Appservice(_) => &mut kinds[0].1,
+			| Destination::Federation(_) => &mut kinds[1].1,
+			| Destination::Push(..) => &mut kinds[2].1,
+		};
+
+		*slot = slot.saturating_add(*depth);
+	}
+
+	kinds
+}
@@ -7,6 +7,7 @@
 pub(super) mod capabilities;
 pub(super) mod context;
 pub(super) mod dehydrated_device;
+pub(super) mod delayed_events;
 pub(super) mod device;
 pub(super) mod directory;
 pub(super) mod events;
@@ -16,6 +17,7 @@
 pub(super) mod media_legacy;
 pub(super) mod membership;
 pub(super) mod message;
+pub(super) mod metrics;
 pub(super) mod openid;
 pub(super) mod presence;
 pub(super) mod profile;
@@ -36,6 +38,7 @@
 pub(super) mod sync;
 pub(super) mod tag;
 pub(super) mod thirdparty;
+pub(super) mod thread_subscriptions;
 pub(super) mod threads;
 pub(super) mod to_device;
 pub(super) mod tuwunel;
@@ -57,6 +60,7 @@
 pub(super) use capabilities::*;
 pub(super) use context::*;
 pub(super) use dehydrated_device::*;
+pub(super) use delayed_events::*;
 pub(super) use device::*;
 pub(super) use directory::*;
 pub(super) use events::*;
@@ -66,6 +70,7 @@
 pub(super) use media_legacy::*;
 pub(super) use membership::*;
 pub(super) use message::*;
+pub(super) use metrics::*;
 pub(super) use openid::*;
 pub(super) use presence::*;
 pub(super) use profile::*;
@@ -86,6 +91,7 @@
 pub(super) use sync::*;
 pub(super) use tag::*;
 pub(super) use thirdparty::*;
+pub(super) use thread_subscriptions::*;
 pub(super) use threads::*;
 pub(super) use to_device::*;
 pub(super) use tuwunel::*;
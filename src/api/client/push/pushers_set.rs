@@ -8,6 +8,8 @@
 ///
 /// Adds a pusher for the sender user.
 ///
+/// - Email pushers require a configured SMTP relay and a pushkey naming an
+///   email address bound to the account
 /// - TODO: Handle `append`
 pub(crate) async fn set_pushers_route(
 	State(services): State<crate::State>,
@@ -6,7 +6,9 @@
 		GlobalAccountDataEventType,
 		push_rules::{PushRulesEvent, PushRulesEventContent},
 	},
-	push::{PredefinedContentRuleId, PredefinedOverrideRuleId, Ruleset},
+	push::{
+		PredefinedContentRuleId, PredefinedOverrideRuleId, PredefinedPostContentRuleId, Ruleset,
+	},
 };
 use tuwunel_core::{Result, err};
 use tuwunel_service::Services;
@@ -43,7 +45,8 @@ pub(crate) async fn get_pushrules_all_route(
 	let mut global_ruleset = account_data_content.global;
 
 	// remove old deprecated mentions push rules as per MSC4210
-	// and update the stored server default push rules
+	// and update the stored server default push rules, adding the MSC4306
+	// thread subscription rules to rulesets stored before them
 	#[expect(deprecated)]
 	{
 		use ruma::push::RuleKind::*;
@@ -59,6 +62,9 @@ pub(crate) async fn get_pushrules_all_route(
 			|| global_ruleset
 				.get(Override, PredefinedOverrideRuleId::Reply.as_str())
 				.is_none()
+			|| global_ruleset
+				.get(PostContent, PredefinedPostContentRuleId::UnsubscribedThread.as_str())
+				.is_none()
 		{
 			global_ruleset
 				.remove(Override, PredefinedOverrideRuleId::ContainsDisplayName)
@@ -34,22 +34,24 @@ pub(crate) async fn redact_event_route(
 		return Err!(Request(UserSuspended("Account is suspended.")));
 	}
 
+	let pdu_builder = PduBuilder {
+		redacts: Some(body.event_id.clone()),
+		..PduBuilder::timeline(&RoomRedactionEventContent {
+			redacts: Some(body.event_id.clone()),
+			reason: body.reason.clone(),
+		})
+	};
+
+	services
+		.timeline
+		.check_pdu_for_spam(&pdu_builder, sender_user, &body.room_id)
+		.await?;
+
 	let state_lock = services.state.mutex.lock(&body.room_id).await;
 
 	let event_id = services
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder {
-				redacts: Some(body.event_id.clone()),
-				..PduBuilder::timeline(&RoomRedactionEventContent {
-					redacts: Some(body.event_id.clone()),
-					reason: body.reason.clone(),
-				})
-			},
-			sender_user,
-			&body.room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(pdu_builder, sender_user, &body.room_id, &state_lock)
 		.await?;
 
 	drop(state_lock);
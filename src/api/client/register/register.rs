@@ -68,6 +68,13 @@ pub(crate) async fn register_route(
 
 	check_appservice_namespace(services, &body, &user_id, emergency_mode_enabled).await?;
 
+	if body.appservice_info.is_none() {
+		services
+			.spam_checker
+			.check_registration_for_spam(&user_id, Some(client))
+			.await?;
+	}
+
 	let email_association = enforce_uiaa(services, &body, is_guest).await?;
 
 	let password = if is_guest { None } else { body.password.as_deref() };
@@ -1,7 +1,14 @@
 use axum::extract::State;
 use ruma::{EventId, RoomId, UserId, api::client::room::report_content};
-use tuwunel_core::{Err, Result, debug_info, info, matrix::pdu::PduEvent, utils::ReadyExt};
-use tuwunel_service::Services;
+use tuwunel_core::{
+	Err, Result, debug_info, info,
+	matrix::pdu::PduEvent,
+	utils::{ReadyExt, time::now_millis},
+};
+use tuwunel_service::{
+	Services,
+	reports::{Kind, Report},
+};
 
 use super::REASON_MAX_LEN;
 use crate::{ClientIp, Ruma};
@@ -39,11 +46,22 @@ pub(crate) async fn report_event_route(
 	)
 	.await?;
 
+	let report_id = services.reports.add(&Report {
+		kind: Kind::Event,
+		reporter: sender_user.to_owned(),
+		room_id: Some(pdu.room_id.clone()),
+		event_id: Some(pdu.event_id.clone()),
+		user_id: Some(pdu.sender.clone()),
+		reason: body.reason.clone(),
+		received_ts: now_millis(),
+		resolved: None,
+	});
+
 	services
 		.admin
 		.send_report(&format!(
-			"@room Event report received from {}\nReport Reason: {}\n\nEvent ID: {}\nRoom ID: \
-			 {}\nSent By: {}",
+			"@room Event report {report_id} received from {}\nReport Reason: {}\n\nEvent ID: \
+			 {}\nRoom ID: {}\nSent By: {}",
 			sender_user, reason, pdu.event_id, pdu.room_id, pdu.sender,
 		))
 		.await;
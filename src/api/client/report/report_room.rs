@@ -1,6 +1,7 @@
 use axum::extract::State;
 use ruma::api::client::room::report_room;
-use tuwunel_core::{Err, Result, info};
+use tuwunel_core::{Err, Result, info, utils::time::now_millis};
+use tuwunel_service::reports::{Kind, Report};
 
 use super::REASON_MAX_LEN;
 use crate::{ClientIp, Ruma};
@@ -37,10 +38,21 @@ pub(crate) async fn report_room_route(
 		)));
 	}
 
+	let report_id = services.reports.add(&Report {
+		kind: Kind::Room,
+		reporter: sender_user.to_owned(),
+		room_id: Some(body.room_id.clone()),
+		event_id: None,
+		user_id: None,
+		reason: Some(body.reason.clone()),
+		received_ts: now_millis(),
+		resolved: None,
+	});
+
 	services
 		.admin
 		.send_report(&format!(
-			"@room Room report received from {}\nReport Reason: {}\n\nRoom ID: {}",
+			"@room Room report {report_id} received from {}\nReport Reason: {}\n\nRoom ID: {}",
 			sender_user, body.reason, body.room_id,
 		))
 		.await;
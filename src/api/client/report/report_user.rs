@@ -1,6 +1,7 @@
 use axum::extract::State;
 use ruma::api::client::reporting::report_user;
-use tuwunel_core::{Err, Result, info};
+use tuwunel_core::{Err, Result, info, utils::time::now_millis};
+use tuwunel_service::reports::{Kind, Report};
 
 use super::REASON_MAX_LEN;
 use crate::{ClientIp, Ruma};
@@ -28,10 +29,21 @@ pub(crate) async fn report_user_route(
 
 	// Succeed regardless of user existence to deter enumeration (MSC4277).
 	if services.users.is_active_local(target_user).await {
+		let report_id = services.reports.add(&Report {
+			kind: Kind::User,
+			reporter: sender_user.to_owned(),
+			room_id: None,
+			event_id: None,
+			user_id: Some(target_user.clone()),
+			reason: Some(reason.clone()),
+			received_ts: now_millis(),
+			resolved: None,
+		});
+
 		services
 			.admin
 			.send_report(&format!(
-				"@room User report received from {sender_user}\nReport Reason: \
+				"@room User report {report_id} received from {sender_user}\nReport Reason: \
 				 {reason}\n\nReported User ID: {target_user}",
 			))
 			.await;
@@ -53,6 +53,16 @@ pub(crate) async fn create_room_route(
 	body: Ruma<create_room::v3::Request>,
 ) -> Result<create_room::v3::Response> {
 	can_create_room_check(&services, &body).await?;
+	services
+		.spam_checker
+		.user_may_create_room(body.sender_user())
+		.await?;
+
+	services
+		.policy_lists
+		.check_user("create room", body.sender_user())
+		.await?;
+
 	can_publish_directory_check(&services, &body).await?;
 
 	// Figure out preset. We need it for preset specific events
@@ -907,6 +917,11 @@ async fn room_alias_check(
 		return Err!(Request(RoomInUse("Room alias already exists.")));
 	}
 
+	services
+		.policy_lists
+		.check_room("create alias", full_room_alias.as_str())
+		.await?;
+
 	if let Some(info) = appservice_info {
 		if !info.aliases.is_match(full_room_alias.as_str()) {
 			return Err!(Request(Exclusive("Room alias is not in namespace.")));
@@ -108,6 +108,26 @@ pub(crate) async fn send_message_event_route(
 		}
 	}
 
+	let mut unsigned = BTreeMap::new();
+	unsigned.insert("transaction_id".to_owned(), body.txn_id.to_string().into());
+
+	let content = from_str(body.body.body.json().get())
+		.map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))?;
+
+	let pdu_builder = PduBuilder {
+		event_type: body.event_type.clone().into(),
+		content,
+		unsigned: Some(unsigned),
+		timestamp: appservice_info.and(body.timestamp),
+		redacts: redacts_id,
+		..Default::default()
+	};
+
+	services
+		.timeline
+		.check_pdu_for_spam(&pdu_builder, sender_user, &body.room_id)
+		.await?;
+
 	let state_lock = services.state.mutex.lock(&body.room_id).await;
 
 	let (existing_txnid, ..) = try_join4(
@@ -122,27 +142,9 @@ pub(crate) async fn send_message_event_route(
 		return existing_txnid;
 	}
 
-	let mut unsigned = BTreeMap::new();
-	unsigned.insert("transaction_id".to_owned(), body.txn_id.to_string().into());
-
-	let content = from_str(body.body.body.json().get())
-		.map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))?;
-
 	let event_id = services
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder {
-				event_type: body.event_type.clone().into(),
-				content,
-				unsigned: Some(unsigned),
-				timestamp: appservice_info.and(body.timestamp),
-				redacts: redacts_id,
-				..Default::default()
-			},
-			sender_user,
-			&body.room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(pdu_builder, sender_user, &body.room_id, &state_lock)
 		.await?;
 
 	services.transaction_ids.add_txnid(
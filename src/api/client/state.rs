@@ -34,7 +34,8 @@
 
 /// # `PUT /_matrix/client/*/rooms/{roomId}/state/{eventType}/{stateKey}`
 ///
-/// Sends a state event into the room.
+/// Sends a state event into the room. Also serves the path without a state
+/// key, which addresses the empty state key.
 pub(crate) async fn send_state_event_for_key_route(
 	State(services): State<crate::State>,
 	body: Ruma<send_state_event::v3::Request>,
@@ -59,19 +60,6 @@ pub(crate) async fn send_state_event_for_key_route(
 	})
 }
 
-/// # `PUT /_matrix/client/*/rooms/{roomId}/state/{eventType}`
-///
-/// Sends a state event into the room.
-pub(crate) async fn send_state_event_for_empty_key_route(
-	State(services): State<crate::State>,
-	body: Ruma<send_state_event::v3::Request>,
-) -> Result<RumaResponse<send_state_event::v3::Response>> {
-	send_state_event_for_key_route(State(services), body)
-		.boxed()
-		.await
-		.map(RumaResponse)
-}
-
 /// # `GET /_matrix/client/v3/rooms/{roomid}/state`
 ///
 /// Get all state events for a room.
@@ -194,6 +182,20 @@ async fn send_state_event_for_key_helper(
 	timestamp: Option<MilliSecondsSinceUnixEpoch>,
 ) -> Result<OwnedEventId> {
 	allowed_to_send_state_event(services, sender, room_id, event_type, state_key, json).await?;
+
+	let pdu_builder = PduBuilder {
+		event_type: event_type.to_string().into(),
+		content: serde_json::from_str(json.json().get())?,
+		state_key: Some(state_key.into()),
+		timestamp,
+		..Default::default()
+	};
+
+	services
+		.timeline
+		.check_pdu_for_spam(&pdu_builder, sender, room_id)
+		.await?;
+
 	let state_lock = services.state.mutex.lock(room_id).await;
 
 	let current = match state_dedup_eligible(event_type, timestamp.as_ref()) {
@@ -223,18 +225,7 @@ async fn send_state_event_for_key_helper(
 
 	let event_id = services
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder {
-				event_type: event_type.to_string().into(),
-				content: serde_json::from_str(json.json().get())?,
-				state_key: Some(state_key.into()),
-				timestamp,
-				..Default::default()
-			},
-			sender,
-			room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(pdu_builder, sender, room_id, &state_lock)
 		.boxed()
 		.await?;
 
@@ -272,7 +263,7 @@ fn is_duplicate_state(
 	Ok(current_content == *content)
 }
 
-async fn allowed_to_send_state_event(
+pub(super) async fn allowed_to_send_state_event(
 	services: &Services,
 	sender: &UserId,
 	room_id: &RoomId,
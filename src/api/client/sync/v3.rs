@@ -1056,10 +1056,26 @@ async fn load_joined_room(
 	filter: &FilterDefinition,
 ) -> Result<(JoinedRoom, HashSet<OwnedUserId>, HashSet<OwnedUserId>)> {
 	let initial = since == 0;
+
+	// MSC3706: withhold a partial-state room from clients which do not
+	// lazy-load members; it is delivered in full once the resync completes.
+	let (partial_state, full_state_count) = join(
+		services.partial_state.is_partial(room_id),
+		services.partial_state.full_state_count(room_id),
+	)
+	.await;
+
+	if partial_state && !filter.room.state.lazy_load_options.is_enabled() {
+		return Ok((JoinedRoom::default(), HashSet::new(), HashSet::new()));
+	}
+
+	let resynced =
+		!initial && full_state_count.is_some_and(|count| count > since && count <= next_batch);
+
 	let (timeline_pdus, limited, last_timeline_count) =
 		load_join_timeline(services, sender_user, room_id, since, next_batch, filter).await?;
 
-	let timeline_changed = last_timeline_count.into_unsigned() > since;
+	let timeline_changed = last_timeline_count.into_unsigned() > since || resynced;
 	debug_assert!(
 		timeline_pdus.is_empty() || timeline_changed,
 		"if timeline events, last_timeline_count must be in the since window."
@@ -1081,6 +1097,7 @@ async fn load_joined_room(
 		&timeline_pdus,
 		last_timeline_count,
 		timeline_changed,
+		resynced,
 		state_after,
 	)
 	.boxed()
@@ -1109,6 +1126,9 @@ async fn load_joined_room(
 	.boxed()
 	.await;
 
+	// The resynced state reaches the client as if the room were newly joined.
+	let joined_since_last_sync = joined_since_last_sync || resynced;
+
 	let (
 		state_after,
 		StateChanges {
@@ -1610,6 +1630,7 @@ async fn gather_room_metadata(
 	timeline_pdus: &[(PduCount, PduEvent)],
 	last_timeline_count: PduCount,
 	timeline_changed: bool,
+	resynced: bool,
 	state_after: StateAfter,
 ) -> Result<RoomMetadata> {
 	let since_shortstatehash = timeline_changed.then_async(|| {
@@ -1641,13 +1662,23 @@ async fn gather_room_metadata(
 			.inspect_err(inspect_debug_log)
 	});
 
-	let current_shortstatehash = timeline_changed.then_async(|| {
+	// A resynced faster join installs room state newer than its last event's.
+	let current_shortstatehash = timeline_changed.then_async(|| async move {
+		if !resynced
+			&& let Ok(shortstatehash) = services
+				.timeline
+				.get_shortstatehash(room_id, last_timeline_count)
+				.inspect_err(inspect_debug_log)
+				.await
+		{
+			return Ok(shortstatehash);
+		}
+
 		services
-			.timeline
-			.get_shortstatehash(room_id, last_timeline_count)
-			.inspect_err(inspect_debug_log)
-			.or_else(|_| services.state.get_room_shortstatehash(room_id))
+			.state
+			.get_room_shortstatehash(room_id)
 			.map_err(|_| err!(Database(error!("Room {room_id} has no state"))))
+			.await
 	});
 
 	let encrypted_room =
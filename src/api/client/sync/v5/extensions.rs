@@ -1,12 +1,13 @@
 mod account_data;
 mod e2ee;
 mod receipts;
+mod thread_subscriptions;
 mod to_device;
 mod typing;
 
 use std::{collections::BTreeMap, fmt::Debug};
 
-use futures::{FutureExt, future::join4};
+use futures::{FutureExt, future::join5};
 use ruma::{
 	OwnedRoomId, RoomId,
 	api::client::sync::sync_events::v5::{
@@ -85,9 +86,17 @@ pub(super) async fn handle(
 		.unwrap_or(false)
 		.then_async(|| e2ee::collect(sync_info, conn));
 
-	let (account_data, typing, to_device, e2ee) = join4(account_data, typing, to_device, e2ee)
-		.map(apply!(4, |t: Option<_>| t.unwrap_or(Ok(Default::default()))))
-		.await;
+	let thread_subscriptions = conn
+		.extensions
+		.thread_subscriptions
+		.enabled
+		.unwrap_or(false)
+		.then_async(|| thread_subscriptions::collect(sync_info, conn));
+
+	let (account_data, typing, to_device, e2ee, thread_subscriptions) =
+		join5(account_data, typing, to_device, e2ee, thread_subscriptions)
+			.map(apply!(5, |t: Option<_>| t.unwrap_or(Ok(Default::default()))))
+			.await;
 
 	// Receipt and room account-data payloads only exist as bounded room-range
 	// outputs, applied by `apply_ranges` after the ranges resolve.
@@ -97,6 +106,7 @@ pub(super) async fn handle(
 		typing: Default::default(),
 		to_device: to_device?,
 		e2ee: e2ee?,
+		thread_subscriptions: thread_subscriptions?,
 	};
 
 	Ok(Collected { response, typing: typing? })
@@ -0,0 +1,53 @@
+use futures::StreamExt;
+use ruma::api::client::sync::sync_events::v5::response;
+use tuwunel_core::Result;
+
+use super::{Connection, SyncInfo};
+use crate::client::thread_subscriptions::group_changes;
+
+/// Changes served when the client gives no limit.
+const LIMIT_DEFAULT: usize = 100;
+
+/// MSC4308: the latest thread subscription changes since the last response.
+/// Older changes in the same range are left to the changes endpoint, from
+/// `prev_batch` back to the request's `pos`.
+#[tracing::instrument(
+	name = "thread_subscriptions",
+	level = "trace",
+	skip_all,
+	ret
+)]
+pub(super) async fn collect(
+	SyncInfo { services, sender_user, .. }: SyncInfo<'_>,
+	conn: &Connection,
+) -> Result<response::ThreadSubscriptions> {
+	let limit = conn
+		.extensions
+		.thread_subscriptions
+		.limit
+		.and_then(|limit| usize::try_from(limit).ok())
+		.unwrap_or(LIMIT_DEFAULT);
+
+	let mut changes: Vec<_> = services
+		.threads
+		.subscription_changes_rev(
+			sender_user,
+			conn.globalsince,
+			conn.next_batch.saturating_add(1),
+		)
+		.take(limit.saturating_add(1))
+		.collect()
+		.await;
+
+	let more = changes.len() > limit;
+	changes.truncate(limit);
+
+	let prev_batch = changes
+		.last()
+		.filter(|_| more)
+		.map(|(count, _)| count.to_string());
+
+	let (subscribed, unsubscribed) = group_changes(changes);
+
+	Ok(response::ThreadSubscriptions { subscribed, unsubscribed, prev_batch })
+}
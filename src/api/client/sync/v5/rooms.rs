@@ -240,7 +240,7 @@ async fn leave_or_ban_response(
 	})
 }
 
-fn merged_room_details(
+pub(super) fn merged_room_details(
 	conn: &Connection,
 	lists: &ListIds,
 	room_id: &RoomId,
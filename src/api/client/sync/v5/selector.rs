@@ -5,8 +5,10 @@
 	future::{join, join3},
 };
 use ruma::{
-	OwnedRoomId, RoomId, UInt, api::client::sync::sync_events::v5::ListId,
-	events::room::member::MembershipState, uint,
+	OwnedRoomId, RoomId, UInt,
+	api::client::sync::sync_events::v5::ListId,
+	events::{StateEventType, room::member::MembershipState},
+	uint,
 };
 use tuwunel_core::{
 	Result, apply, debug_error, is_true,
@@ -24,6 +26,7 @@
 use super::{
 	ListIds, ResponseLists, SyncInfo, Window, WindowRoom,
 	filter::{filter_room, filter_room_meta},
+	rooms::merged_room_details,
 };
 
 #[tracing::instrument(level = "debug", skip_all)]
@@ -121,6 +124,15 @@ async fn matcher(
 		return None;
 	}
 
+	// MSC3706: a partial-state room is only offered to lists which lazy-load
+	// members; others see it once its full state has been resynced.
+	if matches!(membership, Some(MembershipState::Join))
+		&& !lazy_members(conn, &lists, &room_id)
+		&& services.partial_state.is_partial(&room_id).await
+	{
+		return None;
+	}
+
 	let membership_only = membership_only(membership.as_ref());
 
 	let last_notification = async {
@@ -152,12 +164,18 @@ async fn matcher(
 
 	let last_membership = async {
 		let result = match &membership {
-			| Some(MembershipState::Join) => Some(
-				services
-					.state_cache
-					.get_joined_count(&room_id, sender_user)
-					.await,
-			),
+			| Some(MembershipState::Join) => {
+				let (joined_count, full_state_count) = join(
+					services
+						.state_cache
+						.get_joined_count(&room_id, sender_user),
+					services.partial_state.full_state_count(&room_id),
+				)
+				.await;
+
+				// A completed partial-state resync refreshes the room like a join.
+				Some(joined_count.map(|count| count.max(full_state_count.unwrap_or_default())))
+			},
 			| Some(MembershipState::Invite) => Some(
 				services
 					.state_cache
@@ -206,6 +224,12 @@ async fn matcher(
 	})
 }
 
+fn lazy_members(conn: &Connection, lists: &ListIds, room_id: &RoomId) -> bool {
+	let (_, required_state) = merged_room_details(conn, lists, room_id);
+
+	required_state.contains(&(StateEventType::RoomMember, "$LAZY".into()))
+}
+
 #[derive(Copy, Clone, Default)]
 struct ActivityProbe {
 	count: Option<u64>,
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use axum::extract::State;
+use futures::StreamExt;
+use ruma::{
+	EventId, OwnedEventId, OwnedRoomId, RoomId, UInt, UserId,
+	api::{
+		Direction,
+		client::threads::{
+			get_thread_subscription, get_thread_subscriptions_changes,
+			get_thread_subscriptions_changes::unstable::{
+				ThreadSubscription, ThreadUnsubscription,
+			},
+			subscribe_thread, unsubscribe_thread,
+		},
+	},
+};
+use tuwunel_core::{Err, Result, err, matrix::Event};
+use tuwunel_service::{Services, rooms::threads::SubscriptionChange};
+
+use crate::Ruma;
+
+/// Subscriptions and unsubscriptions by room and thread root.
+pub(crate) type Subscribed = BTreeMap<OwnedRoomId, BTreeMap<OwnedEventId, ThreadSubscription>>;
+pub(crate) type Unsubscribed =
+	BTreeMap<OwnedRoomId, BTreeMap<OwnedEventId, ThreadUnsubscription>>;
+
+/// Changes served by one request when the client gives no limit.
+const CHANGES_LIMIT_DEFAULT: usize = 100;
+
+const CHANGES_LIMIT_MAX: usize = 1000;
+
+/// # `PUT /_matrix/client/unstable/io.element.msc4306/rooms/{roomId}/thread/{threadRootId}/subscription`
+///
+/// Subscribes to a thread, automatically when `automatic` names the event
+/// the subscription is made on behalf of.
+pub(crate) async fn subscribe_thread_route(
+	State(services): State<crate::State>,
+	body: Ruma<subscribe_thread::unstable::Request>,
+) -> Result<subscribe_thread::unstable::Response> {
+	let sender_user = body.sender_user();
+
+	check_thread_root(&services, sender_user, &body.room_id, &body.thread_root).await?;
+
+	if let Some(automatic) = body.automatic.as_deref() {
+		let in_thread = services
+			.threads
+			.get_thread_id_for_event(automatic)
+			.await
+			.is_some_and(|thread_root| thread_root == body.thread_root);
+
+		if !in_thread && body.thread_root != automatic {
+			return Err!(Request(InvalidParam("The automatic event is not in this thread.")));
+		}
+	}
+
+	services
+		.threads
+		.subscribe(sender_user, &body.room_id, &body.thread_root, body.automatic.as_deref())
+		.await?;
+
+	Ok(subscribe_thread::unstable::Response {})
+}
+
+/// # `GET /_matrix/client/unstable/io.element.msc4306/rooms/{roomId}/thread/{threadRootId}/subscription`
+pub(crate) async fn get_thread_subscription_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_thread_subscription::unstable::Request>,
+) -> Result<get_thread_subscription::unstable::Response> {
+	let sender_user = body.sender_user();
+
+	check_thread_root(&services, sender_user, &body.room_id, &body.thread_root).await?;
+
+	let subscription = services
+		.threads
+		.get_subscription(sender_user, &body.room_id, &body.thread_root)
+		.await
+		.filter(|subscription| subscription.subscribed)
+		.ok_or_else(|| err!(Request(NotFound("Not subscribed to this thread."))))?;
+
+	Ok(get_thread_subscription::unstable::Response { automatic: subscription.automatic })
+}
+
+/// # `DELETE /_matrix/client/unstable/io.element.msc4306/rooms/{roomId}/thread/{threadRootId}/subscription`
+pub(crate) async fn unsubscribe_thread_route(
+	State(services): State<crate::State>,
+	body: Ruma<unsubscribe_thread::unstable::Request>,
+) -> Result<unsubscribe_thread::unstable::Response> {
+	let sender_user = body.sender_user();
+
+	check_thread_root(&services, sender_user, &body.room_id, &body.thread_root).await?;
+
+	services
+		.threads
+		.unsubscribe(sender_user, &body.room_id, &body.thread_root)
+		.await?;
+
+	Ok(unsubscribe_thread::unstable::Response {})
+}
+
+/// # `GET /_matrix/client/unstable/io.element.msc4308/thread_subscriptions`
+///
+/// Pages through the user's thread subscription changes. Tokens are stream
+/// positions, exclusive at both ends.
+pub(crate) async fn get_thread_subscriptions_changes_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_thread_subscriptions_changes::unstable::Request>,
+) -> Result<get_thread_subscriptions_changes::unstable::Response> {
+	let sender_user = body.sender_user();
+	let from = body
+		.from
+		.as_deref()
+		.map(str::parse::<u64>)
+		.transpose()
+		.map_err(|e| err!(Request(InvalidParam("Invalid `from` token: {e}"))))?;
+
+	let to = body
+		.to
+		.as_deref()
+		.map(str::parse::<u64>)
+		.transpose()
+		.map_err(|e| err!(Request(InvalidParam("Invalid `to` token: {e}"))))?;
+
+	let limit = body
+		.limit
+		.and_then(|limit| usize::try_from(limit).ok())
+		.unwrap_or(CHANGES_LIMIT_DEFAULT)
+		.clamp(1, CHANGES_LIMIT_MAX);
+
+	// One extra change probes whether the range continues past this page.
+	let mut changes: Vec<_> = match body.dir {
+		| Direction::Backward =>
+			services
+				.threads
+				.subscription_changes_rev(sender_user, to.unwrap_or(0), from.unwrap_or(u64::MAX))
+				.take(limit.saturating_add(1))
+				.collect()
+				.await,
+		| Direction::Forward =>
+			services
+				.threads
+				.subscription_changes(
+					sender_user,
+					from.unwrap_or(0),
+					to.map_or(u64::MAX, |to| to.saturating_sub(1)),
+				)
+				.take(limit.saturating_add(1))
+				.collect()
+				.await,
+	};
+
+	let more = changes.len() > limit;
+	changes.truncate(limit);
+
+	let end = changes
+		.last()
+		.filter(|_| more)
+		.map(|(count, _)| count.to_string());
+
+	let (subscribed, unsubscribed) = group_changes(changes);
+
+	Ok(get_thread_subscriptions_changes::unstable::Response { subscribed, unsubscribed, end })
+}
+
+/// Sorts changes into subscriptions and unsubscriptions.
+pub(crate) fn group_changes<I>(changes: I) -> (Subscribed, Unsubscribed)
+where
+	I: IntoIterator<Item = (u64, SubscriptionChange)>,
+{
+	let mut subscribed = Subscribed::new();
+	let mut unsubscribed = Unsubscribed::new();
+	for (count, change) in changes {
+		let bump_stamp = UInt::new_saturating(count);
+		if change.subscribed {
+			subscribed
+				.entry(change.room_id)
+				.or_default()
+				.insert(change.thread_root, ThreadSubscription {
+					automatic: change.automatic,
+					bump_stamp,
+				});
+		} else {
+			unsubscribed
+				.entry(change.room_id)
+				.or_default()
+				.insert(change.thread_root, ThreadUnsubscription { bump_stamp });
+		}
+	}
+
+	(subscribed, unsubscribed)
+}
+
+/// Fails unless the thread root is an event of the room the user may see.
+async fn check_thread_root(
+	services: &Services,
+	sender_user: &UserId,
+	room_id: &RoomId,
+	thread_root: &EventId,
+) -> Result {
+	let in_room = services
+		.timeline
+		.get_pdu(thread_root)
+		.await
+		.is_ok_and(|pdu| pdu.room_id() == room_id);
+
+	if !in_room
+		|| !services
+			.state_accessor
+			.user_can_see_event(sender_user, room_id, thread_root)
+			.await
+	{
+		return Err!(Request(NotFound("Thread root not found.")));
+	}
+
+	Ok(())
+}
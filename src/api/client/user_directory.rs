@@ -1,18 +1,7 @@
 use axum::extract::State;
-use futures::{FutureExt, StreamExt, pin_mut};
-use ruma::{
-	UserId,
-	api::client::user_directory::search_users::{self},
-	events::room::join_rules::JoinRule,
-};
-use tuwunel_core::{
-	Result,
-	utils::{
-		BoolExt, FutureBoolExt,
-		stream::{BroadbandExt, ReadyExt},
-	},
-};
-use tuwunel_service::Services;
+use futures::{StreamExt, future::join};
+use ruma::api::client::user_directory::search_users;
+use tuwunel_core::{Result, utils::stream::IterStream};
 
 use crate::Ruma;
 
@@ -22,10 +11,12 @@
 
 /// # `POST /_matrix/client/r0/user_directory/search`
 ///
-/// Searches all known users for a match.
+/// Searches the user directory for users whose localpart or displayname
+/// words start with each word of the search term.
 ///
-/// - Hides any local users that aren't in any public rooms (i.e. those that
-///   have the join rule set to public) and don't share a room with the sender
+/// - Ranks users sharing a room with the sender first, then local users
+/// - Hides any users that aren't in any public rooms (i.e. those that have the
+///   join rule set to public) and don't share a room with the sender
 /// - Hides appservice senders and users in exclusive appservice user namespaces
 pub(crate) async fn search_users_route(
 	State(services): State<crate::State>,
@@ -36,90 +27,29 @@ pub(crate) async fn search_users_route(
 		.unwrap_or(LIMIT_DEFAULT)
 		.min(LIMIT_MAX);
 
-	let search_term = body.search_term.to_lowercase();
-	let users = services
-		.users
-		.stream()
-		.ready_filter(|&user_id| user_id != sender_user)
-		.map(ToOwned::to_owned)
-		.broad_filter_map(async |user_id| {
-			let display_name = services.profile.displayname(&user_id).await.ok();
+	let (user_ids, limited) = services
+		.user_directory
+		.search(sender_user, &body.search_term, limit)
+		.await;
 
-			should_show_user(
-				&services,
-				sender_user,
-				&user_id,
-				display_name.as_deref(),
-				&search_term,
+	let results = user_ids
+		.into_iter()
+		.stream()
+		.then(async |user_id| {
+			let (display_name, avatar_url) = join(
+				services.profile.displayname(&user_id),
+				services.profile.avatar_url(&user_id),
 			)
-			.await
-			.then_async(async || search_users::v3::User {
-				user_id: user_id.clone(),
-				display_name,
-				avatar_url: services.profile.avatar_url(&user_id).await.ok(),
-			})
-			.await
-		});
+			.await;
 
-	pin_mut!(users);
-	let results = users.by_ref().take(limit).collect().await;
-	let limited = users.next().await.is_some();
+			search_users::v3::User {
+				user_id,
+				display_name: display_name.ok(),
+				avatar_url: avatar_url.ok(),
+			}
+		})
+		.collect()
+		.await;
 
 	Ok(search_users::v3::Response { results, limited })
 }
-
-async fn should_show_user(
-	services: &Services,
-	sender_user: &UserId,
-	target_user: &UserId,
-	target_display_name: Option<&str>,
-	search_term: &str,
-) -> bool {
-	let user_id_matches = target_user
-		.as_str()
-		.to_lowercase()
-		.contains(search_term);
-
-	let display_name_matches = target_display_name
-		.map(str::to_lowercase)
-		.is_some_and(|display_name| display_name.contains(search_term));
-
-	if !user_id_matches && !display_name_matches {
-		return false;
-	}
-
-	if services
-		.appservice
-		.is_exclusive_user_id(target_user)
-		.await
-	{
-		return false;
-	}
-
-	if services
-		.server
-		.config
-		.show_all_local_users_in_user_directory
-	{
-		return true;
-	}
-
-	let user_in_public_room = services
-		.state_cache
-		.rooms_joined(target_user)
-		.map(ToOwned::to_owned)
-		.broad_any(async |room_id| {
-			services
-				.state_accessor
-				.get_join_rules(&room_id)
-				.map(|rule| matches!(rule, JoinRule::Public))
-				.await
-		});
-
-	let user_sees_user = services
-		.state_cache
-		.user_sees_user(sender_user, target_user);
-
-	pin_mut!(user_in_public_room, user_sees_user);
-	user_in_public_room.or(user_sees_user).await
-}
@@ -43,6 +43,13 @@ pub(crate) async fn get_supported_versions_route(
 					.rendezvous_enabled
 					.then_some("org.matrix.msc4108"),
 			)
+			.chain(
+				services
+					.config
+					.max_event_delay_s
+					.gt(&0)
+					.then_some("org.matrix.msc4140"),
+			)
 			.map(Into::into)
 			.zip(once(true).cycle())
 			.collect(),
@@ -81,7 +88,7 @@ pub(crate) async fn get_supported_versions_route(
 	"v1.19",  /* mutual rooms (MSC2666) */
 ];
 
-static UNSTABLE_FEATURES: [&str; 38] = [
+static UNSTABLE_FEATURES: [&str; 40] = [
 	"org.matrix.e2e_cross_signing",
 	// private read receipts (https://github.com/matrix-org/matrix-spec-proposals/pull/2285)
 	"org.matrix.msc2285.stable",
@@ -153,4 +160,8 @@ pub(crate) async fn get_supported_versions_route(
 	"org.matrix.msc3440.stable",
 	// state_after on /sync (https://github.com/matrix-org/matrix-spec-proposals/pull/4222)
 	"org.matrix.msc4222",
+	// Thread subscriptions (https://github.com/matrix-org/matrix-spec-proposals/pull/4306)
+	"org.matrix.msc4306",
+	// Thread subscriptions sliding sync extension (https://github.com/matrix-org/matrix-spec-proposals/pull/4308)
+	"org.matrix.msc4308",
 ];
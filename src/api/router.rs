@@ -2,6 +2,7 @@
 mod auth;
 mod client_ip;
 mod handler;
+mod ratelimit;
 mod request;
 mod response;
 pub mod state;
@@ -9,7 +10,7 @@
 use axum::{
 	Router,
 	response::IntoResponse,
-	routing::{any, get, post},
+	routing::{any, get, post, put},
 };
 pub use client_ip::{ConfiguredIpSource, TrustedPeerSubnets};
 use http::{HeaderValue, header};
@@ -201,6 +202,15 @@ fn register_synapse_admin_media_routes(router: Router<State>) -> Router<State> {
 		.ruma_route(&client::admin::media::admin_delete_user_media_route)
 		.ruma_route(&client::admin::media::admin_purge_media_cache_route)
 		.ruma_route(&client::admin::media::admin_user_media_statistics_route)
+		.ruma_route(&client::admin::media::admin_quarantine_media_route)
+		.ruma_route(&client::admin::media::admin_unquarantine_media_route)
+		.ruma_route(&client::admin::media::admin_quarantine_room_media_route)
+		.ruma_route(&client::admin::media::admin_quarantine_user_media_route)
+		.ruma_route(&client::admin::media::admin_protect_media_route)
+		.ruma_route(&client::admin::media::admin_unprotect_media_route)
+		.ruma_route(&client::reports::admin_list_event_reports_route)
+		.ruma_route(&client::reports::admin_event_report_details_route)
+		.ruma_route(&client::reports::admin_delete_event_report_route)
 		.route(
 			"/_synapse/admin/v1/media/delete",
 			post(client::admin::media::admin_delete_media_by_date_size_route),
@@ -338,8 +348,25 @@ fn register_client_room_routes(router: Router<State>) -> Router<State> {
 
 fn register_client_state_and_sync_routes(router: Router<State>) -> Router<State> {
 	router
-		.ruma_route(&client::send_message_event_route)
-		.ruma_route(&client::send_state_event_for_key_route)
+		// MSC4140 delayed events share the send paths but answer with a delay_id,
+		// so these are routed outside of Ruma and dispatch on the query.
+		.route(
+			"/_matrix/client/r0/rooms/{room_id}/send/{event_type}/{txn_id}",
+			put(client::send_message_or_delayed_event_route),
+		)
+		.route(
+			"/_matrix/client/v3/rooms/{room_id}/send/{event_type}/{txn_id}",
+			put(client::send_message_or_delayed_event_route),
+		)
+		.route(
+			"/_matrix/client/r0/rooms/{room_id}/state/{event_type}/{state_key}",
+			put(client::send_state_or_delayed_event_route),
+		)
+		.route(
+			"/_matrix/client/v3/rooms/{room_id}/state/{event_type}/{state_key}",
+			put(client::send_state_or_delayed_event_route),
+		)
+		.ruma_route(&client::update_delayed_event_route)
 		.ruma_route(&client::get_state_events_route)
 		.ruma_route(&client::get_state_events_for_key_route)
 		// Ruma doesn't have support for multiple paths for a single endpoint yet, and these
@@ -348,23 +375,23 @@ fn register_client_state_and_sync_routes(router: Router<State>) -> Router<State>
 		.route(
 			"/_matrix/client/r0/rooms/{room_id}/state/{event_type}",
 			get(client::get_state_events_for_empty_key_route)
-				.put(client::send_state_event_for_empty_key_route),
+				.put(client::send_state_or_delayed_event_route),
 		)
 		.route(
 			"/_matrix/client/v3/rooms/{room_id}/state/{event_type}",
 			get(client::get_state_events_for_empty_key_route)
-				.put(client::send_state_event_for_empty_key_route),
+				.put(client::send_state_or_delayed_event_route),
 		)
 		// These two endpoints allow trailing slashes
 		.route(
 			"/_matrix/client/r0/rooms/{room_id}/state/{event_type}/",
 			get(client::get_state_events_for_empty_key_route)
-				.put(client::send_state_event_for_empty_key_route),
+				.put(client::send_state_or_delayed_event_route),
 		)
 		.route(
 			"/_matrix/client/v3/rooms/{room_id}/state/{event_type}/",
 			get(client::get_state_events_for_empty_key_route)
-				.put(client::send_state_event_for_empty_key_route),
+				.put(client::send_state_or_delayed_event_route),
 		)
 		.ruma_route(&client::events_route)
 		.ruma_route(&client::sync_events_route)
@@ -374,6 +401,10 @@ fn register_client_state_and_sync_routes(router: Router<State>) -> Router<State>
 		.ruma_route(&client::get_message_events_route)
 		.ruma_route(&client::search_events_route)
 		.ruma_route(&client::get_threads_route)
+		.ruma_route(&client::subscribe_thread_route)
+		.ruma_route(&client::get_thread_subscription_route)
+		.ruma_route(&client::unsubscribe_thread_route)
+		.ruma_route(&client::get_thread_subscriptions_changes_route)
 		.ruma_route(&client::get_relating_events_with_rel_type_and_event_type_route)
 		.ruma_route(&client::get_relating_events_with_rel_type_route)
 		.ruma_route(&client::get_relating_events_route)
@@ -394,6 +425,11 @@ fn register_client_media_and_device_routes(router: Router<State>) -> Router<Stat
 		.ruma_route(&client::create_content_async_route)
 		.ruma_route(&client::get_media_preview_route)
 		.ruma_route(&client::get_media_config_route)
+		.ruma_route(&client::get_media_usage_route)
+		.ruma_route_at(
+			&client::get_media_usage_route,
+			"/_matrix/client/unstable/org.matrix.msc4034/media/usage",
+		)
 		.ruma_route(&client::get_devices_route)
 		.ruma_route(&client::get_device_route)
 		.ruma_route(&client::update_device_route)
@@ -415,6 +451,7 @@ fn register_client_misc_routes(router: Router<State>) -> Router<State> {
 		.ruma_route(&client::well_known_client)
 		.ruma_route(&client::tuwunel_remote_version)
 		.route("/_tuwunel/server_version", get(client::tuwunel_server_version))
+		.route("/_tuwunel/metrics", get(client::tuwunel_metrics_route))
 		.route(
 			"/_tuwunel/3pid/email/validate",
 			get(client::get_email_validate_route).post(client::post_email_validate_route),
@@ -493,6 +530,8 @@ fn register_federation_routes(router: Router<State>, allow_federation: bool) ->
 		router
 			.ruma_route(&server::get_server_version_route)
 			.route("/_matrix/key/v2/server", get(server::get_server_keys_route))
+			.ruma_route(&server::get_remote_server_keys_route)
+			.ruma_route(&server::get_remote_server_keys_batch_route)
 			.ruma_route(&server::get_public_rooms_route)
 			.ruma_route(&server::get_public_rooms_filtered_route)
 			.ruma_route(&server::send_transaction_message_route)
@@ -518,11 +557,13 @@ fn register_federation_routes(router: Router<State>, allow_federation: bool) ->
 			.ruma_route(&server::get_hierarchy_route)
 			.ruma_route(&server::get_content_route)
 			.ruma_route(&server::get_content_thumbnail_route)
+			.ruma_route(&server::policy_sign_route)
 			.route("/_tuwunel/local_user_count", get(client::tuwunel_local_user_count))
 	} else {
 		router
 			.route("/_matrix/federation/{*path}", any(federation_disabled))
 			.route("/_matrix/key/{*path}", any(federation_disabled))
+			.route("/_matrix/policy/{*path}", any(federation_disabled))
 			.route("/_tuwunel/local_user_count", any(federation_disabled))
 	}
 }
@@ -14,7 +14,7 @@
 use super::{
 	auth,
 	auth::{Auth, AuthDispatch},
-	request,
+	ratelimit, request,
 	request::Request,
 };
 use crate::State;
@@ -130,6 +130,8 @@ async fn from_request(
 		)
 		.await?;
 
+		ratelimit::check(services, &mut request, &auth, TypeId::of::<T>()).await?;
+
 		Ok(Self {
 			body: make_body::<T>(services, &mut request, json_body.as_mut(), &auth)?,
 			cookie: request.cookie,
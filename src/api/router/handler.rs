@@ -25,6 +25,12 @@ pub(in super::super) trait RouterExt {
 	fn ruma_route<H, T>(self, handler: &'static H) -> Self
 	where
 		H: RumaHandler<T>;
+
+	/// Routes an additional path the request's metadata does not name, such
+	/// as an unstable prefix ruma has no history for.
+	fn ruma_route_at<H, T>(self, handler: &'static H, path: &str) -> Self
+	where
+		H: RumaHandler<T>;
 }
 
 /// A route handler reduced to one fn-pointer shape: axum's generic routing
@@ -46,6 +52,13 @@ fn ruma_route<H, T>(self, handler: &'static H) -> Self
 	{
 		handler.add_routes(self)
 	}
+
+	fn ruma_route_at<H, T>(self, handler: &'static H, path: &str) -> Self
+	where
+		H: RumaHandler<T>,
+	{
+		handler.add_route(self, path)
+	}
 }
 
 impl Handler<(), State> for Route {
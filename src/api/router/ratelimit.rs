@@ -0,0 +1,81 @@
+use std::any::TypeId;
+
+use axum::RequestPartsExt;
+use ruma::api::client::{
+	account::register,
+	keys::claim_keys,
+	knock::knock_room,
+	membership::{invite_user, join_room_by_id, join_room_by_id_or_alias},
+	message::send_message_event,
+	redact::redact_event,
+	room::create_room,
+	session::login,
+	state::send_state_event,
+};
+use tuwunel_core::Result;
+use tuwunel_service::{
+	Services,
+	ratelimit::{Class, Key},
+};
+
+use super::{auth::Auth, client_ip::ClientIp, request::Request};
+
+/// Charges a throttled request to its sender, or to the client IP for an
+/// unauthenticated one. Appservices registered with `rate_limited: false`
+/// are exempt, as are routes outside every class.
+pub(super) async fn check(
+	services: &Services,
+	request: &mut Request,
+	auth: &Auth,
+	route: TypeId,
+) -> Result {
+	let Some(class) = class(route) else {
+		return Ok(());
+	};
+
+	let exempt = auth
+		.appservice_info
+		.as_ref()
+		.is_some_and(|info| info.registration.rate_limited == Some(false));
+
+	if exempt {
+		return Ok(());
+	}
+
+	let key = match auth.sender_user.as_ref() {
+		| Some(user_id) => Key::User(user_id.clone()),
+		| None => match request.parts.extract::<ClientIp>().await {
+			| Ok(ClientIp(client)) => Key::ip(client),
+			| Err(_) => return Ok(()),
+		},
+	};
+
+	services.ratelimit.check(class, key).await
+}
+
+fn class(route: TypeId) -> Option<Class> {
+	let is = |other: TypeId| route == other;
+
+	if is(TypeId::of::<send_message_event::v3::Request>())
+		|| is(TypeId::of::<send_state_event::v3::Request>())
+		|| is(TypeId::of::<redact_event::v3::Request>())
+		|| is(TypeId::of::<create_room::v3::Request>())
+	{
+		Some(Class::Message)
+	} else if is(TypeId::of::<join_room_by_id::v3::Request>())
+		|| is(TypeId::of::<join_room_by_id_or_alias::v3::Request>())
+		|| is(TypeId::of::<knock_room::v3::Request>())
+	{
+		Some(Class::Join)
+	} else if is(TypeId::of::<invite_user::v3::Request>()) {
+		Some(Class::Invite)
+	} else if is(TypeId::of::<login::v3::Request>()) {
+		Some(Class::Login)
+	} else if is(TypeId::of::<register::v3::Request>()) {
+		Some(Class::Registration)
+	} else if is(TypeId::of::<claim_keys::v3::Request>()) {
+		Some(Class::KeyClaim)
+	} else {
+		None
+	}
+}
@@ -61,6 +61,11 @@ pub(crate) async fn create_invite_route(
 
 	check_invite_permitted(&services, &body, &invited_user).await?;
 
+	services
+		.policy_lists
+		.check_invite(sender, &invited_user, &body.room_id)
+		.await?;
+
 	let pdu = build_pdu(&body)?;
 
 	let invite_state: Vec<_> = body
@@ -1,18 +1,23 @@
-use std::{
-	mem::take,
-	time::{Duration, SystemTime},
-};
+use std::{borrow::Borrow, iter::empty, mem::take};
 
 use axum::{Json, extract::State, response::IntoResponse};
+use futures::StreamExt;
 use ruma::{
-	MilliSecondsSinceUnixEpoch, Signatures,
+	MilliSecondsSinceUnixEpoch,
 	api::{
 		OutgoingResponse,
-		federation::discovery::{OldVerifyKey, ServerSigningKeys, get_server_keys},
+		federation::discovery::{
+			get_remote_server_keys, get_remote_server_keys_batch, get_server_keys,
+		},
 	},
 	serde::Raw,
 };
-use tuwunel_core::{Result, utils::timepoint_from_now};
+use tuwunel_core::{
+	Err, Result,
+	utils::stream::{BroadbandExt, IterStream},
+};
+
+use crate::Ruma;
 
 /// # `GET /_matrix/key/v2/server`
 ///
@@ -25,30 +30,7 @@
 pub(crate) async fn get_server_keys_route(
 	State(services): State<crate::State>,
 ) -> Result<impl IntoResponse> {
-	let server_name = services.globals.server_name();
-	let active_key_id = services.server_keys.active_key_id();
-	let mut all_keys = services
-		.server_keys
-		.verify_keys_for(server_name)
-		.await;
-
-	let verify_keys = all_keys
-		.remove_entry(active_key_id)
-		.expect("active verify_key is missing");
-
-	let old_verify_keys = all_keys
-		.into_iter()
-		.map(|(id, key)| (id, OldVerifyKey::new(expires_ts(), key.key)))
-		.collect();
-
-	let server_key = ServerSigningKeys {
-		verify_keys: [verify_keys].into(),
-		old_verify_keys,
-		server_name: server_name.to_owned(),
-		valid_until_ts: valid_until_ts(),
-		signatures: Signatures::new(),
-	};
-
+	let server_key = services.server_keys.local_server_keys().await;
 	let server_key = Raw::new(&server_key)?;
 	let mut response = get_server_keys::v2::Response::new(server_key)
 		.try_into_http_response::<Vec<u8>>()
@@ -60,13 +42,71 @@ pub(crate) async fn get_server_keys_route(
 	Ok(Json(response))
 }
 
-fn valid_until_ts() -> MilliSecondsSinceUnixEpoch {
-	let dur = Duration::from_hours(168);
-	let timepoint = timepoint_from_now(dur).expect("SystemTime should not overflow");
-	MilliSecondsSinceUnixEpoch::from_system_time(timepoint).expect("UInt should not overflow")
+/// # `GET /_matrix/key/v2/query/{serverName}`
+///
+/// Gets the public signing keys of another server, with this server acting
+/// as a notary.
+pub(crate) async fn get_remote_server_keys_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_remote_server_keys::v2::Request>,
+) -> Result<get_remote_server_keys::v2::Response> {
+	if !services.config.allow_key_notary {
+		return Err!(Request(Forbidden("This server does not act as a key notary.")));
+	}
+
+	let server_keys = services
+		.server_keys
+		.notary_keys(&body.server_name, empty(), body.minimum_valid_until_ts)
+		.await
+		.into_iter()
+		.collect();
+
+	Ok(get_remote_server_keys::v2::Response::new(server_keys))
 }
 
-fn expires_ts() -> MilliSecondsSinceUnixEpoch {
-	let timepoint = SystemTime::now();
-	MilliSecondsSinceUnixEpoch::from_system_time(timepoint).expect("UInt should not overflow")
+/// # `POST /_matrix/key/v2/query`
+///
+/// Gets the public signing keys of other servers in a batch, with this server
+/// acting as a notary. Servers whose keys cannot be found are left out. A
+/// batch may name at most `trusted_server_batch_size` servers, which are
+/// looked up `trusted_server_batch_concurrency` at a time.
+pub(crate) async fn get_remote_server_keys_batch_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_remote_server_keys_batch::v2::Request>,
+) -> Result<get_remote_server_keys_batch::v2::Response> {
+	if !services.config.allow_key_notary {
+		return Err!(Request(Forbidden("This server does not act as a key notary.")));
+	}
+
+	if body.server_keys.len() > services.config.trusted_server_batch_size {
+		return Err!(Request(TooLarge("Too many servers requested in one batch.")));
+	}
+
+	let concurrency = services
+		.config
+		.trusted_server_batch_concurrency
+		.max(1);
+
+	let now = MilliSecondsSinceUnixEpoch::now();
+	let server_keys = body
+		.server_keys
+		.iter()
+		.stream()
+		.broadn_filter_map(concurrency, async |(origin, criteria)| {
+			let minimum_valid_until_ts = criteria
+				.values()
+				.filter_map(|criteria| criteria.minimum_valid_until_ts)
+				.max()
+				.unwrap_or(now);
+
+			services
+				.server_keys
+				.notary_keys(origin, criteria.keys().map(Borrow::borrow), minimum_valid_until_ts)
+				.await
+				.ok()
+		})
+		.collect()
+		.await;
+
+	Ok(get_remote_server_keys_batch::v2::Response::new(server_keys))
 }
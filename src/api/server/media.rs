@@ -64,7 +64,8 @@ pub(crate) async fn get_content_thumbnail_route(
 	ClientIp(client): ClientIp,
 	body: Ruma<get_content_thumbnail::v1::Request>,
 ) -> Result<get_content_thumbnail::v1::Response> {
-	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?;
+	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?
+		.with_animated(body.animated);
 	let mxc = Mxc {
 		server_name: services.globals.server_name(),
 		media_id: &body.media_id,
@@ -10,6 +10,7 @@
 pub(super) mod make_leave;
 pub(super) mod media;
 pub(super) mod openid;
+pub(super) mod policy;
 pub(super) mod publicrooms;
 pub(super) mod query;
 pub(super) mod send;
@@ -35,6 +36,7 @@
 pub(super) use make_leave::*;
 pub(super) use media::*;
 pub(super) use openid::*;
+pub(super) use policy::*;
 pub(super) use publicrooms::*;
 pub(super) use query::*;
 pub(super) use send::*;
@@ -0,0 +1,55 @@
+use axum::extract::State;
+use ruma::{
+	api::federation::policy::sign_event::v1,
+	events::room::policy::POLICY_SERVER_ED25519_SIGNING_KEY_ID,
+};
+use serde_json::json;
+use tuwunel_core::{Err, Result};
+use tuwunel_service::policy_server::Decision;
+
+use super::AccessCheck;
+use crate::Ruma;
+
+/// # `POST /_matrix/policy/v1/sign`
+///
+/// Signs an event with this server acting as the room's policy server, or
+/// refuses it when it breaks one of the configured rules.
+pub(crate) async fn policy_sign_route(
+	State(services): State<crate::State>,
+	body: Ruma<v1::Request>,
+) -> Result<v1::Response> {
+	let (room_id, event_id, value) = services
+		.event_handler
+		.parse_incoming_pdu(&body.pdu)
+		.await?;
+
+	AccessCheck {
+		services: &services,
+		origin: body.origin(),
+		room_id: &room_id,
+		event_id: None,
+	}
+	.check()
+	.await?;
+
+	let signature = match services
+		.policy_server
+		.sign_pdu(&room_id, &event_id, value)
+		.await?
+	{
+		| Decision::Signed(signature) => signature,
+		| Decision::Refused(reason) => {
+			return Err!(HttpJson(BAD_REQUEST, {
+				"errcode": "M_FORBIDDEN",
+				"error": reason,
+			}));
+		},
+	};
+
+	let server_name = services.globals.server_name().as_str();
+	let signatures = json!({
+		server_name: { POLICY_SERVER_ED25519_SIGNING_KEY_ID: signature },
+	});
+
+	Ok(v1::Response::new(serde_json::from_value(signatures)?))
+}
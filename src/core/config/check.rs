@@ -662,6 +662,15 @@ fn check_media_providers(config: &Config) -> Result {
 		}
 	}
 
+	if let Some(provider) = &config.media_tiering_provider
+		&& !config.media_storage_providers.contains(provider)
+	{
+		return Err!(Config(
+			"media_tiering_provider",
+			"Provider must be listed in 'media_storage_providers'"
+		));
+	}
+
 	if config
 		.media_storage_providers
 		.iter()
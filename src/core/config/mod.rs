@@ -35,7 +35,7 @@
 use itertools::Itertools;
 use regex::RegexSet;
 use ruma::{
-	OwnedMxcUri, OwnedRoomOrAliasId, OwnedServerName, OwnedUserId, RoomVersionId,
+	OwnedMxcUri, OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName, OwnedUserId, RoomVersionId,
 	api::client::discovery::discover_support::ContactRole,
 };
 use serde::{Deserialize, de::IgnoredAny};
@@ -58,7 +58,11 @@
 };
 use crate::{
 	Err, Result, err, implement, redacted_debug,
-	utils::{self, bytes::deserialize_bytesize_usize, sys},
+	utils::{
+		self,
+		bytes::{deserialize_bytesize_u64, deserialize_bytesize_usize},
+		sys,
+	},
 };
 
 // Later prefixes override earlier ones.
@@ -658,6 +662,24 @@ pub struct Config {
 	#[serde(default = "default_media_rc_create_burst_count")]
 	pub media_rc_create_burst_count: u32,
 
+	/// Total size of the media a local user may have uploaded. Uploads past
+	/// it fail with M_RESOURCE_LIMIT_EXCEEDED. 0 disables the limit. Admins
+	/// can override it per user with `!admin media set-quota`. Accepts an
+	/// integer byte count or a string with SI/IEC suffix such as "1 GiB".
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default, deserialize_with = "deserialize_bytesize_u64")]
+	pub media_quota_max_bytes: u64,
+
+	/// Number of media files a local user may have uploaded. 0 disables the
+	/// limit. Admins can override it per user like `media_quota_max_bytes`.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub media_quota_max_files: u64,
+
 	/// reloadable: yes
 	/// default: 1024
 	#[serde(default = "default_max_fetch_prev_events")]
@@ -1354,6 +1376,118 @@ pub struct Config {
 	#[serde(default = "default_policy_server_request_timeout")]
 	pub policy_server_request_timeout: u64,
 
+	/// MSC4284: act as the policy server of rooms whose `m.room.policy` event
+	/// names this server. Other servers in such a room ask this server to
+	/// sign their events at `/_matrix/policy/v1/sign`; events passing the
+	/// `policy_server_*` rules below are signed, and the others refused. The
+	/// room's `m.room.policy` must carry this server's signing key. See the
+	/// moderation guide for setting a room up.
+	///
+	/// reloadable: yes
+	/// default: false
+	#[serde(default)]
+	pub allow_policy_server: bool,
+
+	/// Words or phrases refused by this server as a policy server. An event
+	/// is refused when any text in its content contains one, ignoring case.
+	///
+	/// reloadable: yes
+	/// example: ["19dollarfortnitecards", "free crypto"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub policy_server_keywords: Vec<String>,
+
+	/// Regex patterns refused by this server as a policy server. An event is
+	/// refused when any text in its content matches one.
+	///
+	/// reloadable: yes
+	/// example: ["(?i)https?://[^ ]*\.scam\.tld"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub policy_server_regexes: RegexSet,
+
+	/// SHA-256 hashes of media refused by this server as a policy server,
+	/// in hex as printed by `sha256sum`. An event is refused when media it
+	/// refers to has one of these hashes. Only media already stored on this
+	/// server is checked; media it would have to fetch does not refuse the
+	/// event.
+	///
+	/// reloadable: yes
+	///
+	/// default: []
+	#[serde(default)]
+	pub policy_server_media_hashes: Vec<String>,
+
+	/// Seconds since joining the room before a member may post media or
+	/// links, when this server is the room's policy server. 0 lets members
+	/// post them right away.
+	///
+	/// This checks how long the member has been in the room, counted from
+	/// when this server received the join, not how old the account is.
+	/// Members who joined before this server recorded join times are not
+	/// held back.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub policy_server_new_member_secs: u64,
+
+	/// Rooms holding `m.policy.rule.user` and `m.policy.rule.server` events
+	/// applied by this server as a policy server. Events from users and
+	/// servers these rules ban are refused. This server must be joined to
+	/// the rooms.
+	///
+	/// reloadable: yes
+	/// example: ["!banlist:example.com"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub policy_server_ban_lists: Vec<OwnedRoomId>,
+
+	/// Policy rooms whose `m.policy.rule.user`, `m.policy.rule.room` and
+	/// `m.policy.rule.server` rules with the `m.ban` recommendation this
+	/// server enforces. The server user joins them to follow their updates.
+	/// Local users may not join or invite to banned rooms, nor invite or be
+	/// invited by banned users; banned users may not create rooms; aliases
+	/// matching a room rule cannot be created; and banned servers are refused
+	/// over federation. Rules may use `*` and `?` globs.
+	///
+	/// reloadable: yes
+	/// example: ["#community-bans:example.com"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub policy_list_rooms: Vec<OwnedRoomOrAliasId>,
+
+	/// URL of a spam-checker webhook. When set, the server POSTs a JSON
+	/// description of each checked action (sending an event, inviting,
+	/// joining, creating a room, registering, uploading media) and refuses the
+	/// action when the answer is `{"allow": false}`. See the moderation guide
+	/// for the request and response format.
+	///
+	/// reloadable: yes
+	/// example: "http://127.0.0.1:8009/check"
+	pub spam_checker_webhook_url: Option<Url>,
+
+	/// Timeout (seconds) for requests to the spam-checker webhook.
+	///
+	/// reloadable: yes
+	/// default: 5
+	#[serde(default = "default_spam_checker_webhook_timeout")]
+	pub spam_checker_webhook_timeout: u64,
+
+	/// Refuse checked actions while the spam-checker webhook cannot be reached
+	/// or gives an unreadable answer. By default such failures allow the
+	/// action with a warning, so an outage of the checker does not take the
+	/// server down with it.
+	///
+	/// reloadable: yes
+	/// default: false
+	#[serde(default)]
+	pub spam_checker_webhook_fail_closed: bool,
+
 	/// MSC3925: fold the most recent message edit (an `m.replace` relation)
 	/// into `unsigned.m.relations` on a served event as the full replacement
 	/// event, on the client read endpoints. Off by default: it adds a typed
@@ -1479,8 +1613,8 @@ pub struct Config {
 	/// Servers listed here will be used to gather public keys of other servers
 	/// (notary trusted key servers).
 	///
-	/// Currently, tuwunel doesn't support inbound batched key requests, so
-	/// this list should only contain other Synapse servers.
+	/// Other tuwunel servers can be listed here when they enable
+	/// allow_key_notary.
 	///
 	/// reloadable: yes
 	/// example: ["matrix.org", "tchncs.de"]
@@ -1537,6 +1671,30 @@ pub struct Config {
 	#[serde(default = "default_trusted_server_batch_concurrency")]
 	pub trusted_server_batch_concurrency: usize,
 
+	/// Serve other servers' public keys to servers which list this one in
+	/// their trusted_servers, at `/_matrix/key/v2/query`. Keys are answered
+	/// from this server's cache and fetched from their origin when missing or
+	/// not valid for long enough; any server may make this one fetch keys,
+	/// though each origin at most once every five minutes. A batch query may
+	/// name up to trusted_server_batch_size servers, looked up
+	/// trusted_server_batch_concurrency at a time.
+	///
+	/// reloadable: yes
+	#[serde(default)]
+	pub allow_key_notary: bool,
+
+	/// Generate a new signing key and make it active once the current one is
+	/// this many days old. Earlier keys stay published as expired, so events
+	/// they signed still verify. 0 only rotates when
+	/// `!admin server rotate-signing-key` is run. When this server is a room's
+	/// policy server, each rotation requires updating the room's
+	/// `m.room.policy` event with the new key.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub signing_key_rotation_days: u64,
+
 	/// Max log level for tuwunel. Allows debug, info, warn, or error.
 	///
 	/// See also:
@@ -1975,6 +2133,106 @@ pub struct Config {
 	#[serde(default = "default_rendezvous_rc_burst_count")]
 	pub rendezvous_rc_burst_count: u32,
 
+	/// Token-bucket refill rate (requests per second) for sending message and
+	/// state events, redactions and creating rooms.
+	///
+	/// The `rc_*` options throttle classes of client-server endpoints. A
+	/// request is charged to the authenticated user, or to the client IP when
+	/// there is none, as for login and registration. Rates may be fractional:
+	/// `0.2` allows one request every five seconds once the burst is spent. A
+	/// rate or burst of `0` disables the class's throttle, which is the
+	/// default. Appservices registered with `rate_limited: false` are exempt,
+	/// and admins may override a user's rates with `!admin users
+	/// set-rate-limit`.
+	///
+	/// reloadable: yes
+	/// default: 0.0
+	#[serde(default)]
+	pub rc_message_per_second: f64,
+
+	/// Token-bucket depth (burst size) for sending message and state events,
+	/// redactions and creating rooms.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub rc_message_burst_count: u32,
+
+	/// Token-bucket refill rate (requests per second) for joining and knocking
+	/// on rooms. See `rc_message_per_second`.
+	///
+	/// reloadable: yes
+	/// default: 0.0
+	#[serde(default)]
+	pub rc_join_per_second: f64,
+
+	/// Token-bucket depth (burst size) for joining and knocking on rooms.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub rc_join_burst_count: u32,
+
+	/// Token-bucket refill rate (requests per second) for sending invites. See
+	/// `rc_message_per_second`.
+	///
+	/// reloadable: yes
+	/// default: 0.0
+	#[serde(default)]
+	pub rc_invite_per_second: f64,
+
+	/// Token-bucket depth (burst size) for sending invites.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub rc_invite_burst_count: u32,
+
+	/// Token-bucket refill rate (requests per second) for login attempts,
+	/// charged to the client IP. See `rc_message_per_second`.
+	///
+	/// reloadable: yes
+	/// default: 0.0
+	#[serde(default)]
+	pub rc_login_per_second: f64,
+
+	/// Token-bucket depth (burst size) for login attempts.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub rc_login_burst_count: u32,
+
+	/// Token-bucket refill rate (requests per second) for registration
+	/// attempts, charged to the client IP. See `rc_message_per_second`.
+	///
+	/// reloadable: yes
+	/// default: 0.0
+	#[serde(default)]
+	pub rc_registration_per_second: f64,
+
+	/// Token-bucket depth (burst size) for registration attempts.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub rc_registration_burst_count: u32,
+
+	/// Token-bucket refill rate (requests per second) for claiming one-time
+	/// keys through `/keys/claim`. See `rc_message_per_second`.
+	///
+	/// reloadable: yes
+	/// default: 0.0
+	#[serde(default)]
+	pub rc_key_claim_per_second: f64,
+
+	/// Token-bucket depth (burst size) for claiming one-time keys.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub rc_key_claim_burst_count: u32,
+
 	/// Static TURN username to provide the client if not using a shared secret
 	/// ("turn_secret"), It is recommended to use a shared secret over static
 	/// credentials.
@@ -2485,6 +2743,22 @@ pub struct Config {
 	#[serde(default)]
 	pub suppress_push_when_active: bool,
 
+	/// Seconds a notification waits before it is mailed to an email pusher.
+	/// Rooms the user reads in the meantime are left out of the digest.
+	///
+	/// reloadable: yes
+	/// default: 600
+	#[serde(default = "default_push_email_delay_secs")]
+	pub push_email_delay_secs: u64,
+
+	/// Minimum seconds between two digests mailed to the same email pusher.
+	/// Notifications arriving in between are batched into the next one.
+	///
+	/// reloadable: yes
+	/// default: 3600
+	#[serde(default = "default_push_email_throttle_secs")]
+	pub push_email_throttle_secs: u64,
+
 	/// Allow receiving incoming read receipts from remote servers.
 	/// reloadable: yes
 	#[serde(default = "true_fn")]
@@ -2642,7 +2916,8 @@ pub struct Config {
 	/// cost memory whatever the encoded file weighs, so a picture declaring
 	/// more than this is left without a thumbnail instead of decoded. A video
 	/// frame inherits the resolution of the video it came from and is bounded
-	/// here too.
+	/// here too, as is an animated thumbnail by its frames together, beyond
+	/// which it is generated as a still.
 	///
 	/// 50 megapixels is roughly four 8K frames and more than any ordinary
 	/// camera produces. Each pixel is budgeted at four bytes, so the default
@@ -2770,6 +3045,34 @@ pub struct Config {
 	#[serde(default)]
 	pub store_media_on_providers: BTreeSet<String>,
 
+	/// Storage provider which older or larger media is moved to, such as an
+	/// S3 provider, while thumbnails and recent media stay on the providers in
+	/// `store_media_on_providers`. It must be listed in
+	/// `media_storage_providers`. Media is moved by a background task, shown
+	/// by `!admin server list-tasks`, which starts every hour or when
+	/// `!admin media tier` is run.
+	///
+	/// reloadable: yes
+	/// example: "media_on_s3"
+	pub media_tiering_provider: Option<String>,
+
+	/// Move original media to `media_tiering_provider` once it was stored this
+	/// many days ago. 0 disables moving media by age.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub media_tiering_after_days: u64,
+
+	/// Move original media larger than this to `media_tiering_provider`,
+	/// however recent. Accepts an integer byte count or a string with SI/IEC
+	/// suffix such as "100 MiB". 0 disables moving media by size.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default, deserialize_with = "deserialize_bytesize_u64")]
+	pub media_tiering_min_size: u64,
+
 	/// Redirect local media downloads to a presigned object-store URL when the
 	/// client sends `allow_redirect=true` (MSC3860). When a configured storage
 	/// provider can presign the object (S3), the download responds with a 307
@@ -2781,6 +3084,62 @@ pub struct Config {
 	#[serde(default)]
 	pub media_allow_redirect: bool,
 
+	/// Address of a ClamAV `clamd` daemon which scans media before it is
+	/// stored or served: the path of its Unix socket, or `host:port` for TCP.
+	/// Uploads and remote media found infected are refused, and stored media
+	/// found infected is no longer served. Verdicts are kept per content, so
+	/// each file is scanned once. clamd's `StreamMaxLength` should be at
+	/// least `max_request_size`, or larger files cannot be scanned.
+	///
+	/// reloadable: yes
+	/// example: "/run/clamav/clamd.ctl"
+	pub media_scanner_clamd: Option<String>,
+
+	/// URL of an HTTP media scanner, used when `media_scanner_clamd` is not
+	/// set. Each file is POSTed as the request body and the scanner answers
+	/// `{"clean": true}`, or `{"clean": false, "reason": "..."}` for an
+	/// infected file. See the media guide for details.
+	///
+	/// reloadable: yes
+	/// example: "http://127.0.0.1:8010/scan"
+	pub media_scanner_url: Option<Url>,
+
+	/// Timeout (seconds) for scanning one file.
+	///
+	/// reloadable: yes
+	/// default: 30
+	#[serde(default = "default_media_scanner_timeout")]
+	pub media_scanner_timeout: u64,
+
+	/// Refuse media while the scanner cannot be reached or gives an
+	/// unreadable answer. By default such media is stored and served with a
+	/// warning, unscanned, and scanned again when next served.
+	///
+	/// reloadable: yes
+	/// default: false
+	#[serde(default)]
+	pub media_scanner_fail_closed: bool,
+
+	/// Evict remote media which has not been downloaded or thumbnailed for
+	/// this many days. Evicted media is fetched again from its origin if
+	/// requested later. Local uploads are never evicted. 0 keeps remote media
+	/// until an admin deletes it.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default)]
+	pub remote_media_max_idle_days: u64,
+
+	/// Keep the remote media cache within this total size by evicting the
+	/// least recently accessed remote media first. Local uploads neither
+	/// count toward it nor are evicted. Accepts an integer byte count or a
+	/// string with SI/IEC suffix such as "10 GiB". 0 disables the limit.
+	///
+	/// reloadable: yes
+	/// default: 0
+	#[serde(default, deserialize_with = "deserialize_bytesize_u64")]
+	pub remote_media_cache_max_bytes: u64,
+
 	/// Vector list of regex patterns of server names that tuwunel will refuse
 	/// to download remote media from.
 	///
@@ -3013,6 +3372,29 @@ pub struct Config {
 	#[serde(default)]
 	pub url_preview_media_user_agent: Option<String>,
 
+	/// oEmbed provider lists for URL previews, each a file in the standard
+	/// `providers.json` format published at https://oembed.com/providers.json.
+	///
+	/// A URL matching one of a provider's schemes is previewed from that
+	/// provider's oEmbed endpoint before its page is scraped, which suits
+	/// sites whose pages carry no OpenGraph tags until scripts run. YouTube is
+	/// always known. Endpoints are contacted only when the URL preview domain
+	/// lists allow them and never at an address in `ip_range_denylist`.
+	///
+	/// example: ["/etc/tuwunel/oembed-providers.json"]
+	/// default: []
+	#[serde(default)]
+	pub url_preview_oembed_providers: Vec<PathBuf>,
+
+	/// Follow a page's own `<link rel="alternate"
+	/// type="application/json+oembed">` when the page yields no preview of its
+	/// own. The discovered endpoint passes the same checks as a configured
+	/// one.
+	///
+	/// reloadable: yes
+	#[serde(default = "true_fn")]
+	pub url_preview_oembed_discovery: bool,
+
 	/// List of forbidden room aliases and room IDs as strings of regex
 	/// patterns.
 	///
@@ -3084,6 +3466,20 @@ pub struct Config {
 	#[serde(default = "default_max_join_attempts_per_join_request")]
 	pub max_join_attempts_per_join_request: usize,
 
+	/// Join remote rooms with partial state (MSC3706 "faster joins"). The join
+	/// returns to the client as soon as the resident server answers, without
+	/// waiting for the membership events it omitted. The full room state is
+	/// then fetched in the background; until it arrives the room's member
+	/// list is incomplete and sync withholds the room from clients which do
+	/// not lazy-load members.
+	///
+	/// When disabled, the omitted state is fetched before the join completes.
+	///
+	/// reloadable: yes
+	/// default: true
+	#[serde(default = "true_fn")]
+	pub partial_state_joins: bool,
+
 	/// Retry failed and incomplete messages to remote servers immediately upon
 	/// startup. This is called bursting. If this is disabled, said messages may
 	/// not be delivered until more messages are queued for that server. Do not
@@ -3283,6 +3679,18 @@ pub struct Config {
 	#[serde(default = "default_sentry_filter")]
 	pub sentry_filter: String,
 
+	/// Bearer token guarding the OpenMetrics exporter at `/_tuwunel/metrics`.
+	/// When set, a scraper presenting it in an `Authorization: Bearer` header
+	/// receives request latency per route, database, federation sender and
+	/// sync statistics in the OpenMetrics text format. When unset, the
+	/// endpoint does not exist.
+	///
+	/// reloadable: yes
+	/// example: "Nf8qR2vXk5LmT9wPz3cYh7BdJ4sG6uEa"
+	///
+	/// display: sensitive
+	pub metrics_token: Option<String>,
+
 	/// Enable the tokio-console. This option is only relevant to developers.
 	///
 	///	For more information, see:
@@ -3360,6 +3768,39 @@ pub struct Config {
 	#[serde(default = "default_redaction_retention_seconds")]
 	pub redaction_retention_seconds: u64,
 
+	/// Enables purging room history older than the room's `m.room.retention`
+	/// policy (MSC1763). State events are always kept, as is the most recent
+	/// event of each room. Media referenced by purged events is deleted with
+	/// them.
+	///
+	/// Expired history is purged by a background job once an hour.
+	///
+	/// reloadable: yes
+	#[serde(default)]
+	pub retention_enabled: bool,
+
+	/// Maximum lifetime in seconds of events in rooms without an
+	/// `m.room.retention` policy of their own. Unset keeps their history
+	/// forever.
+	///
+	/// reloadable: yes
+	/// example: 31536000
+	pub retention_default_max_lifetime_seconds: Option<u64>,
+
+	/// Lower bound in seconds on the lifetime a room's policy may set. Shorter
+	/// `max_lifetime` values are raised to this bound.
+	///
+	/// reloadable: yes
+	/// example: 86400
+	pub retention_allowed_lifetime_min_seconds: Option<u64>,
+
+	/// Upper bound in seconds on the lifetime a room's policy may set. Longer
+	/// `max_lifetime` values are lowered to this bound.
+	///
+	/// reloadable: yes
+	/// example: 157680000
+	pub retention_allowed_lifetime_max_seconds: Option<u64>,
+
 	/// Allows users with `redact` power level to request unredacted events with
 	/// MSC2815.
 	///
@@ -3378,6 +3819,17 @@ pub struct Config {
 	#[serde(default)]
 	pub disable_local_redactions: bool,
 
+	/// Longest delay, in seconds, a client may request when scheduling a
+	/// delayed event (MSC4140). Delayed events are messages or state events the
+	/// server sends on the client's behalf later, such as the MatrixRTC
+	/// membership cleanup sent when a call client stops responding. Set to 0
+	/// to disable delayed events.
+	///
+	/// reloadable: yes
+	/// default: 86400
+	#[serde(default = "default_max_event_delay_s")]
+	pub max_event_delay_s: u64,
+
 	/// Serve erased senders' events as pruned copies over federation
 	/// (MSC4025). A requesting server retains the unredacted view only when
 	/// one of its users was joined in the room state at the event; join
@@ -5155,6 +5607,10 @@ fn true_fn() -> bool { true }
 
 fn default_policy_server_request_timeout() -> u64 { 5 }
 
+fn default_spam_checker_webhook_timeout() -> u64 { 5 }
+
+fn default_media_scanner_timeout() -> u64 { 30 }
+
 fn default_rendezvous_session_max_bytes() -> usize { 4096 }
 
 fn default_rendezvous_session_ttl() -> u64 { 600 }
@@ -5306,6 +5762,10 @@ fn default_appservice_idle_timeout() -> u64 { 300 }
 
 fn default_pusher_idle_timeout() -> u64 { 15 }
 
+fn default_push_email_delay_secs() -> u64 { 600 }
+
+fn default_push_email_throttle_secs() -> u64 { 3600 }
+
 fn default_max_fetch_prev_events() -> u16 { 1024_u16 }
 
 fn default_fetch_prev_wait_ms() -> u64 { 750 }
@@ -5372,6 +5832,8 @@ fn default_typing_client_timeout_min_s() -> u64 { 15 }
 
 fn default_typing_client_timeout_max_s() -> u64 { 45 }
 
+fn default_max_event_delay_s() -> u64 { 86400 }
+
 fn default_rocksdb_recovery_mode() -> u8 { 1 }
 
 fn default_rocksdb_log_level() -> String { "error".to_owned() }
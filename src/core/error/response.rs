@@ -109,7 +109,7 @@ pub(super) fn bad_request_code(kind: &ErrorKind) -> StatusCode {
 		| TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
 
 		// 409
-		| CannotOverwriteMedia => StatusCode::CONFLICT,
+		| CannotOverwriteMedia | ConflictingUnsubscription => StatusCode::CONFLICT,
 
 		// 404
 		| NotFound | NotImplemented | FeatureDisabled | Unrecognized => StatusCode::NOT_FOUND,
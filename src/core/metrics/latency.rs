@@ -0,0 +1,130 @@
+//! Request latency histograms keyed by route.
+
+use std::{
+	array,
+	collections::BTreeMap,
+	sync::{
+		RwLock,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::Duration,
+};
+
+/// Upper bounds of the latency buckets in seconds. Long-polling endpoints such
+/// as `/sync` routinely hold requests for tens of seconds, hence the tail.
+pub const BOUNDS: [f64; 14] =
+	[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// Method and matched path template of a route.
+pub type RouteKey = (String, String);
+
+/// Latency histograms of every route which has served a request.
+#[derive(Default)]
+pub struct RouteLatency {
+	routes: RwLock<BTreeMap<RouteKey, Histogram>>,
+}
+
+/// Fixed-bucket latency histogram updated without locking.
+pub struct Histogram {
+	/// Per-bucket counts; the last slot counts observations beyond every bound.
+	buckets: [AtomicU64; BOUNDS.len() + 1],
+
+	sum_micros: AtomicU64,
+}
+
+/// Cumulative view of a [`Histogram`] as exposed to scrapers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+	/// `(upper bound, observations at or below it)` for each of [`BOUNDS`].
+	pub buckets: Vec<(f64, u64)>,
+
+	/// Total observations, including those beyond the last bound.
+	pub count: u64,
+
+	/// Sum of all observations in seconds.
+	pub sum: f64,
+}
+
+impl RouteLatency {
+	/// Records one request against its route.
+	///
+	/// # Panics
+	///
+	/// Panics when the route map lock is poisoned.
+	pub fn observe(&self, method: &str, route: &str, elapsed: Duration) {
+		let routes = self.routes.read().expect("locked");
+		if let Some(histogram) = routes.get(&(method.to_owned(), route.to_owned())) {
+			histogram.observe(elapsed);
+			return;
+		}
+
+		drop(routes);
+		self.routes
+			.write()
+			.expect("locked")
+			.entry((method.to_owned(), route.to_owned()))
+			.or_default()
+			.observe(elapsed);
+	}
+
+	/// Snapshots every route's histogram in route order.
+	///
+	/// # Panics
+	///
+	/// Panics when the route map lock is poisoned.
+	#[must_use]
+	pub fn snapshot(&self) -> Vec<(RouteKey, Snapshot)> {
+		self.routes
+			.read()
+			.expect("locked")
+			.iter()
+			.map(|(key, histogram)| (key.clone(), histogram.snapshot()))
+			.collect()
+	}
+}
+
+impl Default for Histogram {
+	fn default() -> Self {
+		Self {
+			buckets: array::from_fn(|_| AtomicU64::new(0)),
+			sum_micros: AtomicU64::new(0),
+		}
+	}
+}
+
+impl Histogram {
+	pub fn observe(&self, elapsed: Duration) {
+		let secs = elapsed.as_secs_f64();
+		let bucket = BOUNDS
+			.iter()
+			.position(|&bound| secs <= bound)
+			.unwrap_or(BOUNDS.len());
+
+		self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+		self.sum_micros
+			.fetch_add(elapsed.as_micros().try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+	}
+
+	#[must_use]
+	#[expect(clippy::as_conversions, clippy::cast_precision_loss)]
+	pub fn snapshot(&self) -> Snapshot {
+		let mut count: u64 = 0;
+		let buckets = BOUNDS
+			.iter()
+			.zip(&self.buckets)
+			.map(|(&bound, bucket)| {
+				count = count.saturating_add(bucket.load(Ordering::Relaxed));
+				(bound, count)
+			})
+			.collect();
+
+		let overflow = self.buckets[BOUNDS.len()].load(Ordering::Relaxed);
+		let sum_micros = self.sum_micros.load(Ordering::Relaxed);
+
+		Snapshot {
+			buckets,
+			count: count.saturating_add(overflow),
+			sum: sum_micros as f64 / 1_000_000.0,
+		}
+	}
+}
@@ -5,6 +5,10 @@
 //! telemetry.
 
 pub mod dump;
+pub mod latency;
+pub mod openmetrics;
+#[cfg(test)]
+mod tests;
 
 use std::sync::{
 	Arc, Mutex,
@@ -20,6 +24,8 @@
 use tokio_metrics::{RuntimeIntervals, RuntimeMonitor};
 use tokio_metrics::{TaskMetrics, TaskMonitor};
 
+use self::latency::RouteLatency;
+
 /// Bucket counts sampled from the scheduler latency histogram.
 ///
 /// The inline budget matches the default bucket count; a runtime configured
@@ -81,6 +87,12 @@ pub struct Metrics {
 	/// The counter is scoped to request handling rather than all process
 	/// panics. It is monotonic for the lifetime of the process.
 	pub requests_panic: AtomicU32,
+
+	/// Latency of requests served by each matched route.
+	///
+	/// Populated by the router's trace layer once a response is produced;
+	/// requests matching no route are not recorded.
+	pub requests_latency: RouteLatency,
 }
 
 impl Metrics {
@@ -135,6 +147,7 @@ pub fn new(runtime: Option<&runtime::Handle>) -> Arc<Self> {
 			requests_handle_finished: AtomicU64::new(0),
 			requests_handle_active: AtomicU32::new(0),
 			requests_panic: AtomicU32::new(0),
+			requests_latency: RouteLatency::default(),
 		})
 	}
 
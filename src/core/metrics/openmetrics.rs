@@ -0,0 +1,137 @@
+//! Writer for the OpenMetrics text exposition format.
+//!
+//! Callers announce each metric family once with [`Exposition::family`] and
+//! then write its samples; [`Exposition::finish`] terminates the document.
+
+use std::fmt::{Display, Write};
+
+use super::latency::Snapshot;
+
+/// Media type of the rendered exposition.
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Label names and values attached to a sample.
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kind {
+	Counter,
+	Gauge,
+	Histogram,
+}
+
+/// An OpenMetrics document under construction.
+#[derive(Default)]
+pub struct Exposition {
+	out: String,
+}
+
+impl Exposition {
+	#[must_use]
+	pub fn new() -> Self { Self::default() }
+
+	/// Writes the `TYPE` and `HELP` metadata introducing a metric family.
+	pub fn family(&mut self, name: &str, kind: Kind, help: &str) -> &mut Self {
+		let kind = match kind {
+			| Kind::Counter => "counter",
+			| Kind::Gauge => "gauge",
+			| Kind::Histogram => "histogram",
+		};
+
+		writeln!(self.out, "# TYPE {name} {kind}").expect("write to string");
+		writeln!(self.out, "# HELP {name} {}", escape(help, false)).expect("write to string");
+		self
+	}
+
+	/// Writes a sample of a gauge family.
+	pub fn gauge(&mut self, name: &str, labels: Labels<'_>, value: impl Display) -> &mut Self {
+		self.sample(name, "", labels, None, value)
+	}
+
+	/// Writes a sample of a counter family; the `_total` suffix is appended.
+	pub fn counter(&mut self, name: &str, labels: Labels<'_>, value: impl Display) -> &mut Self {
+		self.sample(name, "_total", labels, None, value)
+	}
+
+	/// Writes the buckets, count and sum of a histogram family.
+	pub fn histogram(
+		&mut self,
+		name: &str,
+		labels: Labels<'_>,
+		snapshot: &Snapshot,
+	) -> &mut Self {
+		for &(bound, count) in &snapshot.buckets {
+			self.sample(name, "_bucket", labels, Some(&float(bound)), count);
+		}
+
+		self.sample(name, "_bucket", labels, Some("+Inf"), snapshot.count)
+			.sample(name, "_count", labels, None, snapshot.count)
+			.sample(name, "_sum", labels, None, float(snapshot.sum))
+	}
+
+	/// Terminates the document and returns its text.
+	#[must_use]
+	pub fn finish(mut self) -> String {
+		self.out.push_str("# EOF\n");
+		self.out
+	}
+
+	fn sample(
+		&mut self,
+		name: &str,
+		suffix: &str,
+		labels: Labels<'_>,
+		le: Option<&str>,
+		value: impl Display,
+	) -> &mut Self {
+		let out = &mut self.out;
+		out.push_str(name);
+		out.push_str(suffix);
+
+		let labels = labels
+			.iter()
+			.copied()
+			.chain(le.map(|le| ("le", le)));
+
+		let mut labelled = false;
+		for (label, value) in labels {
+			out.push(if labelled { ',' } else { '{' });
+			write!(out, "{label}=\"{}\"", escape(value, true)).expect("write to string");
+			labelled = true;
+		}
+
+		if labelled {
+			out.push('}');
+		}
+
+		writeln!(out, " {value}").expect("write to string");
+		self
+	}
+}
+
+/// Formats a float the way OpenMetrics spells special values.
+#[must_use]
+pub fn float(value: f64) -> String {
+	if value.is_nan() {
+		"NaN".into()
+	} else if value.is_infinite() {
+		if value.is_sign_positive() { "+Inf" } else { "-Inf" }.into()
+	} else {
+		value.to_string()
+	}
+}
+
+/// Escapes a label value (`quoted`) or HELP text.
+fn escape(input: &str, quoted: bool) -> String {
+	let mut out = String::with_capacity(input.len());
+	for c in input.chars() {
+		match c {
+			| '\\' => out.push_str("\\\\"),
+			| '\n' => out.push_str("\\n"),
+			| '"' if quoted => out.push_str("\\\""),
+			| c => out.push(c),
+		}
+	}
+
+	out
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use super::{
+	latency::{BOUNDS, Histogram, RouteLatency},
+	openmetrics::{Exposition, Kind, float},
+};
+
+#[test]
+fn histogram_buckets_are_cumulative() {
+	let histogram = Histogram::default();
+	histogram.observe(Duration::from_millis(3));
+	histogram.observe(Duration::from_millis(40));
+	histogram.observe(Duration::from_secs(600));
+
+	let snapshot = histogram.snapshot();
+	assert_eq!(snapshot.buckets.len(), BOUNDS.len());
+	assert_eq!(snapshot.buckets[0], (0.005, 1));
+	assert_eq!(snapshot.buckets[3], (0.05, 2));
+	assert_eq!(snapshot.buckets.last().map(|b| b.1), Some(2));
+	assert_eq!(snapshot.count, 3);
+	assert!((snapshot.sum - 600.043).abs() < 1e-9);
+}
+
+#[test]
+fn histogram_bound_is_inclusive() {
+	let histogram = Histogram::default();
+	histogram.observe(Duration::from_millis(10));
+
+	let snapshot = histogram.snapshot();
+	assert_eq!(snapshot.buckets[0].1, 0);
+	assert_eq!(snapshot.buckets[1].1, 1);
+}
+
+#[test]
+fn route_latency_keys_by_method_and_route() {
+	let routes = RouteLatency::default();
+	routes.observe("GET", "/a", Duration::from_millis(1));
+	routes.observe("GET", "/a", Duration::from_millis(1));
+	routes.observe("PUT", "/a", Duration::from_millis(1));
+
+	let snapshot = routes.snapshot();
+	assert_eq!(snapshot.len(), 2);
+	assert_eq!(snapshot[0].0, ("GET".to_owned(), "/a".to_owned()));
+	assert_eq!(snapshot[0].1.count, 2);
+	assert_eq!(snapshot[1].1.count, 1);
+}
+
+#[test]
+fn exposition_renders_families_and_terminates() {
+	let mut exposition = Exposition::new();
+	exposition
+		.family("up", Kind::Gauge, "Whether the server is up.")
+		.gauge("up", &[], 1)
+		.family("requests", Kind::Counter, "Requests served.")
+		.counter("requests", &[("route", "/a")], 7);
+
+	assert_eq!(
+		exposition.finish(),
+		"# TYPE up gauge\n# HELP up Whether the server is up.\nup 1\n# TYPE requests counter\n# \
+		 HELP requests Requests served.\nrequests_total{route=\"/a\"} 7\n# EOF\n"
+	);
+}
+
+#[test]
+fn exposition_renders_histogram() {
+	let histogram = Histogram::default();
+	histogram.observe(Duration::from_millis(500));
+
+	let mut exposition = Exposition::new();
+	exposition.histogram("latency", &[("method", "GET")], &histogram.snapshot());
+	let text = exposition.finish();
+
+	assert!(text.contains("latency_bucket{method=\"GET\",le=\"0.25\"} 0\n"));
+	assert!(text.contains("latency_bucket{method=\"GET\",le=\"0.5\"} 1\n"));
+	assert!(text.contains("latency_bucket{method=\"GET\",le=\"+Inf\"} 1\n"));
+	assert!(text.contains("latency_count{method=\"GET\"} 1\n"));
+	assert!(text.contains("latency_sum{method=\"GET\"} 0.5\n"));
+}
+
+#[test]
+fn exposition_escapes_label_values() {
+	let mut exposition = Exposition::new();
+	exposition.gauge("g", &[("server", "a\"b\\c\nd")], 0);
+
+	assert_eq!(exposition.finish(), "g{server=\"a\\\"b\\\\c\\nd\"} 0\n# EOF\n");
+}
+
+#[test]
+fn float_spells_special_values() {
+	assert_eq!(float(f64::NAN), "NaN");
+	assert_eq!(float(f64::INFINITY), "+Inf");
+	assert_eq!(float(f64::NEG_INFINITY), "-Inf");
+	assert_eq!(float(1.0), "1");
+	assert_eq!(float(0.25), "0.25");
+}
@@ -1,19 +1,29 @@
 //! Password hashing and cryptographic digest utilities.
 //!
 //! Password helpers use Argon2id with a fresh random salt for each new hash.
-//! The SHA-256 submodule provides byte-oriented digest helpers.
+//! Bcrypt hashes imported from Synapse are verified but never written, and
+//! only with the `synapse_import` feature. The SHA-256 submodule provides
+//! byte-oriented digest helpers.
 
 mod argon;
+#[cfg(feature = "synapse_import")]
+mod bcrypt;
 
 pub mod sha256;
 
 use crate::Result;
 
-/// Verifies a plaintext password against an encoded Argon2 password hash.
+/// Verifies a plaintext password against an encoded Argon2 or bcrypt password
+/// hash.
 ///
 /// Malformed hashes and password mismatches return an error. Success is
 /// represented by `Ok(())`.
 pub fn verify_password(password: &str, password_hash: &str) -> Result {
+	#[cfg(feature = "synapse_import")]
+	if bcrypt::is_bcrypt(password_hash) {
+		return bcrypt::verify_password(password, password_hash);
+	}
+
 	argon::verify_password(password, password_hash)
 }
 
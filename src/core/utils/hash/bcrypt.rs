@@ -0,0 +1,37 @@
+use crate::{Err, Result, err};
+
+/// Whether the hash is a bcrypt hash in the modular crypt format, as written
+/// by Synapse and other servers accounts are imported from.
+pub(super) fn is_bcrypt(password_hash: &str) -> bool {
+	["$2a$", "$2b$", "$2x$", "$2y$"]
+		.iter()
+		.any(|prefix| password_hash.starts_with(prefix))
+}
+
+pub(super) fn verify_password(password: &str, password_hash: &str) -> Result {
+	let verified = ::bcrypt::verify(password, password_hash).map_err(|e| err!("{e}"))?;
+	if !verified {
+		return Err!("Password does not match the bcrypt hash.");
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn bcrypt_hash_verify() {
+		use crate::utils::hash;
+		let preimage = "temp123";
+		let digest = ::bcrypt::hash(preimage, 4).expect("digest");
+		hash::verify_password(preimage, &digest).expect("verified");
+	}
+
+	#[test]
+	#[should_panic(expected = "unverified")]
+	fn bcrypt_hash_verify_fail() {
+		use crate::utils::hash;
+		let digest = ::bcrypt::hash("temp123", 4).expect("digest");
+		hash::verify_password("temp321", &digest).expect("unverified");
+	}
+}
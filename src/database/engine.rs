@@ -37,6 +37,7 @@
 };
 use tuwunel_core::{Err, Result, debug, implement, info, warn};
 
+pub use self::memory_usage::MemoryStats;
 use crate::{
 	Context, Map,
 	pool::Pool,
@@ -15,6 +15,39 @@
 
 fn mib(input: u64) -> f64 { f64::from(u32::try_from(input / 1024).unwrap_or(0)) / 1024.0 }
 
+/// Byte counts of the engine's memory consumers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryStats {
+	/// Approximate size of all memtables.
+	pub mem_table_total: u64,
+
+	/// Approximate size of memtables not yet flushed.
+	pub mem_table_unflushed: u64,
+
+	/// Memory used by table readers, excluding the block cache.
+	pub table_readers: u64,
+
+	pub row_cache_usage: u64,
+
+	pub row_cache_capacity: u64,
+}
+
+/// Samples the database engine's current memory usage.
+#[implement(Engine)]
+pub fn memory_stats(&self) -> Result<MemoryStats> {
+	let row_cache = self.ctx.row_cache.lock()?;
+	let stats =
+		get_memory_usage_stats(Some(&[&self.db]), Some(&[&*row_cache])).or_else(or_else)?;
+
+	Ok(MemoryStats {
+		mem_table_total: stats.mem_table_total,
+		mem_table_unflushed: stats.mem_table_unflushed,
+		table_readers: stats.mem_table_readers_total,
+		row_cache_usage: u64::try_from(row_cache.get_usage())?,
+		row_cache_capacity: u64::try_from(self.ctx.row_cache_capacity)?,
+	})
+}
+
 /// Formats the database engine's current memory usage.
 ///
 /// The report covers memtables, pending writes, table readers, and the row
@@ -23,25 +56,19 @@ fn mib(input: u64) -> f64 { f64::from(u32::try_from(input / 1024).unwrap_or(0))
 #[implement(Engine)]
 pub fn memory_usage(&self) -> Result<String> {
 	let mut res = String::new();
-	let row_cache = self.ctx.row_cache.lock()?;
-	let row_usage = u64::try_from(row_cache.get_usage())?;
-	let row_capacity = u64::try_from(self.ctx.row_cache_capacity)?;
-	let stats =
-		get_memory_usage_stats(Some(&[&self.db]), Some(&[&*row_cache])).or_else(or_else)?;
+	let stats = self.memory_stats()?;
 
 	writeln!(res, "- Memory buffers: {:.2} MiB", mib(stats.mem_table_total))?;
 	writeln!(res, "- Pending write:  {:.2} MiB", mib(stats.mem_table_unflushed))?;
-	writeln!(res, "- Table readers:  {:.2} MiB", mib(stats.mem_table_readers_total))?;
+	writeln!(res, "- Table readers:  {:.2} MiB", mib(stats.table_readers))?;
 	writeln!(
 		res,
 		"- Row cache:      {:.2} / {:.2} MiB ({:.1}%)",
-		mib(row_usage),
-		mib(row_capacity),
-		utilization_percent(row_usage, row_capacity),
+		mib(stats.row_cache_usage),
+		mib(stats.row_cache_capacity),
+		utilization_percent(stats.row_cache_usage, stats.row_cache_capacity),
 	)?;
 
-	drop(row_cache);
-
 	let pools = self.ctx.col_cache.lock()?;
 	if pools.is_empty() {
 		return Ok(res);
@@ -32,7 +32,10 @@
 	ffi::CStr,
 	fmt,
 	fmt::{Debug, Display},
-	sync::Arc,
+	sync::{
+		Arc,
+		atomic::{AtomicU64, Ordering},
+	},
 };
 
 use rocksdb::{AsColumnFamilyRef, ColumnFamily, DBCommon, ReadOptions, WriteOptions};
@@ -62,6 +65,8 @@ pub struct Map {
 	read_options: ReadOptions,
 	cache_read_options: ReadOptions,
 	write_options: WriteOptions,
+	cache_hits: AtomicU64,
+	cache_misses: AtomicU64,
 }
 
 impl Map {
@@ -79,6 +84,8 @@ pub(crate) fn open(engine: &Arc<Engine>, name: &'static str) -> Result<Arc<Self>
 			read_options: read_options_default(engine),
 			cache_read_options: cache_read_options_default(engine),
 			write_options: write_options_default(engine),
+			cache_hits: AtomicU64::new(0),
+			cache_misses: AtomicU64::new(0),
 		}))
 	}
 
@@ -120,6 +127,18 @@ pub fn property(&self, name: &str) -> Result<String> {
 		self.engine.property(&self.cf(), name)
 	}
 
+	/// Returns how many asynchronous point reads were answered from cache and
+	/// how many had to be deferred to the blocking pool, in that order.
+	///
+	/// Both counts are cumulative since the map opened.
+	#[inline]
+	pub fn cache_stats(&self) -> (u64, u64) {
+		(
+			self.cache_hits.load(Ordering::Relaxed),
+			self.cache_misses.load(Ordering::Relaxed),
+		)
+	}
+
 	/// Returns the column-family name of this map.
 	///
 	/// The name is fixed when the map opens and lives for the duration of the
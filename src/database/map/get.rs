@@ -1,4 +1,7 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+	fmt::Debug,
+	sync::{Arc, atomic::Ordering},
+};
 
 use futures::{
 	FutureExt, TryFutureExt,
@@ -31,12 +34,14 @@ pub fn get<K>(
 
 	let cached = self.get_cached(key);
 	if matches!(cached, Err(_) | Ok(Some(_))) {
+		self.cache_hits.fetch_add(1, Ordering::Relaxed);
 		return Either::Left(
 			task::consume_budget().map(move |()| cached.map_expect("data found in cache")),
 		);
 	}
 
 	debug_assert!(matches!(cached, Ok(None)), "expected status Incomplete");
+	self.cache_misses.fetch_add(1, Ordering::Relaxed);
 	let cmd = Get {
 		map: self.clone(),
 		key: [key.as_ref().into()].into(),
@@ -103,6 +103,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "bannedroomids",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "delayid_delayedevent",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "disabledroomids",
 		..descriptor::RANDOM_SMALL
@@ -189,6 +193,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "logintoken_expiresatuserid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "mediaid_access",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "mediaid_file",
 		..descriptor::RANDOM_SMALL
@@ -215,6 +223,18 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		ttl: 60 * 60 * 24 * 7,
 		..descriptor::RANDOM_SMALL_CACHE
 	},
+	Descriptor {
+		name: "mediaid_quarantine",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "mediaid_sha256",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "mediaid_stored",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "mediaid_user",
 		..descriptor::RANDOM_SMALL
@@ -312,10 +332,19 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "registrationtoken_info",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "reportid_report",
+		..descriptor::SEQUENTIAL_SMALL
+	},
 	Descriptor {
 		name: "roomid_knockedcount",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomid_fullstatecount",
+		val_size_hint: Some(8),
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_invitedcount",
 		..descriptor::RANDOM_SMALL
@@ -332,10 +361,18 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "roomid_maxremotepowerlevel",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomid_partialstate",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_pduleaves",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomid_provisionaleventid",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_shortroomid",
 		val_size_hint: Some(8),
@@ -382,6 +419,11 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "roomuserid_joined",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomuserid_joinedts",
+		val_size_hint: Some(8),
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomuserid_lastprivatereadupdate",
 		..descriptor::RANDOM_SMALL
@@ -418,10 +460,23 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "roomusertype_roomuserdataid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "senderkey_emailsent",
+		val_size_hint: Some(8),
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "senderkey_pusher",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "senderkeycount_emailpduid",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "server_signedkeys",
+		..descriptor::RANDOM
+	},
 	Descriptor {
 		name: "server_signingkeys",
 		..descriptor::RANDOM
@@ -471,6 +526,18 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "serverroomids",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "sha256_mediascan",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "sha256_provider",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "sha256_refcount",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "shorteventid_authchain",
 		..LEGACY_AUTH_CHAIN_DESCRIPTOR
@@ -519,6 +586,11 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		val_size_hint: Some(8),
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "taskid_task",
+		key_size_hint: Some(16),
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "threadactivityid_rootid",
 		key_size_hint: Some(16),
@@ -539,6 +611,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		ttl: 60 * 60 * 24, // pending validation session; minutes to complete
 		..THREEPID_SESSION_DESCRIPTOR
 	},
+	Descriptor {
+		name: "timedue_delayid",
+		..descriptor::SEQUENTIAL_SMALL
+	},
 	Descriptor {
 		name: "timeredacted_eventid",
 		key_size_hint: Some(64),
@@ -581,6 +657,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userdeviceid_metadata",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userdirectory_tokenuserid",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userdeviceconnid_conn",
 		block_size: 1024 * 16,
@@ -636,10 +716,18 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userid_dehydrateddevice",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_delayid",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_devicelistversion",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_directorytokens",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_displayname",
 		..descriptor::RANDOM_SMALL
@@ -664,6 +752,14 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userid_masterkeyid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_mediaquota",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userid_mediausage",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_oauthid",
 		..descriptor::RANDOM_SMALL
@@ -680,6 +776,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userid_presenceid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_ratelimitoverride",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_selfsigningkeyid",
 		..descriptor::RANDOM_SMALL
@@ -697,6 +797,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		limit_size: 1024 * 1024 * 256,
 		..descriptor::RANDOM_SMALL_CACHE
 	},
+	Descriptor {
+		name: "useridcount_threadsubscription",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "useridprofilekey_value",
 		..descriptor::RANDOM_SMALL
@@ -725,4 +829,8 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userroomid_notificationcount",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userroomthreadid_subscription",
+		..descriptor::RANDOM_SMALL
+	},
 ];
@@ -37,7 +37,7 @@
 	cork::Cork,
 	de::{Ignore, IgnoreAll, from_slice as deserialize_from_slice},
 	deserialized::Deserialized,
-	engine::Engine,
+	engine::{Engine, MemoryStats},
 	handle::Handle,
 	keyval::{KeyBuf, KeyVal, Slice, serialize_key, serialize_val},
 	map::{Get, Map, Qry, compact},
@@ -0,0 +1,465 @@
+#![expect(
+	clippy::tests_outside_test_module,
+	reason = "this integration target requires one top-level test entry point"
+)]
+
+use std::{
+	env::{current_exe, var},
+	fs::{read, remove_dir_all, remove_file, write},
+	net::TcpListener,
+	path::{Path, PathBuf},
+	process::{Command, id as process_id},
+	sync::Arc,
+	time::Duration,
+};
+
+use futures::future::join;
+use reqwest::{Client, StatusCode};
+use serde_json::{Value, json};
+use tokio::time::{sleep, timeout};
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{
+	Err, Result, err,
+	ruma::{OwnedRoomId, OwnedUserId, RoomId, UserId, events::StateEventType},
+};
+use tuwunel_service::{Services, users::Register};
+
+const CHILD_DB_ENV: &str = "TUWUNEL_DELAYED_EVENTS_DB";
+const CHILD_PHASE_ENV: &str = "TUWUNEL_DELAYED_EVENTS_PHASE";
+const OWNER_TOKEN: &str = "delayed-events-owner-access-token";
+const OTHER_TOKEN: &str = "delayed-events-other-access-token";
+
+/// Long enough that no delay the test does not send itself falls due.
+const LONG_DELAY_MS: u64 = 3_600_000;
+
+/// Falls due while the server is down between the two phases.
+const OVERDUE_DELAY_MS: u64 = 2_000;
+
+/// Exercises MSC4140 delayed events against a running server: scheduling,
+/// transaction replay, the sender-only update check, restart, cancel and send,
+/// the scheduler sending an event once due, and a schedule persisted across a
+/// restart, including an event which fell due while the server was down.
+#[test]
+fn delayed_events() -> Result {
+	if let Ok(phase) = var(CHILD_PHASE_ENV) {
+		return delayed_events_child(&phase);
+	}
+
+	let db = DatabasePath::new();
+
+	run_child(&db.0, "before-restart")?;
+
+	// The overdue event must fall due while no server is running.
+	std::thread::sleep(Duration::from_millis(OVERDUE_DELAY_MS));
+
+	run_child(&db.0, "after-restart")
+}
+
+fn delayed_events_child(phase: &str) -> Result {
+	let db_path = var(CHILD_DB_ENV)
+		.map(PathBuf::from)
+		.map_err(|e| err!("child database path is unavailable: {e}"))?;
+
+	match phase {
+		| "before-restart" => {
+			let pending = run_server(&db_path, &["fresh"], before_restart)?;
+
+			write(db_path.with_extension("state.json"), serde_json::to_vec(&pending)?)?;
+
+			Ok(())
+		},
+		| "after-restart" => {
+			let pending = serde_json::from_slice(&read(db_path.with_extension("state.json"))?)?;
+
+			run_server(&db_path, &["cleanup"], |services, client, base| {
+				after_restart(services, client, base, pending)
+			})
+		},
+		| phase => Err!("unknown delayed events child phase: {phase}"),
+	}
+}
+
+async fn before_restart(services: Arc<Services>, client: Client, base: String) -> Result<Value> {
+	wait_until_ready(&client, &base).await?;
+
+	register(&services, "delayedowner", OWNER_TOKEN).await?;
+	register(&services, "delayedother", OTHER_TOKEN).await?;
+
+	let owner = Api {
+		client: &client,
+		base: &base,
+		token: OWNER_TOKEN,
+	};
+	let other = Api {
+		client: &client,
+		base: &base,
+		token: OTHER_TOKEN,
+	};
+	let room_id = owner.create_room().await?;
+
+	other.join(&room_id).await?;
+
+	// A retried send answers with the delay_id the first one scheduled.
+	let delay_id = owner
+		.delayed_message(&room_id, "txn1", LONG_DELAY_MS)
+		.await?;
+
+	let replayed = owner
+		.delayed_message(&room_id, "txn1", LONG_DELAY_MS)
+		.await?;
+
+	if replayed != delay_id {
+		return Err!("transaction replay scheduled a second event: {replayed} != {delay_id}");
+	}
+
+	// Only the sender may act on its delayed event.
+	let status = other.update(&delay_id, "cancel").await?;
+	if status != StatusCode::NOT_FOUND {
+		return Err!("another user's cancel answered {status}");
+	}
+
+	let scheduled = services.delayed_events.get(&delay_id).await?;
+
+	sleep(Duration::from_millis(20)).await;
+	expect_ok(owner.update(&delay_id, "restart").await?, "restart")?;
+
+	let restarted = services.delayed_events.get(&delay_id).await?;
+	if restarted.running_since <= scheduled.running_since {
+		return Err!("restart did not postpone the event");
+	}
+
+	expect_ok(owner.update(&delay_id, "cancel").await?, "cancel")?;
+	if services
+		.delayed_events
+		.get(&delay_id)
+		.await
+		.is_ok()
+	{
+		return Err!("a cancelled event is still scheduled");
+	}
+
+	// Sending ahead of the deadline.
+	let delay_id = owner
+		.delayed_state(&room_id, "org.example.sent", LONG_DELAY_MS)
+		.await?;
+
+	expect_ok(owner.update(&delay_id, "send").await?, "send")?;
+	expect_state(&services, &room_id, "org.example.sent").await?;
+
+	// The scheduler sending once due.
+	let delay_id = owner
+		.delayed_state(&room_id, "org.example.scheduled", 200)
+		.await?;
+
+	expect_state(&services, &room_id, "org.example.scheduled").await?;
+	if services
+		.delayed_events
+		.get(&delay_id)
+		.await
+		.is_ok()
+	{
+		return Err!("a sent event is still scheduled");
+	}
+
+	let pending = owner
+		.delayed_state(&room_id, "org.example.pending", LONG_DELAY_MS)
+		.await?;
+
+	let pending_due = services.delayed_events.get(&pending).await?.due();
+
+	let overdue = owner
+		.delayed_state(&room_id, "org.example.overdue", OVERDUE_DELAY_MS)
+		.await?;
+
+	Ok(json!({
+		"room_id": room_id,
+		"pending": pending,
+		"pending_due": pending_due,
+		"overdue": overdue,
+	}))
+}
+
+async fn after_restart(
+	services: Arc<Services>,
+	client: Client,
+	base: String,
+	pending: Value,
+) -> Result {
+	wait_until_ready(&client, &base).await?;
+
+	let room_id: OwnedRoomId = string(&pending, "room_id")?.try_into()?;
+	let overdue = string(&pending, "overdue")?;
+
+	// Events which fell due while the server was down are sent at startup.
+	expect_state(&services, &room_id, "org.example.overdue").await?;
+	if services.delayed_events.get(overdue).await.is_ok() {
+		return Err!("an event sent at startup is still scheduled");
+	}
+
+	let delayed = services
+		.delayed_events
+		.get(string(&pending, "pending")?)
+		.await
+		.map_err(|e| err!("a pending event did not survive the restart: {e}"))?;
+
+	if Some(delayed.due()) != pending["pending_due"].as_u64() {
+		return Err!("the restart moved a pending event's deadline");
+	}
+
+	Ok(())
+}
+
+/// One user's authenticated view of the client API.
+struct Api<'a> {
+	client: &'a Client,
+	base: &'a str,
+	token: &'a str,
+}
+
+impl Api<'_> {
+	async fn create_room(&self) -> Result<OwnedRoomId> {
+		let response = self
+			.client
+			.post(format!("{}/_matrix/client/v3/createRoom", self.base))
+			.bearer_auth(self.token)
+			.json(&json!({ "preset": "public_chat" }))
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Value>()
+			.await?;
+
+		Ok(string(&response, "room_id")?.try_into()?)
+	}
+
+	async fn join(&self, room_id: &RoomId) -> Result {
+		self.client
+			.post(format!("{}/_matrix/client/v3/rooms/{room_id}/join", self.base))
+			.bearer_auth(self.token)
+			.json(&json!({}))
+			.send()
+			.await?
+			.error_for_status()?;
+
+		Ok(())
+	}
+
+	async fn delayed_message(
+		&self,
+		room_id: &RoomId,
+		txn_id: &str,
+		delay: u64,
+	) -> Result<String> {
+		let url = format!(
+			"{}/_matrix/client/v3/rooms/{room_id}/send/m.room.message/{txn_id}?org.matrix.\
+			 msc4140.delay={delay}",
+			self.base
+		);
+
+		let content = json!({ "msgtype": "m.text", "body": "later" });
+
+		self.delayed(self.client.put(url).json(&content))
+			.await
+	}
+
+	async fn delayed_state(
+		&self,
+		room_id: &RoomId,
+		event_type: &str,
+		delay: u64,
+	) -> Result<String> {
+		let url = format!(
+			"{}/_matrix/client/v3/rooms/{room_id}/state/{event_type}?org.matrix.msc4140.\
+			 delay={delay}",
+			self.base
+		);
+
+		let content = json!({ "delayed": true });
+
+		self.delayed(self.client.put(url).json(&content))
+			.await
+	}
+
+	async fn delayed(&self, request: reqwest::RequestBuilder) -> Result<String> {
+		let response = request
+			.bearer_auth(self.token)
+			.send()
+			.await?
+			.error_for_status()?
+			.json::<Value>()
+			.await?;
+
+		Ok(string(&response, "delay_id")?.to_owned())
+	}
+
+	async fn update(&self, delay_id: &str, action: &str) -> Result<StatusCode> {
+		let status = self
+			.client
+			.post(format!(
+				"{}/_matrix/client/unstable/org.matrix.msc4140/delayed_events/{delay_id}",
+				self.base
+			))
+			.bearer_auth(self.token)
+			.json(&json!({ "action": action }))
+			.send()
+			.await?
+			.status();
+
+		Ok(status)
+	}
+}
+
+fn expect_ok(status: StatusCode, action: &str) -> Result {
+	if !status.is_success() {
+		return Err!("the sender's {action} answered {status}");
+	}
+
+	Ok(())
+}
+
+/// Waits for the delayed state event of `event_type` to reach the room.
+async fn expect_state(services: &Services, room_id: &RoomId, event_type: &str) -> Result {
+	let event_type = StateEventType::from(event_type);
+
+	timeout(Duration::from_secs(10), async {
+		while services
+			.state_accessor
+			.room_state_get_id(room_id, &event_type, "")
+			.await
+			.is_err()
+		{
+			sleep(Duration::from_millis(20)).await;
+		}
+	})
+	.await
+	.map_err(|_| err!("delayed {event_type} was never sent"))
+}
+
+/// Register a local user and give it a device holding `token`.
+async fn register(services: &Services, localpart: &str, token: &str) -> Result<OwnedUserId> {
+	let user_id = UserId::parse_with_server_name(localpart, services.globals.server_name())?;
+
+	services
+		.users
+		.full_register(Register {
+			user_id: Some(&user_id),
+			password: Some("delayed-events-password"),
+			..Default::default()
+		})
+		.await?;
+
+	services
+		.users
+		.create_device(&user_id, None, (Some(token), None), None, None, None)
+		.await?;
+
+	Ok(user_id)
+}
+
+/// Wait for the listener to answer, which the boot does not itself await.
+async fn wait_until_ready(client: &Client, base: &str) -> Result {
+	let url = format!("{base}/_matrix/client/versions");
+
+	timeout(Duration::from_secs(10), async {
+		while client.get(&url).send().await.is_err() {
+			sleep(Duration::from_millis(20)).await;
+		}
+	})
+	.await
+	.map_err(|_| err!("server listener did not become ready"))
+}
+
+fn string<'a>(value: &'a Value, name: &str) -> Result<&'a str> {
+	value
+		.get(name)
+		.and_then(Value::as_str)
+		.ok_or_else(|| err!("missing {name} in {value}"))
+}
+
+struct DatabasePath(PathBuf);
+
+impl DatabasePath {
+	fn new() -> Self {
+		let root = var("TMPDIR").unwrap_or_else(|_| "/nvme/target/tmp".into());
+
+		Self(PathBuf::from(root).join(format!("tuwunel-delayed-events-{}", process_id())))
+	}
+}
+
+impl Drop for DatabasePath {
+	fn drop(&mut self) {
+		remove_dir_all(&self.0).ok();
+		remove_file(self.0.with_extension("state.json")).ok();
+	}
+}
+
+fn run_server<T, F, Fut>(db_path: &Path, test_modes: &[&str], exercise: F) -> Result<T>
+where
+	F: FnOnce(Arc<Services>, Client, String) -> Fut,
+	Fut: Future<Output = Result<T>>,
+{
+	let listener = TcpListener::bind(("127.0.0.1", 0))?;
+	let port = listener.local_addr()?.port();
+
+	let mut args = Args::default_test(test_modes);
+	args.option.extend([
+		format!("database_path=\"{}\"", db_path.display()),
+		"address=[\"127.0.0.1\"]".to_owned(),
+		format!("port={port}"),
+		"listening=true".to_owned(),
+		// The test starts multiple servers, so none may claim global tracing.
+		"log_enable=false".to_owned(),
+	]);
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+	let result = runtime.block_on(async {
+		let services = async_start(&server).await?;
+		let client = Client::builder()
+			.pool_max_idle_per_host(0)
+			.build()?;
+
+		let base = format!("http://127.0.0.1:{port}");
+
+		drop(listener);
+
+		let exercise = exercise(services.clone(), client, base);
+		let exercise = async {
+			let outcome = exercise.await;
+			let shutdown = server.server.shutdown();
+
+			outcome.and_then(|outcome| shutdown.map(|()| outcome))
+		};
+
+		let (run_result, outcome) = join(async_run(&server), exercise).await;
+
+		drop(services);
+		async_stop(&server).await?;
+		run_result?;
+
+		outcome
+	});
+
+	drop(server);
+	drop(runtime);
+
+	result
+}
+
+fn run_child(db_path: &Path, phase: &str) -> Result {
+	let output = Command::new(current_exe()?)
+		.env(CHILD_DB_ENV, db_path)
+		.env(CHILD_PHASE_ENV, phase)
+		.output()?;
+
+	if !output.status.success() {
+		return Err(err!(
+			"delayed events child {phase} failed with {}\nstdout:\n{}\nstderr:\n{}",
+			output.status,
+			String::from_utf8_lossy(&output.stdout),
+			String::from_utf8_lossy(&output.stderr),
+		));
+	}
+
+	Ok(())
+}
@@ -176,11 +176,8 @@ async fn send<T>(services: &Services, base: &str, request: T) -> Result<(u16, St
 	T: OutgoingRequest<Authentication = ServerSignatures, PathBuilder = SinglePath>,
 {
 	let server_name = services.globals.server_name().to_owned();
-	let auth = ServerSignaturesInput::new(
-		server_name.clone(),
-		server_name,
-		services.server_keys.keypair(),
-	);
+	let keypair = services.server_keys.keypair();
+	let auth = ServerSignaturesInput::new(server_name.clone(), server_name, &keypair);
 	let request = request.try_into_http_request::<Vec<u8>>(base, auth, ())?;
 	let response = services
 		.client
@@ -0,0 +1,97 @@
+#![cfg(test)]
+
+use std::{fs::remove_dir_all, process::id as process_id};
+
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{Err, Result, ruma::Mxc};
+use tuwunel_service::Services;
+
+/// Two uploads of the same content share one stored file, which outlives the
+/// first delete and goes with the last.
+#[test]
+fn shared_media_file_goes_with_its_last_reference() -> Result {
+	// Isolate the database under /tmp so parallel test binaries do not contend.
+	let db_path = format!("/tmp/tuwunel-test-media-content-refs-{}", process_id());
+
+	let mut args = Args::default_test(&["fresh", "cleanup"]);
+	args.maintenance = true;
+	args.option
+		.push(format!("database_path=\"{db_path}\""));
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+
+	let result: Result = runtime.block_on(async {
+		let services = async_start(&server).await?;
+
+		let outcome = shared_file_survives_one_delete(&services).await;
+
+		server.server.shutdown()?;
+		drop(services);
+
+		async_run(&server).await?;
+		async_stop(&server).await?;
+
+		outcome
+	});
+
+	drop(runtime);
+
+	remove_dir_all(&db_path).ok();
+
+	result
+}
+
+async fn shared_file_survives_one_delete(services: &Services) -> Result {
+	let server_name = services.globals.server_name();
+	let first = Mxc {
+		server_name,
+		media_id: "SharedContentFirst",
+	};
+	let second = Mxc {
+		server_name,
+		media_id: "SharedContentSecond",
+	};
+	let content = b"the same sticker, uploaded twice";
+
+	for mxc in [&first, &second] {
+		services
+			.media
+			.create(mxc, None, None, Some("image/png"), content)
+			.await?;
+	}
+
+	if stored_files(services) != 1 {
+		return Err!("identical uploads are not stored once");
+	}
+
+	services.media.delete(&first).await?;
+
+	if stored_files(services) != 1 {
+		return Err!("the shared file went with its first reference");
+	}
+
+	match services.media.get(&second, None).await {
+		| Ok(media) if media.content == content => {},
+		| Ok(_) => return Err!("the remaining upload lost its content"),
+		| Err(e) => return Err!("the remaining upload is unreadable: {e}"),
+	}
+
+	services.media.delete(&second).await?;
+
+	if stored_files(services) != 0 {
+		return Err!("the shared file outlived its last reference");
+	}
+
+	Ok(())
+}
+
+/// Files stored under content-addressed paths in the media directory.
+fn stored_files(services: &Services) -> usize {
+	services
+		.media
+		.get_media_dir()
+		.join("sha256")
+		.read_dir()
+		.map_or(0, Iterator::count)
+}
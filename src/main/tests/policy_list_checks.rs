@@ -0,0 +1,145 @@
+#![cfg(test)]
+
+use std::{fs::remove_dir_all, process::id as process_id};
+
+use serde_json::{json, value::to_raw_value};
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{
+	Err, Result,
+	pdu::PduBuilder,
+	ruma::{RoomId, ServerName, UserId},
+};
+use tuwunel_service::Services;
+
+/// Joins, invites and federation matched by a rule of a subscribed policy room
+/// are refused, and each refusal is recorded for the admin room.
+#[test]
+fn policy_list_rules_refuse_and_record() -> Result {
+	// Isolate the database under /tmp so parallel test binaries do not contend.
+	let db_path = format!("/tmp/tuwunel-test-policy-list-checks-{}", process_id());
+
+	let mut args = Args::default_test(&["fresh", "cleanup"]);
+	args.maintenance = true;
+	args.option.extend([
+		format!("database_path=\"{db_path}\""),
+		"policy_list_rooms=[\"#admins:localhost\"]".to_owned(),
+	]);
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+
+	let result: Result = runtime.block_on(async {
+		let services = async_start(&server).await?;
+
+		let outcome = rules_refuse_and_record(&services).await;
+
+		server.server.shutdown()?;
+		drop(services);
+
+		async_run(&server).await?;
+		async_stop(&server).await?;
+
+		outcome
+	});
+
+	drop(runtime);
+
+	remove_dir_all(&db_path).ok();
+
+	result
+}
+
+async fn rules_refuse_and_record(services: &Services) -> Result {
+	let room_id = services.admin.get_admin_room().await?;
+	let server_user = &services.globals.server_user;
+	let state_lock = services.state.mutex.lock(&room_id).await;
+
+	let rules = [
+		("m.policy.rule.user", "@spammer:*"),
+		("m.policy.rule.room", "!banned:*"),
+		("m.policy.rule.server", "evil.example"),
+	];
+
+	for (event_type, entity) in rules {
+		let content = json!({
+			"entity": entity,
+			"recommendation": "m.ban",
+			"reason": "spam",
+		});
+
+		let rule = PduBuilder {
+			event_type: event_type.into(),
+			content: to_raw_value(&content).map(Into::into)?,
+			state_key: Some(entity.into()),
+			..Default::default()
+		};
+
+		services
+			.timeline
+			.build_and_append_pdu(rule, server_user, &room_id, &state_lock)
+			.await?;
+	}
+
+	drop(state_lock);
+	services.policy_lists.subscribe().await;
+
+	let alice = UserId::parse("@alice:localhost")?;
+	let spammer = UserId::parse("@spammer:evil.example")?;
+	let banned_room = RoomId::parse("!banned:localhost")?;
+	let evil = ServerName::parse("evil.example")?;
+
+	if services
+		.policy_lists
+		.check_join(&alice, &room_id)
+		.await
+		.is_err()
+	{
+		return Err!("a join matching no rule was refused");
+	}
+
+	if services
+		.policy_lists
+		.check_join(&spammer, &room_id)
+		.await
+		.is_ok()
+	{
+		return Err!("a join by a banned user was allowed");
+	}
+
+	if services
+		.policy_lists
+		.check_invite(&alice, &alice, &banned_room)
+		.await
+		.is_ok()
+	{
+		return Err!("an invite to a banned room was allowed");
+	}
+
+	if services
+		.policy_lists
+		.check_server("federation", &evil)
+		.await
+		.is_ok()
+	{
+		return Err!("federation from a banned server was allowed");
+	}
+
+	let recorded: Vec<_> = services
+		.policy_lists
+		.rejections()
+		.into_iter()
+		.map(|rejection| (rejection.action, rejection.entity))
+		.collect();
+
+	let expected = [
+		("federation", "evil.example".to_owned()),
+		("invite", banned_room.to_string()),
+		("join", spammer.to_string()),
+	];
+
+	if recorded != expected {
+		return Err!("unexpected recorded rejections: {recorded:?}");
+	}
+
+	Ok(())
+}
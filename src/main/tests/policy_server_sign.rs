@@ -0,0 +1,168 @@
+#![cfg(test)]
+
+use std::{collections::BTreeMap, fs::remove_dir_all, process::id as process_id};
+
+use serde_json::{json, value::to_raw_value};
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{
+	Err, Result, err,
+	matrix::room_version,
+	pdu::PduBuilder,
+	ruma::{
+		CanonicalJsonObject, CanonicalJsonValue, OwnedEventId, OwnedRoomId,
+		canonical_json::redact,
+		events::room::message::RoomMessageEventContent,
+		signatures::{PublicKeyMap, verify_json},
+	},
+};
+use tuwunel_service::{Services, policy_server::Decision};
+
+/// Key id the policy server signature is published under.
+const POLICY_KEY_ID: &str = "ed25519:policy_server";
+
+/// As the room's policy server, events breaking a rule are refused with its
+/// reason, and others are signed with the key `m.room.policy` publishes.
+#[test]
+fn policy_server_refuses_or_signs() -> Result {
+	// Isolate the database under /tmp so parallel test binaries do not contend.
+	let db_path = format!("/tmp/tuwunel-test-policy-server-sign-{}", process_id());
+
+	let mut args = Args::default_test(&["fresh", "cleanup"]);
+	args.maintenance = true;
+	args.option.extend([
+		format!("database_path=\"{db_path}\""),
+		"allow_policy_server=true".to_owned(),
+		"enable_policy_servers=false".to_owned(),
+		"policy_server_keywords=[\"forbidden\"]".to_owned(),
+		"policy_server_new_member_secs=3600".to_owned(),
+	]);
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+
+	let result: Result = runtime.block_on(async {
+		let services = async_start(&server).await?;
+
+		let outcome = refuses_or_signs(&services).await;
+
+		server.server.shutdown()?;
+		drop(services);
+
+		async_run(&server).await?;
+		async_stop(&server).await?;
+
+		outcome
+	});
+
+	drop(runtime);
+
+	remove_dir_all(&db_path).ok();
+
+	result
+}
+
+async fn refuses_or_signs(services: &Services) -> Result {
+	let room_id = services.admin.get_admin_room().await?;
+	let (_, verify_key) = services.server_keys.active_verify_key();
+	let policy_key = verify_key.key.encode();
+
+	let policy = json!({
+		"via": services.globals.server_name(),
+		"public_keys": { "ed25519": policy_key },
+	});
+
+	let state_lock = services.state.mutex.lock(&room_id).await;
+	services
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder {
+				event_type: "m.room.policy".into(),
+				content: to_raw_value(&policy).map(Into::into)?,
+				state_key: Some("".into()),
+				..Default::default()
+			},
+			&services.globals.server_user,
+			&room_id,
+			&state_lock,
+		)
+		.await?;
+
+	drop(state_lock);
+
+	match decide(services, &room_id, "this is forbidden").await? {
+		| Decision::Refused(reason) if reason.contains("blocked keyword") => {},
+		| decision => return Err!("a blocked keyword was not refused: {decision:?}"),
+	}
+
+	// the server user joined the admin room at startup, well within the hour
+	match decide(services, &room_id, "see https://example.com").await? {
+		| Decision::Refused(reason) if reason.contains("New members") => {},
+		| decision => return Err!("a link from a new member was not refused: {decision:?}"),
+	}
+
+	let (event_id, value) = event(services, &room_id, "hello").await?;
+	let signature = match services
+		.policy_server
+		.sign_pdu(&room_id, &event_id, value.clone())
+		.await?
+	{
+		| Decision::Signed(signature) => signature,
+		| decision => return Err!("a plain message was not signed: {decision:?}"),
+	};
+
+	let server_name = services.globals.server_name().to_string();
+	let room_version = services.state.get_room_version(&room_id).await?;
+	let rules = room_version::rules(&room_version)?;
+	let mut redacted =
+		redact(value, &rules.redaction, None).map_err(|e| err!("Cannot redact: {e}"))?;
+	redacted.insert(
+		"signatures".to_owned(),
+		CanonicalJsonValue::Object(CanonicalJsonObject::from([(
+			server_name.clone(),
+			CanonicalJsonValue::Object(CanonicalJsonObject::from([(
+				POLICY_KEY_ID.to_owned(),
+				CanonicalJsonValue::String(signature),
+			)])),
+		)])),
+	);
+
+	let keys: PublicKeyMap = BTreeMap::from([(
+		server_name,
+		BTreeMap::from([(POLICY_KEY_ID.to_owned(), verify_key.key)]),
+	)]);
+
+	if let Err(e) = verify_json(&keys, &redacted) {
+		return Err!("the signature does not verify against the m.room.policy key: {e}");
+	}
+
+	Ok(())
+}
+
+async fn decide(services: &Services, room_id: &OwnedRoomId, body: &str) -> Result<Decision> {
+	let (event_id, value) = event(services, room_id, body).await?;
+
+	services
+		.policy_server
+		.sign_pdu(room_id, &event_id, value)
+		.await
+}
+
+/// A message from the server user in federation format, built but not sent.
+async fn event(
+	services: &Services,
+	room_id: &OwnedRoomId,
+	body: &str,
+) -> Result<(OwnedEventId, CanonicalJsonObject)> {
+	let state_lock = services.state.mutex.lock(room_id).await;
+	let (pdu, value) = services
+		.timeline
+		.create_hash_and_sign_event(
+			PduBuilder::timeline(&RoomMessageEventContent::text_plain(body)),
+			&services.globals.server_user,
+			room_id,
+			&state_lock,
+		)
+		.await?;
+
+	Ok((pdu.event_id, value))
+}
@@ -0,0 +1,238 @@
+#![cfg(test)]
+
+use std::{
+	fs::remove_dir_all,
+	net::TcpListener as StdTcpListener,
+	process::id as process_id,
+	sync::{
+		Arc,
+		atomic::{AtomicUsize, Ordering},
+	},
+};
+
+use tokio::{
+	io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+	net::{TcpListener, TcpStream},
+	spawn,
+	task::JoinHandle,
+};
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{
+	Err, Result,
+	pdu::PduBuilder,
+	ruma::{
+		MilliSecondsSinceUnixEpoch, OwnedEventId, RoomId, UserId,
+		api::client::push::{
+			EmailPusherData, PusherIds, PusherInit, PusherKind,
+			set_pusher::v3::Request as SetPusherRequest,
+		},
+		device_id,
+		events::room::message::RoomMessageEventContent,
+		push::Ruleset,
+		thirdparty::Medium,
+	},
+};
+use tuwunel_service::Services;
+
+const ADDRESS: &str = "alice@example.org";
+
+/// Messages the SMTP sink accepted, across every boot.
+type Sent = Arc<AtomicUsize>;
+
+/// A pusher mailed a digest is not mailed again within the throttle, neither
+/// by the same server nor after it restarts.
+#[test]
+fn email_throttle_survives_restart() -> Result {
+	// Isolate the database under /tmp so parallel test binaries do not contend.
+	let db_path = format!("/tmp/tuwunel-test-pusher-email-throttle-{}", process_id());
+
+	// Bound before the server so its address can be configured.
+	let smtp = StdTcpListener::bind("127.0.0.1:0")?;
+	smtp.set_nonblocking(true)?;
+	let sent = Sent::default();
+
+	let result = boot(&db_path, &["fresh"], &smtp, &sent, async |services| {
+		mail_then_throttle(services, &sent).await
+	})
+	.and_then(|()| {
+		boot(&db_path, &["cleanup"], &smtp, &sent, async |services| {
+			services.pusher.send_email_digests().await;
+
+			match sent.load(Ordering::Acquire) {
+				| 1 => Ok(()),
+				| count => Err!("the throttle was lost on restart; {count} digests were sent"),
+			}
+		})
+	});
+
+	remove_dir_all(&db_path).ok();
+
+	result
+}
+
+async fn mail_then_throttle(services: &Services, sent: &Sent) -> Result {
+	let alice = UserId::parse("@alice:localhost")?;
+	let room_id = services.admin.get_admin_room().await?;
+	let now = MilliSecondsSinceUnixEpoch::now();
+
+	services
+		.threepid
+		.put_binding(&alice, ADDRESS, Medium::Email, now, now)
+		.await;
+
+	let pusher = PusherInit {
+		ids: PusherIds::new(ADDRESS.to_owned(), "m.email".to_owned()),
+		kind: PusherKind::Email(EmailPusherData::new()),
+		app_display_name: "Email Notifications".into(),
+		device_display_name: "Email Notifications".into(),
+		profile_tag: None,
+		lang: "en".into(),
+	}
+	.into();
+
+	services
+		.pusher
+		.set_pusher(&alice, device_id!("EMAIL"), &SetPusherRequest::post(pusher).action)
+		.await?;
+
+	let pusher = services
+		.pusher
+		.get_pusher(&alice, ADDRESS)
+		.await?;
+	let ruleset = Ruleset::server_default(&alice);
+
+	for (body, expected) in [("first", 1), ("second", 1)] {
+		let event_id = send_message(services, &room_id, body).await?;
+		let pdu = services.timeline.get_pdu(&event_id).await?;
+
+		services
+			.pusher
+			.send_push_notice(&alice, &pusher, &ruleset, &pdu)
+			.await?;
+
+		services.pusher.send_email_digests().await;
+
+		let count = sent.load(Ordering::Acquire);
+		if count != expected {
+			return Err!(
+				"{count} digests were sent after the {body} message, expected {expected}"
+			);
+		}
+	}
+
+	Ok(())
+}
+
+async fn send_message(services: &Services, room_id: &RoomId, body: &str) -> Result<OwnedEventId> {
+	let state_lock = services.state.mutex.lock(room_id).await;
+
+	services
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder::timeline(&RoomMessageEventContent::text_plain(body)),
+			&services.globals.server_user,
+			room_id,
+			&state_lock,
+		)
+		.await
+}
+
+/// Runs the server over the database once, mailing through the sink.
+fn boot(
+	db_path: &str,
+	test_modes: &[&str],
+	smtp: &StdTcpListener,
+	sent: &Sent,
+	exercise: impl AsyncFnOnce(&Services) -> Result,
+) -> Result {
+	let mut args = Args::default_test(test_modes);
+	args.maintenance = true;
+	args.option.extend([
+		format!("database_path=\"{db_path}\""),
+		format!("smtp.connection_uri=\"smtp://{}\"", smtp.local_addr()?),
+		"smtp.sender=\"noreply@example.org\"".to_owned(),
+		"push_email_delay_secs=0".to_owned(),
+		"push_email_throttle_secs=86400".to_owned(),
+	]);
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+
+	let result: Result = runtime.block_on(async {
+		let _sink = AbortOnDrop(spawn(smtp_sink(
+			TcpListener::from_std(smtp.try_clone()?)?,
+			sent.clone(),
+		)));
+
+		let services = async_start(&server).await?;
+
+		let outcome = exercise(&services).await;
+
+		server.server.shutdown()?;
+		drop(services);
+
+		async_run(&server).await?;
+		async_stop(&server).await?;
+
+		outcome
+	});
+
+	drop(runtime);
+
+	result
+}
+
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+	fn drop(&mut self) { self.0.abort(); }
+}
+
+/// Accepts every message offered, counting each.
+async fn smtp_sink(listener: TcpListener, sent: Sent) {
+	while let Ok((socket, _)) = listener.accept().await {
+		let sent = sent.clone();
+		spawn(async move {
+			smtp_session(socket, &sent).await.ok();
+		});
+	}
+}
+
+async fn smtp_session(socket: TcpStream, sent: &Sent) -> Result {
+	let (reader, mut writer) = socket.into_split();
+	let mut lines = BufReader::new(reader).lines();
+
+	writer
+		.write_all(b"220 localhost ESMTP\r\n")
+		.await?;
+
+	while let Some(line) = lines.next_line().await? {
+		match line.as_str() {
+			| "DATA" => {
+				writer
+					.write_all(b"354 End data with <CRLF>.<CRLF>\r\n")
+					.await?;
+
+				while let Some(line) = lines.next_line().await? {
+					if line == "." {
+						break;
+					}
+				}
+
+				sent.fetch_add(1, Ordering::AcqRel);
+				writer
+					.write_all(b"250 Message accepted\r\n")
+					.await?;
+			},
+			| "QUIT" => {
+				writer.write_all(b"221 Bye\r\n").await?;
+				break;
+			},
+			| _ if line.starts_with("EHLO ") || line.starts_with("HELO ") =>
+				writer.write_all(b"250 localhost\r\n").await?,
+			| _ => writer.write_all(b"250 OK\r\n").await?,
+		}
+	}
+
+	Ok(())
+}
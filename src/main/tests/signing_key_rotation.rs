@@ -0,0 +1,112 @@
+#![cfg(test)]
+
+use std::{
+	env::var, fs::remove_dir_all, path::PathBuf, process::id as process_id, time::Duration,
+};
+
+use tokio::time::sleep;
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{
+	Err, Result,
+	ruma::{MilliSecondsSinceUnixEpoch, OwnedServerSigningKeyId},
+};
+use tuwunel_service::Services;
+
+struct DatabasePath(PathBuf);
+
+impl Drop for DatabasePath {
+	fn drop(&mut self) { remove_dir_all(&self.0).ok(); }
+}
+
+#[test]
+fn rotated_keys_stay_published_as_expired() -> Result {
+	let root = var("TMPDIR").unwrap_or_else(|_| "/nvme/target/tmp".into());
+	let db_path = DatabasePath(
+		PathBuf::from(root).join(format!("tuwunel-signing-key-rotation-{}", process_id())),
+	);
+
+	let mut args = Args::default_test(&["fresh", "cleanup"]);
+	args.maintenance = true;
+	args.option
+		.push(format!("database_path={:?}", db_path.0));
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+	let result = runtime.block_on(async {
+		let services = async_start(&server).await?;
+		let outcome = exercise(&services).await;
+		let shutdown = server.server.shutdown();
+
+		drop(services);
+
+		let run = async_run(&server).await;
+		let stop = async_stop(&server).await;
+
+		outcome.and(shutdown).and(run).and(stop)
+	});
+
+	drop(runtime);
+
+	result
+}
+
+async fn exercise(services: &Services) -> Result {
+	let server_keys = &services.server_keys;
+	let (first_key_id, first_key) = server_keys.active_verify_key();
+
+	let second_key_id = server_keys.rotate_keypair().await?;
+	if second_key_id == first_key_id {
+		return Err!("rotation kept {first_key_id} active");
+	}
+
+	let first_expired_ts = expired_ts(services, &first_key_id).await?;
+
+	// expiry is fixed at rotation rather than renewed as the keys are served
+	sleep(Duration::from_millis(5)).await;
+	if expired_ts(services, &first_key_id).await? != first_expired_ts {
+		return Err!("the expiry of {first_key_id} moved after rotation");
+	}
+
+	let third_key_id = server_keys.rotate_keypair().await?;
+	let keys = server_keys.local_server_keys().await;
+	if !keys.verify_keys.contains_key(&third_key_id) || keys.verify_keys.len() != 1 {
+		return Err!("{third_key_id} is not the only active key");
+	}
+
+	if expired_ts(services, &first_key_id).await? != first_expired_ts {
+		return Err!("the expiry of {first_key_id} moved at the next rotation");
+	}
+
+	if expired_ts(services, &second_key_id).await? < first_expired_ts {
+		return Err!("{second_key_id} expired before the key it replaced");
+	}
+
+	let server_name = services.globals.server_name();
+	let verify_keys = server_keys.verify_keys_for(server_name).await;
+	if verify_keys
+		.get(&first_key_id)
+		.is_none_or(|key| key.key != first_key.key)
+	{
+		return Err!("{first_key_id} no longer verifies events of this server");
+	}
+
+	for key_id in [&second_key_id, &third_key_id] {
+		if !verify_keys.contains_key(key_id) {
+			return Err!("{key_id} does not verify events of this server");
+		}
+	}
+
+	Ok(())
+}
+
+async fn expired_ts(
+	services: &Services,
+	key_id: &OwnedServerSigningKeyId,
+) -> Result<MilliSecondsSinceUnixEpoch> {
+	let keys = services.server_keys.local_server_keys().await;
+	let Some(old) = keys.old_verify_keys.get(key_id) else {
+		return Err!("{key_id} is not published as an old verify key");
+	};
+
+	Ok(old.expired_ts)
+}
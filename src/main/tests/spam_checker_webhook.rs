@@ -0,0 +1,284 @@
+#![cfg(test)]
+
+use std::{
+	fs::remove_dir_all,
+	future::pending,
+	net::TcpListener as StdTcpListener,
+	process::id as process_id,
+	str::from_utf8,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::{TcpListener, TcpStream},
+	spawn,
+	task::JoinHandle,
+};
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{
+	Err, Result,
+	matrix::PduBuilder,
+	ruma::{RoomId, UserId, events::room::message::RoomMessageEventContent},
+};
+use tuwunel_service::{
+	Services,
+	spam_checker::{Checker, Verdict},
+};
+
+/// Order in which the webhook and the registered checkers were asked.
+type Log = Arc<Mutex<Vec<String>>>;
+
+/// Bound on a check against a webhook configured with a one-second timeout.
+const FAILED_CHECK_LIMIT: Duration = Duration::from_secs(4);
+
+/// The webhook is asked before the registered checkers, the first denial
+/// refuses the action without asking those after it, and a webhook which times
+/// out or drops the request refuses it when configured to fail closed.
+#[test]
+fn spam_checkers_dispatch_in_order() -> Result {
+	// Isolate the database under /tmp so parallel test binaries do not contend.
+	let db_path = format!("/tmp/tuwunel-test-spam-checker-webhook-{}", process_id());
+
+	// Bound before the server so its address can be configured.
+	let listener = StdTcpListener::bind("127.0.0.1:0")?;
+	listener.set_nonblocking(true)?;
+	let url = format!("http://{}/check", listener.local_addr()?);
+
+	let mut args = Args::default_test(&["fresh", "cleanup"]);
+	args.maintenance = true;
+	args.option.extend([
+		format!("database_path=\"{db_path}\""),
+		format!("spam_checker_webhook_url=\"{url}\""),
+		"spam_checker_webhook_timeout=1".to_owned(),
+		"spam_checker_webhook_fail_closed=true".to_owned(),
+		"ip_range_denylist=[]".to_owned(),
+	]);
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+
+	let result: Result = runtime.block_on(async {
+		let log = Log::default();
+		let _stub =
+			AbortOnDrop(spawn(stub_webhook(TcpListener::from_std(listener)?, log.clone())));
+
+		let services = async_start(&server).await?;
+
+		let outcome = dispatch_in_order(&services, &log).await;
+
+		server.server.shutdown()?;
+		drop(services);
+
+		async_run(&server).await?;
+		async_stop(&server).await?;
+
+		outcome
+	});
+
+	drop(runtime);
+
+	remove_dir_all(&db_path).ok();
+
+	result
+}
+
+async fn dispatch_in_order(services: &Services, log: &Log) -> Result {
+	let spam_checker = &services.spam_checker;
+	spam_checker.register(Arc::new(Recorder { name: "first", log: log.clone() }));
+	spam_checker.register(Arc::new(Recorder { name: "second", log: log.clone() }));
+
+	let alice = UserId::parse("@alice:localhost")?;
+	let bob = UserId::parse("@bob:localhost")?;
+	let room_id = RoomId::parse("!room:localhost")?;
+	let message = PduBuilder::timeline(&RoomMessageEventContent::text_plain("hello"));
+
+	spam_checker
+		.check_event_for_spam(&alice, &room_id, &message)
+		.await?;
+
+	expect_asked(log, &[
+		"webhook check_event_for_spam",
+		"first check_event_for_spam",
+		"second check_event_for_spam",
+	])?;
+
+	match spam_checker.user_may_create_room(&alice).await {
+		| Err(e) if e.message().contains("Rooms are paused") => {},
+		| Err(e) => return Err!("the webhook's denial lost its reason: {e}"),
+		| Ok(()) => return Err!("a room creation denied by the webhook was allowed"),
+	}
+
+	expect_asked(log, &["webhook user_may_create_room"])?;
+
+	match spam_checker
+		.user_may_invite(&alice, &bob, &room_id)
+		.await
+	{
+		| Err(e) if e.message().contains("Invites are paused") => {},
+		| Err(e) => return Err!("the checker's denial lost its reason: {e}"),
+		| Ok(()) => return Err!("an invite denied by a checker was allowed"),
+	}
+
+	expect_asked(log, &["webhook user_may_invite", "first user_may_invite"])?;
+
+	expect_unavailable("timed out", spam_checker.user_may_join_room(&alice, &room_id, false))
+		.await?;
+
+	expect_unavailable("dropped", spam_checker.check_registration_for_spam(&bob, None)).await?;
+
+	expect_asked(log, &["webhook user_may_join_room", "webhook check_registration_for_spam"])
+}
+
+/// Checks that an action the webhook failed to answer was refused in time.
+async fn expect_unavailable(action: &str, check: impl Future<Output = Result>) -> Result {
+	let started = Instant::now();
+	match check.await {
+		| Err(e) if e.message().contains("unavailable") => {},
+		| result => return Err!("a check the webhook {action} was not refused: {result:?}"),
+	}
+
+	if started.elapsed() > FAILED_CHECK_LIMIT {
+		return Err!("a check the webhook {action} outlived its timeout");
+	}
+
+	Ok(())
+}
+
+/// Checks the callbacks asked since the last call, in order.
+fn expect_asked(log: &Log, expected: &[&str]) -> Result {
+	let asked: Vec<_> = log.lock().expect("locked").drain(..).collect();
+
+	if asked != expected {
+		return Err!("checkers were asked {asked:?}, expected {expected:?}");
+	}
+
+	Ok(())
+}
+
+/// Records each check; the first of them refuses invites.
+struct Recorder {
+	name: &'static str,
+	log: Log,
+}
+
+impl Recorder {
+	fn record(&self, callback: &str) {
+		self.log
+			.lock()
+			.expect("locked")
+			.push(format!("{} {callback}", self.name));
+	}
+}
+
+#[async_trait]
+impl Checker for Recorder {
+	async fn check_event_for_spam(
+		&self,
+		_sender: &UserId,
+		_room_id: &RoomId,
+		_event: &PduBuilder,
+	) -> Verdict {
+		self.record("check_event_for_spam");
+		Verdict::Allow
+	}
+
+	async fn user_may_invite(
+		&self,
+		_inviter: &UserId,
+		_invitee: &UserId,
+		_room_id: &RoomId,
+	) -> Verdict {
+		self.record("user_may_invite");
+		match self.name {
+			| "first" => Verdict::Deny("Invites are paused".to_owned()),
+			| _ => Verdict::Allow,
+		}
+	}
+
+	async fn user_may_create_room(&self, _user_id: &UserId) -> Verdict {
+		self.record("user_may_create_room");
+		Verdict::Allow
+	}
+}
+
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+	fn drop(&mut self) { self.0.abort(); }
+}
+
+/// Denies room creation, never answers joins, drops registrations unanswered
+/// and allows everything else.
+async fn stub_webhook(listener: TcpListener, log: Log) {
+	while let Ok((mut socket, _)) = listener.accept().await {
+		let log = log.clone();
+		spawn(async move {
+			let Some(body) = read_request(&mut socket).await else {
+				return;
+			};
+
+			let check: Value = serde_json::from_slice(&body).unwrap_or_default();
+			let callback = check["callback"].as_str().unwrap_or_default();
+			log.lock()
+				.expect("locked")
+				.push(format!("webhook {callback}"));
+
+			let answer = match callback {
+				| "user_may_join_room" => return pending().await,
+				| "check_registration_for_spam" => return,
+				| "user_may_create_room" => json!({"allow": false, "reason": "Rooms are paused"}),
+				| _ => json!({"allow": true}),
+			};
+
+			let answer = answer.to_string();
+			let response = format!(
+				"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: \
+				 {}\r\nConnection: close\r\n\r\n{answer}",
+				answer.len(),
+			);
+
+			socket.write_all(response.as_bytes()).await.ok();
+			socket.flush().await.ok();
+		});
+	}
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<Vec<u8>> {
+	let mut buf = Vec::new();
+	let mut chunk = [0_u8; 4096];
+	loop {
+		if let Some(head_end) = buf
+			.windows(4)
+			.position(|window| window == b"\r\n\r\n")
+		{
+			let content_length = content_length(&buf[..head_end])?;
+			let body_start = head_end.checked_add(4)?;
+			let body_end = body_start.checked_add(content_length)?;
+			if buf.len() >= body_end {
+				return Some(buf[body_start..body_end].to_vec());
+			}
+		}
+
+		let read = socket.read(&mut chunk).await.ok()?;
+		if read == 0 {
+			return None;
+		}
+
+		buf.extend_from_slice(&chunk[..read]);
+	}
+}
+
+fn content_length(head: &[u8]) -> Option<usize> {
+	from_utf8(head)
+		.ok()?
+		.lines()
+		.find_map(|line| {
+			line.split_once(':')
+				.filter(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+		})
+		.and_then(|(_, value)| value.trim().parse().ok())
+}
@@ -0,0 +1,295 @@
+#![cfg(test)]
+
+use std::{fs::remove_dir_all, process::id as process_id};
+
+use futures::{StreamExt, future::join_all};
+use serde_json::{json, value::to_raw_value};
+use tuwunel::{Args, Runtime, Server, async_run, async_start, async_stop};
+use tuwunel_core::{
+	Err, Result,
+	pdu::PduBuilder,
+	ruma::{
+		EventId, OwnedEventId, RoomId, UserId,
+		events::room::message::RoomMessageEventContent,
+		push::{Action, Ruleset},
+		serde::Raw,
+	},
+};
+use tuwunel_service::{Services, pusher::Evaluate};
+
+/// Subscriptions to a thread are recorded, refused when they conflict with a
+/// later unsubscription, paged through as changes, kept consistent under
+/// concurrent changes, and decide whether the thread's events notify.
+#[test]
+fn thread_subscriptions() -> Result {
+	// Isolate the database under /tmp so parallel test binaries do not contend.
+	let db_path = format!("/tmp/tuwunel-test-thread-subscriptions-{}", process_id());
+
+	let mut args = Args::default_test(&["fresh", "cleanup"]);
+	args.maintenance = true;
+	args.option
+		.push(format!("database_path=\"{db_path}\""));
+
+	let runtime = Runtime::new(Some(&args))?;
+	let server = Server::new(Some(&args), Some(&runtime))?;
+
+	let result: Result = runtime.block_on(async {
+		let services = async_start(&server).await?;
+
+		let outcome = async {
+			subscribe_and_unsubscribe(&services).await?;
+			page_changes(&services).await?;
+			concurrent_changes(&services).await?;
+			suppress_push(&services).await
+		}
+		.await;
+
+		server.server.shutdown()?;
+		drop(services);
+
+		async_run(&server).await?;
+		async_stop(&server).await?;
+
+		outcome
+	});
+
+	drop(runtime);
+
+	remove_dir_all(&db_path).ok();
+
+	result
+}
+
+async fn subscribe_and_unsubscribe(services: &Services) -> Result {
+	let threads = &services.threads;
+	let alice = UserId::parse("@alice:localhost")?;
+	let room_id = services.admin.get_admin_room().await?;
+	let root = send_message(services, &room_id, "root").await?;
+
+	threads
+		.subscribe(&alice, &room_id, &root, None)
+		.await?;
+
+	match threads
+		.get_subscription(&alice, &room_id, &root)
+		.await
+	{
+		| Some(subscription) if subscription.subscribed && !subscription.automatic => {},
+		| subscription => return Err!("not subscribed on request: {subscription:?}"),
+	}
+
+	threads
+		.unsubscribe(&alice, &room_id, &root)
+		.await?;
+
+	if threads
+		.is_subscribed(&alice, &room_id, &root)
+		.await
+	{
+		return Err!("still subscribed after unsubscribing");
+	}
+
+	if threads
+		.unsubscribe(&alice, &room_id, &root)
+		.await
+		.is_ok()
+	{
+		return Err!("unsubscribed twice from the same thread");
+	}
+
+	// the root was sent before the unsubscription, so it cannot cause one
+	if threads
+		.subscribe(&alice, &room_id, &root, Some(&root))
+		.await
+		.is_ok()
+	{
+		return Err!("an automatic subscription overrode a later unsubscription");
+	}
+
+	let reply = send_message(services, &room_id, "reply").await?;
+	threads
+		.subscribe(&alice, &room_id, &root, Some(&reply))
+		.await?;
+
+	match threads
+		.get_subscription(&alice, &room_id, &root)
+		.await
+	{
+		| Some(subscription) if subscription.subscribed && subscription.automatic => Ok(()),
+		| subscription => Err!("not subscribed automatically: {subscription:?}"),
+	}
+}
+
+async fn page_changes(services: &Services) -> Result {
+	let threads = &services.threads;
+	let bob = UserId::parse("@bob:localhost")?;
+	let room_id = RoomId::parse("!paging:localhost")?;
+	let roots: Vec<OwnedEventId> = ["$first", "$second", "$third"]
+		.into_iter()
+		.map(EventId::parse)
+		.collect::<Result<_, _>>()?;
+
+	for root in &roots {
+		threads
+			.subscribe(&bob, &room_id, root, None)
+			.await?;
+	}
+
+	// the latest change to a thread replaces its earlier one
+	threads
+		.unsubscribe(&bob, &room_id, &roots[0])
+		.await?;
+
+	let forward: Vec<_> = threads
+		.subscription_changes(&bob, 0, u64::MAX)
+		.collect()
+		.await;
+
+	let order: Vec<_> = forward
+		.iter()
+		.map(|(_, change)| (change.thread_root.as_str(), change.subscribed))
+		.collect();
+
+	if order != [("$second", true), ("$third", true), ("$first", false)] {
+		return Err!("unexpected changes oldest first: {order:?}");
+	}
+
+	let (since, _) = forward[0];
+	let (until, _) = forward[1];
+	let page: Vec<_> = threads
+		.subscription_changes(&bob, since, until)
+		.map(|(_, change)| change.thread_root)
+		.collect()
+		.await;
+
+	if page != [roots[2].clone()] {
+		return Err!("a page after {since} through {until} held {page:?}");
+	}
+
+	let (before, _) = forward[2];
+	let backward: Vec<_> = threads
+		.subscription_changes_rev(&bob, 0, before)
+		.map(|(_, change)| change.thread_root)
+		.collect()
+		.await;
+
+	if backward != [roots[2].clone(), roots[1].clone()] {
+		return Err!("unexpected changes newest first before {before}: {backward:?}");
+	}
+
+	Ok(())
+}
+
+async fn concurrent_changes(services: &Services) -> Result {
+	let threads = &services.threads;
+	let carol = UserId::parse("@carol:localhost")?;
+	let room_id = RoomId::parse("!racing:localhost")?;
+	let root = EventId::parse("$racing")?;
+
+	threads
+		.subscribe(&carol, &room_id, &root, None)
+		.await?;
+
+	// unsubscribing when no longer subscribed fails, which is not at issue here
+	join_all((0..32_u32).map(async |i| {
+		if i.is_multiple_of(2) {
+			threads.unsubscribe(&carol, &room_id, &root).await
+		} else {
+			threads
+				.subscribe(&carol, &room_id, &root, None)
+				.await
+		}
+	}))
+	.await;
+
+	let changes: Vec<_> = threads
+		.subscription_changes(&carol, 0, u64::MAX)
+		.collect()
+		.await;
+
+	let Some(subscription) = threads
+		.get_subscription(&carol, &room_id, &root)
+		.await
+	else {
+		return Err!("the subscription was lost");
+	};
+
+	match changes.as_slice() {
+		| [(count, change)]
+			if *count == subscription.bump_stamp
+				&& change.subscribed == subscription.subscribed =>
+			Ok(()),
+		| _ => Err!("the changes stream diverged from {subscription:?}: {changes:?}"),
+	}
+}
+
+async fn suppress_push(services: &Services) -> Result {
+	let threads = &services.threads;
+	let dave = UserId::parse("@dave:localhost")?;
+	let room_id = RoomId::parse("!push:localhost")?;
+	let root = EventId::parse("$pushroot")?;
+	let ruleset = Ruleset::server_default(&dave);
+	let reply = Raw::from_json(to_raw_value(&json!({
+		"type": "m.room.message",
+		"event_id": "$pushreply",
+		"sender": "@erin:localhost",
+		"origin_server_ts": 1,
+		"content": {
+			"msgtype": "m.text",
+			"body": "in the thread",
+			"m.relates_to": {"rel_type": "m.thread", "event_id": root},
+		},
+	}))?);
+
+	let notifies = async || {
+		services
+			.pusher
+			.get_actions(Evaluate {
+				user: &dave,
+				ruleset: &ruleset,
+				power_levels: None,
+				pdu: &reply,
+				room_id: &room_id,
+				related_events: None,
+			})
+			.await
+			.iter()
+			.any(Action::should_notify)
+	};
+
+	if notifies().await {
+		return Err!("a thread never subscribed to notified");
+	}
+
+	threads
+		.subscribe(&dave, &room_id, &root, None)
+		.await?;
+
+	if !notifies().await {
+		return Err!("a subscribed thread did not notify");
+	}
+
+	threads
+		.unsubscribe(&dave, &room_id, &root)
+		.await?;
+
+	if notifies().await {
+		return Err!("an unsubscribed thread notified");
+	}
+
+	Ok(())
+}
+
+async fn send_message(services: &Services, room_id: &RoomId, body: &str) -> Result<OwnedEventId> {
+	let state_lock = services.state.mutex.lock(room_id).await;
+
+	services
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder::timeline(&RoomMessageEventContent::text_plain(body)),
+			&services.globals.server_user,
+			room_id,
+			&state_lock,
+		)
+		.await
+}
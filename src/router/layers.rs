@@ -36,16 +36,20 @@
 	sensitive_headers::SetSensitiveHeadersLayer,
 	set_header::SetResponseHeaderLayer,
 	timeout::{RequestBodyTimeoutLayer, ResponseBodyTimeoutLayer, TimeoutLayer},
-	trace::{DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer},
+	trace::{DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, OnResponse, TraceLayer},
 };
-use tracing::Level;
+use tracing::{Level, Span};
 use tuwunel_api::router::{ConfiguredIpSource, TrustedPeerSubnets, state::Guard};
 use tuwunel_core::{
-	Result, Server, config::IpSource, debug, error, utils::content_disposition::content_type_is,
+	Result, Server, config::IpSource, debug, error, metrics::Metrics,
+	utils::content_disposition::content_type_is,
 };
 use tuwunel_service::Services;
 
-use crate::{request, router};
+use crate::{
+	request::{self, MatchedRoute},
+	router,
+};
 
 type Convert = fn(Result<Response, StatusCode>) -> Result<Response, Infallible>;
 
@@ -57,6 +61,14 @@ pub(crate) struct HandleLayer<F> {
 	pub(crate) handler: F,
 }
 
+/// Trace layer response hook recording each request's latency against the
+/// route which served it.
+#[derive(Clone)]
+struct RouteLatency {
+	metrics: Arc<Metrics>,
+	inner: DefaultOnResponse,
+}
+
 #[derive(Clone)]
 pub(crate) struct Handle<S, F> {
 	services: Arc<Services>,
@@ -95,7 +107,10 @@ pub(crate) fn build(services: &Arc<Services>) -> Result<(Router, Guard)> {
 				.make_span_with(tracing_span::<_>)
 				.on_failure(DefaultOnFailure::new().level(Level::ERROR))
 				.on_request(DefaultOnRequest::new().level(Level::TRACE))
-				.on_response(DefaultOnResponse::new().level(Level::DEBUG)),
+				.on_response(RouteLatency {
+					metrics: server.metrics.clone(),
+					inner: DefaultOnResponse::new().level(Level::DEBUG),
+				}),
 		)
 		.layer(HandleLayer {
 			services: Arc::clone(services),
@@ -324,6 +339,18 @@ fn catch_panic(
 		.expect("Failed to create response for our panic catcher?")
 }
 
+impl<B> OnResponse<B> for RouteLatency {
+	fn on_response(self, response: &http::Response<B>, latency: Duration, span: &Span) {
+		if let Some(MatchedRoute { method, path }) = response.extensions().get() {
+			self.metrics
+				.requests_latency
+				.observe(method.as_str(), path.as_str(), latency);
+		}
+
+		self.inner.on_response(response, latency, span);
+	}
+}
+
 fn tracing_span<T>(request: &http::Request<T>) -> tracing::Span {
 	let path = request
 		.extensions()
@@ -6,7 +6,7 @@
 };
 
 use axum::{
-	extract::Request,
+	extract::{MatchedPath, Request},
 	response::{IntoResponse, Response},
 };
 use futures::FutureExt;
@@ -18,6 +18,14 @@
 use tuwunel_core::{Error, Result, debug, debug_error, debug_warn, defer, error, trace};
 use tuwunel_service::Services;
 
+/// Route which served a request, attached to its response for the trace
+/// layer's latency accounting.
+#[derive(Clone)]
+pub(crate) struct MatchedRoute {
+	pub(crate) method: Method,
+	pub(crate) path: MatchedPath,
+}
+
 #[tracing::instrument(
 	name = "request",
 	level = "debug",
@@ -54,6 +62,7 @@ pub(crate) async fn handle<S>(
 
 	let uri = req.uri().clone();
 	let method = req.method().clone();
+	let path = req.extensions().get::<MatchedPath>().cloned();
 	let parent = Span::current();
 	let response = match method {
 		| Method::PUT | Method::POST | Method::DELETE | Method::PATCH =>
@@ -61,7 +70,14 @@ pub(crate) async fn handle<S>(
 		| _ => execute(&services, req, inner, &parent).await,
 	};
 
-	handle_result(&method, &uri, response)
+	let mut response = handle_result(&method, &uri, response)?;
+	if let Some(path) = path {
+		response
+			.extensions_mut()
+			.insert(MatchedRoute { method, path });
+	}
+
+	Ok(response)
 }
 
 async fn spawn_execute<S>(
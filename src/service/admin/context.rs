@@ -1,6 +1,7 @@
 use std::{fmt, fmt::Debug, time::SystemTime};
 
 use futures::{FutureExt, lock::Mutex};
+use ruma::UserId;
 use tokio::time::Instant;
 use tuwunel_core::{Err, Result};
 
@@ -13,6 +14,8 @@
 pub struct Context<'a> {
 	pub services: &'a Services,
 	pub body: &'a [&'a str],
+	/// The user who invoked the command.
+	pub sender: &'a UserId,
 	pub timer: SystemTime,
 	pub output: Mutex<String>,
 }
@@ -19,7 +19,9 @@
 pub use context::Context;
 pub use create::create_admin_room;
 use futures::TryFutureExt;
-use ruma::{OwnedEventId, OwnedRoomAliasId, OwnedRoomId, RoomId, RoomOrAliasId, UserId};
+use ruma::{
+	OwnedEventId, OwnedRoomAliasId, OwnedRoomId, OwnedUserId, RoomId, RoomOrAliasId, UserId,
+};
 use tokio::sync::mpsc;
 use tuwunel_core::{Err, Event, Result, debug, err, error::default_log, warn};
 
@@ -33,11 +35,13 @@ pub struct Service {
 	pub console: Arc<console::Console>,
 }
 
-/// Inputs to a command are a multi-line string and optional reply_id.
+/// Inputs to a command are a multi-line string, optional reply_id and the
+/// user who invoked it; commands without one run as the server user.
 #[derive(Clone, Debug, Default)]
 pub struct CommandInput {
 	pub command: String,
 	pub reply_id: Option<OwnedEventId>,
+	pub sender: Option<OwnedUserId>,
 }
 
 /// Root of a clap command tree installed by a downstream crate.
@@ -140,7 +144,12 @@ impl Service {
 	/// Posts a command to the command processor queue and returns. Processing
 	/// will take place on the service worker's task asynchronously. Errors if
 	/// the queue is full.
-	pub async fn command(&self, command: String, reply_id: Option<OwnedEventId>) -> Result {
+	pub async fn command(
+		&self,
+		command: String,
+		reply_id: Option<OwnedEventId>,
+		sender: Option<OwnedUserId>,
+	) -> Result {
 		let Some(sender) = self
 			.channel
 			.read()
@@ -151,7 +160,7 @@ pub async fn command(&self, command: String, reply_id: Option<OwnedEventId>) ->
 		};
 
 		sender
-			.send(CommandInput { command, reply_id })
+			.send(CommandInput { command, reply_id, sender })
 			.await
 			.map_err(|e| err!("Failed to enqueue admin command: {e:?}"))
 	}
@@ -163,7 +172,7 @@ pub async fn command_in_place(
 		command: String,
 		reply_id: Option<OwnedEventId>,
 	) -> ProcessorResult {
-		self.process_command(&CommandInput { command, reply_id })
+		self.process_command(&CommandInput { command, reply_id, sender: None })
 			.await
 	}
 
@@ -88,9 +88,15 @@ async fn process_command(
 ) -> ProcessorResult {
 	let (matches, args, body) = parse(&services, command.clap(), input)?;
 
+	let sender = input
+		.sender
+		.as_deref()
+		.unwrap_or(&services.globals.server_user);
+
 	let context = Context {
 		services: &services,
 		body: &body,
+		sender,
 		timer: SystemTime::now(),
 		output: String::new().into(),
 	};
@@ -5,6 +5,7 @@
 use async_trait::async_trait;
 #[cfg(all(feature = "systemd", target_os = "linux"))]
 use sd_notify::{NotifyState, notify};
+use tokio::sync::watch;
 #[cfg(all(feature = "systemd", target_os = "linux"))]
 use tuwunel_core::itertools::Itertools;
 use tuwunel_core::{
@@ -15,6 +16,7 @@
 
 pub struct Service {
 	server: Arc<Server>,
+	reloaded: watch::Sender<()>,
 }
 
 const SIGNAL: &str = "SIGUSR1";
@@ -26,7 +28,10 @@ pub struct Service {
 #[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
-		Ok(Arc::new(Self { server: args.server.clone() }))
+		Ok(Arc::new(Self {
+			server: args.server.clone(),
+			reloaded: watch::Sender::new(()),
+		}))
 	}
 
 	async fn worker(self: Arc<Self>) -> Result {
@@ -119,5 +124,13 @@ pub fn reload<'a, I>(&'a self, paths: I) -> Result<Arc<Config>>
 		.and_then(|raw| Config::new(&raw))?;
 
 	check::reload(&old, &new)?;
-	self.server.config.update(new)
+	let old = self.server.config.update(new)?;
+	self.reloaded.send_replace(());
+
+	Ok(old)
 }
+
+/// Marked changed after each successful reload, for services holding state
+/// derived from the config.
+#[implement(Service)]
+pub fn reloads(&self) -> watch::Receiver<()> { self.reloaded.subscribe() }
@@ -351,11 +351,9 @@ fn to_http_request<T>(
 		features: Default::default(),
 	};
 
-	let auth = T::Authentication::input(
-		self.services.server.name.clone(),
-		dest.to_owned(),
-		self.services.server_keys.keypair(),
-	);
+	let keypair = self.services.server_keys.keypair();
+	let auth =
+		T::Authentication::input(self.services.server.name.clone(), dest.to_owned(), &keypair);
 	let path = T::PathBuilder::input(&supported);
 
 	request
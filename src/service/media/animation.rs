@@ -0,0 +1,115 @@
+//! Animated Thumbnails (MSC2705)
+//!
+//! A thumbnail requested with `animated=true` of an animated GIF, APNG or WebP
+//! keeps its animation: every frame is scaled as the still thumbnail would be
+//! and the frames are encoded together as a GIF, the one animated format the
+//! encoder writes. A source that turns out to be a still, or whose frames
+//! together are past the pixel budget, gets the still thumbnail instead.
+
+use std::io::Cursor;
+
+use image::{
+	AnimationDecoder, DynamicImage, Frame, Frames, ImageDecoder, ImageFormat,
+	codecs::{
+		gif::{GifDecoder, GifEncoder, Repeat},
+		png::PngDecoder,
+		webp::WebPDecoder,
+	},
+};
+use tuwunel_core::{Result, err, implement, utils::result::LogDebugErr};
+
+use super::{Dim, thumbnail::thumbnail_generate};
+
+/// Encodes an animated thumbnail of the picture, or `None` when it should
+/// have a still one.
+#[implement(super::Service)]
+#[tracing::instrument(name = "animate", level = "debug", skip(self, bytes))]
+pub(super) fn animate(&self, bytes: &[u8], format: ImageFormat, dim: &Dim) -> Option<Vec<u8>> {
+	let budget = self.services.config.media_thumbnail_max_pixels;
+
+	encode_animation(bytes, format, dim, budget)
+		.log_debug_err()
+		.ok()
+		.flatten()
+}
+
+pub(super) fn encode_animation(
+	bytes: &[u8],
+	format: ImageFormat,
+	dim: &Dim,
+	budget: u64,
+) -> Result<Option<Vec<u8>>> {
+	let Some((frames, (width, height))) = frames(bytes, format)? else {
+		return Ok(None);
+	};
+
+	// every frame is decoded onto a canvas of the full picture, so the budget
+	// bounds their number
+	let pixels = u64::from(width)
+		.saturating_mul(u64::from(height))
+		.max(1);
+
+	let max_frames = usize::try_from(budget / pixels).unwrap_or(usize::MAX);
+
+	let mut thumbnails = Vec::new();
+	for frame in frames {
+		if thumbnails.len() >= max_frames {
+			return Ok(None);
+		}
+
+		let frame =
+			frame.map_err(|error| err!(debug_warn!(?error, "Failed to decode frame.")))?;
+		let delay = frame.delay();
+		let image = DynamicImage::ImageRgba8(frame.into_buffer());
+		let thumbnail = thumbnail_generate(&image, dim)?.into_rgba8();
+
+		thumbnails.push(Frame::from_parts(thumbnail, 0, 0, delay));
+	}
+
+	if thumbnails.len() < 2 {
+		return Ok(None);
+	}
+
+	let mut bytes = Vec::new();
+	{
+		let mut encoder = GifEncoder::new(&mut bytes);
+		encoder
+			.set_repeat(Repeat::Infinite)
+			.and_then(|()| encoder.encode_frames(thumbnails))
+			.map_err(|error| err!(error!(?error, "Error writing GIF thumbnail.")))?;
+	}
+
+	Ok(Some(bytes))
+}
+
+/// The frames of an animated picture with its canvas dimensions, or `None`
+/// for a format or a file without animation.
+fn frames(bytes: &[u8], format: ImageFormat) -> Result<Option<(Frames<'_>, (u32, u32))>> {
+	let cursor = Cursor::new(bytes);
+	let decoded = match format {
+		| ImageFormat::Gif => GifDecoder::new(cursor).map(|decoder| {
+			let dimensions = decoder.dimensions();
+
+			Some((decoder.into_frames(), dimensions))
+		}),
+		| ImageFormat::Png => PngDecoder::new(cursor).and_then(|decoder| {
+			if !decoder.is_apng()? {
+				return Ok(None);
+			}
+
+			let dimensions = decoder.dimensions();
+
+			Ok(Some((decoder.apng()?.into_frames(), dimensions)))
+		}),
+		| ImageFormat::WebP => WebPDecoder::new(cursor).map(|decoder| {
+			let dimensions = decoder.dimensions();
+
+			decoder
+				.has_animation()
+				.then(|| (decoder.into_frames(), dimensions))
+		}),
+		| _ => Ok(None),
+	};
+
+	decoded.map_err(|error| err!(debug_warn!(?error, "Failed to read animation.")))
+}
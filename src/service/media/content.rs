@@ -0,0 +1,156 @@
+//! Content-addressed media storage.
+//!
+//! Files are stored once per SHA-256 digest of their content and shared by
+//! every media key holding that content, such as a forwarded image or a reused
+//! sticker. Each key maps to its digest and each digest counts its keys; the
+//! stored object goes with its last reference. Keys stored before content
+//! addressing keep their key-addressed path until the dedupe migration moves
+//! them. Only the generic object-store operations are used, so this holds for
+//! every storage provider.
+
+use futures::{Stream, TryStreamExt};
+use sha2::{Digest, Sha256};
+use tuwunel_core::{Err, Result, debug, implement, utils::time::now_millis};
+
+use super::{data::Stored, encode_key};
+use crate::storage::{CopyMode, provider::FetchItem};
+
+/// Directory of content-addressed objects within each storage provider.
+const CONTENT_DIR: &str = "sha256";
+
+/// Path of the file stored under the media key.
+#[implement(super::Service)]
+pub(super) async fn media_path(&self, key: &[u8]) -> String {
+	match self.db.get_content_digest(key).await {
+		| Some(digest) => content_path(&digest),
+		| None => self.get_media_name_sha256(key),
+	}
+}
+
+/// Stores the file under the media key, uploading it only when no other key
+/// already holds the same content.
+#[implement(super::Service)]
+pub(super) async fn create_media_file(&self, key: &[u8], file: &[u8]) -> Result {
+	let digest = content_digest(file);
+	match self.db.get_content_digest(key).await {
+		| Some(prev) if prev == digest => return Ok(()),
+		| Some(_) => self.remove_media_file(key).await?,
+		| None => {},
+	}
+
+	let _lock = self
+		.content_mutex
+		.lock(&encode_key(&digest))
+		.await;
+	let refs = self.db.get_content_refs(&digest).await;
+	if refs == 0 {
+		self.put_media_file(&content_path(&digest), file)
+			.await?;
+	} else {
+		debug!(?key, %refs, "Media content already stored");
+	}
+
+	self.db
+		.insert_content_ref(key, &digest, refs.saturating_add(1));
+
+	let size = u64::try_from(file.len()).unwrap_or(u64::MAX);
+	self.db
+		.set_stored(key, &Stored { size, stored_ts: now_millis() });
+
+	Ok(())
+}
+
+/// Releases the media key's reference to its file, deleting the file and its
+/// scan verdict once no key references it. Unmapped keys delete their
+/// key-addressed file.
+#[implement(super::Service)]
+pub(super) async fn remove_media_file(&self, key: &[u8]) -> Result {
+	let Some(digest) = self.db.get_content_digest(key).await else {
+		return self
+			.delete_media_file(&self.get_media_name_sha256(key))
+			.await;
+	};
+
+	let _lock = self
+		.content_mutex
+		.lock(&encode_key(&digest))
+		.await;
+	let refs = self
+		.db
+		.get_content_refs(&digest)
+		.await
+		.saturating_sub(1);
+
+	self.db.remove_content_ref(key, &digest, refs);
+	if refs > 0 {
+		debug!(?key, %refs, "Media content still referenced");
+		return Ok(());
+	}
+
+	self.db.remove_scan(&digest);
+	self.delete_media_file(&content_path(&digest))
+		.await
+}
+
+/// Moves an unmapped key's file to its content path on every provider holding
+/// it, dropping the copy instead where the content is already stored. Returns
+/// whether the content was already stored under another key.
+#[implement(super::Service)]
+pub(super) async fn dedupe_media_file(&self, key: &[u8]) -> Result<bool> {
+	let legacy = self.get_media_name_sha256(key);
+
+	let mut holders = Vec::new();
+	for provider in self.storage_providers() {
+		if provider.head(&legacy).await.is_ok() {
+			holders.push(provider);
+		}
+	}
+
+	let Some(provider) = holders.first() else {
+		return Err!(Request(NotFound("Media file missing on every provider.")));
+	};
+
+	let digest = stream_digest(provider.fetch(&legacy)).await?;
+	let path = content_path(&digest);
+
+	let _lock = self
+		.content_mutex
+		.lock(&encode_key(&digest))
+		.await;
+	let refs = self.db.get_content_refs(&digest).await;
+	for provider in holders {
+		if provider.head(&path).await.is_ok() {
+			provider.delete_one(&legacy).await?;
+		} else {
+			provider
+				.rename(&legacy, &path, CopyMode::Overwrite)
+				.await?;
+		}
+	}
+
+	self.db
+		.insert_content_ref(key, &digest, refs.saturating_add(1));
+
+	Ok(refs > 0)
+}
+
+pub(super) fn content_digest(file: &[u8]) -> Vec<u8> { Sha256::digest(file).to_vec() }
+
+/// Digest of a file read chunk by chunk, so it is never held whole in memory.
+async fn stream_digest<S>(chunks: S) -> Result<Vec<u8>>
+where
+	S: Stream<Item = Result<FetchItem>> + Send,
+{
+	let hasher = chunks
+		.try_fold(Sha256::new(), async |mut hasher, (bytes, _)| {
+			hasher.update(&bytes);
+			Ok(hasher)
+		})
+		.await?;
+
+	Ok(hasher.finalize().to_vec())
+}
+
+pub(super) fn content_path(digest: &[u8]) -> String {
+	format!("{CONTENT_DIR}/{}", encode_key(digest))
+}
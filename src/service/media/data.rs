@@ -2,9 +2,7 @@
 
 use futures::{Stream, StreamExt, pin_mut};
 use ruma::{Mxc, OwnedMxcUri, OwnedUserId, UserId, http_headers::ContentDisposition};
-use serde::Deserialize;
-#[cfg(feature = "url_preview")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tuwunel_core::{
 	Err, Result, at, debug, debug_info, err,
 	utils::{
@@ -13,18 +11,36 @@
 		string_from_bytes,
 	},
 };
-use tuwunel_database::{Cbor, Database, Deserialized, Ignore, Interfix, Map, Txn, serialize_key};
+use tuwunel_database::{
+	Cbor, Database, Deserialized, Ignore, Interfix, Json, Map, Txn, serialize_key,
+};
 
-use super::{Media, preview::CachedPreview, thumbnail::Dim};
+use super::{
+	Media,
+	preview::CachedPreview,
+	quarantine::Quarantine,
+	quota::{QuotaOverride, Usage},
+	scan::ScanVerdict,
+	thumbnail::Dim,
+};
 
 pub(crate) struct Data {
 	db: Arc<Database>,
+	mediaid_access: Arc<Map>,
 	mediaid_file: Arc<Map>,
 	mediaid_lazy: Arc<Map>,
 	mediaid_lazycontent: Arc<Map>,
 	mediaid_pending: Arc<Map>,
+	mediaid_quarantine: Arc<Map>,
+	mediaid_sha256: Arc<Map>,
+	mediaid_stored: Arc<Map>,
 	mediaid_user: Arc<Map>,
+	sha256_mediascan: Arc<Map>,
+	sha256_provider: Arc<Map>,
+	sha256_refcount: Arc<Map>,
 	url_preview: Arc<Map>,
+	userid_mediaquota: Arc<Map>,
+	userid_mediausage: Arc<Map>,
 }
 
 #[derive(Debug)]
@@ -34,6 +50,15 @@ pub struct Metadata {
 	pub(super) key: Vec<u8>,
 }
 
+/// Size of the file stored under a media key and when it was stored, in
+/// milliseconds since the epoch; recorded with the file so that passes over
+/// all media need no storage provider requests.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(super) struct Stored {
+	pub(super) size: u64,
+	pub(super) stored_ts: u64,
+}
+
 /// Borrowed staging-cache value: written zero-copy from the measured bytes.
 #[cfg(feature = "url_preview")]
 #[derive(Serialize)]
@@ -72,12 +97,21 @@ impl Data {
 	pub(super) fn new(db: &Arc<Database>) -> Self {
 		Self {
 			db: db.clone(),
+			mediaid_access: db["mediaid_access"].clone(),
 			mediaid_file: db["mediaid_file"].clone(),
 			mediaid_lazy: db["mediaid_lazy"].clone(),
 			mediaid_lazycontent: db["mediaid_lazycontent"].clone(),
 			mediaid_pending: db["mediaid_pending"].clone(),
+			mediaid_quarantine: db["mediaid_quarantine"].clone(),
+			mediaid_sha256: db["mediaid_sha256"].clone(),
+			mediaid_stored: db["mediaid_stored"].clone(),
 			mediaid_user: db["mediaid_user"].clone(),
+			sha256_mediascan: db["sha256_mediascan"].clone(),
+			sha256_provider: db["sha256_provider"].clone(),
+			sha256_refcount: db["sha256_refcount"].clone(),
 			url_preview: db["url_preview"].clone(),
+			userid_mediaquota: db["userid_mediaquota"].clone(),
+			userid_mediausage: db["userid_mediausage"].clone(),
 		}
 	}
 
@@ -89,8 +123,8 @@ pub(super) fn create_file_metadata(
 		content_disposition: Option<&ContentDisposition>,
 		content_type: Option<&str>,
 	) -> Result<Vec<u8>> {
-		let dim: &[u32] = &[dim.width, dim.height];
-		let key = (mxc, dim, content_disposition, content_type);
+		let dim = dim.key();
+		let key = (mxc, dim.as_slice(), content_disposition, content_type);
 		let key = serialize_key(key)?;
 		let mut txn = self.db.txn();
 
@@ -229,12 +263,13 @@ pub(super) async fn delete_file_mxc(&self, mxc: &Mxc<'_>) {
 			.ignore_err()
 			.ready_fold(self.db.txn(), |mut txn, key| {
 				txn.del_raw(&self.mediaid_file, key);
+				txn.del_raw(&self.mediaid_stored, key);
 
 				txn
 			})
 			.await;
 
-		let txn = self
+		let mut txn = self
 			.mediaid_user
 			.stream_prefix_raw(&prefix)
 			.ignore_err()
@@ -253,6 +288,7 @@ pub(super) async fn delete_file_mxc(&self, mxc: &Mxc<'_>) {
 			})
 			.await;
 
+		txn.del_raw(&self.mediaid_access, mxc.to_string());
 		txn.execute();
 	}
 
@@ -279,8 +315,8 @@ pub(super) async fn search_mxc_metadata_prefix(&self, mxc: &Mxc<'_>) -> Result<V
 	}
 
 	pub(super) async fn file_metadata_exists(&self, mxc: &Mxc<'_>, dim: &Dim) -> bool {
-		let dim: &[u32] = &[dim.width, dim.height];
-		let prefix = (mxc, dim, Interfix);
+		let dim = dim.key();
+		let prefix = (mxc, dim.as_slice(), Interfix);
 		let keys = self
 			.mediaid_file
 			.keys_prefix_raw(&prefix)
@@ -295,8 +331,8 @@ pub(super) async fn search_file_metadata(
 		mxc: &Mxc<'_>,
 		dim: &Dim,
 	) -> Result<Metadata> {
-		let dim: &[u32] = &[dim.width, dim.height];
-		let prefix = (mxc, dim, Interfix);
+		let dim = dim.key();
+		let prefix = (mxc, dim.as_slice(), Interfix);
 
 		let keys = self
 			.mediaid_file
@@ -388,6 +424,164 @@ pub(super) async fn get_url_preview(&self, url: &str) -> Result<CachedPreview> {
 			.ok_or(err!(Request(NotFound("Expired from cache"))))
 	}
 
+	/// When the remote media at the given MXC was last downloaded or
+	/// thumbnailed; absent until first accessed after being cached.
+	pub(super) async fn get_last_access(&self, mxc: &Mxc<'_>) -> Option<u64> {
+		self.mediaid_access
+			.get(&mxc.to_string())
+			.await
+			.deserialized()
+			.ok()
+	}
+
+	pub(super) fn set_last_access(&self, mxc: &Mxc<'_>, ts: u64) {
+		self.mediaid_access
+			.insert(&mxc.to_string(), ts.to_be_bytes());
+	}
+
+	pub(super) async fn get_stored(&self, key: &[u8]) -> Option<Stored> {
+		self.mediaid_stored
+			.get(key)
+			.await
+			.deserialized::<Cbor<_>>()
+			.map(at!(0))
+			.ok()
+	}
+
+	pub(super) fn set_stored(&self, key: &[u8], stored: &Stored) {
+		self.mediaid_stored.raw_put(key, Cbor(stored));
+	}
+
+	/// Quarantine and protection flags of the media at the given MXC; kept
+	/// apart from the file metadata so they outlive a purged remote copy.
+	pub(super) async fn get_quarantine(&self, mxc: &Mxc<'_>) -> Option<Quarantine> {
+		self.mediaid_quarantine
+			.get(&mxc.to_string())
+			.await
+			.deserialized::<Cbor<_>>()
+			.map(at!(0))
+			.ok()
+	}
+
+	/// Stores the flags, dropping the row once neither is set.
+	pub(super) fn set_quarantine(&self, mxc: &Mxc<'_>, quarantine: &Quarantine) {
+		let key = mxc.to_string();
+		if *quarantine == Quarantine::default() {
+			self.mediaid_quarantine.remove(&key);
+		} else {
+			self.mediaid_quarantine
+				.raw_put(key, Cbor(quarantine));
+		}
+	}
+
+	/// The user's tallied media usage; absent until first tallied.
+	pub(super) async fn get_usage(&self, user_id: &UserId) -> Option<Usage> {
+		self.userid_mediausage
+			.get(user_id)
+			.await
+			.deserialized::<Json<_>>()
+			.map(|Json(usage)| usage)
+			.ok()
+	}
+
+	pub(super) fn set_usage(&self, user_id: &UserId, usage: Usage) {
+		self.userid_mediausage
+			.raw_put(user_id, Json(usage));
+	}
+
+	pub(super) async fn get_quota_override(&self, user_id: &UserId) -> Option<QuotaOverride> {
+		self.userid_mediaquota
+			.get(user_id)
+			.await
+			.deserialized::<Json<_>>()
+			.map(|Json(quota)| quota)
+			.ok()
+	}
+
+	pub(super) fn set_quota_override(&self, user_id: &UserId, quota: QuotaOverride) {
+		self.userid_mediaquota
+			.raw_put(user_id, Json(quota));
+	}
+
+	pub(super) fn remove_quota_override(&self, user_id: &UserId) {
+		self.userid_mediaquota.remove(user_id);
+	}
+
+	/// Content digest of the file stored under the media key; absent until the
+	/// file is content-addressed.
+	pub(super) async fn get_content_digest(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.mediaid_sha256
+			.get(key)
+			.await
+			.map(|digest| digest.to_vec())
+			.ok()
+	}
+
+	/// Number of media keys sharing the content.
+	pub(super) async fn get_content_refs(&self, digest: &[u8]) -> u64 {
+		self.sha256_refcount
+			.get(digest)
+			.await
+			.deserialized()
+			.unwrap_or(0)
+	}
+
+	/// Maps the media key to the content, which is now shared by `refs` keys.
+	pub(super) fn insert_content_ref(&self, key: &[u8], digest: &[u8], refs: u64) {
+		let mut txn = self.db.txn();
+
+		txn.insert_raw(&self.mediaid_sha256, key, digest);
+		txn.insert_raw(&self.sha256_refcount, digest, refs.to_be_bytes());
+		txn.execute();
+	}
+
+	/// Storage provider the content was moved to by tiering; absent while it
+	/// is where new media is stored.
+	pub(super) async fn get_content_provider(&self, digest: &[u8]) -> Option<String> {
+		self.sha256_provider
+			.get(digest)
+			.await
+			.deserialized()
+			.ok()
+	}
+
+	pub(super) fn set_content_provider(&self, digest: &[u8], provider: &str) {
+		self.sha256_provider.insert(digest, provider);
+	}
+
+	/// Unmaps the media key from the content, which is left shared by `refs`
+	/// keys; the count and the content's placement go with the last of them.
+	pub(super) fn remove_content_ref(&self, key: &[u8], digest: &[u8], refs: u64) {
+		let mut txn = self.db.txn();
+
+		txn.del_raw(&self.mediaid_sha256, key);
+		if refs > 0 {
+			txn.insert_raw(&self.sha256_refcount, digest, refs.to_be_bytes());
+		} else {
+			txn.del_raw(&self.sha256_refcount, digest);
+			txn.del_raw(&self.sha256_provider, digest);
+		}
+
+		txn.execute();
+	}
+
+	/// Scanner verdict on the content, when it has been scanned.
+	pub(super) async fn get_scan(&self, digest: &[u8]) -> Option<ScanVerdict> {
+		self.sha256_mediascan
+			.get(digest)
+			.await
+			.deserialized::<Json<_>>()
+			.map(|Json(verdict)| verdict)
+			.ok()
+	}
+
+	pub(super) fn set_scan(&self, digest: &[u8], verdict: &ScanVerdict) {
+		self.sha256_mediascan
+			.raw_put(digest, Json(verdict));
+	}
+
+	pub(super) fn remove_scan(&self, digest: &[u8]) { self.sha256_mediascan.remove(digest); }
+
 	/// Streams every (mxc, uploader) pair in the user-media index.
 	pub(super) fn all_uploads(
 		&self,
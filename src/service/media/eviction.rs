@@ -0,0 +1,197 @@
+//! Remote Media Eviction
+//!
+//! Remote media is a cache of other servers' uploads, fetched again from its
+//! origin when requested after being evicted. Each download, thumbnail or
+//! redirect records when the media was last accessed, at most once per
+//! `ACCESS_GRANULARITY`; media not accessed since it was cached counts from
+//! when it was stored. The service worker periodically evicts remote media
+//! idle for longer than `remote_media_max_idle_days`, then the least recently
+//! accessed until the cache fits within `remote_media_cache_max_bytes`. Local
+//! uploads are never evicted, nor is protected media.
+
+use std::{collections::BTreeSet, time::Duration};
+
+use futures::StreamExt;
+use ruma::{Mxc, OwnedMxcUri};
+use tuwunel_core::{
+	Result, debug_info, implement,
+	utils::{stream::IterStream, time::now_millis},
+	warn,
+};
+
+use super::Stored;
+
+/// Accesses closer together than this are recorded once.
+const ACCESS_GRANULARITY: Duration = Duration::from_hours(1);
+
+pub(super) const DAY: Duration = Duration::from_hours(24);
+
+/// A cached remote media item as eviction weighs it: all of its files,
+/// original and thumbnails, go together.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) struct Cached {
+	pub(super) mxc: OwnedMxcUri,
+	pub(super) last_access: u64,
+	pub(super) size: u64,
+}
+
+/// Records an access to the media when it is remote.
+#[implement(super::Service)]
+pub(super) async fn touch(&self, mxc: &Mxc<'_>) {
+	if self
+		.services
+		.globals
+		.server_is_ours(mxc.server_name)
+	{
+		return;
+	}
+
+	let now = now_millis();
+	let granularity = u64::try_from(ACCESS_GRANULARITY.as_millis()).unwrap_or(u64::MAX);
+	let recent = self
+		.db
+		.get_last_access(mxc)
+		.await
+		.is_some_and(|last| now.saturating_sub(last) < granularity);
+
+	if !recent {
+		self.db.set_last_access(mxc, now);
+	}
+}
+
+/// Runs one eviction pass when either limit is configured.
+#[implement(super::Service)]
+pub(super) async fn evict_remote_media_if_enabled(&self) {
+	let config = &self.services.config;
+	let max_idle = (config.remote_media_max_idle_days > 0).then(|| {
+		Duration::from_secs(
+			config
+				.remote_media_max_idle_days
+				.saturating_mul(DAY.as_secs()),
+		)
+	});
+
+	let max_bytes =
+		(config.remote_media_cache_max_bytes > 0).then_some(config.remote_media_cache_max_bytes);
+
+	if max_idle.is_none() && max_bytes.is_none() {
+		return;
+	}
+
+	match self.evict_remote_media(max_idle, max_bytes).await {
+		| Ok(evicted) => debug_info!(evicted = evicted.len(), "Finished evicting remote media"),
+		| Err(e) => warn!("Failed to evict remote media: {e}"),
+	}
+}
+
+/// Evicts remote media idle for longer than `max_idle`, then the least
+/// recently accessed until the rest fits within `max_bytes`. Returns the
+/// evicted MXCs.
+#[implement(super::Service)]
+pub async fn evict_remote_media(
+	&self,
+	max_idle: Option<Duration>,
+	max_bytes: Option<u64>,
+) -> Result<Vec<OwnedMxcUri>> {
+	let cached = self.cached_remote_media().await?;
+	let max_idle =
+		max_idle.map(|max_idle| u64::try_from(max_idle.as_millis()).unwrap_or(u64::MAX));
+	let evictions = select_evictions(cached, now_millis(), max_idle, max_bytes);
+
+	let mut evicted = Vec::with_capacity(evictions.len());
+	for mxc in evictions {
+		let Ok(parts) = mxc.parts() else {
+			continue;
+		};
+
+		match self.delete(&parts).await {
+			| Ok(()) => evicted.push(mxc),
+			| Err(e) => warn!(%mxc, "Failed to evict remote media: {e}"),
+		}
+	}
+
+	Ok(evicted)
+}
+
+/// Every unprotected remote media item with its last access and the size of
+/// its files.
+#[implement(super::Service)]
+async fn cached_remote_media(&self) -> Result<Vec<Cached>> {
+	let mxcs: BTreeSet<_> = self
+		.get_all_mxcs()
+		.await?
+		.into_iter()
+		.filter(|mxc| !self.is_local(mxc))
+		.collect();
+
+	let cached = mxcs
+		.into_iter()
+		.stream()
+		.filter_map(async |mxc| {
+			let parts = mxc.parts().ok()?;
+			if self
+				.quarantine_state(&parts)
+				.await
+				.safe_from_quarantine
+			{
+				return None;
+			}
+
+			let keys = self
+				.db
+				.search_mxc_metadata_prefix(&parts)
+				.await
+				.ok()?;
+
+			let (mut size, mut stored) = (0_u64, 0_u64);
+			for key in keys {
+				if let Some(Stored { size: file_size, stored_ts }) = self.stored(&key).await {
+					size = size.saturating_add(file_size);
+					stored = stored.max(stored_ts);
+				}
+			}
+
+			let last_access = self
+				.db
+				.get_last_access(&parts)
+				.await
+				.map_or(stored, |last| last.max(stored));
+
+			Some(Cached { mxc, last_access, size })
+		})
+		.collect()
+		.await;
+
+	Ok(cached)
+}
+
+/// The media to evict, least recently accessed first: all idle for longer than
+/// `max_idle` milliseconds at `now`, and then as many more as bring the total
+/// size within `max_bytes`.
+pub(super) fn select_evictions(
+	mut cached: Vec<Cached>,
+	now: u64,
+	max_idle: Option<u64>,
+	max_bytes: Option<u64>,
+) -> Vec<OwnedMxcUri> {
+	cached.sort_by_key(|item| item.last_access);
+
+	let mut total = cached
+		.iter()
+		.fold(0_u64, |total, item| total.saturating_add(item.size));
+
+	let mut evictions = Vec::new();
+	for item in cached {
+		let idle =
+			max_idle.is_some_and(|max_idle| now.saturating_sub(item.last_access) > max_idle);
+		let over = max_bytes.is_some_and(|max_bytes| total > max_bytes);
+		if !idle && !over {
+			break;
+		}
+
+		total = total.saturating_sub(item.size);
+		evictions.push(item.mxc);
+	}
+
+	evictions
+}
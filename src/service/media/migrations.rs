@@ -8,6 +8,7 @@
 	time::Instant,
 };
 
+use serde::{Deserialize, Serialize};
 use tuwunel_core::{
 	Config, Result, debug, debug_info, debug_warn, error,
 	error::inspect_debug_log,
@@ -17,7 +18,7 @@
 };
 use tuwunel_database::{Database, Map};
 
-use crate::Services;
+use crate::{Services, tasks::Params};
 
 struct MediaStorage<'a> {
 	database: &'a Arc<Database>,
@@ -63,10 +64,92 @@ pub(crate) async fn migrate_sha256_media(services: &Services) -> Result {
 	Ok(())
 }
 
+/// Schedules the move of key-addressed media files to content-addressed paths
+/// as a background task, so startup does not wait on it. The task tracker runs
+/// it once its worker starts and resumes it after a restart.
+pub(crate) async fn migrate_dedupe_media(services: &Services) {
+	info!("Scheduling deduplication of media files by content");
+	services
+		.tasks
+		.schedule_resumable(Params::DedupeMedia)
+		.await;
+}
+
+/// Files handled by [`dedupe_media`], kept as task progress.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub(crate) struct DedupeCounts {
+	moved: u64,
+	deduped: u64,
+	failed: u64,
+}
+
+/// Moves key-addressed media files to content-addressed paths, keeping one
+/// copy of each distinct content. Keys already moved are skipped, so an
+/// interrupted run resumes. A key which fails is counted and left at its
+/// key-addressed path; files missing from every provider are left to the
+/// startup check. `progress` is told the counts after each key.
+pub(crate) async fn dedupe_media<F>(
+	services: &Services,
+	mut counts: DedupeCounts,
+	progress: F,
+) -> DedupeCounts
+where
+	F: Fn(&DedupeCounts),
+{
+	use crate::media::encode_key;
+
+	warn!("Deduplicating media files by content");
+	let media = &services.media;
+	let timer = Instant::now();
+
+	// keys which failed before a restart are tried again
+	counts.failed = 0;
+
+	for key in media.db.get_all_media_keys().await {
+		if media.db.get_content_digest(&key).await.is_some() {
+			continue;
+		}
+
+		match media.dedupe_media_file(&key).await {
+			| Ok(true) => counts.deduped = counts.deduped.saturating_add(1),
+			| Ok(false) => counts.moved = counts.moved.saturating_add(1),
+			| Err(e) => {
+				debug_warn!(media_id = ?encode_key(&key), "Media file not deduplicated: {e}");
+				counts.failed = counts.failed.saturating_add(1);
+			},
+		}
+
+		progress(&counts);
+
+		// The legacy compat link pointed at the key-addressed file just moved.
+		let link = media.get_media_path_b64(&key);
+		if tokio::fs::symlink_metadata(&link)
+			.await
+			.is_ok_and(|md| md.is_symlink())
+			&& let Err(e) = tokio::fs::remove_file(&link).await
+		{
+			debug_warn!(?link, "Legacy media link not removed: {e}");
+		}
+	}
+
+	services.db["global"].insert(b"dedupe_media_content", []);
+	info!(
+		moved = %counts.moved,
+		deduped = %counts.deduped,
+		failed = %counts.failed,
+		elapsed = ?timer.elapsed(),
+		"Finished deduplicating media files"
+	);
+
+	counts
+}
+
 /// Check is run on startup for prior-migrated media directories. This handles:
 /// - Going back and forth to non-sha256 legacy binaries (e.g. upstream).
 /// - Deletion of artifacts in the media directory which will then fall out of
 ///   sync with the database.
+///
+/// Content-addressed media is not key-addressed on disk and is skipped.
 pub(crate) async fn checkup_sha256_media(services: &Services) -> Result {
 	use crate::media::encode_key;
 
@@ -88,6 +171,10 @@ pub(crate) async fn checkup_sha256_media(services: &Services) -> Result {
 		.collect();
 
 	for key in media.db.get_all_media_keys().await {
+		if media.db.get_content_digest(&key).await.is_some() {
+			continue;
+		}
+
 		let new_path = media.get_media_path_sha256(&key).into_os_string();
 		let old_path = media.get_media_path_b64(&key).into_os_string();
 		if let Err(e) =
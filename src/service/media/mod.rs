@@ -1,9 +1,20 @@
+#[cfg(feature = "media_thumbnail")]
+mod animation;
+mod content;
 mod data;
+mod eviction;
 pub(super) mod migrations;
+#[cfg(feature = "url_preview")]
+mod oembed;
 mod preview;
+mod quarantine;
+mod quota;
 mod remote;
+mod room;
+mod scan;
 mod tests;
 mod thumbnail;
+mod tiering;
 #[cfg(feature = "media_thumbnail")]
 mod video;
 use std::{
@@ -40,8 +51,20 @@
 
 #[cfg(feature = "media_thumbnail")]
 use self::video::{FAILURES, Failures, sweep_staging_dir};
-use self::{data::Data, preview::Agent, remote::Fetch};
-pub use self::{data::Metadata, preview::UrlPreviewData, thumbnail::Dim};
+pub use self::{
+	data::Metadata,
+	preview::UrlPreviewData,
+	quarantine::Quarantine,
+	quota::{Quota, QuotaOverride, Usage},
+	room::RoomMedia,
+	scan::ScanVerdict,
+	thumbnail::Dim,
+};
+use self::{
+	data::{Data, Stored},
+	preview::Agent,
+	remote::Fetch,
+};
 use crate::storage::Provider;
 
 #[derive(Debug)]
@@ -52,8 +75,9 @@ pub struct Media {
 }
 
 /// One row of a user's uploaded media, holding only the fields tuwunel can
-/// derive from the uploader index and storage-provider object metadata. The
-/// uploader is carried when the index holds a row for it.
+/// derive from the uploader index, storage-provider object metadata and the
+/// quarantine flags. The uploader is carried when the index holds a row for
+/// it.
 #[derive(Clone, Debug)]
 pub struct UserMediaEntry {
 	pub mxc: OwnedMxcUri,
@@ -62,6 +86,8 @@ pub struct UserMediaEntry {
 	pub media_length: Option<u64>,
 	pub created_ts: u64,
 	pub user_id: Option<OwnedUserId>,
+	pub quarantined_by: Option<OwnedUserId>,
+	pub safe_from_quarantine: bool,
 }
 
 /// One locally-uploaded media item's uploader and storage-object byte length
@@ -86,7 +112,11 @@ pub struct Service {
 	services: Arc<crate::services::OnceServices>,
 	url_preview_mutex: MutexMap<String, ()>,
 	federation_mutex: MutexMap<String, ()>,
+	quota_mutex: MutexMap<OwnedUserId, ()>,
+	content_mutex: MutexMap<String, ()>,
 	mxc_state: MXCState,
+	#[cfg(feature = "url_preview")]
+	oembed: oembed::Providers,
 	#[cfg(feature = "media_thumbnail")]
 	video_thumbnail_slots: Semaphore,
 	#[cfg(feature = "media_thumbnail")]
@@ -105,6 +135,9 @@ pub struct Service {
 /// Validity window for a presigned media download redirect (MSC3860).
 const REDIRECT_TTL: Duration = Duration::from_mins(5);
 
+/// Period of the worker's remote media eviction and tiering passes.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_hours(1);
+
 #[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
@@ -113,10 +146,14 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 			services: args.services.clone(),
 			url_preview_mutex: MutexMap::new(),
 			federation_mutex: MutexMap::new(),
+			quota_mutex: MutexMap::new(),
+			content_mutex: MutexMap::new(),
 			mxc_state: MXCState {
 				notifiers: Mutex::new(HashMap::new()),
 				ratelimiter: Mutex::new(HashMap::new()),
 			},
+			#[cfg(feature = "url_preview")]
+			oembed: oembed::Providers::load(&args.server.config.url_preview_oembed_providers)?,
 			#[cfg(feature = "media_thumbnail")]
 			video_thumbnail_failures: Failures::new(FAILURES).into(),
 			#[cfg(feature = "media_thumbnail")]
@@ -134,6 +171,28 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(service)
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		loop {
+			tokio::select! {
+				() = tokio::time::sleep(MAINTENANCE_INTERVAL) => {},
+				() = self.services.server.until_shutdown() => return Ok(())
+			};
+
+			self.evict_remote_media_if_enabled().await;
+
+			// the first pass waits out startup, when the task tracker resumes
+			// an interrupted tiering task
+			if self
+				.services
+				.config
+				.media_tiering_provider
+				.is_some()
+			{
+				self.start_tiering().log_debug_err().ok();
+			}
+		}
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -251,6 +310,24 @@ pub async fn create(
 		content_type: Option<&str>,
 		file: &[u8],
 	) -> Result {
+		self.services
+			.spam_checker
+			.check_media_file_for_spam(user, content_type, file)
+			.await?;
+
+		self.check_scan(file).await?;
+
+		// serialize a user's uploads so concurrent ones can't overrun the quota
+		let len = u64::try_from(file.len())?;
+		let _quota_lock = match user {
+			| Some(user) => {
+				let lock = self.quota_mutex.lock(user).await;
+				self.check_quota(user, len).await?;
+				Some(lock)
+			},
+			| None => None,
+		};
+
 		// Width, Height = 0 if it's not a thumbnail
 		let key = self.db.create_file_metadata(
 			mxc,
@@ -261,7 +338,13 @@ pub async fn create(
 		)?;
 
 		//TODO: Dangling metadata in database if creation fails
-		self.create_media_file(&key, file).await
+		self.create_media_file(&key, file).await?;
+
+		if let Some(user) = user {
+			self.add_usage(user, len).await;
+		}
+
+		Ok(())
 	}
 
 	/// Deletes a file in the database and from the media directory via an MXC
@@ -281,6 +364,8 @@ pub async fn delete(&self, mxc: &Mxc<'_>) -> Result {
 
 		match self.db.search_mxc_metadata_prefix(mxc).await {
 			| Ok(keys) => {
+				self.remove_usage(mxc).await;
+
 				for key in keys {
 					trace!(?mxc, "MXC Key: {key:?}");
 					debug_info!(?mxc, "Deleting from storage provider");
@@ -380,7 +465,10 @@ pub async fn get_or_fetch(&self, mxc: &Mxc<'_>, timeout_ms: Duration) -> Result<
 		skip(self),
 	)]
 	pub async fn get(&self, mxc: &Mxc<'_>, timeout: Option<Duration>) -> Result<Media> {
+		self.check_quarantine(mxc).await?;
+
 		if let Ok(meta) = self.get_stored(mxc).await {
+			self.touch(mxc).await;
 			return Ok(meta);
 		}
 
@@ -410,6 +498,17 @@ pub async fn get(&self, mxc: &Mxc<'_>, timeout: Option<Duration>) -> Result<Medi
 		self.get_stored(mxc).await
 	}
 
+	/// Get file from local storage, never fetching it, not even the staged
+	/// content of a URL preview.
+	#[tracing::instrument(level = "debug", skip(self))]
+	pub async fn get_if_stored(&self, mxc: &Mxc<'_>) -> Result<Media> {
+		self.db
+			.search_file_metadata(mxc, &Dim::default())
+			.await?;
+
+		self.get_stored(mxc).await
+	}
+
 	/// Get file from local storage.
 	#[tracing::instrument(level = "debug", skip(self))]
 	pub async fn get_stored(&self, mxc: &Mxc<'_>) -> Result<Media> {
@@ -422,9 +521,11 @@ pub async fn get_stored(&self, mxc: &Mxc<'_>) -> Result<Media> {
 			return self.fetch_lazy_media(mxc).await;
 		};
 
-		let path = self.get_media_name_sha256(&key);
+		let path = self.media_path(&key).await;
 		let fetch = self
-			.storage_providers()
+			.media_providers(&key)
+			.await
+			.into_iter()
 			.stream()
 			.filter_map(async |provider| {
 				provider
@@ -439,6 +540,8 @@ pub async fn get_stored(&self, mxc: &Mxc<'_>) -> Result<Media> {
 			return Err!(Request(NotFound("Media not found.")));
 		};
 
+		self.check_stored_scan(&key, &bytes).await?;
+
 		Ok(Media {
 			content: bytes.to_vec(),
 			content_type,
@@ -513,21 +616,33 @@ async fn fetch_lazy_media(&self, mxc: &Mxc<'_>) -> Result<Media> {
 	/// Presigned redirect URL for locally-stored media (MSC3860).
 	///
 	/// Returns the first configured provider's signed URL for the object, or
-	/// `None` when redirects are disabled, the media is unknown, or no provider
-	/// can presign (filesystem-only media).
+	/// `None` when redirects are disabled, the media is unknown or not yet
+	/// scanned clean, or no provider can presign (filesystem-only media).
 	#[tracing::instrument(level = "debug", skip(self))]
 	pub async fn redirect_url(&self, mxc: &Mxc<'_>, dim: &Dim) -> Result<Option<Url>> {
 		if !self.services.config.media_allow_redirect {
 			return Ok(None);
 		}
 
+		self.check_quarantine(mxc).await?;
+
 		let Ok(Metadata { key, .. }) = self.db.search_file_metadata(mxc, dim).await else {
 			return Ok(None);
 		};
 
-		let path = self.get_media_name_sha256(&key);
+		// a redirect bypasses the scan on download, so only content already
+		// scanned clean is redirected
+		if !self.scanned_clean(&key).await {
+			return Ok(None);
+		}
+
+		self.touch(mxc).await;
+
+		let path = self.media_path(&key).await;
 		let urls = self
-			.storage_providers()
+			.media_providers(&key)
+			.await
+			.into_iter()
 			.stream()
 			.filter_map(async |provider| {
 				provider
@@ -587,8 +702,8 @@ pub async fn get_all_mxcs(&self) -> Result<Vec<OwnedMxcUri>> {
 
 	/// Every media item uploaded by a local user, carrying the fields tuwunel
 	/// can derive: content type, upload name, byte length and modification time
-	/// from storage-provider object metadata. Untracked columns (last-access,
-	/// quarantine, url-cache) are not represented.
+	/// from storage-provider object metadata, and the quarantine flags.
+	/// Untracked columns (last-access, url-cache) are not represented.
 	#[tracing::instrument(level = "debug", skip(self))]
 	pub async fn user_media(&self, user: &UserId) -> Result<Vec<UserMediaEntry>> {
 		let entries = self
@@ -614,6 +729,12 @@ pub async fn media_entry(&self, mxc: &Mxc<'_>) -> Option<UserMediaEntry> {
 			.await
 	}
 
+	/// The local user who uploaded the media at the given MXC, from the
+	/// uploader index; remote media has none.
+	pub async fn uploader(&self, mxc: &Mxc<'_>) -> Option<OwnedUserId> {
+		self.db.mxc_user(mxc).await
+	}
+
 	async fn user_media_entry(
 		&self,
 		user: Option<&UserId>,
@@ -625,6 +746,8 @@ async fn user_media_entry(
 
 		let object = self.head_meta(&key).await;
 		let upload_name = content_disposition.and_then(|disposition| disposition.filename);
+		let Quarantine { quarantined_by, safe_from_quarantine } =
+			self.quarantine_state(&parts).await;
 
 		Some(UserMediaEntry {
 			media_type: content_type,
@@ -632,6 +755,8 @@ async fn user_media_entry(
 			media_length: object.as_ref().map(|object| object.size),
 			created_ts: object.as_ref().map(mtime_millis).unwrap_or(0),
 			user_id: user.map(ToOwned::to_owned),
+			quarantined_by,
+			safe_from_quarantine,
 			mxc,
 		})
 	}
@@ -657,9 +782,8 @@ pub fn upload_stats(&self) -> impl Stream<Item = UploadStat> + Send + '_ {
 
 	/// Deletes local media older than `before_ts` (by storage-provider mtime)
 	/// and strictly larger than `size_gt` bytes, sparing profile and
-	/// room-avatar media when `keep_profiles`. Returns the deleted MXCs, empty
-	/// when none match. Quarantine and protection flags are not tracked, so no
-	/// media is spared on those grounds.
+	/// room-avatar media when `keep_profiles`, and protected media always.
+	/// Returns the deleted MXCs, empty when none match.
 	#[tracing::instrument(level = "debug", skip(self))]
 	pub async fn delete_by_date_size(
 		&self,
@@ -682,8 +806,13 @@ pub async fn delete_by_date_size(
 				let parts = mxc.parts().ok()?;
 				let Metadata { key, .. } = self.get_metadata(&parts).await?;
 				let object = self.head_meta(&key).await?;
+				let protected = self
+					.quarantine_state(&parts)
+					.await
+					.safe_from_quarantine;
 
-				let eligible = mtime_millis(&object) < before_ts && object.size > size_gt;
+				let eligible =
+					!protected && mtime_millis(&object) < before_ts && object.size > size_gt;
 
 				eligible
 					.then_async(|| self.delete(&parts))
@@ -733,10 +862,12 @@ fn is_local(&self, mxc: &OwnedMxcUri) -> bool {
 	/// `key` (byte length and modification time), or `None` when no provider
 	/// holds it.
 	async fn head_meta(&self, key: &[u8]) -> Option<ObjectMeta> {
-		let path = self.get_media_name_sha256(key);
+		let path = self.media_path(key).await;
 
 		let stream = self
-			.storage_providers()
+			.media_providers(key)
+			.await
+			.into_iter()
 			.stream()
 			.filter_map(async |provider| provider.head(&path).await.log_debug_err().ok());
 
@@ -744,6 +875,25 @@ async fn head_meta(&self, key: &[u8]) -> Option<ObjectMeta> {
 		stream.next().await
 	}
 
+	/// Size and stored time of the file under `key`, as recorded when it was
+	/// stored. Files stored before that are looked up on the storage
+	/// providers once and recorded.
+	async fn stored(&self, key: &[u8]) -> Option<Stored> {
+		if let Some(stored) = self.db.get_stored(key).await {
+			return Some(stored);
+		}
+
+		let object = self.head_meta(key).await?;
+		let stored = Stored {
+			size: object.size,
+			stored_ts: mtime_millis(&object),
+		};
+
+		self.db.set_stored(key, &stored);
+
+		Some(stored)
+	}
+
 	/// Deletes all media files before or after the given time. Returns a usize
 	/// with the number of media files deleted.
 	pub async fn delete_range(
@@ -788,25 +938,25 @@ pub async fn delete_range(
 				continue;
 			}
 
+			let path = self.media_path(&key).await;
 			let file_created_at = if let Some(file_metadata) = self
-				.storage_providers()
+				.media_providers(&key)
+				.await
+				.into_iter()
 				.stream()
-				.filter_map(async |provider| {
-					let path = self.get_media_name_sha256(&key);
-					match provider.head(&path).await {
-						| Ok(file_metadata) => {
-							trace!(%mxc, ?path, "Provider file metadata: {file_metadata:?}");
-							Some(file_metadata)
-						},
-						| Err(e) => {
-							debug_warn!(
-								"Failed to obtain {:?} file metadata for MXC {mxc} at file path \
-								 {path:?}\", skipping: {e}",
-								provider.name,
-							);
-							None
-						},
-					}
+				.filter_map(async |provider| match provider.head(&path).await {
+					| Ok(file_metadata) => {
+						trace!(%mxc, ?path, "Provider file metadata: {file_metadata:?}");
+						Some(file_metadata)
+					},
+					| Err(e) => {
+						debug_warn!(
+							"Failed to obtain {:?} file metadata for MXC {mxc} at file path \
+							 {path:?}\", skipping: {e}",
+							provider.name,
+						);
+						None
+					},
 				})
 				.boxed()
 				.next()
@@ -864,18 +1014,17 @@ pub async fn create_media_dir(&self) -> Result {
 		Ok(fs::create_dir_all(dir).await?)
 	}
 
-	async fn remove_media_file(&self, key: &[u8]) -> Result {
-		let path = self.get_media_name_sha256(key);
+	async fn delete_media_file(&self, path: &str) -> Result {
 		self.storage_providers()
 			.stream()
 			.filter_map(async |provider| {
 				debug!(
-					?key, ?path, provider = ?provider.name,
+					?path, provider = ?provider.name,
 					"Deleting media file from provider",
 				);
 
 				provider
-					.delete_one(&path)
+					.delete_one(path)
 					.await
 					.log_debug_err()
 					.ok()
@@ -890,7 +1039,7 @@ async fn remove_media_file(&self, key: &[u8]) -> Result {
 			.await
 	}
 
-	async fn create_media_file(&self, key: &[u8], file: &[u8]) -> Result {
+	async fn put_media_file(&self, path: &str, file: &[u8]) -> Result {
 		self.storage_providers()
 			.try_stream()
 			.ready_try_filter(|provider| {
@@ -900,18 +1049,14 @@ async fn create_media_file(&self, key: &[u8], file: &[u8]) -> Result {
 					|| store_media_on_providers.contains(&provider.name)
 			})
 			.and_then(async |provider| {
-				let path = self.get_media_name_sha256(key);
 				debug!(
-					?key, ?path,
+					?path,
 					len = ?file.len(),
 					provider = ?provider.name,
 					"Creating media file on storage provider."
 				);
 
-				if let Err(e) = provider
-					.put_one(path.as_str(), file.to_vec())
-					.await
-				{
+				if let Err(e) = provider.put_one(path, file.to_vec()).await {
 					return Err!(Database(error!(
 						?path,
 						?provider,
@@ -0,0 +1,181 @@
+//! oEmbed Providers
+//!
+//! The registry of oEmbed endpoints URL previews consult: YouTube, which is
+//! always known, and every provider of the configured `providers.json` lists.
+//! A provider's schemes are globs in which `*` matches anything. A page may
+//! also name its own endpoint in a `<link>` tag, found by `discover`.
+
+use std::{fs, path::PathBuf, sync::LazyLock};
+
+use regex::{Regex, RegexSet};
+use serde::Deserialize;
+use tuwunel_core::{Result, err};
+use url::Url;
+
+use super::preview::YOUTUBE_HOSTS;
+
+/// Endpoint answering oEmbed for every host in `YOUTUBE_HOSTS`, including
+/// the short and subdomain forms.
+const YOUTUBE_OEMBED: &str = "https://www.youtube.com/oembed";
+
+/// Media type of a JSON oEmbed document, as a discovery link declares it.
+const JSON_OEMBED: &str = "application/json+oembed";
+
+static LINK: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"(?is)<link\b[^>]*>").expect("valid link pattern"));
+
+static ATTR: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r#"(?s)([a-zA-Z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#)
+		.expect("valid attribute pattern")
+});
+
+/// One provider of a `providers.json` list. Only the fields locating an
+/// endpoint are read.
+#[derive(Deserialize)]
+struct ProviderEntry {
+	#[serde(default)]
+	endpoints: Vec<EndpointEntry>,
+}
+
+#[derive(Deserialize)]
+struct EndpointEntry {
+	#[serde(default)]
+	schemes: Vec<String>,
+	url: String,
+	#[serde(default)]
+	formats: Vec<String>,
+}
+
+pub(super) struct Providers {
+	endpoints: Vec<Endpoint>,
+}
+
+struct Endpoint {
+	schemes: RegexSet,
+	url: String,
+}
+
+impl Providers {
+	/// YouTube and the providers of each list.
+	pub(super) fn load(paths: &[PathBuf]) -> Result<Self> {
+		let mut providers = Self::default();
+		for path in paths {
+			let list = fs::read(path)
+				.map_err(|e| err!(Config("url_preview_oembed_providers", "{path:?}: {e}")))?;
+
+			providers
+				.extend(&list)
+				.map_err(|e| err!(Config("url_preview_oembed_providers", "{path:?}: {e}")))?;
+		}
+
+		Ok(providers)
+	}
+
+	/// Adds the providers of a `providers.json` list. Endpoints without
+	/// schemes are reachable only by discovery, and endpoints not answering in
+	/// JSON not at all, so neither is kept.
+	pub(super) fn extend(&mut self, list: &[u8]) -> Result {
+		let list: Vec<ProviderEntry> = serde_json::from_slice(list)?;
+		for entry in list
+			.into_iter()
+			.flat_map(|provider| provider.endpoints)
+		{
+			let json = entry.formats.is_empty() || entry.formats.iter().any(|f| f == "json");
+			if !json || entry.schemes.is_empty() {
+				continue;
+			}
+
+			let schemes = RegexSet::new(
+				entry
+					.schemes
+					.iter()
+					.map(|glob| scheme_pattern(glob)),
+			)?;
+
+			self.endpoints
+				.push(Endpoint { schemes, url: entry.url });
+		}
+
+		Ok(())
+	}
+
+	/// The oEmbed request for `url` at the first provider whose schemes match
+	/// it.
+	pub(super) fn endpoint(&self, url: &Url) -> Option<Url> {
+		self.endpoints
+			.iter()
+			.find(|endpoint| endpoint.schemes.is_match(url.as_str()))
+			.and_then(|endpoint| request_url(&endpoint.url, url))
+	}
+}
+
+impl Default for Providers {
+	fn default() -> Self {
+		let globs = YOUTUBE_HOSTS
+			.iter()
+			.flat_map(|host| ["http", "https"].map(|scheme| format!("{scheme}://{host}/*")));
+
+		let youtube = Endpoint {
+			schemes: RegexSet::new(globs.map(|glob| scheme_pattern(&glob)))
+				.expect("valid YouTube schemes"),
+			url: YOUTUBE_OEMBED.to_owned(),
+		};
+
+		Self { endpoints: vec![youtube] }
+	}
+}
+
+/// The endpoint's request for the page. A `{format}` placeholder in the
+/// endpoint selects JSON in the path, otherwise the query does.
+fn request_url(endpoint: &str, page: &Url) -> Option<Url> {
+	if endpoint.contains("{format}") {
+		let endpoint = endpoint.replace("{format}", "json");
+
+		return Url::parse_with_params(&endpoint, [("url", page.as_str())]).ok();
+	}
+
+	Url::parse_with_params(endpoint, [("url", page.as_str()), ("format", "json")]).ok()
+}
+
+/// An anchored, case-insensitive pattern for a scheme glob.
+fn scheme_pattern(glob: &str) -> String {
+	let pattern = regex::escape(glob).replace(r"\*", ".*");
+
+	format!("(?i)^{pattern}$")
+}
+
+/// The JSON oEmbed endpoint a page names in a `<link rel="alternate">` tag,
+/// resolved against the page and limited to http(s).
+pub(super) fn discover(html: &str, page: &Url) -> Option<Url> {
+	LINK.find_iter(html)
+		.find_map(|link| {
+			let mut rel = None;
+			let mut kind = None;
+			let mut href = None;
+			for attr in ATTR.captures_iter(link.as_str()) {
+				let value = attr
+					.get(2)
+					.or_else(|| attr.get(3))
+					.or_else(|| attr.get(4))
+					.map(|value| value.as_str());
+
+				match attr[1].to_ascii_lowercase().as_str() {
+					| "rel" => rel = value,
+					| "type" => kind = value,
+					| "href" => href = value,
+					| _ => {},
+				}
+			}
+
+			let alternate = rel.is_some_and(|rel| {
+				rel.split_ascii_whitespace()
+					.any(|rel| rel.eq_ignore_ascii_case("alternate"))
+			});
+
+			let json = kind.is_some_and(|kind| kind.trim().eq_ignore_ascii_case(JSON_OEMBED));
+
+			(alternate && json).then_some(href).flatten()
+		})
+		.and_then(|href| page.join(&href.replace("&amp;", "&")).ok())
+		.filter(|endpoint| ["http", "https"].contains(&endpoint.scheme()))
+}
@@ -164,7 +164,7 @@ pub(super) enum Agent {
 
 /// Hosts whose pages carry their `<head>` metadata only for an allowlisted
 /// crawler, and which answer oEmbed for any agent.
-const YOUTUBE_HOSTS: [&str; 5] = [
+pub(super) const YOUTUBE_HOSTS: [&str; 5] = [
 	"youtu.be",
 	"youtube.com",
 	"www.youtube.com",
@@ -179,11 +179,6 @@ pub(super) enum Agent {
 /// endpoints still honor.
 const YOUTUBE_CONSENT_COOKIE: &str = "SOCS=CAI; CONSENT=PENDING+999";
 
-/// Endpoint answering oEmbed for every host in `YOUTUBE_HOSTS`, including
-/// the short and subdomain forms.
-#[cfg(feature = "url_preview")]
-const YOUTUBE_OEMBED: &str = "https://www.youtube.com/oembed";
-
 /// An oEmbed document runs to a few hundred bytes; the cap bounds only a
 /// hostile origin.
 #[cfg(feature = "url_preview")]
@@ -222,6 +217,13 @@ pub async fn get_url_preview(&self, url: &Url) -> Result<UrlPreviewData> {
 pub async fn request_url_preview(&self, url: &Url) -> Result<UrlPreviewData> {
 	self.check_url_host(url)?;
 
+	if let Some(data) = self.oembed_provided(url).await {
+		let cached = CachedPreview::new(data);
+		self.db.set_url_preview(url.as_str(), &cached)?;
+
+		return Ok(cached.preview);
+	}
+
 	let response = self.preview_get(url, Agent::Page).send().await?;
 
 	debug!(?url, "URL preview response headers: {:?}", response.headers());
@@ -271,9 +273,9 @@ pub async fn request_url_preview(&self, url: &Url) -> Result<UrlPreviewData> {
 				))));
 			}
 
-			let data = self.download_html(url, response).await?;
+			let (data, discovered) = self.download_html(url, response).await?;
 
-			self.oembed_recover(url, data).await
+			self.oembed_recover(url, data, discovered).await
 		},
 		| img if img.starts_with("image/") => {
 			let response = self
@@ -377,8 +379,29 @@ fn check_remote_addr(&self, response: &reqwest::Response) -> Result {
 		.ok_or_else(|| err!(Request(Forbidden("Requesting from this address is forbidden"))))
 }
 
-/// Recover a preview from the origin's oEmbed endpoint when the page yielded
-/// nothing usable.
+/// Preview a URL from the oEmbed endpoint of a known provider matching it,
+/// sparing the page request.
+///
+/// Such providers are known precisely because their pages preview poorly, so
+/// the page is scraped only when the endpoint fails.
+#[cfg(feature = "url_preview")]
+#[implement(Service)]
+async fn oembed_provided(&self, url: &Url) -> Option<UrlPreviewData> {
+	let endpoint = self.oembed.endpoint(url)?;
+
+	self.oembed_preview(&endpoint, url)
+		.await
+		.inspect_err(|e| debug!(%url, %e, "oEmbed provider failed"))
+		.ok()
+}
+
+#[cfg(not(feature = "url_preview"))]
+#[implement(Service)]
+#[expect(clippy::unused_async)]
+async fn oembed_provided(&self, _url: &Url) -> Option<UrlPreviewData> { None }
+
+/// Recover a preview from the oEmbed endpoint the page names for itself when
+/// the page yielded nothing usable.
 ///
 /// Some origins serve their `<head>` metadata only to an agent they
 /// recognise as a link-preview crawler, while answering oEmbed for anyone.
@@ -386,13 +409,18 @@ fn check_remote_addr(&self, response: &reqwest::Response) -> Result {
 /// request, and the original preview stands if that request fails too.
 #[cfg(feature = "url_preview")]
 #[implement(Service)]
-async fn oembed_recover(&self, url: &Url, data: UrlPreviewData) -> UrlPreviewData {
+async fn oembed_recover(
+	&self,
+	url: &Url,
+	data: UrlPreviewData,
+	discovered: Option<Url>,
+) -> UrlPreviewData {
 	// an already-staged image would be orphaned by replacing the preview
 	if data.title.is_some() || data.image.is_some() {
 		return data;
 	}
 
-	let Some(endpoint) = oembed_endpoint(url) else {
+	let Some(endpoint) = discovered else {
 		return data;
 	};
 
@@ -405,16 +433,13 @@ async fn oembed_recover(&self, url: &Url, data: UrlPreviewData) -> UrlPreviewDat
 #[cfg(not(feature = "url_preview"))]
 #[implement(Service)]
 #[expect(clippy::unused_async)]
-async fn oembed_recover(&self, _url: &Url, data: UrlPreviewData) -> UrlPreviewData { data }
-
-/// The oEmbed endpoint answering for `url`, when its origin has one.
-#[cfg(feature = "url_preview")]
-fn oembed_endpoint(url: &Url) -> Option<Url> {
-	is_youtube(url)
-		.then(|| {
-			Url::parse_with_params(YOUTUBE_OEMBED, [("url", url.as_str()), ("format", "json")])
-		})
-		.and_then(Result::ok)
+async fn oembed_recover(
+	&self,
+	_url: &Url,
+	data: UrlPreviewData,
+	_discovered: Option<Url>,
+) -> UrlPreviewData {
+	data
 }
 
 /// Fetch an oEmbed document and render it as a preview.
@@ -424,8 +449,8 @@ fn oembed_endpoint(url: &Url) -> Option<Url> {
 #[cfg(feature = "url_preview")]
 #[implement(Service)]
 async fn oembed_preview(&self, endpoint: &Url, page: &Url) -> Result<UrlPreviewData> {
-	// this host is chosen here rather than named by the page, so the operator's
-	// own allowlist decides whether it may be contacted at all
+	// the endpoint may be a configured provider's or one the page discovered,
+	// and either way the operator's allowlist decides whether it is contacted
 	if !self.url_preview_allowed(endpoint) {
 		return Err!(Request(Forbidden(debug_warn!(
 			%endpoint,
@@ -775,9 +800,15 @@ fn checked_media_size(response: &reqwest::Response, limit: usize) -> Result<Opti
 	Ok(size)
 }
 
+/// Scrape a page's preview, along with the oEmbed endpoint it names for
+/// itself when discovery is enabled.
 #[cfg(feature = "url_preview")]
 #[implement(Service)]
-async fn download_html(&self, url: &Url, response: reqwest::Response) -> Result<UrlPreviewData> {
+async fn download_html(
+	&self,
+	url: &Url,
+	response: reqwest::Response,
+) -> Result<(UrlPreviewData, Option<Url>)> {
 	use webpage::HTML;
 
 	let limit = self.services.config.url_preview_max_spider_size;
@@ -788,6 +819,13 @@ async fn download_html(&self, url: &Url, response: reqwest::Response) -> Result<
 	let body = String::from_utf8(bytes)
 		.unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
 
+	let discovered = self
+		.services
+		.config
+		.url_preview_oembed_discovery
+		.then(|| super::oembed::discover(&body, url))
+		.flatten();
+
 	let Ok(html) = HTML::from_string(body, Some(url.as_str().to_owned())) else {
 		return Err!(Request(Unknown("Failed to parse HTML")));
 	};
@@ -880,7 +918,7 @@ async fn download_html(&self, url: &Url, response: reqwest::Response) -> Result<
 		);
 	}
 
-	Ok(data)
+	Ok((data, discovered))
 }
 
 #[cfg(not(feature = "url_preview"))]
@@ -890,7 +928,7 @@ async fn download_html(
 	&self,
 	_url: &Url,
 	_response: reqwest::Response,
-) -> Result<UrlPreviewData> {
+) -> Result<(UrlPreviewData, Option<Url>)> {
 	Err!(FeatureDisabled("url_preview"))
 }
 
@@ -1128,9 +1166,9 @@ mod tests {
 	use minicbor_serde::{from_slice, to_vec};
 	use url::Url;
 
-	use super::{CachedPreview, UrlPreviewData, is_youtube};
 	#[cfg(feature = "url_preview")]
-	use super::{oembed_endpoint, reserve_capped, video_type};
+	use super::{super::oembed::Providers, reserve_capped, video_type};
+	use super::{CachedPreview, UrlPreviewData, is_youtube};
 
 	fn sample() -> UrlPreviewData {
 		UrlPreviewData {
@@ -1258,7 +1296,10 @@ fn youtube_hosts_matched() {
 	#[test]
 	fn oembed_endpoint_carries_the_page_url() {
 		let url = Url::parse("https://www.youtube.com/watch?v=a&b=c").expect("parses");
-		let endpoint = oembed_endpoint(&url).expect("youtube has an endpoint");
+		let providers = Providers::default();
+		let endpoint = providers
+			.endpoint(&url)
+			.expect("youtube has an endpoint");
 
 		assert_eq!(endpoint.path(), "/oembed");
 
@@ -1268,7 +1309,11 @@ fn oembed_endpoint_carries_the_page_url() {
 			("format".into(), "json".into())
 		]);
 
-		assert!(oembed_endpoint(&Url::parse("https://example.org/").expect("parses")).is_none());
+		assert!(
+			providers
+				.endpoint(&Url::parse("https://example.org/").expect("parses"))
+				.is_none()
+		);
 	}
 
 	#[cfg(feature = "url_preview")]
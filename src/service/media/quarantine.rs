@@ -0,0 +1,128 @@
+//! Media quarantine and protection.
+//!
+//! Quarantined media is refused on every download and thumbnail path, local
+//! or federated, and is never fetched from its origin again. Protected media
+//! cannot be quarantined and is spared by the date and size purge. The flags
+//! are keyed by MXC, so a quarantine placed on remote media also holds before
+//! the media is first fetched.
+
+use futures::StreamExt;
+use ruma::{Mxc, OwnedMxcUri, OwnedUserId, RoomId, UserId};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{Err, Result, debug_info, implement, utils::stream::IterStream};
+
+/// Quarantine and protection flags of one media item.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Quarantine {
+	/// The admin who quarantined the media, while it is quarantined.
+	pub quarantined_by: Option<OwnedUserId>,
+
+	/// Protected from quarantine.
+	pub safe_from_quarantine: bool,
+}
+
+#[implement(super::Service)]
+pub async fn quarantine_state(&self, mxc: &Mxc<'_>) -> Quarantine {
+	self.db
+		.get_quarantine(mxc)
+		.await
+		.unwrap_or_default()
+}
+
+#[implement(super::Service)]
+pub async fn is_quarantined(&self, mxc: &Mxc<'_>) -> bool {
+	self.quarantine_state(mxc)
+		.await
+		.quarantined_by
+		.is_some()
+}
+
+/// Fails with `M_NOT_FOUND` when the media is quarantined.
+#[implement(super::Service)]
+pub(super) async fn check_quarantine(&self, mxc: &Mxc<'_>) -> Result {
+	if self.is_quarantined(mxc).await {
+		return Err!(Request(NotFound("Media not found.")));
+	}
+
+	Ok(())
+}
+
+/// Quarantines the media on behalf of `by`, or lifts its quarantine when `by`
+/// is `None`. Returns whether the media changed; protected media is never
+/// quarantined.
+#[implement(super::Service)]
+pub async fn set_quarantined(&self, mxc: &Mxc<'_>, by: Option<&UserId>) -> bool {
+	let state = self.quarantine_state(mxc).await;
+	let Some(state) = quarantined(state, by) else {
+		return false;
+	};
+
+	debug_info!(%mxc, quarantined_by = ?state.quarantined_by, "Setting media quarantine");
+	self.db.set_quarantine(mxc, &state);
+
+	true
+}
+
+/// Sets or clears the protection flag. Returns whether the media changed.
+#[implement(super::Service)]
+pub async fn set_protected(&self, mxc: &Mxc<'_>, protected: bool) -> bool {
+	let mut state = self.quarantine_state(mxc).await;
+	if state.safe_from_quarantine == protected {
+		return false;
+	}
+
+	state.safe_from_quarantine = protected;
+	self.db.set_quarantine(mxc, &state);
+
+	true
+}
+
+/// Quarantines or releases each of the given media, returning how many
+/// changed. Invalid MXC URIs are skipped.
+#[implement(super::Service)]
+pub async fn set_quarantined_many<I>(&self, mxcs: I, by: Option<&UserId>) -> usize
+where
+	I: IntoIterator<Item = OwnedMxcUri> + Send,
+	I::IntoIter: Send,
+{
+	mxcs.into_iter()
+		.stream()
+		.filter_map(async |mxc| {
+			let mxc = mxc.parts().ok()?;
+
+			self.set_quarantined(&mxc, by).await.then_some(())
+		})
+		.count()
+		.await
+}
+
+/// Quarantines or releases every media item, local or remote, referenced by
+/// the room's events.
+#[implement(super::Service)]
+pub async fn set_quarantined_room(&self, room_id: &RoomId, by: Option<&UserId>) -> usize {
+	let (local, remote) = self.room_media(room_id).await;
+
+	self.set_quarantined_many(local.into_iter().chain(remote), by)
+		.await
+}
+
+/// Quarantines or releases every media item uploaded by the local user.
+#[implement(super::Service)]
+pub async fn set_quarantined_user(&self, user_id: &UserId, by: Option<&UserId>) -> usize {
+	let mxcs = self.db.get_all_user_mxcs(user_id).await;
+
+	self.set_quarantined_many(mxcs, by).await
+}
+
+/// The flags after quarantining on behalf of `by`, or releasing when `None`;
+/// `None` when nothing changes.
+pub(super) fn quarantined(state: Quarantine, by: Option<&UserId>) -> Option<Quarantine> {
+	match (by, &state.quarantined_by) {
+		| (Some(_), _) if state.safe_from_quarantine => None,
+		| (Some(_), Some(_)) | (None, None) => None,
+		| _ => Some(Quarantine {
+			quarantined_by: by.map(ToOwned::to_owned),
+			..state
+		}),
+	}
+}
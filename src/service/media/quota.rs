@@ -0,0 +1,193 @@
+//! Per-user media quotas (MSC4034).
+//!
+//! Each local user's uploads are tallied by total bytes and file count. The
+//! tally is built from the uploader index the first time it is needed and
+//! kept current as media is uploaded and deleted. Uploads that would exceed
+//! the configured limits, or an admin's override of them, are refused.
+
+use futures::StreamExt;
+use http::StatusCode;
+use ruma::{
+	Mxc, UserId,
+	api::error::{ErrorKind, ResourceLimitExceededErrorData},
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{Error, Result, debug, implement, utils::stream::IterStream};
+
+use super::Metadata;
+
+/// Media a user has uploaded.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Usage {
+	pub bytes: u64,
+	pub files: u64,
+}
+
+/// An admin-set replacement for the configured quota. A zero limit disables
+/// it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct QuotaOverride {
+	pub max_bytes: u64,
+	pub max_files: u64,
+}
+
+/// The quota in force for a user; `None` is unlimited.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Quota {
+	pub max_bytes: Option<u64>,
+	pub max_files: Option<u64>,
+}
+
+/// The user's media usage, tallied from their uploads when not yet known.
+#[implement(super::Service)]
+pub async fn usage(&self, user_id: &UserId) -> Usage {
+	if let Some(usage) = self.db.get_usage(user_id).await {
+		return usage;
+	}
+
+	let usage = self.tally_usage(user_id).await;
+	debug!(%user_id, ?usage, "Tallied media usage");
+	self.db.set_usage(user_id, usage);
+
+	usage
+}
+
+#[implement(super::Service)]
+async fn tally_usage(&self, user_id: &UserId) -> Usage {
+	self.db
+		.get_all_user_mxcs(user_id)
+		.await
+		.into_iter()
+		.stream()
+		.filter_map(async |mxc| {
+			let mxc = mxc.parts().ok()?;
+			let Metadata { key, .. } = self.get_metadata(&mxc).await?;
+
+			self.head_meta(&key).await
+		})
+		.fold(Usage::default(), async |usage, object| Usage {
+			bytes: usage.bytes.saturating_add(object.size),
+			files: usage.files.saturating_add(1),
+		})
+		.await
+}
+
+/// The quota in force for the user: their override, else the configuration.
+#[implement(super::Service)]
+pub async fn quota(&self, user_id: &UserId) -> Quota {
+	let config = &self.services.config;
+	let quota_override = self.db.get_quota_override(user_id).await;
+
+	effective_quota(quota_override, config.media_quota_max_bytes, config.media_quota_max_files)
+}
+
+#[implement(super::Service)]
+pub async fn get_quota_override(&self, user_id: &UserId) -> Option<QuotaOverride> {
+	self.db.get_quota_override(user_id).await
+}
+
+/// Replaces the configured quota for the user.
+#[implement(super::Service)]
+pub fn set_quota_override(&self, user_id: &UserId, quota: QuotaOverride) {
+	self.db.set_quota_override(user_id, quota);
+}
+
+/// Returns the user to the configured quota.
+#[implement(super::Service)]
+pub fn clear_quota_override(&self, user_id: &UserId) { self.db.remove_quota_override(user_id); }
+
+/// Refuses an upload of `len` bytes which would take the user over quota.
+/// Callers hold the user's quota lock through the upload.
+#[implement(super::Service)]
+pub(super) async fn check_quota(&self, user_id: &UserId, len: u64) -> Result {
+	let quota = self.quota(user_id).await;
+	if quota == Quota::default() {
+		return Ok(());
+	}
+
+	let usage = self.usage(user_id).await;
+	if !exceeds(quota, usage, len) {
+		return Ok(());
+	}
+
+	let admin_contact = self
+		.services
+		.config
+		.well_known
+		.support_page
+		.as_ref()
+		.map(ToString::to_string)
+		.unwrap_or_default();
+
+	Err(Error::Request(
+		ErrorKind::ResourceLimitExceeded(ResourceLimitExceededErrorData { admin_contact }),
+		"Media storage quota exceeded.".into(),
+		StatusCode::FORBIDDEN,
+	))
+}
+
+/// Counts an upload of `len` bytes against the user, once their usage is
+/// tallied; an untallied usage will include it when first tallied.
+#[implement(super::Service)]
+pub(super) async fn add_usage(&self, user_id: &UserId, len: u64) {
+	if let Some(usage) = self.db.get_usage(user_id).await {
+		self.db.set_usage(user_id, Usage {
+			bytes: usage.bytes.saturating_add(len),
+			files: usage.files.saturating_add(1),
+		});
+	}
+}
+
+/// Releases the usage of media about to be deleted, returning it to its
+/// uploader's quota.
+#[implement(super::Service)]
+pub(super) async fn remove_usage(&self, mxc: &Mxc<'_>) {
+	let Some(user_id) = self.db.mxc_user(mxc).await else {
+		return;
+	};
+
+	let _lock = self.quota_mutex.lock(&user_id).await;
+	let Some(usage) = self.db.get_usage(&user_id).await else {
+		return;
+	};
+
+	let len = match self.get_metadata(mxc).await {
+		| Some(Metadata { key, .. }) => self.head_meta(&key).await,
+		| None => None,
+	}
+	.map_or(0, |object| object.size);
+
+	self.db.set_usage(&user_id, Usage {
+		bytes: usage.bytes.saturating_sub(len),
+		files: usage.files.saturating_sub(1),
+	});
+}
+
+/// The override where one is set, else the configured limits; zero limits
+/// are unlimited.
+pub(super) fn effective_quota(
+	quota_override: Option<QuotaOverride>,
+	max_bytes: u64,
+	max_files: u64,
+) -> Quota {
+	let (max_bytes, max_files) =
+		quota_override.map_or((max_bytes, max_files), |quota| (quota.max_bytes, quota.max_files));
+
+	Quota {
+		max_bytes: (max_bytes > 0).then_some(max_bytes),
+		max_files: (max_files > 0).then_some(max_files),
+	}
+}
+
+/// Whether one more upload of `len` bytes would take the usage over quota.
+pub(super) fn exceeds(quota: Quota, usage: Usage, len: u64) -> bool {
+	let bytes = quota
+		.max_bytes
+		.is_some_and(|max| usage.bytes.saturating_add(len) > max);
+
+	let files = quota
+		.max_files
+		.is_some_and(|max| usage.files >= max);
+
+	bytes || files
+}
@@ -43,6 +43,7 @@ pub async fn fetch_remote_thumbnail(
 	dim: &Dim,
 ) -> Result<Media> {
 	self.check_fetch_authorized(mxc)?;
+	self.check_quarantine(mxc).await?;
 
 	let result = self
 		.fetch_thumbnail_authenticated(mxc, server, timeout_ms, dim)
@@ -68,6 +69,7 @@ pub async fn fetch_remote_content(
 	timeout_ms: Duration,
 ) -> Result<Media> {
 	self.check_fetch_authorized(mxc)?;
+	self.check_quarantine(mxc).await?;
 
 	let result = self
 		.fetch_content_authenticated(mxc, server, timeout_ms)
@@ -99,7 +101,7 @@ async fn fetch_thumbnail_authenticated(
 		method: dim.method.clone().into(),
 		width: dim.width.into(),
 		height: dim.height.into(),
-		animated: true.into(),
+		animated: dim.animated.into(),
 		timeout_ms,
 	};
 
@@ -153,7 +155,7 @@ async fn fetch_thumbnail_unauthenticated(
 	let request = Request {
 		allow_remote: true,
 		allow_redirect: true,
-		animated: true.into(),
+		animated: dim.animated.into(),
 		method: dim.method.clone().into(),
 		width: dim.width.into(),
 		height: dim.height.into(),
@@ -400,6 +402,7 @@ pub async fn fetch_remote_thumbnail_legacy(
 
 	self.check_legacy_freeze()?;
 	self.check_fetch_authorized(&mxc)?;
+	self.check_quarantine(&mxc).await?;
 	let response = self
 		.services
 		.federation
@@ -416,7 +419,8 @@ pub async fn fetch_remote_thumbnail_legacy(
 		})
 		.await?;
 
-	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?;
+	let dim = Dim::from_ruma(body.width, body.height, body.method.clone())?
+		.with_animated(body.animated);
 	self.upload_thumbnail(&mxc, None, response.content_type.as_deref(), &dim, &response.file)
 		.await?;
 
@@ -433,6 +437,7 @@ pub async fn fetch_remote_content_legacy(
 ) -> Result<media::get_content::v3::Response, Error> {
 	self.check_legacy_freeze()?;
 	self.check_fetch_authorized(mxc)?;
+	self.check_quarantine(mxc).await?;
 	let response = self
 		.services
 		.federation
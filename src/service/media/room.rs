@@ -0,0 +1,70 @@
+use std::iter::once;
+
+use ruma::{Mxc, OwnedMxcUri, RoomId, ServerName};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tuwunel_core::{
+	implement,
+	matrix::Event,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+
+/// A room's media split by origin: this server's, then remote.
+pub type RoomMedia = (Vec<OwnedMxcUri>, Vec<OwnedMxcUri>);
+
+#[derive(Deserialize)]
+pub(super) struct ExtractUrls {
+	url: Option<String>,
+	info: Option<JsonValue>,
+}
+
+/// Every MXC URI referenced by the room's unencrypted events, newest first,
+/// split by origin server. Unknown rooms yield empty lists.
+#[implement(super::Service)]
+pub async fn room_media(&self, room_id: &RoomId) -> RoomMedia {
+	let server_name = self.services.globals.server_name();
+
+	self.services
+		.timeline
+		.pdus_rev(None, room_id, None)
+		.ignore_err()
+		.ready_filter_map(|(_, pdu)| pdu.get_content().ok())
+		.ready_fold_default(|lists, content: ExtractUrls| {
+			collect_urls(lists, server_name, &content)
+		})
+		.await
+}
+
+/// An event contributes only when its content carries a string `url`, mirroring
+/// Synapse's contains_url row filter; `info.thumbnail_url` rides along.
+/// Candidates parsing as MXC URIs are appended by origin, newest first,
+/// duplicates preserved.
+pub(super) fn collect_urls(
+	(mut local, mut remote): RoomMedia,
+	server_name: &ServerName,
+	content: &ExtractUrls,
+) -> RoomMedia {
+	let Some(url) = content.url.as_deref() else {
+		return (local, remote);
+	};
+
+	let thumbnail_url = content
+		.info
+		.as_ref()
+		.and_then(JsonValue::as_object)
+		.and_then(|info| info.get("thumbnail_url"))
+		.and_then(JsonValue::as_str);
+
+	once(url)
+		.chain(thumbnail_url)
+		.filter_map(|url| Mxc::try_from(url).ok().map(|mxc| (url, mxc)))
+		.for_each(|(url, mxc)| {
+			if mxc.server_name == server_name {
+				local.push(url.into());
+			} else {
+				remote.push(url.into());
+			}
+		});
+
+	(local, remote)
+}
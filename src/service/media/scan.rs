@@ -0,0 +1,147 @@
+//! Media Scanning
+//!
+//! With a scanner configured, media is scanned before it is stored, whether
+//! uploaded or fetched from a remote server, and before it is served when no
+//! verdict on its content is known yet, as for media stored before the scanner
+//! was configured. Verdicts are kept per content digest, so content shared by
+//! several keys is scanned once. Infected media is refused with `M_FORBIDDEN`
+//! naming the scanner's signature.
+
+pub(super) mod clamd;
+pub(super) mod http;
+
+use std::time::Duration;
+
+use ruma::Mxc;
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{Err, Result, err, implement, utils::time::now_millis, warn};
+
+use super::{Metadata, content::content_digest};
+
+/// A scanner's verdict on some content.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScanVerdict {
+	/// The signature or reason the scanner gave, when the content is infected.
+	pub infected: Option<String>,
+
+	/// When the content was scanned, in milliseconds since the epoch.
+	pub scanned_ts: u64,
+}
+
+#[implement(super::Service)]
+pub fn scanner_enabled(&self) -> bool {
+	let config = &self.services.config;
+
+	config.media_scanner_clamd.is_some() || config.media_scanner_url.is_some()
+}
+
+/// The verdict on the media's stored content, when it has been scanned. Media
+/// not yet content-addressed has none.
+#[implement(super::Service)]
+pub async fn scan_verdict(&self, mxc: &Mxc<'_>) -> Option<ScanVerdict> {
+	let Metadata { key, .. } = self.get_metadata(mxc).await?;
+	let digest = self.db.get_content_digest(&key).await?;
+
+	self.db.get_scan(&digest).await
+}
+
+/// Refuses infected content about to be stored.
+#[implement(super::Service)]
+pub(super) async fn check_scan(&self, file: &[u8]) -> Result {
+	if !self.scanner_enabled() {
+		return Ok(());
+	}
+
+	self.check_content(&content_digest(file), file)
+		.await
+}
+
+/// Refuses infected content read back from the media key.
+#[implement(super::Service)]
+pub(super) async fn check_stored_scan(&self, key: &[u8], file: &[u8]) -> Result {
+	if !self.scanner_enabled() {
+		return Ok(());
+	}
+
+	let digest = match self.db.get_content_digest(key).await {
+		| Some(digest) => digest,
+		| None => content_digest(file),
+	};
+
+	self.check_content(&digest, file).await
+}
+
+/// Whether the media key's content is known to be clean, so it can be served
+/// from elsewhere without passing through a check.
+#[implement(super::Service)]
+pub(super) async fn scanned_clean(&self, key: &[u8]) -> bool {
+	if !self.scanner_enabled() {
+		return true;
+	}
+
+	let Some(digest) = self.db.get_content_digest(key).await else {
+		return false;
+	};
+
+	self.db
+		.get_scan(&digest)
+		.await
+		.is_some_and(|verdict| verdict.infected.is_none())
+}
+
+/// Scans the content unless its verdict is known. Scanner failures allow the
+/// content unless `media_scanner_fail_closed` is set; no verdict is kept, so
+/// it is scanned again next time.
+#[implement(super::Service)]
+async fn check_content(&self, digest: &[u8], file: &[u8]) -> Result {
+	let verdict = match self.db.get_scan(digest).await {
+		| Some(verdict) => verdict,
+		| None => match self.scan(file).await {
+			| Ok(infected) => {
+				if let Some(signature) = &infected {
+					warn!(%signature, size = file.len(), "Media scanner found infected content");
+				}
+
+				let verdict = ScanVerdict { infected, scanned_ts: now_millis() };
+				self.db.set_scan(digest, &verdict);
+
+				verdict
+			},
+			| Err(e) if self.services.config.media_scanner_fail_closed => {
+				warn!("Media scanner failed; refusing: {e}");
+				return Err!(Request(Forbidden("The media scanner is unavailable.")));
+			},
+			| Err(e) => {
+				warn!("Media scanner failed; allowing: {e}");
+				return Ok(());
+			},
+		},
+	};
+
+	match verdict.infected {
+		| None => Ok(()),
+		| Some(signature) =>
+			Err!(Request(Forbidden("Media blocked by the content scanner: {signature}"))),
+	}
+}
+
+/// Asks the configured scanner, answering the infection's signature or `None`
+/// for clean content.
+#[implement(super::Service)]
+async fn scan(&self, file: &[u8]) -> Result<Option<String>> {
+	let config = &self.services.config;
+	let timeout = Duration::from_secs(config.media_scanner_timeout);
+	let scan = async {
+		if let Some(address) = &config.media_scanner_clamd {
+			clamd::scan(address, file).await
+		} else if let Some(url) = config.media_scanner_url.clone() {
+			self.scan_http(url, file).await
+		} else {
+			Ok(None)
+		}
+	};
+
+	tokio::time::timeout(timeout, scan)
+		.await
+		.map_err(|_| err!("Media scan timed out after {timeout:?}."))?
+}
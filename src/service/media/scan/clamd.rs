@@ -0,0 +1,69 @@
+//! ClamAV `clamd` backend.
+//!
+//! The file is streamed with the `INSTREAM` command: chunks each prefixed by
+//! their length as a big-endian `u32`, ended by a zero length. clamd answers
+//! `stream: OK`, `stream: <signature> FOUND`, or an error such as its
+//! `StreamMaxLength` being exceeded, and then closes the connection.
+
+use tokio::{
+	io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+	net::{TcpStream, UnixStream},
+};
+use tuwunel_core::{Err, Result};
+
+/// Size of each chunk streamed to clamd.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bound on clamd's reply; the longest are a signature name or an error.
+const MAX_REPLY: u64 = 4096;
+
+/// Scans the file with the clamd listening at the Unix socket path or TCP
+/// `host:port`.
+pub(super) async fn scan(address: &str, file: &[u8]) -> Result<Option<String>> {
+	if address.starts_with('/') {
+		instream(UnixStream::connect(address).await?, file).await
+	} else {
+		instream(TcpStream::connect(address).await?, file).await
+	}
+}
+
+pub(super) async fn instream<S>(mut stream: S, file: &[u8]) -> Result<Option<String>>
+where
+	S: AsyncRead + AsyncWrite + Unpin,
+{
+	stream.write_all(b"zINSTREAM\0").await?;
+	for chunk in file.chunks(CHUNK_SIZE) {
+		let len = u32::try_from(chunk.len())?;
+
+		stream.write_all(&len.to_be_bytes()).await?;
+		stream.write_all(chunk).await?;
+	}
+
+	stream.write_all(&0_u32.to_be_bytes()).await?;
+	stream.flush().await?;
+
+	let mut reply = Vec::new();
+	(&mut stream)
+		.take(MAX_REPLY)
+		.read_to_end(&mut reply)
+		.await?;
+
+	verdict(&reply)
+}
+
+/// Reads clamd's reply to a scan.
+pub(super) fn verdict(reply: &[u8]) -> Result<Option<String>> {
+	let reply = String::from_utf8_lossy(reply);
+	let reply = reply.trim_end_matches(['\0', '\n']);
+	let result = reply.strip_prefix("stream: ").unwrap_or(reply);
+
+	if result == "OK" {
+		return Ok(None);
+	}
+
+	if let Some(signature) = result.strip_suffix(" FOUND") {
+		return Ok(Some(signature.to_owned()));
+	}
+
+	Err!("clamd: {result}")
+}
@@ -0,0 +1,57 @@
+//! HTTP scanner backend.
+//!
+//! The file is POSTed as the request body. The scanner answers
+//! `{"clean": true}`, or `{"clean": false, "reason": "..."}` for an infected
+//! file.
+
+use http::header::CONTENT_TYPE;
+use serde::Deserialize;
+use tuwunel_core::{Result, implement};
+use url::Url;
+
+use crate::client::read_response_capped;
+
+/// Bound on the scanner's answer.
+const MAX_ANSWER: usize = 64 * 1024;
+
+/// A scanner's answer.
+#[derive(Deserialize)]
+struct Answer {
+	clean: bool,
+
+	#[serde(default)]
+	reason: Option<String>,
+}
+
+#[implement(super::super::Service)]
+pub(super) async fn scan_http(&self, url: Url, file: &[u8]) -> Result<Option<String>> {
+	let response = self
+		.services
+		.client
+		.default
+		.post(url)
+		.header(CONTENT_TYPE, "application/octet-stream")
+		.body(file.to_vec())
+		.send()
+		.await?
+		.error_for_status()?;
+
+	let body = read_response_capped(response, MAX_ANSWER).await?;
+
+	verdict(&body)
+}
+
+/// Reads a scanner's answer.
+pub(super) fn verdict(body: &[u8]) -> Result<Option<String>> {
+	let answer: Answer = serde_json::from_slice(body)?;
+	if answer.clean {
+		return Ok(None);
+	}
+
+	let reason = answer
+		.reason
+		.filter(|reason| !reason.is_empty())
+		.unwrap_or_else(|| "unspecified".to_owned());
+
+	Ok(Some(reason))
+}
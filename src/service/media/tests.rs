@@ -78,6 +78,499 @@ fn passthrough_when_crop_request_matches_source() {
 	);
 }
 
+/// Animated thumbnails are cached apart from still ones of the same size, and
+/// still keys keep the layout they had before MSC2705.
+#[test]
+fn animated_thumbnail_keys_apart() {
+	let still = scale(640, 480);
+	let animated = scale(640, 480).with_animated(Some(true));
+
+	assert_eq!(still.key(), [640, 480]);
+	assert_eq!(animated.key(), [640, 480, 1]);
+	assert!(animated.normalized().animated);
+	assert!(!animated.with_animated(None).animated);
+}
+
+mod content {
+	use super::super::content::{content_digest, content_path};
+
+	#[test]
+	fn identical_content_shares_a_path() {
+		let a = content_path(&content_digest(b"sticker"));
+		let b = content_path(&content_digest(b"sticker"));
+		let c = content_path(&content_digest(b"stickers"));
+
+		assert_eq!(a, b);
+		assert_ne!(a, c);
+	}
+
+	#[test]
+	fn content_path_names_the_digest() {
+		let path = content_path(&content_digest(b""));
+
+		assert_eq!(path, "sha256/47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU");
+	}
+}
+
+mod eviction {
+	use ruma::OwnedMxcUri;
+
+	use super::super::eviction::{Cached, select_evictions};
+
+	const DAY: u64 = 24 * 60 * 60 * 1000;
+
+	fn cached(id: &str, last_access: u64, size: u64) -> Cached {
+		Cached {
+			mxc: format!("mxc://remote.test/{id}").into(),
+			last_access,
+			size,
+		}
+	}
+
+	fn ids(evicted: &[OwnedMxcUri]) -> Vec<&str> {
+		evicted
+			.iter()
+			.map(|mxc| mxc.media_id().unwrap())
+			.collect()
+	}
+
+	fn cache() -> Vec<Cached> {
+		vec![
+			cached("recent", 9 * DAY, 100),
+			cached("stale", DAY, 100),
+			cached("idle", 5 * DAY, 300),
+		]
+	}
+
+	#[test]
+	fn nothing_without_limits() {
+		assert!(select_evictions(cache(), 10 * DAY, None, None).is_empty());
+	}
+
+	#[test]
+	fn idle_media_evicted() {
+		let evicted = select_evictions(cache(), 10 * DAY, Some(3 * DAY), None);
+
+		assert_eq!(ids(&evicted), ["stale", "idle"]);
+	}
+
+	#[test]
+	fn least_recently_accessed_evicted_to_budget() {
+		let evicted = select_evictions(cache(), 10 * DAY, None, Some(400));
+		assert_eq!(ids(&evicted), ["stale"]);
+
+		let evicted = select_evictions(cache(), 10 * DAY, None, Some(399));
+		assert_eq!(ids(&evicted), ["stale", "idle"]);
+
+		let evicted = select_evictions(cache(), 10 * DAY, None, Some(500));
+		assert!(evicted.is_empty());
+	}
+
+	#[test]
+	fn budget_applies_after_idle() {
+		let evicted = select_evictions(cache(), 10 * DAY, Some(8 * DAY), Some(150));
+
+		assert_eq!(ids(&evicted), ["stale", "idle"]);
+
+		let evicted = select_evictions(cache(), 10 * DAY, Some(8 * DAY), Some(50));
+		assert_eq!(ids(&evicted), ["stale", "idle", "recent"]);
+	}
+}
+
+#[cfg(feature = "url_preview")]
+mod oembed {
+	use url::Url;
+
+	use super::super::oembed::{Providers, discover};
+
+	const PROVIDERS: &str = r#"[
+		{
+			"provider_name": "Vimeo",
+			"provider_url": "https://vimeo.com/",
+			"endpoints": [{
+				"schemes": ["https://vimeo.com/*", "https://player.vimeo.com/video/*"],
+				"url": "https://vimeo.com/api/oembed.{format}",
+				"discovery": true
+			}]
+		},
+		{
+			"provider_name": "Legacy",
+			"endpoints": [{
+				"schemes": ["https://legacy.example/*"],
+				"url": "https://legacy.example/oembed",
+				"formats": ["xml"]
+			}]
+		}
+	]"#;
+
+	fn url(url: &str) -> Url { Url::parse(url).expect("parses") }
+
+	#[test]
+	fn schemes_select_the_endpoint() {
+		let mut providers = Providers::default();
+		providers.extend(PROVIDERS.as_bytes()).unwrap();
+
+		let page = url("https://vimeo.com/76979871");
+		let endpoint = providers.endpoint(&page).expect("vimeo matches");
+
+		assert_eq!(endpoint.path(), "/api/oembed.json");
+		assert_eq!(endpoint.query_pairs().collect::<Vec<_>>(), [(
+			"url".into(),
+			page.as_str().into()
+		)]);
+
+		assert!(
+			providers
+				.endpoint(&url("https://vimeo.org/1"))
+				.is_none()
+		);
+	}
+
+	/// An endpoint answering only XML is of no use to a preview.
+	#[test]
+	fn xml_only_endpoints_skipped() {
+		let mut providers = Providers::default();
+		providers.extend(PROVIDERS.as_bytes()).unwrap();
+
+		assert!(
+			providers
+				.endpoint(&url("https://legacy.example/a"))
+				.is_none()
+		);
+	}
+
+	#[test]
+	fn discovery_link_resolves_against_the_page() {
+		let html = r#"<html><head>
+			<link rel="stylesheet" href="/style.css">
+			<link type="application/json+oembed" rel="alternate"
+				href="/oembed?url=https%3A%2F%2Fsite.example%2Fa&amp;format=json">
+		</head></html>"#;
+
+		let endpoint = discover(html, &url("https://site.example/a")).expect("discovered");
+
+		assert_eq!(
+			endpoint.as_str(),
+			"https://site.example/oembed?url=https%3A%2F%2Fsite.example%2Fa&format=json"
+		);
+	}
+
+	#[test]
+	fn discovery_ignores_other_links() {
+		let html = r#"<link rel="alternate" type="text/xml+oembed" href="/x">
+			<link rel="alternate" type="application/json+oembed" href="javascript:alert(1)">"#;
+
+		assert!(discover(html, &url("https://site.example/")).is_none());
+	}
+}
+
+mod quarantine {
+	use ruma::user_id;
+
+	use super::super::quarantine::{Quarantine, quarantined};
+
+	#[test]
+	fn quarantine_records_the_admin() {
+		let admin = user_id!("@admin:example.org");
+		let state = quarantined(Quarantine::default(), Some(admin)).unwrap();
+
+		assert_eq!(state.quarantined_by.as_deref(), Some(admin));
+		assert!(!state.safe_from_quarantine);
+	}
+
+	#[test]
+	fn protected_media_is_never_quarantined() {
+		let state = Quarantine {
+			quarantined_by: None,
+			safe_from_quarantine: true,
+		};
+
+		assert_eq!(quarantined(state, Some(user_id!("@admin:example.org"))), None);
+	}
+
+	#[test]
+	fn release_keeps_protection() {
+		let state = Quarantine {
+			quarantined_by: Some(user_id!("@admin:example.org").to_owned()),
+			safe_from_quarantine: true,
+		};
+
+		let state = quarantined(state, None).unwrap();
+
+		assert_eq!(state.quarantined_by, None);
+		assert!(state.safe_from_quarantine);
+	}
+
+	#[test]
+	fn unchanged_state_is_no_change() {
+		let admin = user_id!("@admin:example.org");
+		let state = Quarantine {
+			quarantined_by: Some(user_id!("@other:example.org").to_owned()),
+			safe_from_quarantine: false,
+		};
+
+		assert_eq!(quarantined(state, Some(admin)), None);
+		assert_eq!(quarantined(Quarantine::default(), None), None);
+	}
+}
+
+mod quota {
+	use super::super::quota::{Quota, QuotaOverride, Usage, effective_quota, exceeds};
+
+	#[test]
+	fn zero_limits_are_unlimited() {
+		assert_eq!(effective_quota(None, 0, 0), Quota::default());
+	}
+
+	#[test]
+	fn override_replaces_configured_limits() {
+		let quota_override = QuotaOverride { max_bytes: 0, max_files: 10 };
+
+		assert_eq!(effective_quota(Some(quota_override), 1024, 5), Quota {
+			max_bytes: None,
+			max_files: Some(10),
+		});
+	}
+
+	#[test]
+	fn upload_reaching_the_byte_limit_fits() {
+		let quota = Quota { max_bytes: Some(100), max_files: None };
+		let usage = Usage { bytes: 60, files: 3 };
+
+		assert!(!exceeds(quota, usage, 40));
+		assert!(exceeds(quota, usage, 41));
+	}
+
+	#[test]
+	fn file_limit_counts_the_new_upload() {
+		let quota = Quota { max_bytes: None, max_files: Some(3) };
+
+		assert!(!exceeds(quota, Usage { bytes: 0, files: 2 }, 0));
+		assert!(exceeds(quota, Usage { bytes: 0, files: 3 }, 0));
+	}
+}
+
+mod room {
+	use ruma::server_name;
+	use serde_json::json;
+
+	use super::super::room::{ExtractUrls, RoomMedia, collect_urls};
+
+	fn collect(content: serde_json::Value) -> RoomMedia {
+		let content: ExtractUrls = serde_json::from_value(content).expect("valid ExtractUrls");
+
+		collect_urls(RoomMedia::default(), server_name!("example.org"), &content)
+	}
+
+	#[test]
+	fn url_and_thumbnail_split_by_origin() {
+		let (local, remote) = collect(json!({
+			"url": "mxc://example.org/abc",
+			"info": { "thumbnail_url": "mxc://remote.example/def" },
+		}));
+
+		assert_eq!(local, ["mxc://example.org/abc"]);
+		assert_eq!(remote, ["mxc://remote.example/def"]);
+	}
+
+	#[test]
+	fn thumbnail_only_event_contributes_nothing() {
+		let (local, remote) = collect(json!({
+			"info": { "thumbnail_url": "mxc://example.org/xyz" },
+		}));
+
+		assert!(local.is_empty(), "{local:?}");
+		assert!(remote.is_empty(), "{remote:?}");
+	}
+
+	#[test]
+	fn non_object_info_still_lists_url() {
+		let (local, remote) = collect(json!({
+			"url": "mxc://example.org/abc",
+			"info": "weird",
+		}));
+
+		assert_eq!(local, ["mxc://example.org/abc"]);
+		assert!(remote.is_empty(), "{remote:?}");
+	}
+
+	#[test]
+	fn non_mxc_and_invalid_urls_skipped() {
+		let (local, remote) = collect(json!({
+			"url": "https://example.org/pic.png",
+			"info": { "thumbnail_url": "mxc://example.org/has/slash" },
+		}));
+
+		assert!(local.is_empty(), "{local:?}");
+		assert!(remote.is_empty(), "{remote:?}");
+	}
+
+	#[test]
+	fn empty_url_still_lists_thumbnail() {
+		let (local, remote) = collect(json!({
+			"url": "",
+			"info": { "thumbnail_url": "mxc://remote.example/def" },
+		}));
+
+		assert!(local.is_empty(), "{local:?}");
+		assert_eq!(remote, ["mxc://remote.example/def"]);
+	}
+
+	#[test]
+	fn duplicates_preserved_across_events() {
+		let content: ExtractUrls =
+			serde_json::from_value(json!({ "url": "mxc://example.org/abc" }))
+				.expect("valid ExtractUrls");
+
+		let lists = collect_urls(RoomMedia::default(), server_name!("example.org"), &content);
+		let (local, remote) = collect_urls(lists, server_name!("example.org"), &content);
+
+		assert_eq!(local, ["mxc://example.org/abc", "mxc://example.org/abc"]);
+		assert!(remote.is_empty(), "{remote:?}");
+	}
+
+	#[test]
+	fn non_string_url_fails_the_row_filter() {
+		let result = serde_json::from_value::<ExtractUrls>(json!({ "url": 5 }));
+
+		assert!(result.is_err());
+	}
+}
+
+mod scan {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream, duplex};
+
+	use super::super::scan::{clamd, http};
+
+	const EICAR: &[u8] = br"X5O!P%@AP[4\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+	/// Answers one `INSTREAM` scan as clamd does, finding the EICAR test file.
+	async fn clamd_stand_in(mut stream: DuplexStream) {
+		let mut command = [0_u8; 10];
+		stream.read_exact(&mut command).await.unwrap();
+		assert_eq!(&command, b"zINSTREAM\0");
+
+		let mut file = Vec::new();
+		loop {
+			let len = usize::try_from(stream.read_u32().await.unwrap()).unwrap();
+			if len == 0 {
+				break;
+			}
+
+			let mut chunk = vec![0; len];
+			stream.read_exact(&mut chunk).await.unwrap();
+			file.extend(chunk);
+		}
+
+		let infected = file
+			.windows(EICAR.len())
+			.any(|window| window == EICAR);
+
+		let reply: &[u8] = if infected {
+			b"stream: Eicar-Test-Signature FOUND\0"
+		} else {
+			b"stream: OK\0"
+		};
+
+		stream.write_all(reply).await.unwrap();
+	}
+
+	async fn scan(file: &[u8]) -> Option<String> {
+		let (client, server) = duplex(1024);
+		let server = tokio::spawn(clamd_stand_in(server));
+		let verdict = clamd::instream(client, file).await.unwrap();
+		server.await.unwrap();
+
+		verdict
+	}
+
+	#[tokio::test]
+	async fn clamd_passes_clean_file() {
+		assert_eq!(scan(b"just a picture").await, None);
+		assert_eq!(scan(b"").await, None);
+	}
+
+	#[tokio::test]
+	async fn clamd_finds_infected_file() {
+		assert_eq!(scan(EICAR).await.as_deref(), Some("Eicar-Test-Signature"));
+	}
+
+	#[tokio::test]
+	async fn clamd_reassembles_chunks() {
+		// the signature straddles the boundary of the first two chunks
+		let mut file = vec![b'.'; 64 * 1024 - 10];
+		file.extend_from_slice(EICAR);
+		file.resize(200 * 1024, b'.');
+
+		assert_eq!(scan(&file).await.as_deref(), Some("Eicar-Test-Signature"));
+	}
+
+	#[test]
+	fn clamd_replies() {
+		assert_eq!(clamd::verdict(b"stream: OK\0").unwrap(), None);
+		assert_eq!(clamd::verdict(b"stream: OK\n").unwrap(), None);
+		assert_eq!(
+			clamd::verdict(b"stream: Win.Test.EICAR_HDB-1 FOUND\0")
+				.unwrap()
+				.as_deref(),
+			Some("Win.Test.EICAR_HDB-1")
+		);
+
+		assert!(clamd::verdict(b"INSTREAM size limit exceeded. ERROR\0").is_err());
+		assert!(clamd::verdict(b"").is_err());
+	}
+
+	#[test]
+	fn http_answers() {
+		assert_eq!(http::verdict(br#"{"clean": true}"#).unwrap(), None);
+		assert_eq!(
+			http::verdict(br#"{"clean": false, "reason": "Eicar-Test-Signature"}"#)
+				.unwrap()
+				.as_deref(),
+			Some("Eicar-Test-Signature")
+		);
+
+		assert_eq!(
+			http::verdict(br#"{"clean": false, "reason": ""}"#)
+				.unwrap()
+				.as_deref(),
+			Some("unspecified")
+		);
+
+		assert!(http::verdict(br#"{"infected": true}"#).is_err());
+		assert!(http::verdict(b"OK").is_err());
+	}
+}
+
+mod tiering {
+	use std::time::Duration;
+
+	use super::super::tiering::should_tier;
+
+	const DAY: u64 = 24 * 60 * 60 * 1000;
+
+	#[test]
+	fn nothing_without_criteria() {
+		assert!(!should_tier(0, u64::MAX, 100 * DAY, None, None));
+	}
+
+	#[test]
+	fn old_media_moved() {
+		let after = Some(Duration::from_secs(30 * 24 * 60 * 60));
+
+		assert!(should_tier(DAY, 10, 40 * DAY, after, None));
+		assert!(!should_tier(20 * DAY, 10, 40 * DAY, after, None));
+	}
+
+	#[test]
+	fn large_media_moved_however_recent() {
+		let after = Some(Duration::from_secs(30 * 24 * 60 * 60));
+
+		assert!(should_tier(40 * DAY, 1001, 40 * DAY, after, Some(1000)));
+		assert!(!should_tier(40 * DAY, 1000, 40 * DAY, after, Some(1000)));
+	}
+}
+
 #[cfg(feature = "media_thumbnail")]
 mod generate {
 	use image::{DynamicImage, RgbImage};
@@ -124,6 +617,81 @@ fn scale_never_upscales() {
 	}
 }
 
+#[cfg(feature = "media_thumbnail")]
+mod animation {
+	use image::{
+		AnimationDecoder, Delay, Frame, ImageFormat, RgbaImage,
+		codecs::gif::{GifDecoder, GifEncoder},
+	};
+
+	use super::{
+		super::{animation::encode_animation, thumbnail::Encoding},
+		scale,
+	};
+
+	fn gif(frames: usize) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		{
+			let mut encoder = GifEncoder::new(&mut bytes);
+			let frames = (0..frames).map(|_| {
+				let delay = Delay::from_numer_denom_ms(100, 1);
+
+				Frame::from_parts(RgbaImage::new(400, 300), 0, 0, delay)
+			});
+
+			encoder.encode_frames(frames).unwrap();
+		}
+
+		bytes
+	}
+
+	#[test]
+	fn every_frame_is_scaled() {
+		let bytes = encode_animation(&gif(3), ImageFormat::Gif, &scale(320, 240), u64::MAX)
+			.unwrap()
+			.unwrap();
+
+		let frames = GifDecoder::new(std::io::Cursor::new(bytes))
+			.unwrap()
+			.into_frames()
+			.collect_frames()
+			.unwrap();
+
+		assert_eq!(frames.len(), 3);
+		assert!(frames.iter().all(|frame| {
+			let buffer = frame.buffer();
+
+			buffer.width() <= 320 && buffer.height() <= 240
+		}));
+	}
+
+	/// A single frame is a still, and the still thumbnail serves it.
+	#[test]
+	fn single_frame_is_still() {
+		let animation =
+			encode_animation(&gif(1), ImageFormat::Gif, &scale(320, 240), u64::MAX).unwrap();
+
+		assert!(animation.is_none());
+	}
+
+	/// The pixel budget covers every frame, not just the first.
+	#[test]
+	fn frames_past_the_budget_are_still() {
+		let budget = 400 * 300 * 2;
+		let animation =
+			encode_animation(&gif(3), ImageFormat::Gif, &scale(320, 240), budget).unwrap();
+
+		assert!(animation.is_none());
+	}
+
+	#[test]
+	fn photographs_stay_jpeg() {
+		assert_eq!(Encoding::still(Some(ImageFormat::Jpeg)), Encoding::Jpeg);
+		assert_eq!(Encoding::still(Some(ImageFormat::Png)), Encoding::WebP);
+		assert_eq!(Encoding::still(None), Encoding::Jpeg);
+	}
+}
+
 #[cfg(feature = "media_thumbnail")]
 mod video {
 	use std::{borrow::Cow, path::Path};
@@ -11,7 +11,11 @@
 
 use futures::{StreamExt, pin_mut};
 #[cfg(feature = "media_thumbnail")]
-use image::{DynamicImage, ImageFormat, ImageReader, Limits, imageops::FilterType};
+use image::{
+	DynamicImage, ImageFormat, ImageReader, Limits,
+	codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+	imageops::FilterType,
+};
 #[cfg(feature = "media_thumbnail")]
 use ruma::http_headers::ContentDispositionType;
 use ruma::{Mxc, UInt, UserId, http_headers::ContentDisposition, media::Method};
@@ -23,18 +27,13 @@
 
 use super::{Media, data::Metadata};
 
-/// Content type of every thumbnail tuwunel generates.
-#[cfg(feature = "media_thumbnail")]
-const PNG: &str = "image/png";
-
 /// Bytes the decoder is budgeted per pixel of the picture it is asked for.
 #[cfg(feature = "media_thumbnail")]
 const BYTES_PER_PIXEL: u64 = 4;
 
-/// Filename a generated thumbnail is disposed under, per the media repository
-/// specification, rather than the name of the file it was generated from.
+/// Quality of generated JPEG thumbnails.
 #[cfg(feature = "media_thumbnail")]
-const THUMBNAIL_NAME: &str = "thumbnail.png";
+const JPEG_QUALITY: u8 = 80;
 
 /// Dimension specification for a thumbnail.
 #[derive(Debug)]
@@ -42,6 +41,18 @@ pub struct Dim {
 	pub width: u32,
 	pub height: u32,
 	pub method: Method,
+
+	/// An animated thumbnail was asked for (MSC2705).
+	pub animated: bool,
+}
+
+/// Format a thumbnail is generated in.
+#[cfg(feature = "media_thumbnail")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Encoding {
+	Jpeg,
+	WebP,
+	Gif,
 }
 
 impl super::Service {
@@ -59,6 +70,8 @@ pub async fn upload_thumbnail(
 		dim: &Dim,
 		file: &[u8],
 	) -> Result {
+		self.check_scan(file).await?;
+
 		let key =
 			self.db
 				.create_file_metadata(mxc, None, dim, content_disposition, content_type)?;
@@ -122,7 +135,10 @@ pub async fn get_thumbnail(
 		dim: &Dim,
 		timeout_duration: Option<Duration>,
 	) -> Result<Media> {
+		self.check_quarantine(mxc).await?;
+
 		if let Ok(meta) = self.get_stored_thumbnail(mxc, dim).await {
+			self.touch(mxc).await;
 			return Ok(meta);
 		}
 
@@ -205,9 +221,11 @@ pub async fn get_stored_thumbnail(&self, mxc: &Mxc<'_>, dim: &Dim) -> Result<Med
 #[implement(super::Service)]
 #[tracing::instrument(name = "saved", level = "debug", skip_all)]
 async fn get_thumbnail_saved(&self, data: Metadata) -> Result<Media> {
-	let path = self.get_media_name_sha256(&data.key);
+	let path = self.media_path(&data.key).await;
 	let fetch = self
-		.storage_providers()
+		.media_providers(&data.key)
+		.await
+		.into_iter()
 		.stream()
 		.filter_map(async |provider| {
 			provider
@@ -222,6 +240,8 @@ async fn get_thumbnail_saved(&self, data: Metadata) -> Result<Media> {
 		return Err!(Request(NotFound("Media thumbnail not found.")));
 	};
 
+	self.check_stored_scan(&data.key, &bytes).await?;
+
 	Ok(into_media(data, bytes.to_vec()))
 }
 
@@ -262,28 +282,39 @@ async fn get_thumbnail_generate(
 		return Ok(into_media(data, media.content));
 	}
 
+	// a frame taken from a video is a photograph whatever it was written as
+	let format = (!from_video)
+		.then(|| reader(&media.content).ok()?.format())
+		.flatten();
+
+	let animation = format
+		.filter(|_| dim.animated)
+		.and_then(|format| self.animate(&media.content, format, dim));
+
 	// nothing below reads the original, which on the video path is the whole
 	// staged file, and the encode and the store must not hold it
 	drop(media);
 
-	let mut thumbnail_bytes = Vec::new();
-	let thumbnail = thumbnail_generate(&image, dim)?;
-	let mut cursor = Cursor::new(&mut thumbnail_bytes);
+	let (encoding, thumbnail_bytes) = match animation {
+		| Some(bytes) => (Encoding::Gif, bytes),
+		| None => {
+			let encoding = Encoding::still(format);
+			let thumbnail = thumbnail_generate(&image, dim)?;
 
-	thumbnail
-		.write_to(&mut cursor, ImageFormat::Png)
-		.map_err(|error| err!(error!(?error, "Error writing PNG thumbnail.")))?;
+			(encoding, encoding.encode(&thumbnail)?)
+		},
+	};
 
-	// a generated thumbnail is a PNG rather than the uploaded file, and carries
-	// the name the media repository specification asks of one whether or not the
-	// original arrived with a name of its own
+	// a generated thumbnail is not the uploaded file, and carries the name the
+	// media repository specification asks of one whether or not the original
+	// arrived with a name of its own
 	let content_disposition = ContentDisposition {
 		disposition_type: ContentDispositionType::Inline,
-		filename: Some(THUMBNAIL_NAME.to_owned()),
+		filename: Some(encoding.file_name().to_owned()),
 	};
 
 	let data = Metadata {
-		content_type: Some(PNG.to_owned()),
+		content_type: Some(encoding.content_type().to_owned()),
 		content_disposition: Some(content_disposition),
 		..data
 	};
@@ -372,6 +403,59 @@ pub(super) fn thumbnail_generate(image: &DynamicImage, requested: &Dim) -> Resul
 	Ok(thumbnail)
 }
 
+#[cfg(feature = "media_thumbnail")]
+impl Encoding {
+	/// Photographs stay JPEG, which holds them far smaller than a lossless
+	/// format would; anything else, transparency included, becomes lossless
+	/// WebP. Video frames are photographs.
+	pub(super) fn still(source: Option<ImageFormat>) -> Self {
+		match source {
+			| Some(ImageFormat::Jpeg) | None => Self::Jpeg,
+			| Some(_) => Self::WebP,
+		}
+	}
+
+	fn encode(self, image: &DynamicImage) -> Result<Vec<u8>> {
+		let mut bytes = Vec::new();
+		let cursor = Cursor::new(&mut bytes);
+		let result = match self {
+			| Self::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+				.write_with_encoder(JpegEncoder::new_with_quality(cursor, JPEG_QUALITY)),
+			| Self::WebP if image.color().has_alpha() =>
+				DynamicImage::ImageRgba8(image.to_rgba8())
+					.write_with_encoder(WebPEncoder::new_lossless(cursor)),
+			| Self::WebP => DynamicImage::ImageRgb8(image.to_rgb8())
+				.write_with_encoder(WebPEncoder::new_lossless(cursor)),
+			| Self::Gif => image.write_to(cursor, ImageFormat::Gif),
+		};
+
+		result.map_err(|error| {
+			err!(error!(?error, encoding = ?self, "Error writing thumbnail."))
+		})?;
+
+		Ok(bytes)
+	}
+
+	pub(super) fn content_type(self) -> &'static str {
+		match self {
+			| Self::Jpeg => "image/jpeg",
+			| Self::WebP => "image/webp",
+			| Self::Gif => "image/gif",
+		}
+	}
+
+	/// Filename a generated thumbnail is disposed under, per the media
+	/// repository specification, rather than the name of the file it was
+	/// generated from.
+	pub(super) fn file_name(self) -> &'static str {
+		match self {
+			| Self::Jpeg => "thumbnail.jpg",
+			| Self::WebP => "thumbnail.webp",
+			| Self::Gif => "thumbnail.gif",
+		}
+	}
+}
+
 fn into_media(data: Metadata, content: Vec<u8>) -> Media {
 	Media {
 		content,
@@ -401,9 +485,32 @@ pub fn new(width: u32, height: u32, method: Option<Method>) -> Self {
 			width,
 			height,
 			method: method.unwrap_or(Method::Scale),
+			animated: false,
+		}
+	}
+
+	/// Asks for an animated thumbnail when `animated` is set (MSC2705).
+	#[inline]
+	#[must_use]
+	pub fn with_animated(self, animated: Option<bool>) -> Self {
+		Self {
+			animated: animated.unwrap_or(false),
+			..self
 		}
 	}
 
+	/// Dimensions as stored in the media key. An animated thumbnail carries a
+	/// third element, so it is kept apart from the still one of its size.
+	#[must_use]
+	pub(super) fn key(&self) -> Vec<u32> {
+		let mut key = vec![self.width, self.height];
+		if self.animated {
+			key.push(1);
+		}
+
+		key
+	}
+
 	pub fn scaled(&self, image: &Self) -> Result<Self> {
 		let image_width = image.width;
 		let image_height = image.height;
@@ -431,6 +538,7 @@ pub fn scaled(&self, image: &Self) -> Result<Self> {
 			width: x,
 			height: y,
 			method: Method::Scale,
+			animated: false,
 		})
 	}
 
@@ -454,17 +562,19 @@ pub fn is_passthrough(&self, source: &Self) -> Result<bool> {
 
 	/// Returns width, height of the thumbnail and whether it should be cropped.
 	/// Returns None when the server should send the original file.
-	/// Ignores the input Method.
+	/// Ignores the input Method; keeps the animation flag of a thumbnail.
 	#[must_use]
 	pub fn normalized(&self) -> Self {
-		match (self.width, self.height) {
+		let dim = match (self.width, self.height) {
 			| (0..=32, 0..=32) => Self::new(32, 32, Some(Method::Crop)),
 			| (0..=96, 0..=96) => Self::new(96, 96, Some(Method::Crop)),
 			| (0..=320, 0..=240) => Self::new(320, 240, Some(Method::Scale)),
 			| (0..=640, 0..=480) => Self::new(640, 480, Some(Method::Scale)),
 			| (0..=800, 0..=600) => Self::new(800, 600, Some(Method::Scale)),
-			| _ => Self::default(),
-		}
+			| _ => return Self::default(),
+		};
+
+		Self { animated: self.animated, ..dim }
 	}
 
 	/// Returns true if the method is Crop.
@@ -480,6 +590,7 @@ fn default() -> Self {
 			width: 0,
 			height: 0,
 			method: Method::Scale,
+			animated: false,
 		}
 	}
 }
@@ -0,0 +1,166 @@
+//! Media Tiering
+//!
+//! New media is stored on `store_media_on_providers`, typically the local
+//! disk. Original media older than `media_tiering_after_days`, or larger than
+//! `media_tiering_min_size`, is moved to `media_tiering_provider`, typically
+//! S3, while thumbnails stay where they were stored. Moves run as a resumable
+//! task of the admin task tracker, started periodically or by an admin. Each
+//! content digest records the provider it was moved to, and reads try that
+//! provider first.
+
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use tuwunel_core::{
+	Err, Result, debug, implement,
+	utils::{stream::IterStream, time::now_millis},
+};
+
+use super::{Metadata, Stored, content::content_path, encode_key, eviction::DAY};
+use crate::{storage::Provider, tasks::Params};
+
+/// The providers to read the media key's file from: the provider it was moved
+/// to first, then the others in configured order.
+#[implement(super::Service)]
+pub(super) async fn media_providers(&self, key: &[u8]) -> Vec<&Arc<Provider>> {
+	let placed = match self.db.get_content_digest(key).await {
+		| Some(digest) => self.db.get_content_provider(&digest).await,
+		| None => None,
+	};
+
+	let mut providers: Vec<_> = self.storage_providers().collect();
+	if let Some(placed) = placed {
+		providers.sort_by_key(|provider| provider.name != placed);
+	}
+
+	providers
+}
+
+/// Starts a tiering task, returning its id. Fails when tiering is not
+/// configured or a tiering task is already running.
+#[implement(super::Service)]
+pub fn start_tiering(&self) -> Result<String> {
+	let Some(provider) = self
+		.services
+		.config
+		.media_tiering_provider
+		.clone()
+	else {
+		return Err!(Config("media_tiering_provider", "Media tiering is not configured."));
+	};
+
+	let params = Params::TierMedia { provider };
+	if self
+		.services
+		.tasks
+		.has_nonterminal(params.action(), &params.resource_id())
+	{
+		return Err!("A media tiering task is already running.");
+	}
+
+	let id = self.services.tasks.spawn_resumable(params);
+
+	Ok(id.to_string())
+}
+
+/// The content digests the tiering policy moves to `provider`: those of
+/// original media past the configured age or size which are not yet there.
+#[implement(super::Service)]
+pub async fn tiering_candidates(&self, provider: &str) -> Result<Vec<Vec<u8>>> {
+	let config = &self.services.config;
+	let after = (config.media_tiering_after_days > 0).then(|| {
+		Duration::from_secs(
+			config
+				.media_tiering_after_days
+				.saturating_mul(DAY.as_secs()),
+		)
+	});
+
+	let min_size = (config.media_tiering_min_size > 0).then_some(config.media_tiering_min_size);
+
+	let now = now_millis();
+	let mxcs: BTreeSet<_> = self.get_all_mxcs().await?.into_iter().collect();
+	let candidates: BTreeSet<_> = mxcs
+		.into_iter()
+		.stream()
+		.filter_map(async |mxc| {
+			let mxc = mxc.parts().ok()?;
+			let Metadata { key, .. } = self.get_metadata(&mxc).await?;
+			let digest = self.db.get_content_digest(&key).await?;
+			if self
+				.db
+				.get_content_provider(&digest)
+				.await
+				.is_some_and(|placed| placed == provider)
+			{
+				return None;
+			}
+
+			let Stored { size, stored_ts } = self.stored(&key).await?;
+			should_tier(stored_ts, size, now, after, min_size).then_some(digest)
+		})
+		.collect()
+		.await;
+
+	Ok(candidates.into_iter().collect())
+}
+
+/// Moves the content to `provider` from every other provider holding it and
+/// records it there.
+#[implement(super::Service)]
+pub async fn tier_content(&self, digest: &[u8], provider: &str) -> Result {
+	let tier = self.services.storage.provider(provider)?;
+	let path = content_path(digest);
+
+	let _lock = self.content_mutex.lock(&encode_key(digest)).await;
+
+	// deleted since it was selected
+	if self.db.get_content_refs(digest).await == 0 {
+		return Ok(());
+	}
+
+	let mut holders = Vec::new();
+	for holder in self.storage_providers() {
+		if holder.name != tier.name && holder.head(&path).await.is_ok() {
+			holders.push(holder);
+		}
+	}
+
+	if tier.head(&path).await.is_err() {
+		let Some(holder) = holders.first() else {
+			return Err!(Request(NotFound("Media file missing on every provider.")));
+		};
+
+		tier.put_one(&path, holder.get(&path).await?)
+			.await?;
+	}
+
+	// until recorded, reads find the content by trying every provider, so a
+	// move interrupted here is completed by the next one
+	for holder in holders {
+		holder.delete_one(&path).await?;
+	}
+
+	self.db.set_content_provider(digest, provider);
+
+	debug!(?path, %provider, "Moved media content");
+
+	Ok(())
+}
+
+/// Whether media stored at `stored` with `size` bytes is due to be moved at
+/// `now`, in milliseconds since the epoch: older than `after`, or larger than
+/// `min_size`.
+pub(super) fn should_tier(
+	stored: u64,
+	size: u64,
+	now: u64,
+	after: Option<Duration>,
+	min_size: Option<u64>,
+) -> bool {
+	let age = Duration::from_millis(now.saturating_sub(stored));
+	let old = after.is_some_and(|after| age > after);
+	let large = min_size.is_some_and(|min_size| size > min_size);
+
+	old || large
+}
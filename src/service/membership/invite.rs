@@ -27,6 +27,16 @@ pub async fn invite(
 	reason: Option<&String>,
 	is_direct: bool,
 ) -> Result {
+	self.services
+		.spam_checker
+		.user_may_invite(sender_user, user_id, room_id)
+		.await?;
+
+	self.services
+		.policy_lists
+		.check_invite(sender_user, user_id, room_id)
+		.await?;
+
 	if self.services.globals.user_is_local(user_id) {
 		self.local_invite(sender_user, user_id, room_id, reason, is_direct)
 			.boxed()
@@ -39,6 +39,7 @@
 	Services,
 	federation::{Candidates, WhenAllBackedOff},
 	rooms::{
+		partial_state::PartialState,
 		state::RoomMutexGuard,
 		state_compressor::{CompressedState, HashSetCompressStateEvent},
 		state_res,
@@ -76,6 +77,22 @@ pub async fn join<'a>(
 		extra_content,
 	}: Join<'a>,
 ) -> Result {
+	let is_invited = self
+		.services
+		.state_cache
+		.is_invited(sender_user, room_id)
+		.await;
+
+	self.services
+		.spam_checker
+		.user_may_join_room(sender_user, room_id, is_invited)
+		.await?;
+
+	self.services
+		.policy_lists
+		.check_join(sender_user, room_id)
+		.await?;
+
 	let servers =
 		get_servers_for_room(&self.services, sender_user, room_id, orig_room_id, servers).await?;
 
@@ -193,7 +210,10 @@ async fn is_local_join(&self, room_id: &RoomId, servers: &[OwnedServerName]) ->
 }
 
 #[implement(Service)]
-async fn lock_join_remote(&self, room_id: &RoomId) -> (RoomMutexGuard, RoomMutexGuard) {
+pub(super) async fn lock_join_remote(
+	&self,
+	room_id: &RoomId,
+) -> (RoomMutexGuard, RoomMutexGuard) {
 	// Hold federation before state so inbound events stay belayed until the join
 	// response is applied.
 	let federation_lock = self
@@ -280,7 +300,10 @@ async fn join_remote(
 		)
 		.await?;
 
-	if response.members_omitted {
+	// MSC3706: keep the partial state and resync the rest in the background.
+	let partial_state = response.members_omitted && self.services.config.partial_state_joins;
+
+	if response.members_omitted && !partial_state {
 		self.fetch_omitted_state(&remote_server, room_id, &event_id, servers, &mut response)
 			.await?;
 	}
@@ -364,6 +387,17 @@ async fn join_remote(
 	self.apply_send_join_state(room_id, &state, &state_lock)
 		.await?;
 
+	if partial_state {
+		let servers = self
+			.omitted_state_servers(&remote_server, servers, response.servers_in_room.as_deref())
+			.into_vec();
+
+		info!(servers = servers.len(), "Joined with partial state; resync pending.");
+		self.services
+			.partial_state
+			.mark_partial(room_id, &PartialState { event_id: event_id.clone(), servers });
+	}
+
 	// We append to state before appending the pdu, so we don't have a moment in
 	// time with the pdu without it's state. This is okay because append_pdu can't
 	// fail.
@@ -477,11 +511,30 @@ async fn fetch_omitted_state(
 	servers: &[OwnedServerName],
 	response: &mut federation::membership::create_join_event::v2::RoomState,
 ) -> Result {
-	use federation::event::get_room_state::v1::{Request, Response};
-
 	let eligible =
 		self.omitted_state_servers(remote_server, servers, response.servers_in_room.as_deref());
 
+	let federation::event::get_room_state::v1::Response { mut auth_chain, mut pdus } = self
+		.fetch_state_at_event(room_id, event_id, eligible)
+		.await?;
+
+	response.auth_chain = take(&mut auth_chain);
+	response.state = take(&mut pdus);
+
+	Ok(())
+}
+
+/// Requests the full room state at `event_id` from the first of `eligible`
+/// able to supply it, ranked by federation backoff.
+#[implement(Service)]
+pub(super) async fn fetch_state_at_event(
+	&self,
+	room_id: &RoomId,
+	event_id: &OwnedEventId,
+	eligible: Candidates,
+) -> Result<federation::event::get_room_state::v1::Response> {
+	use federation::event::get_room_state::v1::Request;
+
 	let candidates = self
 		.services
 		.federation
@@ -505,17 +558,14 @@ async fn fetch_omitted_state(
 				debug_warn!(?server, "state fetch failed: {e}");
 				last_error = Err(e);
 			},
-			| Ok(Response { mut auth_chain, mut pdus }) => {
-				response.auth_chain = take(&mut auth_chain);
-				response.state = take(&mut pdus);
-
+			| Ok(response) => {
 				info!(
 					auth_chain = response.auth_chain.len(),
-					state = response.state.len(),
+					state = response.pdus.len(),
 					"state finished"
 				);
 
-				return Ok(());
+				return Ok(response);
 			},
 		}
 	}
@@ -611,7 +661,7 @@ fn merge_restricted_signature(
 }
 
 #[implement(Service)]
-async fn ingest_send_join_state(
+pub(crate) async fn ingest_send_join_state(
 	&self,
 	room_id: &RoomId,
 	room_version_id: &RoomVersionId,
@@ -662,7 +712,7 @@ async fn ingest_send_join_state(
 }
 
 #[implement(Service)]
-async fn ingest_send_join_auth_chain(
+pub(crate) async fn ingest_send_join_auth_chain(
 	&self,
 	room_id: &RoomId,
 	room_version_id: &RoomVersionId,
@@ -701,7 +751,7 @@ async fn ingest_send_join_auth_chain(
 }
 
 #[implement(Service)]
-async fn apply_send_join_state(
+pub(crate) async fn apply_send_join_state(
 	&self,
 	room_id: &RoomId,
 	state: &HashMap<u64, OwnedEventId>,
@@ -4,6 +4,7 @@
 mod kick;
 mod knock;
 mod leave;
+mod resync;
 mod stripped_state;
 mod unban;
 
@@ -0,0 +1,218 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+	borrow::Borrow,
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+use futures::{FutureExt, StreamExt, stream};
+use ruma::{EventId, OwnedEventId, RoomId};
+use tuwunel_core::{
+	Result, debug, implement, info,
+	matrix::{Event, PduEvent, room_version},
+	warn,
+};
+
+use super::Service;
+use crate::rooms::{
+	partial_state::PartialState, short::ShortStateKey, state_compressor::CompressedState,
+};
+
+/// Completes a faster (MSC3706) join by fetching the room state omitted from
+/// `send_join` and installing it beneath the state accumulated since.
+///
+/// The fetched state is the room state before our join event. It becomes that
+/// event's state, and the room's current state becomes the fetched state
+/// overlaid with every entry gathered since the join, so later events win.
+///
+/// Events accepted only because the partial state lacked a membership are
+/// checked again against the resynced state. Those it refuses are withdrawn
+/// from the timeline and soft-failed, and their state entries are left out.
+#[implement(Service)]
+#[tracing::instrument(name = "resync", level = "debug", skip(self))]
+pub async fn resync_partial_state(&self, room_id: &RoomId) -> Result {
+	let PartialState { event_id, servers } = self.services.partial_state.get(room_id).await?;
+
+	let room_version_id = self
+		.services
+		.state
+		.get_room_version(room_id)
+		.await?;
+
+	let room_version_rules = room_version::rules(&room_version_id)?;
+
+	let response = self
+		.fetch_state_at_event(room_id, &event_id, servers.into_iter().collect())
+		.await?;
+
+	self.services
+		.server_keys
+		.acquire_events_pubkeys(
+			response
+				.auth_chain
+				.iter()
+				.chain(response.pdus.iter()),
+		)
+		.await;
+
+	let mut state = self
+		.ingest_send_join_state(room_id, &room_version_id, &room_version_rules, &response.pdus)
+		.await;
+
+	self.ingest_send_join_auth_chain(
+		room_id,
+		&room_version_id,
+		&room_version_rules,
+		&response.auth_chain,
+	)
+	.await;
+
+	let (_federation_lock, state_lock) = self.lock_join_remote(room_id).await;
+
+	debug!(events = state.len(), "Replacing partial state at the join event...");
+	let state_at_join: CompressedState = self
+		.services
+		.state_compressor
+		.compress_state_events(state.iter().map(|(ssk, eid)| (ssk, eid.borrow())))
+		.collect()
+		.await;
+
+	self.services
+		.state
+		.set_event_state(&event_id, room_id, Arc::new(state_at_join))
+		.await?;
+
+	let current_shortstatehash = self
+		.services
+		.state
+		.get_room_shortstatehash(room_id)
+		.await?;
+
+	let current: Vec<_> = self
+		.services
+		.state_accessor
+		.state_full_ids(current_shortstatehash)
+		.collect()
+		.boxed()
+		.await;
+
+	let provisional: HashSet<OwnedEventId> = self
+		.services
+		.partial_state
+		.provisional_events(room_id)
+		.collect()
+		.await;
+
+	let (current, held): (Vec<_>, Vec<_>) = current
+		.into_iter()
+		.partition(|(_, event_id)| !provisional.contains(event_id));
+
+	// state gathered since the join wins over the resynced state
+	state.extend(current);
+
+	let held: HashMap<_, _> = held
+		.into_iter()
+		.map(|(shortstatekey, event_id)| (event_id, shortstatekey))
+		.collect();
+
+	let mut events: Vec<_> = stream::iter(&provisional)
+		.filter_map(async |event_id| {
+			let count = self
+				.services
+				.timeline
+				.get_pdu_count(event_id)
+				.await
+				.ok()?;
+
+			let pdu = self
+				.services
+				.timeline
+				.get_pdu(event_id)
+				.await
+				.ok()?;
+
+			Some((count, pdu))
+		})
+		.collect()
+		.await;
+
+	events.sort_by_key(|(count, _)| *count);
+
+	let check = async |pdu: &PduEvent, state: &HashMap<ShortStateKey, OwnedEventId>| {
+		self.services
+			.event_handler
+			.auth_check_outlier_pdu(room_id, pdu, &room_version_rules, state)
+			.await
+			.is_ok()
+	};
+
+	let events = events.into_iter().map(|(_, pdu)| pdu);
+	let rejected = recheck_provisional(&mut state, &held, events, check).await;
+
+	info!(events = state.len(), "Installing resynced room state...");
+	self.apply_send_join_state(room_id, &state, &state_lock)
+		.await?;
+
+	self.services.partial_state.mark_full(room_id);
+
+	for event_id in &rejected {
+		self.withdraw_rejected(event_id).await;
+	}
+
+	self.services
+		.partial_state
+		.clear_provisional(room_id)
+		.await;
+
+	Ok(())
+}
+
+/// Takes an event the resynced state refuses out of the timeline and marks it
+/// soft-failed.
+#[implement(Service)]
+async fn withdraw_rejected(&self, event_id: &EventId) {
+	warn!(%event_id, "Withdrawing event refused by the resynced room state.");
+
+	if let Err(error) = self
+		.services
+		.timeline
+		.withdraw_pdu(event_id)
+		.await
+	{
+		warn!(%event_id, %error, "Failed to withdraw event from the timeline.");
+	}
+
+	self.services
+		.pdu_metadata
+		.mark_event_soft_failed(event_id);
+}
+
+/// Checks again, in timeline order, the events accepted against partial state,
+/// returning those refused. State entries held back from the overlay are
+/// restored for the events which pass.
+async fn recheck_provisional<I, Check>(
+	state: &mut HashMap<ShortStateKey, OwnedEventId>,
+	held: &HashMap<OwnedEventId, ShortStateKey>,
+	events: I,
+	check: Check,
+) -> Vec<OwnedEventId>
+where
+	I: IntoIterator<Item = PduEvent>,
+	Check: AsyncFn(&PduEvent, &HashMap<ShortStateKey, OwnedEventId>) -> bool,
+{
+	let mut rejected = Vec::new();
+	for pdu in events {
+		if !check(&pdu, &*state).await {
+			rejected.push(pdu.event_id);
+			continue;
+		}
+
+		if let Some(shortstatekey) = held.get(pdu.event_id()) {
+			state.insert(*shortstatekey, pdu.event_id);
+		}
+	}
+
+	rejected
+}
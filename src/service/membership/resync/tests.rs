@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use ruma::{
+	OwnedEventId, UserId, events::TimelineEventType, owned_event_id, owned_room_id, uint, user_id,
+};
+use serde_json::{json, value::to_raw_value};
+use tuwunel_core::matrix::{Event, EventHash, PduEvent};
+
+use super::recheck_provisional;
+
+fn pdu(
+	event_id: &str,
+	sender: &UserId,
+	kind: TimelineEventType,
+	state_key: Option<&str>,
+) -> PduEvent {
+	PduEvent {
+		event_id: event_id.try_into().unwrap(),
+		room_id: owned_room_id!("!room:remote.example.org"),
+		sender: sender.to_owned(),
+		origin: None,
+		origin_server_ts: uint!(1),
+		state_key: state_key.map(Into::into),
+		kind,
+		content: to_raw_value(&json!({})).unwrap().into(),
+		redacts: None,
+		unsigned: None,
+		auth_events: Default::default(),
+		prev_events: Default::default(),
+		depth: uint!(1),
+		hashes: EventHash::default(),
+	}
+}
+
+/// Stands in for the auth check: the sender must be a member in the state.
+async fn sender_is_member(pdu: &PduEvent, state: &HashMap<u64, OwnedEventId>) -> bool {
+	let shortstatekey = match pdu.sender().as_str() {
+		| "@alice:remote.example.org" => 2,
+		| _ => 5,
+	};
+
+	state.contains_key(&shortstatekey)
+}
+
+#[tokio::test]
+async fn recheck_rejects_events_from_non_members() {
+	let alice = user_id!("@alice:remote.example.org");
+	let mallory = user_id!("@mallory:evil.example.org");
+
+	let mut state = HashMap::from([
+		(1, owned_event_id!("$create:remote.example.org")),
+		(2, owned_event_id!("$alice_join:remote.example.org")),
+		(3, owned_event_id!("$topic:remote.example.org")),
+	]);
+
+	let held = HashMap::from([(owned_event_id!("$mallory_topic:evil.example.org"), 3)]);
+
+	let events = [
+		pdu("$alice_message:remote.example.org", alice, TimelineEventType::RoomMessage, None),
+		pdu(
+			"$mallory_message:evil.example.org",
+			mallory,
+			TimelineEventType::RoomMessage,
+			None,
+		),
+		pdu(
+			"$mallory_topic:evil.example.org",
+			mallory,
+			TimelineEventType::RoomTopic,
+			Some(""),
+		),
+	];
+
+	let rejected = recheck_provisional(&mut state, &held, events, sender_is_member).await;
+
+	assert_eq!(rejected, [
+		owned_event_id!("$mallory_message:evil.example.org"),
+		owned_event_id!("$mallory_topic:evil.example.org"),
+	]);
+	assert_eq!(state[&3], "$topic:remote.example.org");
+}
+
+#[tokio::test]
+async fn recheck_restores_state_of_events_which_pass() {
+	let alice = user_id!("@alice:remote.example.org");
+
+	let mut state = HashMap::from([(2, owned_event_id!("$alice_join:remote.example.org"))]);
+	let held = HashMap::from([(owned_event_id!("$alice_topic:remote.example.org"), 3)]);
+	let events = [pdu(
+		"$alice_topic:remote.example.org",
+		alice,
+		TimelineEventType::RoomTopic,
+		Some(""),
+	)];
+
+	let rejected = recheck_provisional(&mut state, &held, events, sender_is_member).await;
+
+	assert!(rejected.is_empty());
+	assert_eq!(state[&3], "$alice_topic:remote.example.org");
+}
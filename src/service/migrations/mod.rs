@@ -37,7 +37,7 @@
 	split_conduit_highlight_counts::split_conduit_highlight_counts,
 	upgrade_legacy_mediaid_user::upgrade_legacy_mediaid_user,
 };
-use crate::Services;
+use crate::{Services, media::migrations::migrate_dedupe_media};
 
 mod account_status;
 mod clear_servername_status;
@@ -226,6 +226,8 @@ async fn fresh(services: &Services) -> Result {
 	db["global"].insert(b"clear_servername_status", []);
 	db["global"].insert(b"adopt_foreign_account_status", []);
 	db["global"].insert(b"adopt_foreign_email_bindings", []);
+	db["global"].insert(b"build_user_directory", []);
+	db["global"].insert(b"dedupe_media_content", []);
 	mark_clean_injectivity(services);
 
 	// Create the admin room and server user on first run
@@ -413,6 +415,24 @@ async fn migrate(services: &Services, foreign_lineage: bool) -> Result {
 		db["global"].insert(b"adopt_foreign_email_bindings", []);
 	}
 
+	if db["global"]
+		.get(b"build_user_directory")
+		.await
+		.is_not_found()
+	{
+		services.user_directory.rebuild().await?;
+
+		db["global"].insert(b"build_user_directory", []);
+	}
+
+	if db["global"]
+		.get(b"dedupe_media_content")
+		.await
+		.is_not_found()
+	{
+		migrate_dedupe_media(services).await;
+	}
+
 	// A newer same-lineage database was already refused; stamping ours is safe. A
 	// foreign import above our version was already stamped down before the import
 	// ran, so this is a no-op for it.
@@ -20,22 +20,29 @@
 pub mod media;
 pub mod membership;
 pub mod oauth;
+pub mod policy_lists;
+pub mod policy_server;
 pub mod presence;
 pub mod profile;
 pub mod pusher;
+pub mod ratelimit;
 pub mod registration_tokens;
 pub mod rendezvous;
+pub mod reports;
 pub mod resolver;
 pub mod rooms;
 pub mod sending;
 pub mod sendmail;
 pub mod server_keys;
+pub mod spam_checker;
 pub mod storage;
+pub mod synapse;
 pub mod sync;
 pub mod tasks;
 pub mod threepid;
 pub mod transaction_ids;
 pub mod uiaa;
+pub mod user_directory;
 pub mod users;
 
 pub(crate) use once_services::OnceServices;
@@ -0,0 +1,107 @@
+use ruma::{RoomId, ServerName, UserId};
+use tuwunel_core::{Err, Result, debug_info, implement, utils::time::now_millis};
+
+use super::{Kind, Rule};
+
+/// Most refusals kept for the admin room.
+const REJECTIONS_KEPT: usize = 128;
+
+/// An action refused because of a policy rule.
+#[derive(Clone, Debug)]
+pub struct Rejection {
+	pub rejected_ts: u64,
+
+	/// The refused action, as in `join` or `invite`.
+	pub action: &'static str,
+
+	/// The user, room or server the rule matched.
+	pub entity: String,
+
+	pub rule: Rule,
+}
+
+/// Refuses `user_id` joining `room_id` when either is banned.
+#[implement(super::Service)]
+pub async fn check_join(&self, user_id: &UserId, room_id: &RoomId) -> Result {
+	self.check_user("join", user_id).await?;
+	self.check_room("join", room_id.as_str()).await
+}
+
+/// Refuses an invite when the inviter, the invitee or the room is banned.
+#[implement(super::Service)]
+pub async fn check_invite(&self, sender: &UserId, user_id: &UserId, room_id: &RoomId) -> Result {
+	self.check_user("invite", sender).await?;
+	self.check_user("invite", user_id).await?;
+	self.check_room("invite", room_id.as_str()).await
+}
+
+#[implement(super::Service)]
+pub async fn check_user(&self, action: &'static str, user_id: &UserId) -> Result {
+	self.check(action, Kind::User, user_id.as_str())
+		.await
+}
+
+/// Checks a room ID or alias against the room rules.
+#[implement(super::Service)]
+pub async fn check_room(&self, action: &'static str, room: &str) -> Result {
+	self.check(action, Kind::Room, room).await
+}
+
+/// Checks a server against the server rules. This server is never refused.
+#[implement(super::Service)]
+pub async fn check_server(&self, action: &'static str, server_name: &ServerName) -> Result {
+	if self.services.globals.server_is_ours(server_name) {
+		return Ok(());
+	}
+
+	self.check(action, Kind::Server, server_name.as_str())
+		.await
+}
+
+#[implement(super::Service)]
+async fn check(&self, action: &'static str, kind: Kind, entity: &str) -> Result {
+	let Some(rule) = self.matching_rule(kind, entity).await else {
+		return Ok(());
+	};
+
+	debug_info!(
+		%action,
+		%entity,
+		policy_room = %rule.policy_room,
+		rule = %rule.entity,
+		"Refused by policy list"
+	);
+
+	let reason = rule.reason.clone();
+	self.record(Rejection {
+		rejected_ts: now_millis(),
+		action,
+		entity: entity.to_owned(),
+		rule,
+	});
+
+	Err!(Request(Forbidden("{entity} is banned by a policy list: {reason}")))
+}
+
+#[implement(super::Service)]
+fn record(&self, rejection: Rejection) {
+	let mut rejections = self.rejections.lock().expect("locked");
+
+	if rejections.len() >= REJECTIONS_KEPT {
+		rejections.pop_front();
+	}
+
+	rejections.push_back(rejection);
+}
+
+/// The recent refusals, newest first.
+#[implement(super::Service)]
+pub fn rejections(&self) -> Vec<Rejection> {
+	self.rejections
+		.lock()
+		.expect("locked")
+		.iter()
+		.rev()
+		.cloned()
+		.collect()
+}
@@ -0,0 +1,173 @@
+use futures::StreamExt;
+use regex::{RegexBuilder, RegexSet, RegexSetBuilder};
+use ruma::{RoomId, events::StateEventType};
+use serde::Deserialize;
+use tuwunel_core::{implement, matrix::Event, utils::ReadyExt, warn};
+
+use super::{Kind, Rule};
+use crate::rooms::short::ShortStateHash;
+
+/// The rules of one policy room at one state, with the globs of each kind
+/// compiled into one set.
+pub(super) struct List {
+	pub(super) shortstatehash: ShortStateHash,
+	users: Matcher,
+	rooms: Matcher,
+	servers: Matcher,
+}
+
+pub(super) struct Matcher {
+	set: RegexSet,
+	rules: Vec<Rule>,
+}
+
+/// Content of a `m.policy.rule.*` event; removed rules have none.
+#[derive(Deserialize)]
+struct RuleContent {
+	entity: String,
+	recommendation: String,
+
+	#[serde(default)]
+	reason: String,
+}
+
+/// Recommendations meaning `m.ban`, the unstable one included.
+const BAN: [&str; 2] = ["m.ban", "org.matrix.mjolnir.ban"];
+
+/// Compiled size allowed for the regex of one rule; a larger glob is left out
+/// rather than the whole list.
+const RULE_SIZE_LIMIT: usize = 1 << 20;
+
+#[implement(super::Service)]
+pub(super) async fn compile(&self, room_id: &RoomId, shortstatehash: ShortStateHash) -> List {
+	List {
+		shortstatehash,
+		users: self
+			.compile_kind(room_id, shortstatehash, Kind::User)
+			.await,
+		rooms: self
+			.compile_kind(room_id, shortstatehash, Kind::Room)
+			.await,
+		servers: self
+			.compile_kind(room_id, shortstatehash, Kind::Server)
+			.await,
+	}
+}
+
+#[implement(super::Service)]
+async fn compile_kind(
+	&self,
+	room_id: &RoomId,
+	shortstatehash: ShortStateHash,
+	kind: Kind,
+) -> Matcher {
+	let event_type = match kind {
+		| Kind::User => StateEventType::PolicyRuleUser,
+		| Kind::Room => StateEventType::PolicyRuleRoom,
+		| Kind::Server => StateEventType::PolicyRuleServer,
+	};
+
+	let rules: Vec<Rule> = self
+		.services
+		.state_accessor
+		.state_type_pdus(shortstatehash, &event_type)
+		.ready_filter_map(|pdu| pdu.get_content::<RuleContent>().ok())
+		.ready_filter(|content| BAN.contains(&content.recommendation.as_str()))
+		.map(|content| Rule {
+			policy_room: room_id.to_owned(),
+			kind,
+			entity: content.entity,
+			reason: content.reason,
+		})
+		.collect()
+		.await;
+
+	let (matcher, failed) = Matcher::new(rules);
+	for (rule, e) in failed {
+		warn!(%room_id, %kind, entity = %rule.entity, "Policy rule left out: {e}");
+	}
+
+	matcher
+}
+
+#[implement(List)]
+pub(super) fn matching_rule(&self, kind: Kind, entity: &str) -> Option<&Rule> {
+	let matcher = match kind {
+		| Kind::User => &self.users,
+		| Kind::Room => &self.rooms,
+		| Kind::Server => &self.servers,
+	};
+
+	matcher.matching_rule(entity)
+}
+
+#[implement(List)]
+pub(super) fn len(&self) -> usize {
+	self.users
+		.rules
+		.len()
+		.saturating_add(self.rooms.rules.len())
+		.saturating_add(self.servers.rules.len())
+}
+
+impl Matcher {
+	/// Compiles the rules into one set, returning alongside it those left out
+	/// because their glob does not compile within `RULE_SIZE_LIMIT`.
+	pub(super) fn new(rules: Vec<Rule>) -> (Self, Vec<(Rule, regex::Error)>) {
+		let mut compiled = Vec::with_capacity(rules.len());
+		let mut failed = Vec::new();
+		for rule in rules {
+			let built = RegexBuilder::new(&glob_regex(&rule.entity))
+				.size_limit(RULE_SIZE_LIMIT)
+				.build();
+
+			match built {
+				| Ok(_) => compiled.push(rule),
+				| Err(e) => failed.push((rule, e)),
+			}
+		}
+
+		let set = RegexSetBuilder::new(
+			compiled
+				.iter()
+				.map(|rule| glob_regex(&rule.entity)),
+		)
+		.size_limit(RULE_SIZE_LIMIT.saturating_mul(compiled.len().max(1)))
+		.build();
+
+		match set {
+			| Ok(set) => (Self { set, rules: compiled }, failed),
+			| Err(e) => {
+				failed.extend(compiled.into_iter().map(|rule| (rule, e.clone())));
+				(Self::default(), failed)
+			},
+		}
+	}
+
+	pub(super) fn matching_rule(&self, entity: &str) -> Option<&Rule> {
+		self.set
+			.matches(entity)
+			.iter()
+			.next()
+			.and_then(|i| self.rules.get(i))
+	}
+}
+
+impl Default for Matcher {
+	fn default() -> Self {
+		Self {
+			set: RegexSet::empty(),
+			rules: Vec::new(),
+		}
+	}
+}
+
+/// The regex of a policy rule glob, where `*` stands for any characters and
+/// `?` for any one.
+pub(super) fn glob_regex(glob: &str) -> String {
+	let pattern = regex::escape(glob)
+		.replace(r"\*", ".*")
+		.replace(r"\?", ".");
+
+	format!("^{pattern}$")
+}
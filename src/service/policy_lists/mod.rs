@@ -0,0 +1,264 @@
+//! Moderation Policy Lists
+//!
+//! Policy rooms publish `m.policy.rule.user`, `m.policy.rule.room` and
+//! `m.policy.rule.server` state events. The server user joins the rooms
+//! listed in `policy_list_rooms`, and the `m.ban` rules of their current
+//! state are compiled into glob matchers, kept until the room's state
+//! changes. Joins, invites, federation from banned servers, and the creation
+//! of rooms and aliases are refused when a rule matches; recent refusals are
+//! kept for the admin room. A policy room which cannot be resolved or joined
+//! again keeps the room ID it last resolved to, so its rules stay in force.
+
+mod check;
+mod compile;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::{HashMap, VecDeque},
+	fmt,
+	sync::{Arc, Mutex, RwLock},
+	time::Duration,
+};
+
+use async_trait::async_trait;
+use ruma::{OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName, RoomId, RoomOrAliasId};
+use tokio::time::sleep;
+use tuwunel_core::{Result, debug, implement, info, warn};
+
+pub use self::check::Rejection;
+use self::compile::List;
+use crate::membership::Join;
+
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+	subscribed: RwLock<Vec<(OwnedRoomOrAliasId, OwnedRoomId)>>,
+	lists: RwLock<HashMap<OwnedRoomId, Arc<List>>>,
+	rejections: Mutex<VecDeque<Rejection>>,
+}
+
+/// A `m.ban` rule of a policy room.
+#[derive(Clone, Debug)]
+pub struct Rule {
+	pub policy_room: OwnedRoomId,
+	pub kind: Kind,
+
+	/// The glob the rule applies to.
+	pub entity: String,
+
+	pub reason: String,
+}
+
+/// What a rule applies to, after the `m.policy.rule.*` event type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kind {
+	User,
+
+	/// Room IDs and aliases.
+	Room,
+
+	Server,
+}
+
+/// How often the subscribed rooms are resolved and joined again, besides on
+/// each config reload.
+const SUBSCRIBE_INTERVAL: Duration = Duration::from_hours(1);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: args.services.clone(),
+			subscribed: RwLock::default(),
+			lists: RwLock::default(),
+			rejections: Mutex::default(),
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let mut reloads = self.services.config.reloads();
+		loop {
+			self.subscribe().await;
+
+			tokio::select! {
+				() = sleep(SUBSCRIBE_INTERVAL) => {},
+				_ = reloads.changed() => {},
+				() = self.services.server.until_shutdown() => return Ok(()),
+			}
+		}
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Resolves the configured policy rooms, joining the server user to those it
+/// is not in yet. A room which fails keeps its previous room ID.
+#[implement(Service)]
+pub async fn subscribe(&self) {
+	let previous = self
+		.subscribed
+		.read()
+		.expect("locked for reading")
+		.clone();
+
+	let mut subscribed = Vec::new();
+	for room in &self.services.config.policy_list_rooms {
+		match self.subscribe_room(room).await {
+			| Ok(room_id) => subscribed.push((room.clone(), room_id)),
+			| Err(e) => {
+				let kept = previous
+					.iter()
+					.find(|(subscribed, _)| subscribed == room)
+					.cloned();
+
+				warn!(
+					%room,
+					kept = ?kept.as_ref().map(|(_, room_id)| room_id),
+					"Could not subscribe to policy room: {e}"
+				);
+
+				subscribed.extend(kept);
+			},
+		}
+	}
+
+	let ban_lists = &self.services.config.policy_server_ban_lists;
+	self.lists
+		.write()
+		.expect("locked for writing")
+		.retain(|room_id, _| {
+			subscribed
+				.iter()
+				.any(|(_, subscribed)| subscribed == room_id)
+				|| ban_lists.contains(room_id)
+		});
+
+	*self
+		.subscribed
+		.write()
+		.expect("locked for writing") = subscribed;
+}
+
+#[implement(Service)]
+async fn subscribe_room(&self, room: &RoomOrAliasId) -> Result<OwnedRoomId> {
+	let server_user = &self.services.globals.server_user;
+	let (room_id, mut servers) = self
+		.services
+		.alias
+		.maybe_resolve_with_servers(room, None)
+		.await?;
+
+	if !self
+		.services
+		.state_cache
+		.is_joined(server_user, &room_id)
+		.await
+	{
+		servers.extend(room_id.server_name().map(ToOwned::to_owned));
+		self.join(room, &room_id, &servers).await?;
+
+		info!(%room_id, "Subscribed to policy room");
+	}
+
+	Ok(room_id)
+}
+
+#[implement(Service)]
+async fn join(
+	&self,
+	room: &RoomOrAliasId,
+	room_id: &RoomId,
+	servers: &[OwnedServerName],
+) -> Result {
+	self.services
+		.membership
+		.join(Join {
+			sender_user: &self.services.globals.server_user,
+			room_id,
+			orig_room_id: Some(room),
+			reason: Some("Following this policy list".to_owned()),
+			servers,
+			is_appservice: false,
+			extra_content: None,
+		})
+		.await
+}
+
+/// The first rule of the subscribed policy rooms matching `entity`.
+#[implement(Service)]
+pub async fn matching_rule(&self, kind: Kind, entity: &str) -> Option<Rule> {
+	let subscribed: Vec<_> = self
+		.subscribed
+		.read()
+		.expect("locked for reading")
+		.iter()
+		.map(|(_, room_id)| room_id.clone())
+		.collect();
+
+	self.matching_rule_in(&subscribed, kind, entity)
+		.await
+}
+
+/// The first rule of the given policy rooms matching `entity`.
+#[implement(Service)]
+pub async fn matching_rule_in(
+	&self,
+	rooms: &[OwnedRoomId],
+	kind: Kind,
+	entity: &str,
+) -> Option<Rule> {
+	for room_id in rooms {
+		let Some(list) = self.list(room_id).await else {
+			continue;
+		};
+
+		if let Some(rule) = list.matching_rule(kind, entity) {
+			return Some(rule.clone());
+		}
+	}
+
+	None
+}
+
+/// The compiled rules of the policy room at its current state.
+#[implement(Service)]
+async fn list(&self, room_id: &RoomId) -> Option<Arc<List>> {
+	let shortstatehash = self
+		.services
+		.state
+		.get_room_shortstatehash(room_id)
+		.await
+		.ok()?;
+
+	let cached = self
+		.lists
+		.read()
+		.expect("locked for reading")
+		.get(room_id)
+		.filter(|list| list.shortstatehash == shortstatehash)
+		.cloned();
+
+	if cached.is_some() {
+		return cached;
+	}
+
+	let list = Arc::new(self.compile(room_id, shortstatehash).await);
+	debug!(%room_id, rules = list.len(), "Compiled policy list");
+
+	self.lists
+		.write()
+		.expect("locked for writing")
+		.insert(room_id.to_owned(), list.clone());
+
+	Some(list)
+}
+
+impl fmt::Display for Kind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			| Self::User => "user",
+			| Self::Room => "room",
+			| Self::Server => "server",
+		})
+	}
+}
@@ -0,0 +1,68 @@
+use ruma::owned_room_id;
+
+use super::{
+	Kind, Rule,
+	compile::{Matcher, glob_regex},
+};
+
+fn rule(entity: &str) -> Rule {
+	Rule {
+		policy_room: owned_room_id!("!list:example.com"),
+		kind: Kind::User,
+		entity: entity.to_owned(),
+		reason: "spam".to_owned(),
+	}
+}
+
+#[test]
+fn globs_match_whole_entities() {
+	let (matcher, failed) = Matcher::new(vec![
+		rule("@*:spam.example"),
+		rule("@bot?:example.com"),
+		rule("@a.b:example.com"),
+	]);
+
+	assert!(failed.is_empty());
+
+	let matching = |entity| {
+		matcher
+			.matching_rule(entity)
+			.map(|rule| rule.entity.as_str())
+	};
+
+	assert_eq!(matching("@bot1:spam.example"), Some("@*:spam.example"));
+	assert_eq!(matching("@bot7:example.com"), Some("@bot?:example.com"));
+	assert_eq!(matching("@bot77:example.com"), None);
+	assert_eq!(matching("@a.b:example.com"), Some("@a.b:example.com"));
+	assert_eq!(matching("@axb:example.com"), None);
+	assert_eq!(matching("@bot1:spam.example.org"), None);
+}
+
+#[test]
+fn oversized_glob_is_left_out_alone() {
+	let huge = format!("@{}:example.com", "?".repeat(200_000));
+	let (matcher, failed) = Matcher::new(vec![rule(&huge), rule("@*:spam.example")]);
+
+	assert_eq!(failed.len(), 1);
+	assert_eq!(failed[0].0.entity, huge);
+	assert!(
+		matcher
+			.matching_rule("@bot1:spam.example")
+			.is_some()
+	);
+}
+
+#[test]
+fn glob_regex_escapes_everything_else() {
+	assert_eq!(glob_regex("*.example.com"), r"^.*\.example\.com$");
+	assert_eq!(glob_regex("#room?:ex+ample"), r"^\#room.:ex\+ample$");
+}
+
+#[test]
+fn empty_matcher_matches_nothing() {
+	assert!(
+		Matcher::default()
+			.matching_rule("@a:example.com")
+			.is_none()
+	);
+}
@@ -0,0 +1,138 @@
+//! Policy Server
+//!
+//! MSC4284 lets a room name a policy server whose signature its events
+//! carry. With `allow_policy_server` set, this server can be that policy
+//! server: events sent in rooms whose `m.room.policy` names it are checked
+//! against the configured rules, then signed with this server's signing key
+//! under the `ed25519:policy_server` key id, or refused with the rule's
+//! reason.
+
+mod rules;
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
+
+use ruma::{
+	CanonicalJsonObject, CanonicalJsonValue, EventId, RoomId, RoomVersionId, SigningKeyAlgorithm,
+};
+use tuwunel_core::{
+	Err, Result, debug, err, implement,
+	matrix::{pdu::Pdu, room_version},
+};
+
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+}
+
+/// Answer of this server, as a policy server, to one event.
+#[derive(Debug)]
+pub enum Decision {
+	/// The event passed; this is the signature for `ed25519:policy_server`.
+	Signed(String),
+
+	/// The event broke a rule; the reason names it.
+	Refused(String),
+}
+
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self { services: args.services.clone() }))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Checks an event in federation format against the rules and signs it when
+/// it passes. Fails when this server is not the policy server of the room,
+/// or the room's `m.room.policy` does not carry this server's signing key.
+#[implement(Service)]
+#[tracing::instrument(name = "policy_serve", level = "debug", skip_all, fields(%event_id))]
+pub async fn sign_pdu(
+	&self,
+	room_id: &RoomId,
+	event_id: &EventId,
+	value: CanonicalJsonObject,
+) -> Result<Decision> {
+	if !self.services.config.allow_policy_server {
+		return Err!(Request(NotFound("This server is not a policy server.")));
+	}
+
+	self.check_room_policy(room_id).await?;
+
+	let room_version = self
+		.services
+		.state
+		.get_room_version(room_id)
+		.await?;
+
+	let rules = room_version::rules(&room_version)?;
+	let (pdu, _) = Pdu::from_object_federation(room_id, event_id, value.clone(), &rules)?;
+
+	if let Some(reason) = self.evaluate(&pdu).await {
+		debug!(%room_id, sender = %pdu.sender, "Refusing event: {reason}");
+		return Ok(Decision::Refused(reason));
+	}
+
+	self.sign(value, &room_version)
+		.map(Decision::Signed)
+}
+
+/// Signs the redacted event with this server's active key, as the room's
+/// policy server signature is checked.
+#[implement(Service)]
+fn sign(&self, value: CanonicalJsonObject, room_version: &RoomVersionId) -> Result<String> {
+	let rules = room_version::rules(room_version)?;
+	let mut redacted = ruma::canonical_json::redact(value, &rules.redaction, None)
+		.map_err(|e| err!(Request(BadJson("Event cannot be redacted: {e}"))))?;
+
+	redacted.remove("signatures");
+	self.services
+		.server_keys
+		.sign_json(&mut redacted)?;
+
+	let server_name = self.services.globals.server_name();
+	let key_id = self.services.server_keys.active_key_id();
+	let signature = redacted
+		.get("signatures")
+		.and_then(CanonicalJsonValue::as_object)
+		.and_then(|servers| servers.get(server_name.as_str()))
+		.and_then(CanonicalJsonValue::as_object)
+		.and_then(|keys| keys.get(key_id.as_str()))
+		.and_then(CanonicalJsonValue::as_str)
+		.ok_or_else(|| err!("Signing the event left no signature for {key_id}"))?;
+
+	Ok(signature.to_owned())
+}
+
+/// Ensures the room's policy server is this server, with the public key of
+/// its active signing key.
+#[implement(Service)]
+async fn check_room_policy(&self, room_id: &RoomId) -> Result {
+	let Some(policy) = self
+		.services
+		.event_handler
+		.lookup_policy_server(room_id)
+		.await
+	else {
+		return Err!(Request(Forbidden("Room {room_id} has no policy server.")));
+	};
+
+	if !self.services.globals.server_is_ours(&policy.via) {
+		return Err!(Request(Forbidden("This server is not the policy server of {room_id}.")));
+	}
+
+	let (_, verify_key) = self.services.server_keys.active_verify_key();
+	if policy
+		.public_keys
+		.get(&SigningKeyAlgorithm::Ed25519)
+		.is_none_or(|key| *key != verify_key.key)
+	{
+		return Err!(Request(Forbidden(warn!(
+			"The policy key of {room_id} is not this server's signing key; update its \
+			 m.room.policy event."
+		))));
+	}
+
+	Ok(())
+}
@@ -0,0 +1,187 @@
+use ruma::{Mxc, events::TimelineEventType};
+use serde_json::Value as JsonValue;
+use tuwunel_core::{
+	debug, implement,
+	matrix::{Event, pdu::Pdu},
+	utils::time::now_millis,
+};
+
+use crate::policy_lists::Kind;
+
+/// Most files of one event whose hashes are checked.
+const MEDIA_CHECK_LIMIT: usize = 8;
+
+/// The reason the event breaks one of the rules, when it does.
+#[implement(super::Service)]
+pub(super) async fn evaluate(&self, pdu: &Pdu) -> Option<String> {
+	let content = pdu.get_content_as_value();
+	let mut texts = Vec::new();
+	content_strings(&content, &mut texts);
+
+	if let Some(reason) = self.check_ban_lists(pdu).await {
+		return Some(reason);
+	}
+
+	if let Some(reason) = self.check_text(&texts) {
+		return Some(reason);
+	}
+
+	if let Some(reason) = self.check_new_member(pdu, &texts).await {
+		return Some(reason);
+	}
+
+	self.check_media(&texts).await
+}
+
+#[implement(super::Service)]
+fn check_text(&self, texts: &[&str]) -> Option<String> {
+	let config = &self.services.config;
+	let keywords: Vec<String> = config
+		.policy_server_keywords
+		.iter()
+		.map(|keyword| keyword.to_lowercase())
+		.collect();
+
+	texts.iter().find_map(|text| {
+		let lowered = text.to_lowercase();
+		if keywords
+			.iter()
+			.any(|keyword| lowered.contains(keyword))
+		{
+			return Some("Event content contains a blocked keyword.".to_owned());
+		}
+
+		config
+			.policy_server_regexes
+			.is_match(text)
+			.then(|| "Event content matches a blocked pattern.".to_owned())
+	})
+}
+
+/// Refuses media and links from members who joined the room too recently, as
+/// this server saw it. Members who joined before this server recorded join
+/// times are not refused.
+#[implement(super::Service)]
+async fn check_new_member(&self, pdu: &Pdu, texts: &[&str]) -> Option<String> {
+	let min_secs = self.services.config.policy_server_new_member_secs;
+	if min_secs == 0 || *pdu.kind() == TimelineEventType::RoomMember {
+		return None;
+	}
+
+	if !texts
+		.iter()
+		.any(|text| is_media(text) || is_link(text))
+	{
+		return None;
+	}
+
+	let joined_ms = self
+		.services
+		.state_cache
+		.get_joined_ts(pdu.room_id(), pdu.sender())
+		.await
+		.ok()?;
+
+	let member_ms = now_millis().saturating_sub(joined_ms);
+
+	(member_ms < min_secs.saturating_mul(1000))
+		.then(|| "New members may not post media or links yet.".to_owned())
+}
+
+/// Refuses events referring to media with a blocked hash. Only media already
+/// stored here is checked; nothing is fetched on the event's behalf.
+#[implement(super::Service)]
+async fn check_media(&self, texts: &[&str]) -> Option<String> {
+	let hashes: Vec<String> = self
+		.services
+		.config
+		.policy_server_media_hashes
+		.iter()
+		.map(|hash| hash.to_lowercase())
+		.collect();
+
+	if hashes.is_empty() {
+		return None;
+	}
+
+	let mxcs = texts
+		.iter()
+		.filter(|text| is_media(text))
+		.filter_map(|text| Mxc::try_from(*text).ok())
+		.take(MEDIA_CHECK_LIMIT);
+
+	for mxc in mxcs {
+		let media = match self.services.media.get_if_stored(&mxc).await {
+			| Ok(media) => media,
+			| Err(e) => {
+				debug!(%mxc, "Media left unchecked: {e}");
+				continue;
+			},
+		};
+
+		if hashes.contains(&sha256_hex(&media.content)) {
+			return Some("Event refers to blocked media.".to_owned());
+		}
+	}
+
+	None
+}
+
+/// Refuses events from users and servers banned by the configured policy
+/// rooms, and membership events for banned users.
+#[implement(super::Service)]
+async fn check_ban_lists(&self, pdu: &Pdu) -> Option<String> {
+	let ban_lists = &self.services.config.policy_server_ban_lists;
+	if ban_lists.is_empty() {
+		return None;
+	}
+
+	let sender = pdu.sender();
+	let target = (*pdu.kind() == TimelineEventType::RoomMember)
+		.then(|| pdu.state_key())
+		.flatten();
+
+	let entities = [
+		Some((Kind::User, sender.as_str())),
+		target.map(|target| (Kind::User, target)),
+		Some((Kind::Server, sender.server_name().as_str())),
+	];
+
+	for (kind, entity) in entities.into_iter().flatten() {
+		if let Some(rule) = self
+			.services
+			.policy_lists
+			.matching_rule_in(ban_lists, kind, entity)
+			.await
+		{
+			return Some(format!("{entity} is banned by {}: {}", rule.policy_room, rule.reason));
+		}
+	}
+
+	None
+}
+
+/// Every string value in the event's content, nested ones included.
+pub(super) fn content_strings<'a>(value: &'a JsonValue, out: &mut Vec<&'a str>) {
+	match value {
+		| JsonValue::String(text) => out.push(text),
+		| JsonValue::Array(values) => values
+			.iter()
+			.for_each(|value| content_strings(value, out)),
+		| JsonValue::Object(map) => map
+			.values()
+			.for_each(|value| content_strings(value, out)),
+		| _ => {},
+	}
+}
+
+pub(super) fn sha256_hex(content: &[u8]) -> String {
+	<sha2::Sha256 as sha2::Digest>::digest(content)
+		.iter()
+		.map(|byte| format!("{byte:02x}"))
+		.collect()
+}
+
+fn is_media(text: &str) -> bool { text.starts_with("mxc://") }
+
+fn is_link(text: &str) -> bool { text.contains("http://") || text.contains("https://") }
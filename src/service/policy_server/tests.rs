@@ -0,0 +1,26 @@
+use serde_json::json;
+
+use super::rules::{content_strings, sha256_hex};
+
+#[test]
+fn content_strings_are_collected_from_nested_values() {
+	let content = json!({
+		"body": "hello",
+		"info": {"thumbnail_url": "mxc://example.com/thumb", "size": 42},
+		"m.mentions": {"user_ids": ["@a:example.com"]},
+	});
+
+	let mut texts = Vec::new();
+	content_strings(&content, &mut texts);
+	texts.sort_unstable();
+
+	assert_eq!(texts, ["@a:example.com", "hello", "mxc://example.com/thumb"]);
+}
+
+#[test]
+fn media_hashes_are_lowercase_hex() {
+	assert_eq!(
+		sha256_hex(b""),
+		"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+	);
+}
@@ -184,16 +184,17 @@ async fn update_room(
 
 	content.reason = None;
 
+	let pdu_builder = PduBuilder::state(user_id.as_str(), &content);
+	self.services
+		.timeline
+		.check_pdu_for_spam(&pdu_builder, user_id, room_id)
+		.await?;
+
 	let state_lock = self.services.state.mutex.lock(room_id).await;
 
 	self.services
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder::state(user_id.as_str(), &content),
-			user_id,
-			room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(pdu_builder, user_id, room_id, &state_lock)
 		.await?;
 
 	Ok(())
@@ -359,6 +360,14 @@ pub async fn set_profile_keys(
 		} else {
 			self.useridprofilekey_value.del(key);
 		}
+
+		if matches!(name, ProfileFieldName::DisplayName) {
+			let displayname = value.as_ref().and_then(Value::as_str);
+			self.services
+				.user_directory
+				.update(user_id, displayname)
+				.await;
+		}
 	}
 
 	Ok(())
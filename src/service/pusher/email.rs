@@ -0,0 +1,383 @@
+//! Email pushers.
+//!
+//! Notifications for an email pusher are not delivered one by one; they are
+//! queued durably and mailed as a per-room digest once they have waited out
+//! `push_email_delay_secs`, at most once per `push_email_throttle_secs`.
+//! Rooms read in the meantime are dropped from the digest.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use futures::{StreamExt, future::join};
+use ruma::{
+	OwnedRoomId, OwnedUserId, UserId, api::client::push::Pusher, events::TimelineEventType,
+	thirdparty::Medium,
+};
+use serde_json::Value;
+use tuwunel_core::{
+	Err, Result, debug, implement,
+	matrix::{Event, PduEvent},
+	utils::{
+		html::escape as html_escape,
+		stream::{ReadyExt, TryIgnore},
+		time::now_millis,
+	},
+	warn,
+};
+use tuwunel_database::{Deserialized, Interfix};
+
+use crate::rooms::timeline::RawPduId;
+
+/// The `app_id` the specification reserves for email pushers.
+const EMAIL_APP_ID: &str = "m.email";
+
+/// Messages listed per room; the rest are summarised as a count.
+const MESSAGES_PER_ROOM: usize = 5;
+
+/// Characters of a message body quoted in a digest.
+const BODY_MAX_CHARS: usize = 200;
+
+/// One room's section of a digest.
+#[derive(Debug)]
+pub(super) struct DigestRoom {
+	pub(super) name: String,
+	pub(super) messages: Vec<DigestMessage>,
+}
+
+#[derive(Debug)]
+pub(super) struct DigestMessage {
+	pub(super) sender: String,
+	pub(super) body: String,
+}
+
+/// Rejects an email pusher unless this server can send mail and its pushkey is
+/// an address bound to the user.
+#[implement(super::Service)]
+pub(super) async fn check_email_pusher(&self, sender: &UserId, pusher: &Pusher) -> Result {
+	if !self.services.sendmail.is_enabled() {
+		return Err!(Request(InvalidParam(
+			"Email notifications are not enabled on this server."
+		)));
+	}
+
+	if pusher.ids.app_id != EMAIL_APP_ID {
+		return Err!(Request(InvalidParam("Email pushers must use the app ID \"m.email\".")));
+	}
+
+	if !self
+		.email_is_bound(sender, &pusher.ids.pushkey)
+		.await
+	{
+		return Err!(Request(InvalidParam("Email pusher address is not bound to this account.")));
+	}
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+async fn email_is_bound(&self, user_id: &UserId, address: &str) -> bool {
+	let Ok(address) = crate::threepid::canonicalize_email(address) else {
+		return false;
+	};
+
+	self.services
+		.threepid
+		.get_bindings(user_id)
+		.ready_any(|binding| binding.medium == Medium::Email && binding.address == address)
+		.await
+}
+
+/// Queues a notification for the pusher's next digest.
+#[implement(super::Service)]
+pub(super) async fn queue_email_notice<E: Event>(
+	&self,
+	user_id: &UserId,
+	pusher: &Pusher,
+	event: &E,
+) -> Result {
+	let pdu_id = self
+		.services
+		.timeline
+		.get_pdu_id(event.event_id())
+		.await?;
+
+	let count = pdu_id.pdu_count().into_unsigned();
+	let key = (user_id, pusher.ids.pushkey.as_str(), count);
+
+	self.db
+		.senderkeycount_emailpduid
+		.put_raw(key, pdu_id);
+
+	Ok(())
+}
+
+/// Drops every notification queued for the pusher, and when it last mailed.
+#[implement(super::Service)]
+pub(super) async fn clear_email_notices(&self, user_id: &UserId, pushkey: &str) {
+	self.db
+		.senderkey_emailsent
+		.del((user_id, pushkey));
+
+	self.db
+		.senderkeycount_emailpduid
+		.keys_prefix_raw(&(user_id, pushkey, Interfix))
+		.ignore_err()
+		.ready_for_each(|key| {
+			self.db.senderkeycount_emailpduid.remove(key);
+		})
+		.await;
+}
+
+/// Mails a digest to every email pusher whose queue is due.
+#[implement(super::Service)]
+pub async fn send_email_digests(&self) {
+	type Queued = BTreeMap<(OwnedUserId, String), Vec<(u64, RawPduId)>>;
+
+	let queued: Queued = self
+		.db
+		.senderkeycount_emailpduid
+		.stream()
+		.ignore_err()
+		.ready_fold(
+			Queued::new(),
+			|mut queued,
+			 ((user_id, pushkey, count), pdu_id): ((&UserId, &str, u64), RawPduId)| {
+				queued
+					.entry((user_id.to_owned(), pushkey.to_owned()))
+					.or_default()
+					.push((count, pdu_id));
+
+				queued
+			},
+		)
+		.await;
+
+	for ((user_id, pushkey), notices) in queued {
+		if let Err(e) = self
+			.send_email_digest(&user_id, &pushkey, notices)
+			.await
+		{
+			warn!(%user_id, %e, "Failed to send email notification digest");
+		}
+	}
+}
+
+#[implement(super::Service)]
+async fn send_email_digest(
+	&self,
+	user_id: &UserId,
+	pushkey: &str,
+	notices: Vec<(u64, RawPduId)>,
+) -> Result {
+	let config = &self.services.server.config;
+	let now = now_millis();
+
+	let throttled = self
+		.email_last_sent(user_id, pushkey)
+		.await
+		.is_some_and(|sent| {
+			now.saturating_sub(sent)
+				< config
+					.push_email_throttle_secs
+					.saturating_mul(1000)
+		});
+
+	if throttled {
+		return Ok(());
+	}
+
+	if !self.email_is_bound(user_id, pushkey).await {
+		debug!(%user_id, "Email address unbound; removing its pusher");
+		self.delete_pusher(user_id, pushkey).await;
+		return Ok(());
+	}
+
+	let mut unread: BTreeMap<OwnedRoomId, Vec<PduEvent>> = BTreeMap::new();
+	for &(count, pdu_id) in &notices {
+		if let Some(pdu) = self.unread_notice(user_id, count, &pdu_id).await {
+			unread
+				.entry(pdu.room_id().to_owned())
+				.or_default()
+				.push(pdu);
+		} else {
+			self.db
+				.senderkeycount_emailpduid
+				.del((user_id, pushkey, count));
+		}
+	}
+
+	let oldest = unread
+		.values()
+		.flatten()
+		.map(|pdu| u64::from(pdu.origin_server_ts().get()))
+		.min();
+
+	let Some(oldest) = oldest else {
+		return Ok(());
+	};
+
+	if now.saturating_sub(oldest) < config.push_email_delay_secs.saturating_mul(1000) {
+		return Ok(());
+	}
+
+	let mut rooms = Vec::with_capacity(unread.len());
+	for (room_id, pdus) in &unread {
+		rooms.push(self.digest_room(room_id, pdus).await);
+	}
+
+	self.services
+		.sendmail
+		.send_to(
+			pushkey,
+			&digest_subject(&rooms),
+			digest_html(config.server_name.as_str(), &rooms),
+		)
+		.await?;
+
+	for (count, _) in notices {
+		self.db
+			.senderkeycount_emailpduid
+			.del((user_id, pushkey, count));
+	}
+
+	self.record_email_sent(user_id, pushkey, now);
+
+	Ok(())
+}
+
+/// The queued event, unless it is gone, redacted or its room was read since.
+#[implement(super::Service)]
+async fn unread_notice(
+	&self,
+	user_id: &UserId,
+	count: u64,
+	pdu_id: &RawPduId,
+) -> Option<PduEvent> {
+	let pdu = self
+		.services
+		.timeline
+		.get_pdu_from_id(pdu_id)
+		.await
+		.ok()
+		.filter(|pdu| !pdu.is_redacted())?;
+
+	let last_read = self
+		.last_notification_read(user_id, pdu.room_id())
+		.await
+		.unwrap_or(0);
+
+	(count > last_read).then_some(pdu)
+}
+
+#[implement(super::Service)]
+async fn digest_room(&self, room_id: &OwnedRoomId, pdus: &[PduEvent]) -> DigestRoom {
+	let (name, alias) = join(
+		self.services.state_accessor.get_name(room_id),
+		self.services
+			.state_accessor
+			.get_canonical_alias(room_id),
+	)
+	.await;
+
+	let name = name
+		.ok()
+		.or_else(|| alias.ok().map(|alias| alias.to_string()))
+		.unwrap_or_else(|| room_id.to_string());
+
+	let mut messages = Vec::with_capacity(pdus.len());
+	for pdu in pdus {
+		let sender = self
+			.services
+			.profile
+			.displayname(pdu.sender())
+			.await
+			.unwrap_or_else(|_| pdu.sender().to_string());
+
+		messages.push(DigestMessage { sender, body: message_body(pdu) });
+	}
+
+	DigestRoom { name, messages }
+}
+
+/// When the pusher was last mailed a digest, kept across restarts so the
+/// throttle holds through them.
+#[implement(super::Service)]
+async fn email_last_sent(&self, user_id: &UserId, pushkey: &str) -> Option<u64> {
+	self.db
+		.senderkey_emailsent
+		.qry(&(user_id, pushkey))
+		.await
+		.deserialized()
+		.ok()
+}
+
+#[implement(super::Service)]
+fn record_email_sent(&self, user_id: &UserId, pushkey: &str, sent: u64) {
+	self.db
+		.senderkey_emailsent
+		.put((user_id, pushkey), sent);
+}
+
+fn message_body(pdu: &PduEvent) -> String {
+	if *pdu.kind() == TimelineEventType::RoomEncrypted {
+		return "Sent an encrypted message".to_owned();
+	}
+
+	pdu.get_content::<Value>()
+		.ok()
+		.as_ref()
+		.and_then(|content| content.get("body"))
+		.and_then(Value::as_str)
+		.map_or_else(
+			|| format!("Sent a {} event", pdu.kind()),
+			|body| body.chars().take(BODY_MAX_CHARS).collect(),
+		)
+}
+
+pub(super) fn digest_subject(rooms: &[DigestRoom]) -> String {
+	let messages: usize = rooms.iter().map(|room| room.messages.len()).sum();
+	let plural = if messages == 1 { "" } else { "s" };
+
+	match rooms {
+		| [room] => format!("{messages} unread message{plural} in {}", room.name),
+		| _ => format!("{messages} unread message{plural} in {} rooms", rooms.len()),
+	}
+}
+
+pub(super) fn digest_html(server_name: &str, rooms: &[DigestRoom]) -> String {
+	let mut sections = String::new();
+	for room in rooms {
+		let _ = write!(sections, "\n    <h2>{}</h2>\n    <ul>", html_escape(&room.name));
+
+		for message in room.messages.iter().take(MESSAGES_PER_ROOM) {
+			let _ = write!(
+				sections,
+				"\n      <li><b>{}</b>: {}</li>",
+				html_escape(&message.sender),
+				html_escape(&message.body),
+			);
+		}
+
+		let more = room
+			.messages
+			.len()
+			.saturating_sub(MESSAGES_PER_ROOM);
+		if more > 0 {
+			let _ = write!(sections, "\n      <li>and {more} more</li>");
+		}
+
+		sections.push_str("\n    </ul>");
+	}
+
+	let server_name = html_escape(server_name);
+
+	format!(
+		"<!DOCTYPE html>
+<html lang=\"en\">
+  <body>
+    <h1>You have unread messages on {server_name}</h1>{sections}
+    <p>You are receiving this because email notifications are enabled for your account. Remove \
+		 the email pusher in your client to stop them.</p>
+  </body>
+</html>"
+	)
+}
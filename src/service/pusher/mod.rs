@@ -1,5 +1,6 @@
 mod append;
 mod badge;
+mod email;
 mod notification;
 mod request;
 mod send;
@@ -10,11 +11,13 @@
 use std::{
 	collections::BTreeMap,
 	sync::{Arc, LazyLock},
+	time::Duration,
 };
 
+use async_trait::async_trait;
 use futures::{Stream, StreamExt, TryFutureExt, future::join};
 use ruma::{
-	DeviceId, OwnedDeviceId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
+	DeviceId, EventId, OwnedDeviceId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
 	api::client::push::{Pusher, PusherKind, set_pusher::v3::PusherAction},
 	events::{AnySyncTimelineEvent, room::power_levels::RoomPowerLevels},
 	push::{Action, FlattenedJson, PushConditionPowerLevelsCtx, PushConditionRoomCtx, Ruleset},
@@ -59,6 +62,9 @@ pub struct Evaluate<'a, 'b> {
 /// MSC3664 matches a reply as if it carried a relation of this type.
 const IN_REPLY_TO: &str = "m.in_reply_to";
 
+/// How often queued email notifications are checked for a due digest.
+const EMAIL_DIGEST_INTERVAL: Duration = Duration::from_secs(60);
+
 /// Shared by every event that resolves no relations, which is every event
 /// while MSC3664 evaluation is disabled.
 static NO_RELATED_EVENTS: LazyLock<Arc<RelatedEvents>> = LazyLock::new(Arc::default);
@@ -95,7 +101,9 @@ pub struct Service {
 
 struct Data {
 	db: Arc<Database>,
+	senderkey_emailsent: Arc<Map>,
 	senderkey_pusher: Arc<Map>,
+	senderkeycount_emailpduid: Arc<Map>,
 	pushkey_deviceid: Arc<Map>,
 	useridcount_notification: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
@@ -103,6 +111,7 @@ struct Data {
 	roomuserid_lastnotificationread: Arc<Map>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
@@ -111,7 +120,9 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 			highlight_increment_mutex: MutexMap::new(),
 			db: Data {
 				db: args.db.clone(),
+				senderkey_emailsent: args.db["senderkey_emailsent"].clone(),
 				senderkey_pusher: args.db["senderkey_pusher"].clone(),
+				senderkeycount_emailpduid: args.db["senderkeycount_emailpduid"].clone(),
 				pushkey_deviceid: args.db["pushkey_deviceid"].clone(),
 				useridcount_notification: args.db["useridcount_notification"].clone(),
 				userroomid_highlightcount: args.db["userroomid_highlightcount"].clone(),
@@ -124,6 +135,19 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		loop {
+			if self.services.sendmail.is_enabled() {
+				self.send_email_digests().await;
+			}
+
+			tokio::select! {
+				() = tokio::time::sleep(EMAIL_DIGEST_INTERVAL) => {},
+				() = self.services.server.until_shutdown() => return Ok(())
+			};
+		}
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
@@ -139,7 +163,8 @@ pub async fn set_pusher(
 			self.set_pusher_delete(sender, ids.pushkey.as_str())
 				.await,
 		| PusherAction::Post(data) =>
-			self.set_pusher_post(sender, sender_device, pusher, &data.pusher)?,
+			self.set_pusher_post(sender, sender_device, pusher, &data.pusher)
+				.await?,
 	}
 
 	Ok(())
@@ -151,7 +176,7 @@ async fn set_pusher_delete(&self, sender: &UserId, pushkey: &str) {
 }
 
 #[implement(Service)]
-fn set_pusher_post(
+async fn set_pusher_post(
 	&self,
 	sender: &UserId,
 	sender_device: &DeviceId,
@@ -177,6 +202,10 @@ fn set_pusher_post(
 		self.check_http_pusher_url(&url)?;
 	}
 
+	if matches!(pusher.kind, PusherKind::Email(_)) {
+		self.check_email_pusher(sender, pusher).await?;
+	}
+
 	let key = (sender, pushkey);
 	self.db.senderkey_pusher.put(key, Json(action));
 	self.db
@@ -221,6 +250,7 @@ pub async fn delete_pusher(&self, sender: &UserId, pushkey: &str) {
 	self.db.pushkey_deviceid.remove(pushkey);
 	self.clear_suppressed_pushkey(sender, pushkey);
 	self.forget_sent_badge(sender, pushkey);
+	self.clear_email_notices(sender, pushkey).await;
 
 	self.services
 		.sending
@@ -349,6 +379,22 @@ pub async fn get_actions<'a>(
 		user_display_name,
 	);
 
+	// MSC4306: the thread subscription condition asks after the recipient's
+	// subscription to the thread the event is in.
+	let services = self.services.clone();
+	let (subscriber, subscriber_room) = (user.to_owned(), room_id.to_owned());
+	let ctx = ctx.with_has_thread_subscription_fn(move |thread_root: &EventId| {
+		let services = services.clone();
+		let (subscriber, subscriber_room) = (subscriber.clone(), subscriber_room.clone());
+
+		Box::pin(async move {
+			services
+				.threads
+				.is_subscribed(&subscriber, &subscriber_room, thread_root)
+				.await
+		})
+	});
+
 	let ctx = match related_events {
 		| Some(related_events) => ctx.with_related_events(related_events.clone()),
 		| None => ctx,
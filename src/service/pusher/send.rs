@@ -121,13 +121,13 @@ async fn send_notice<Pdu: Event>(
 	tweaks: Vec<Tweak>,
 	event: &Pdu,
 ) -> Result {
-	// TODO: email
 	match &pusher.kind {
 		| PusherKind::Http(http) =>
 			self.send_http_event_notice(user_id, pusher, http, tweaks, event)
 				.await,
-		// TODO: Handle email
-		//PusherKind::Email(_) => Ok(()),
+		| PusherKind::Email(_) =>
+			self.queue_email_notice(user_id, pusher, event)
+				.await,
 		| _ => Ok(()),
 	}
 }
@@ -6,7 +6,10 @@
 	Ignore, IgnoreAll, Interfix, SEP, deserialize_from_slice, serialize_to_vec,
 };
 
-use super::ExtractRelatesTo;
+use super::{
+	ExtractRelatesTo,
+	email::{DigestMessage, DigestRoom, digest_html, digest_subject},
+};
 
 const ROOM: &str = "!room:example.com";
 const USER: &str = "@user:example.com";
@@ -150,3 +153,38 @@ fn unrelated_message_resolves_no_relation() {
 
 	assert!(from_value::<ExtractRelatesTo>(content).is_err(), "content carries no relation");
 }
+
+fn digest_room(name: &str, messages: usize) -> DigestRoom {
+	DigestRoom {
+		name: name.to_owned(),
+		messages: (0..messages)
+			.map(|i| DigestMessage {
+				sender: "Alice".to_owned(),
+				body: format!("message {i}"),
+			})
+			.collect(),
+	}
+}
+
+#[test]
+fn digest_subject_names_a_single_room() {
+	assert_eq!(digest_subject(&[digest_room("Lobby", 1)]), "1 unread message in Lobby");
+	assert_eq!(
+		digest_subject(&[digest_room("Lobby", 2), digest_room("Dev", 1)]),
+		"3 unread messages in 2 rooms"
+	);
+}
+
+#[test]
+fn digest_html_escapes_and_truncates() {
+	let mut room = digest_room("<Lobby>", 7);
+	room.messages[0].body = "a & b".to_owned();
+
+	let html = digest_html("example.com", &[room]);
+
+	assert!(html.contains("<h2>&lt;Lobby&gt;</h2>"));
+	assert!(html.contains("<b>Alice</b>: a &amp; b"));
+	assert!(html.contains("message 4"));
+	assert!(!html.contains("message 5"));
+	assert!(html.contains("<li>and 2 more</li>"));
+}
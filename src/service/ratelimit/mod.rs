@@ -0,0 +1,297 @@
+//! Token-bucket throttling for the client-server API.
+//!
+//! Each endpoint class has its own configured refill rate and burst. Requests
+//! are charged to the authenticated user, or to the client IP where there is
+//! none; an IPv6 client is charged to its /64. Admins may override a user's
+//! rate and burst, or exempt them, and the override applies to every class.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::HashMap,
+	hash::Hash,
+	net::{IpAddr, Ipv6Addr},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use http::StatusCode;
+use ruma::{
+	OwnedUserId, UserId,
+	api::error::{ErrorKind, LimitExceededErrorData, RetryAfter},
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::{MissedTickBehavior, interval};
+use tuwunel_core::{Error, Result, debug, implement};
+use tuwunel_database::{Deserialized, Json, Map};
+
+/// Interval between sweeps dropping fully refilled buckets.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most buckets held between sweeps. Reaching it drops the refilled buckets,
+/// then the longest idle, down to [`EVICT_TO`].
+const CAPACITY: usize = 100_000;
+
+/// Buckets kept by an eviction, leaving room for new keys before the next.
+const EVICT_TO: usize = CAPACITY / 10 * 9;
+
+/// Mask of the prefix an IPv6 client is charged by, as a host is usually given
+/// a whole /64.
+const IPV6_PREFIX_MASK: u128 = u128::MAX << 64;
+
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+	buckets: Mutex<HashMap<(Class, Key), Bucket>>,
+	db: Data,
+}
+
+struct Data {
+	userid_ratelimitoverride: Arc<Map>,
+}
+
+/// Endpoint classes throttled independently of one another.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Class {
+	/// Sending message and state events, redactions and room creation.
+	Message,
+
+	/// Joining and knocking on rooms.
+	Join,
+
+	Invite,
+
+	Login,
+
+	Registration,
+
+	/// Claiming one-time keys.
+	KeyClaim,
+}
+
+/// Whom a request is charged to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Key {
+	User(OwnedUserId),
+
+	/// Built by [`Key::ip`], holding an IPv6 address masked to its prefix.
+	Ip(IpAddr),
+}
+
+/// An admin-set replacement for a user's configured rates.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Override {
+	/// Refill rate in requests per second. A zero rate or burst exempts the
+	/// user.
+	pub per_second: f64,
+
+	pub burst_count: u32,
+}
+
+/// Tokens held by one key in one class, with the rates it was last charged
+/// at.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+	last: Instant,
+	tokens: f64,
+	rate: f64,
+	burst: f64,
+}
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: args.services.clone(),
+			buckets: Mutex::new(HashMap::new()),
+			db: Data {
+				userid_ratelimitoverride: args.db["userid_ratelimitoverride"].clone(),
+			},
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let mut sweep = interval(PRUNE_INTERVAL);
+		sweep.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+		loop {
+			tokio::select! {
+				_ = sweep.tick() => self.prune(),
+				() = self.services.server.until_shutdown() => return Ok(()),
+			}
+		}
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Charges one request of `class` to `key`, failing with `M_LIMIT_EXCEEDED`
+/// and the time until the next token once the bucket is empty.
+#[implement(Service)]
+pub async fn check(&self, class: Class, key: Key) -> Result {
+	let (rate, burst) = match &key {
+		| Key::User(user_id) => match self.get_override(user_id).await {
+			| Some(Override { per_second, burst_count }) => (per_second, burst_count),
+			| None => self.configured(class),
+		},
+		| Key::Ip(_) => self.configured(class),
+	};
+
+	let burst = f64::from(burst);
+	if rate <= 0.0 || burst <= 0.0 {
+		return Ok(());
+	}
+
+	let now = Instant::now();
+	let mut buckets = self.buckets.lock()?;
+	let key = (class, key);
+	if buckets.len() >= CAPACITY && !buckets.contains_key(&key) {
+		evict(&mut buckets, now, EVICT_TO);
+	}
+
+	let bucket = buckets
+		.entry(key)
+		.or_insert(Bucket { last: now, tokens: burst, rate, burst });
+
+	bucket.rate = rate;
+	bucket.burst = burst;
+	take(bucket, now).map_err(|retry_after| {
+		debug!(?class, ?retry_after, "Rate limit exceeded");
+		Error::Request(
+			ErrorKind::LimitExceeded(LimitExceededErrorData {
+				retry_after: Some(RetryAfter::Delay(retry_after)),
+			}),
+			"Too many requests.".into(),
+			StatusCode::TOO_MANY_REQUESTS,
+		)
+	})
+}
+
+/// Replaces the user's configured rates in every class.
+#[implement(Service)]
+pub fn set_override(&self, user_id: &UserId, rates: Override) {
+	self.db
+		.userid_ratelimitoverride
+		.raw_put(user_id, Json(rates));
+
+	self.forget(user_id);
+}
+
+/// Returns the user to the configured rates.
+#[implement(Service)]
+pub fn clear_override(&self, user_id: &UserId) {
+	self.db.userid_ratelimitoverride.remove(user_id);
+
+	self.forget(user_id);
+}
+
+#[implement(Service)]
+pub async fn get_override(&self, user_id: &UserId) -> Option<Override> {
+	self.db
+		.userid_ratelimitoverride
+		.get(user_id)
+		.await
+		.deserialized::<Json<_>>()
+		.map(|Json(rates)| rates)
+		.ok()
+}
+
+/// Drops fully refilled buckets, which equal absent ones, so a source-address
+/// spray cannot grow the table without bound.
+#[implement(Service)]
+fn prune(&self) {
+	if let Ok(mut buckets) = self.buckets.lock() {
+		prune_refilled(&mut buckets, Instant::now());
+	}
+}
+
+/// Drops the user's buckets so new rates take effect at once.
+#[implement(Service)]
+fn forget(&self, user_id: &UserId) {
+	if let Ok(mut buckets) = self.buckets.lock() {
+		buckets.retain(|(_, key), _| !matches!(key, Key::User(user) if user == user_id));
+	}
+}
+
+#[implement(Service)]
+fn configured(&self, class: Class) -> (f64, u32) {
+	let config = &self.services.server.config;
+	match class {
+		| Class::Message => (config.rc_message_per_second, config.rc_message_burst_count),
+		| Class::Join => (config.rc_join_per_second, config.rc_join_burst_count),
+		| Class::Invite => (config.rc_invite_per_second, config.rc_invite_burst_count),
+		| Class::Login => (config.rc_login_per_second, config.rc_login_burst_count),
+		| Class::Registration =>
+			(config.rc_registration_per_second, config.rc_registration_burst_count),
+		| Class::KeyClaim => (config.rc_key_claim_per_second, config.rc_key_claim_burst_count),
+	}
+}
+
+impl Key {
+	/// Charges a client by its address, an IPv6 one by its prefix. IPv4 mapped
+	/// into IPv6 is charged as IPv4.
+	#[must_use]
+	pub fn ip(addr: IpAddr) -> Self {
+		let addr = match addr {
+			| IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+				| Some(v4) => IpAddr::V4(v4),
+				| None => IpAddr::V6(Ipv6Addr::from_bits(v6.to_bits() & IPV6_PREFIX_MASK)),
+			},
+			| v4 => v4,
+		};
+
+		Self::Ip(addr)
+	}
+}
+
+/// Takes a token from the bucket, or returns how long until one is available.
+fn take(bucket: &mut Bucket, now: Instant) -> Result<(), Duration> {
+	let tokens = refill(bucket, now);
+	if tokens < 1.0 {
+		return Err(Duration::from_secs_f64((1.0 - tokens) / bucket.rate));
+	}
+
+	bucket.last = now;
+	bucket.tokens = tokens - 1.0;
+
+	Ok(())
+}
+
+fn prune_refilled<K>(buckets: &mut HashMap<K, Bucket>, now: Instant) {
+	buckets.retain(|_, bucket| refill(bucket, now) < bucket.burst);
+}
+
+/// Drops the refilled buckets, then the longest idle, until at most `keep`
+/// remain.
+fn evict<K>(buckets: &mut HashMap<K, Bucket>, now: Instant, keep: usize)
+where
+	K: Clone + Eq + Hash,
+{
+	prune_refilled(buckets, now);
+
+	let excess = buckets.len().saturating_sub(keep);
+	if excess == 0 {
+		return;
+	}
+
+	let mut idle: Vec<(Instant, K)> = buckets
+		.iter()
+		.map(|(key, bucket)| (bucket.last, key.clone()))
+		.collect();
+
+	idle.select_nth_unstable_by_key(excess.saturating_sub(1), |(last, _)| *last);
+	idle.truncate(excess);
+
+	debug!(evicted = excess, "Rate limit buckets over capacity");
+	for (_, key) in idle {
+		buckets.remove(&key);
+	}
+}
+
+fn refill(bucket: &Bucket, now: Instant) -> f64 {
+	now.duration_since(bucket.last)
+		.as_secs_f64()
+		.mul_add(bucket.rate, bucket.tokens)
+		.min(bucket.burst)
+}
@@ -0,0 +1,121 @@
+use std::{
+	collections::HashMap,
+	net::IpAddr,
+	time::{Duration, Instant},
+};
+
+use super::{Bucket, Key, evict, prune_refilled, take};
+
+fn bucket(last: Instant, tokens: f64, rate: f64, burst: f64) -> Bucket {
+	Bucket { last, tokens, rate, burst }
+}
+
+#[test]
+fn burst_then_refill() {
+	let now = Instant::now();
+	let mut bucket = bucket(now, 2.0, 0.5, 2.0);
+
+	take(&mut bucket, now).expect("first request should be accepted");
+	take(&mut bucket, now).expect("burst request should be accepted");
+
+	let retry_after = take(&mut bucket, now).expect_err("bucket should be empty");
+	assert_eq!(retry_after, Duration::from_secs(2));
+
+	take(&mut bucket, now + Duration::from_secs(2)).expect("refilled request should be accepted");
+}
+
+#[test]
+fn retry_after_accounts_for_partial_tokens() {
+	let now = Instant::now();
+	let mut bucket = bucket(now, 0.0, 1.0, 5.0);
+
+	let later = now + Duration::from_millis(500);
+	let retry_after = take(&mut bucket, later).expect_err("bucket should be empty");
+
+	assert_eq!(retry_after, Duration::from_millis(500));
+}
+
+#[test]
+fn refill_is_capped_at_burst() {
+	let now = Instant::now();
+	let mut bucket = bucket(now, 0.0, 1.0, 3.0);
+	let later = now + Duration::from_secs(3600);
+
+	for _ in 0..3 {
+		take(&mut bucket, later).expect("burst request should be accepted");
+	}
+
+	assert!(take(&mut bucket, later).is_err());
+}
+
+#[test]
+fn prune_uses_each_buckets_own_rates() {
+	let now = Instant::now();
+	let later = now + Duration::from_secs(10);
+	let mut buckets = HashMap::from([
+		// Refills one token per second; full again after ten.
+		("fast", bucket(now, 0.0, 1.0, 5.0)),
+		// Refills one token per minute; still short after ten seconds.
+		("slow", bucket(now, 0.0, 1.0 / 60.0, 5.0)),
+	]);
+
+	prune_refilled(&mut buckets, later);
+
+	assert!(!buckets.contains_key("fast"), "a refilled bucket should be dropped");
+	assert!(buckets.contains_key("slow"), "a draining bucket should be kept");
+}
+
+#[test]
+fn eviction_drops_refilled_then_longest_idle() {
+	let now = Instant::now();
+	let later = now + Duration::from_secs(10);
+	let mut buckets = HashMap::from([
+		("refilled", bucket(now, 0.0, 1.0, 5.0)),
+		("oldest", bucket(now, 0.0, 0.01, 5.0)),
+		("older", bucket(now + Duration::from_secs(1), 0.0, 0.01, 5.0)),
+		("newest", bucket(now + Duration::from_secs(2), 0.0, 0.01, 5.0)),
+	]);
+
+	evict(&mut buckets, later, 2);
+
+	let mut kept: Vec<_> = buckets.into_keys().collect();
+	kept.sort_unstable();
+	assert_eq!(kept, ["newest", "older"]);
+}
+
+#[test]
+fn eviction_keeps_buckets_under_the_cap() {
+	let now = Instant::now();
+	let mut buckets = HashMap::from([("draining", bucket(now, 0.0, 0.01, 5.0))]);
+
+	evict(&mut buckets, now, 2);
+
+	assert!(buckets.contains_key("draining"));
+}
+
+#[test]
+fn ipv6_clients_share_their_prefix() {
+	let ip = |addr: &str| {
+		Key::ip(
+			addr.parse::<IpAddr>()
+				.expect("address should parse"),
+		)
+	};
+
+	assert_eq!(ip("2001:db8:1:2:aaaa::1"), ip("2001:db8:1:2:bbbb::2"));
+	assert_ne!(ip("2001:db8:1:2::1"), ip("2001:db8:1:3::1"));
+	assert_eq!(ip("2001:db8:1:2:aaaa::1"), Key::Ip("2001:db8:1:2::".parse().unwrap()));
+}
+
+#[test]
+fn ipv4_clients_are_charged_by_address() {
+	let ip = |addr: &str| {
+		Key::ip(
+			addr.parse::<IpAddr>()
+				.expect("address should parse"),
+		)
+	};
+
+	assert_ne!(ip("192.0.2.1"), ip("192.0.2.2"));
+	assert_eq!(ip("::ffff:192.0.2.1"), ip("192.0.2.1"));
+}
@@ -0,0 +1,191 @@
+//! Abuse reports submitted by users.
+//!
+//! Event, room and user reports are stored under an increasing id and stay
+//! open until a server admin resolves or deletes them. Each is also announced
+//! in the report room as it arrives.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+use ruma::{OwnedEventId, OwnedRoomId, OwnedUserId, UserId};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	Err, Result, implement,
+	utils::{ReadyExt, stream::TryIgnore, time::now_millis},
+};
+use tuwunel_database::{Deserialized, Json, Map};
+
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+	db: Data,
+}
+
+struct Data {
+	reportid_report: Arc<Map>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Report {
+	pub kind: Kind,
+
+	/// The user who filed the report.
+	pub reporter: OwnedUserId,
+
+	pub room_id: Option<OwnedRoomId>,
+
+	pub event_id: Option<OwnedEventId>,
+
+	/// The reported user, or the sender of the reported event.
+	pub user_id: Option<OwnedUserId>,
+
+	pub reason: Option<String>,
+
+	pub received_ts: u64,
+
+	pub resolved: Option<Resolution>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+	Event,
+	Room,
+	User,
+}
+
+/// The admin who closed a report, and when.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Resolution {
+	pub by: OwnedUserId,
+	pub ts: u64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum State {
+	Open,
+	Resolved,
+}
+
+/// Criteria a listed report must meet; unset fields match every report.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+	pub kind: Option<Kind>,
+	pub room_id: Option<OwnedRoomId>,
+	pub reporter: Option<OwnedUserId>,
+	pub user_id: Option<OwnedUserId>,
+	pub state: Option<State>,
+}
+
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: args.services.clone(),
+			db: Data {
+				reportid_report: args.db["reportid_report"].clone(),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Stores a new report, returning its id.
+#[implement(Service)]
+pub fn add(&self, report: &Report) -> u64 {
+	let id = *self.services.globals.next_count();
+
+	self.db.reportid_report.put(id, Json(report));
+
+	id
+}
+
+#[implement(Service)]
+pub async fn get(&self, id: u64) -> Result<Report> {
+	self.db
+		.reportid_report
+		.qry(&id)
+		.await
+		.deserialized::<Json<_>>()
+		.map(|Json(report)| report)
+}
+
+/// Reports matching `filter`, oldest first, or newest first when `rev`.
+#[implement(Service)]
+pub fn reports<'a>(
+	&'a self,
+	filter: &'a Filter,
+	rev: bool,
+) -> impl Stream<Item = (u64, Report)> + Send + 'a {
+	let reports = if rev {
+		self.db.reportid_report.rev_stream().boxed()
+	} else {
+		self.db.reportid_report.stream().boxed()
+	};
+
+	reports
+		.ignore_err()
+		.ready_filter_map(|(id, Json(report)): (u64, Json<Report>)| {
+			filter.matches(&report).then_some((id, report))
+		})
+}
+
+/// Closes the report on behalf of the admin `by`.
+#[implement(Service)]
+pub async fn resolve(&self, id: u64, by: &UserId) -> Result {
+	let mut report = self.get(id).await?;
+	if report.resolved.is_some() {
+		return Err!(Request(InvalidParam("Report {id} is already resolved.")));
+	}
+
+	report.resolved = Some(Resolution { by: by.to_owned(), ts: now_millis() });
+	self.db.reportid_report.put(id, Json(&report));
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub async fn delete(&self, id: u64) -> Result {
+	if self.db.reportid_report.qry(&id).await.is_err() {
+		return Err!(Request(NotFound("Report {id} not found.")));
+	}
+
+	self.db.reportid_report.del(id);
+
+	Ok(())
+}
+
+impl Report {
+	#[must_use]
+	pub fn state(&self) -> State {
+		if self.resolved.is_some() {
+			State::Resolved
+		} else {
+			State::Open
+		}
+	}
+}
+
+impl Filter {
+	#[must_use]
+	pub fn matches(&self, report: &Report) -> bool {
+		self.kind.is_none_or(|kind| report.kind == kind)
+			&& self
+				.room_id
+				.as_ref()
+				.is_none_or(|room_id| report.room_id.as_ref() == Some(room_id))
+			&& self
+				.reporter
+				.as_ref()
+				.is_none_or(|reporter| report.reporter == *reporter)
+			&& self
+				.user_id
+				.as_ref()
+				.is_none_or(|user_id| report.user_id.as_ref() == Some(user_id))
+			&& self
+				.state
+				.is_none_or(|state| report.state() == state)
+	}
+}
@@ -0,0 +1,65 @@
+use ruma::{owned_event_id, owned_room_id, owned_user_id};
+
+use super::{Filter, Kind, Report, Resolution, State};
+
+fn event_report() -> Report {
+	Report {
+		kind: Kind::Event,
+		reporter: owned_user_id!("@alice:example.com"),
+		room_id: Some(owned_room_id!("!room:example.com")),
+		event_id: Some(owned_event_id!("$event:example.com")),
+		user_id: Some(owned_user_id!("@mallory:example.com")),
+		reason: Some("spam".to_owned()),
+		received_ts: 1000,
+		resolved: None,
+	}
+}
+
+#[test]
+fn empty_filter_matches_everything() {
+	assert!(Filter::default().matches(&event_report()));
+}
+
+#[test]
+fn filter_requires_every_set_field() {
+	let report = event_report();
+	let filter = Filter {
+		kind: Some(Kind::Event),
+		room_id: Some(owned_room_id!("!room:example.com")),
+		reporter: Some(owned_user_id!("@alice:example.com")),
+		..Filter::default()
+	};
+	assert!(filter.matches(&report));
+
+	let other_room = Filter {
+		room_id: Some(owned_room_id!("!other:example.com")),
+		..filter.clone()
+	};
+	assert!(!other_room.matches(&report));
+
+	let other_kind = Filter { kind: Some(Kind::User), ..filter };
+	assert!(!other_kind.matches(&report));
+}
+
+#[test]
+fn filter_by_state_follows_resolution() {
+	let open = Filter {
+		state: Some(State::Open),
+		..Filter::default()
+	};
+	let resolved = Filter {
+		state: Some(State::Resolved),
+		..Filter::default()
+	};
+
+	let mut report = event_report();
+	assert!(open.matches(&report));
+	assert!(!resolved.matches(&report));
+
+	report.resolved = Some(Resolution {
+		by: owned_user_id!("@admin:example.com"),
+		ts: 2000,
+	});
+	assert!(!open.matches(&report));
+	assert!(resolved.matches(&report));
+}
@@ -0,0 +1,259 @@
+use std::{borrow::Borrow, collections::HashSet, path::Path};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as b64};
+use futures::{StreamExt, TryStreamExt};
+use ruma::{EventId, MilliSecondsSinceUnixEpoch, Mxc, OwnedEventId, RoomId, RoomVersionId};
+use serde_json::value::RawValue as RawJsonValue;
+use tokio::{
+	fs::{File, OpenOptions, hard_link, remove_file, try_exists},
+	io::{AsyncWrite, AsyncWriteExt, BufWriter},
+};
+use tuwunel_core::{Err, Result, at, implement, info, warn};
+
+use super::{Exported, FORMAT, Header, MediaFile, Record, Snapshot};
+
+/// Writes the room to a new archive at `path`, with the room's local media
+/// when `media` is set. An existing file is not overwritten. The archive is
+/// written beside `path` and only appears there once complete.
+#[implement(super::Service)]
+pub async fn export(&self, room_id: &RoomId, path: &Path, media: bool) -> Result<Exported> {
+	if !self.services.metadata.exists(room_id).await {
+		return Err!(Request(NotFound("Room {room_id} is not known to this server.")));
+	}
+
+	let Some(name) = path.file_name() else {
+		return Err!(Request(InvalidParam("{path:?} does not name a file.")));
+	};
+
+	if try_exists(path).await? {
+		return Err!(Request(InvalidParam("{path:?} already exists.")));
+	}
+
+	let mut partial = name.to_owned();
+	partial.push(".partial");
+	let partial = path.with_file_name(partial);
+
+	let file = OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(&partial)
+		.await?;
+
+	let written = match self.write_archive(room_id, file, media).await {
+		// Linking fails rather than replace a file which appeared meanwhile.
+		| Ok(exported) => hard_link(&partial, path)
+			.await
+			.map(|()| exported)
+			.map_err(Into::into),
+		| Err(e) => Err(e),
+	};
+
+	if let Err(e) = remove_file(&partial).await {
+		warn!(?partial, "Could not remove the partial archive: {e}");
+	}
+
+	let exported = written?;
+
+	info!(%room_id, ?path, ?exported, "Exported room");
+
+	Ok(exported)
+}
+
+#[implement(super::Service)]
+async fn write_archive(&self, room_id: &RoomId, file: File, media: bool) -> Result<Exported> {
+	let room_version = self
+		.services
+		.state
+		.get_room_version(room_id)
+		.await?;
+
+	let mut out = BufWriter::new(file);
+	let mut exported = Exported::default();
+
+	write_record(
+		&mut out,
+		&Record::Header(Header {
+			format: FORMAT,
+			room_id: room_id.to_owned(),
+			room_version: room_version.clone(),
+			origin: self.services.globals.server_name().to_owned(),
+			exported_at: MilliSecondsSinceUnixEpoch::now(),
+		}),
+	)
+	.await?;
+
+	let timeline = self.timeline_ids(room_id).await?;
+	let snapshots = self.snapshots(room_id).await;
+
+	let mut outliers: HashSet<OwnedEventId> = self
+		.services
+		.auth_chain
+		.event_ids_iter(room_id, &room_version, timeline.iter().map(Borrow::borrow))
+		.try_collect()
+		.await?;
+
+	outliers.extend(
+		snapshots
+			.iter()
+			.flat_map(|snapshot| snapshot.state.iter().cloned()),
+	);
+
+	for event_id in &timeline {
+		outliers.remove(event_id);
+	}
+
+	// Auth and state events come first, so an import holds them before the
+	// timeline events which refer to them.
+	for event_id in &outliers {
+		let pdu = self
+			.federation_pdu(event_id, &room_version)
+			.await?;
+		write_record(&mut out, &Record::Outlier(pdu)).await?;
+		exported.outliers = exported.outliers.saturating_add(1);
+	}
+
+	for event_id in &timeline {
+		let pdu = self
+			.federation_pdu(event_id, &room_version)
+			.await?;
+		write_record(&mut out, &Record::Pdu(pdu)).await?;
+		exported.pdus = exported.pdus.saturating_add(1);
+	}
+
+	for snapshot in snapshots {
+		write_record(&mut out, &Record::State(snapshot)).await?;
+		exported.snapshots = exported.snapshots.saturating_add(1);
+	}
+
+	if media {
+		self.export_media(&mut out, room_id, &mut exported)
+			.await?;
+	}
+
+	out.shutdown().await?;
+
+	Ok(exported)
+}
+
+/// The room's timeline, oldest first, backfilled history included.
+#[implement(super::Service)]
+async fn timeline_ids(&self, room_id: &RoomId) -> Result<Vec<OwnedEventId>> {
+	self.services
+		.timeline
+		.pdus(None, room_id, None)
+		.map_ok(|(_, pdu)| pdu.event_id)
+		.try_collect()
+		.await
+}
+
+/// The state before each of the room's forward extremities.
+#[implement(super::Service)]
+async fn snapshots(&self, room_id: &RoomId) -> Vec<Snapshot> {
+	let extremities: Vec<OwnedEventId> = self
+		.services
+		.state
+		.get_forward_extremities(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut snapshots = Vec::with_capacity(extremities.len());
+	for event_id in extremities {
+		let Ok(shortstatehash) = self
+			.services
+			.state
+			.pdu_shortstatehash(&event_id)
+			.await
+		else {
+			warn!(%room_id, %event_id, "No state for forward extremity");
+			continue;
+		};
+
+		let state = self
+			.services
+			.state_accessor
+			.state_full_ids(shortstatehash)
+			.map(at!(1))
+			.collect()
+			.await;
+
+		snapshots.push(Snapshot { event_id, state });
+	}
+
+	snapshots
+}
+
+#[implement(super::Service)]
+async fn federation_pdu(
+	&self,
+	event_id: &EventId,
+	room_version: &RoomVersionId,
+) -> Result<Box<RawJsonValue>> {
+	let pdu_json = self
+		.services
+		.timeline
+		.get_pdu_json(event_id)
+		.await?;
+
+	Ok(self
+		.services
+		.federation
+		.format_pdu_into(pdu_json, Some(room_version))
+		.await)
+}
+
+/// The files of the media this server holds which the room's events refer
+/// to. Remote media is left to be fetched again from its origin.
+#[implement(super::Service)]
+async fn export_media<W>(&self, out: &mut W, room_id: &RoomId, exported: &mut Exported) -> Result
+where
+	W: AsyncWrite + Send + Unpin,
+{
+	let (mut local, _) = self.services.media.room_media(room_id).await;
+	local.sort_unstable();
+	local.dedup();
+
+	for mxc in local {
+		let Ok(parts) = Mxc::try_from(mxc.as_str()) else {
+			continue;
+		};
+
+		let file = match self.services.media.get_stored(&parts).await {
+			| Ok(file) => file,
+			| Err(e) => {
+				warn!(%mxc, "Could not export media: {e}");
+				exported.media_failed = exported.media_failed.saturating_add(1);
+				continue;
+			},
+		};
+
+		write_record(
+			out,
+			&Record::Media(MediaFile {
+				mxc,
+				content_type: file.content_type,
+				content_disposition: file
+					.content_disposition
+					.as_ref()
+					.map(ToString::to_string),
+				content: b64.encode(&file.content),
+			}),
+		)
+		.await?;
+
+		exported.media = exported.media.saturating_add(1);
+	}
+
+	Ok(())
+}
+
+async fn write_record<W>(out: &mut W, record: &Record) -> Result
+where
+	W: AsyncWrite + Send + Unpin,
+{
+	let mut line = serde_json::to_vec(record)?;
+	line.push(b'\n');
+	out.write_all(&line).await?;
+
+	Ok(())
+}
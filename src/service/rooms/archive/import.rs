@@ -0,0 +1,385 @@
+use std::{
+	borrow::Borrow,
+	collections::{HashMap, HashSet},
+	io::SeekFrom,
+	iter::once,
+	path::Path,
+};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as b64};
+use futures::FutureExt;
+use ruma::{
+	Mxc, OwnedEventId, RoomId, RoomVersionId, ServerName, room_version_rules::RoomVersionRules,
+};
+use serde_json::value::RawValue as RawJsonValue;
+use tokio::{
+	fs::File,
+	io::{AsyncBufReadExt, AsyncSeekExt, BufReader},
+};
+use tuwunel_core::{
+	Err, Result, err, implement, info,
+	matrix::{event::gen_event_id_canonical_json, room_version},
+	pdu::Pdu,
+	warn,
+};
+
+use super::{FORMAT, Header, Imported, MediaFile, Record, Snapshot};
+use crate::rooms::{state::RoomMutexGuard, state_res};
+
+/// An archive as read from its file. Media is left in the file, where each
+/// record starts at the recorded offset, and read one file at a time.
+#[derive(Default)]
+pub(super) struct Archive {
+	pub(super) header: Option<Header>,
+	pub(super) outliers: Vec<Box<RawJsonValue>>,
+	pub(super) pdus: Vec<Box<RawJsonValue>>,
+	pub(super) snapshots: Vec<Snapshot>,
+	pub(super) media: Vec<u64>,
+}
+
+/// Loads an archive as a room this server does not know yet. Auth chain and
+/// state events are stored as outliers. The room starts at the latest timeline
+/// event with a state snapshot, appended onto the state before it. The older
+/// timeline is backfilled behind it and the newer appended after it, both
+/// through the event handler.
+#[implement(super::Service)]
+pub async fn import(&self, path: &Path) -> Result<Imported> {
+	let mut archive = read_archive(path).await?;
+	let Some(Header {
+		format, room_id, room_version, origin, ..
+	}) = archive.header.take()
+	else {
+		return Err!(Request(InvalidParam("The archive has no header.")));
+	};
+
+	if format != FORMAT {
+		return Err!(Request(InvalidParam(
+			"Archive format {format} is not supported; expected {FORMAT}."
+		)));
+	}
+
+	if !self
+		.services
+		.config
+		.supported_room_version(&room_version)
+	{
+		return Err!(Request(UnsupportedRoomVersion(
+			"Room version {room_version} is not supported by this server."
+		)));
+	}
+
+	if self.services.metadata.exists(&room_id).await {
+		return Err!(Request(InvalidParam(
+			"Room {room_id} already exists on this server; delete it before importing."
+		)));
+	}
+
+	let rules = room_version::rules(&room_version)?;
+	let (seed, seed_state) = seed(&archive, &room_version)?;
+
+	self.services
+		.server_keys
+		.acquire_events_pubkeys(archive.outliers.iter().chain(archive.pdus.iter()))
+		.await;
+
+	self.services
+		.membership
+		.ingest_send_join_auth_chain(&room_id, &room_version, &rules, &archive.outliers)
+		.await;
+
+	self.services
+		.short
+		.get_or_create_shortroomid(&room_id)
+		.await;
+
+	let state_lock = self.services.state.mutex.lock(&room_id).await;
+	let state = self
+		.services
+		.membership
+		.ingest_send_join_state(&room_id, &room_version, &rules, &seed_state)
+		.await;
+
+	self.append_seed(&room_id, &room_version, &rules, &archive.pdus[seed], &state, &state_lock)
+		.await?;
+
+	drop(state_lock);
+
+	let mut imported = Imported {
+		room_id,
+		outliers: archive.outliers.len(),
+		pdus: 1,
+		pdus_failed: 0,
+		media: 0,
+		media_failed: 0,
+	};
+
+	let mut pdus = archive.pdus.into_iter();
+	let older: Vec<_> = pdus.by_ref().take(seed).collect();
+	let newer = pdus.skip(1);
+
+	// Each backfilled event is placed before those already in the timeline, so
+	// the older timeline goes in newest first.
+	for pdu in older.into_iter().rev() {
+		let backfilled = self
+			.services
+			.timeline
+			.backfill_pdu(&imported.room_id, &origin, pdu)
+			.boxed()
+			.await;
+
+		imported.count_pdu(backfilled);
+	}
+
+	for pdu in newer {
+		let appended = self
+			.append_newer(&imported.room_id, &origin, &pdu)
+			.await;
+
+		imported.count_pdu(appended);
+	}
+
+	let mut reader = BufReader::new(File::open(path).await?);
+	for offset in archive.media {
+		let file = match read_media(&mut reader, offset).await {
+			| Ok(file) => file,
+			| Err(e) => {
+				warn!(offset, "Could not read media: {e}");
+				imported.media_failed = imported.media_failed.saturating_add(1);
+				continue;
+			},
+		};
+
+		match self.import_media(&file).await {
+			| Ok(()) => imported.media = imported.media.saturating_add(1),
+			| Err(e) => {
+				warn!(mxc = %file.mxc, "Could not import media: {e}");
+				imported.media_failed = imported.media_failed.saturating_add(1);
+			},
+		}
+	}
+
+	info!(?path, ?imported, "Imported room");
+
+	Ok(imported)
+}
+
+/// Sets the room's state, checks the seed event against it and appends it,
+/// which makes the room known to this server.
+#[implement(super::Service)]
+async fn append_seed(
+	&self,
+	room_id: &RoomId,
+	room_version: &RoomVersionId,
+	rules: &RoomVersionRules,
+	pdu: &RawJsonValue,
+	state: &HashMap<u64, OwnedEventId>,
+	state_lock: &RoomMutexGuard,
+) -> Result {
+	let (event_id, value) = self
+		.services
+		.server_keys
+		.validate_and_add_event_id_no_fetch(pdu, room_version)
+		.await?;
+
+	let (pdu, value) = Pdu::from_object_federation(room_id, &event_id, value, rules)?;
+
+	state_res::auth_check(
+		rules,
+		&pdu,
+		&async |event_id| self.services.timeline.get_pdu(&event_id).await,
+		&async |event_type, state_key| {
+			let shortstatekey = self
+				.services
+				.short
+				.get_shortstatekey(&event_type, state_key.as_str())
+				.await?;
+
+			let event_id = state.get(&shortstatekey).ok_or_else(|| {
+				err!(Request(NotFound("Missing archived state {shortstatekey:?}")))
+			})?;
+
+			self.services.timeline.get_pdu(event_id).await
+		},
+	)
+	.boxed()
+	.await?;
+
+	self.services
+		.membership
+		.apply_send_join_state(room_id, state, state_lock)
+		.await?;
+
+	let shortstatehash = self.services.state.append_to_state(&pdu).await?;
+
+	self.services
+		.timeline
+		.append_pdu(&pdu, value, once(pdu.event_id.borrow()), state_lock)
+		.await?;
+
+	self.services
+		.state
+		.set_room_state(room_id, shortstatehash, state_lock);
+
+	Ok(())
+}
+
+/// Appends an event newer than the seed to the timeline, checked as an event
+/// received over federation.
+#[implement(super::Service)]
+async fn append_newer(
+	&self,
+	room_id: &RoomId,
+	origin: &ServerName,
+	pdu: &RawJsonValue,
+) -> Result {
+	let (_, event_id, value) = self
+		.services
+		.event_handler
+		.parse_incoming_pdu(pdu)
+		.await?;
+
+	let _lock = self
+		.services
+		.event_handler
+		.mutex_federation
+		.lock(room_id)
+		.await;
+
+	self.services
+		.event_handler
+		.handle_incoming_pdu(origin, room_id, &event_id, value, true)
+		.boxed()
+		.await?;
+
+	Ok(())
+}
+
+/// Stores an archived file under its MXC. Media of another server is
+/// protected, as its origin may no longer serve it once evicted from the
+/// remote media cache.
+#[implement(super::Service)]
+async fn import_media(&self, file: &MediaFile) -> Result {
+	let mxc: Mxc<'_> = file.mxc.as_str().try_into()?;
+	let content = b64
+		.decode(&file.content)
+		.map_err(|e| err!(Request(InvalidParam("Media content is not base64: {e}"))))?;
+
+	let content_disposition = file
+		.content_disposition
+		.as_deref()
+		.and_then(|disposition| disposition.parse().ok());
+
+	self.services
+		.media
+		.create(&mxc, None, content_disposition.as_ref(), file.content_type.as_deref(), &content)
+		.await?;
+
+	if !self
+		.services
+		.globals
+		.server_is_ours(mxc.server_name)
+	{
+		self.services
+			.media
+			.set_protected(&mxc, true)
+			.await;
+	}
+
+	Ok(())
+}
+
+/// The index of the latest timeline event with a state snapshot, and the
+/// events of that state.
+pub(super) fn seed(
+	archive: &Archive,
+	room_version: &RoomVersionId,
+) -> Result<(usize, Vec<Box<RawJsonValue>>)> {
+	let snapshots: HashMap<_, _> = archive
+		.snapshots
+		.iter()
+		.map(|snapshot| (&snapshot.event_id, &snapshot.state))
+		.collect();
+
+	let seed = archive
+		.pdus
+		.iter()
+		.enumerate()
+		.rev()
+		.find_map(|(i, pdu)| {
+			let (event_id, _) = gen_event_id_canonical_json(pdu, room_version).ok()?;
+			snapshots.get(&event_id).map(|state| (i, *state))
+		});
+
+	let Some((seed, state)) = seed else {
+		return Err!(Request(InvalidParam(
+			"The archive holds no timeline event with a state snapshot."
+		)));
+	};
+
+	let state: HashSet<&OwnedEventId> = state.iter().collect();
+	let state_pdus = archive
+		.outliers
+		.iter()
+		.chain(archive.pdus.iter())
+		.filter(|pdu| {
+			gen_event_id_canonical_json(pdu, room_version)
+				.is_ok_and(|(event_id, _)| state.contains(&event_id))
+		})
+		.cloned()
+		.collect();
+
+	Ok((seed, state_pdus))
+}
+
+async fn read_archive(path: &Path) -> Result<Archive> {
+	let mut reader = BufReader::new(File::open(path).await?);
+	let mut archive = Archive::default();
+	let mut line = String::new();
+	let mut offset: u64 = 0;
+	let mut number: usize = 0;
+
+	loop {
+		line.clear();
+		let read = reader.read_line(&mut line).await?;
+		if read == 0 {
+			break;
+		}
+
+		let start = offset;
+		offset = offset.saturating_add(read.try_into()?);
+		number = number.saturating_add(1);
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		let record = serde_json::from_str(&line).map_err(|e| {
+			err!(Request(InvalidParam("Line {number} of the archive is invalid: {e}")))
+		})?;
+
+		match record {
+			| Record::Header(header) =>
+				if archive.header.replace(header).is_some() {
+					return Err!(Request(InvalidParam("The archive has more than one header.")));
+				},
+			| Record::Outlier(pdu) => archive.outliers.push(pdu),
+			| Record::Pdu(pdu) => archive.pdus.push(pdu),
+			| Record::State(snapshot) => archive.snapshots.push(snapshot),
+			| Record::Media(_) => archive.media.push(start),
+		}
+	}
+
+	Ok(archive)
+}
+
+/// Reads the media record starting at `offset` of the archive.
+async fn read_media(reader: &mut BufReader<File>, offset: u64) -> Result<MediaFile> {
+	reader.seek(SeekFrom::Start(offset)).await?;
+
+	let mut line = String::new();
+	reader.read_line(&mut line).await?;
+
+	match serde_json::from_str(&line)? {
+		| Record::Media(media) => Ok(media),
+		| _ => Err!(Request(InvalidParam("No media record at offset {offset} of the archive."))),
+	}
+}
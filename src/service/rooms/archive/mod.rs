@@ -0,0 +1,117 @@
+//! Room Archives
+//!
+//! Exports a room to a self-contained file and loads it into another server.
+//! An archive is JSON Lines: a header, then the room's auth chain and state
+//! events outside the timeline, the timeline oldest first, the state before
+//! each forward extremity, and optionally the room's local media. Events are
+//! kept in their federation format, signatures intact, so an import checks
+//! them as it would events received over federation. Imported media of another
+//! server is protected from remote media eviction.
+
+mod export;
+mod import;
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
+
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedMxcUri, OwnedRoomId, OwnedServerName,
+	RoomVersionId,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue as RawJsonValue;
+use tuwunel_core::{Result, warn};
+
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+}
+
+/// What an export wrote.
+#[derive(Debug, Default)]
+pub struct Exported {
+	pub pdus: usize,
+	pub outliers: usize,
+	pub snapshots: usize,
+	pub media: usize,
+	pub media_failed: usize,
+}
+
+/// What an import loaded.
+#[derive(Debug)]
+pub struct Imported {
+	pub room_id: OwnedRoomId,
+	pub outliers: usize,
+	pub pdus: usize,
+	pub pdus_failed: usize,
+	pub media: usize,
+	pub media_failed: usize,
+}
+
+impl Imported {
+	fn count_pdu(&mut self, result: Result) {
+		match result {
+			| Ok(()) => self.pdus = self.pdus.saturating_add(1),
+			| Err(e) => {
+				warn!(room_id = %self.room_id, "Could not import event: {e}");
+				self.pdus_failed = self.pdus_failed.saturating_add(1);
+			},
+		}
+	}
+}
+
+/// Version of the archive layout, bumped on incompatible changes.
+const FORMAT: u64 = 1;
+
+/// One line of an archive.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+	Header(Header),
+
+	/// An auth chain or state event which is not in the timeline.
+	Outlier(Box<RawJsonValue>),
+
+	/// A timeline event.
+	Pdu(Box<RawJsonValue>),
+
+	/// The state before a forward extremity.
+	State(Snapshot),
+
+	Media(MediaFile),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Header {
+	format: u64,
+	room_id: OwnedRoomId,
+	room_version: RoomVersionId,
+
+	/// The server the archive was exported from.
+	origin: OwnedServerName,
+	exported_at: MilliSecondsSinceUnixEpoch,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Snapshot {
+	event_id: OwnedEventId,
+	state: Vec<OwnedEventId>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct MediaFile {
+	mxc: OwnedMxcUri,
+	content_type: Option<String>,
+	content_disposition: Option<String>,
+
+	/// The file, base64 encoded.
+	content: String,
+}
+
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self { services: args.services.clone() }))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
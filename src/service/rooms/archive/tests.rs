@@ -0,0 +1,132 @@
+use ruma::{OwnedEventId, RoomVersionId};
+use serde_json::{
+	json,
+	value::{RawValue as RawJsonValue, to_raw_value},
+};
+use tuwunel_core::matrix::event::gen_event_id_canonical_json;
+
+use super::{
+	Record, Snapshot,
+	import::{Archive, seed},
+};
+
+const ROOM_VERSION: RoomVersionId = RoomVersionId::V11;
+
+fn event(event_type: &str, depth: u64) -> Box<RawJsonValue> {
+	to_raw_value(&json!({
+		"type": event_type,
+		"room_id": "!room:example.com",
+		"sender": "@alice:example.com",
+		"origin_server_ts": depth,
+		"depth": depth,
+		"content": {"body": depth},
+		"state_key": (event_type != "m.room.message").then_some(""),
+		"prev_events": [],
+		"auth_events": [],
+		"hashes": {"sha256": "aGk"},
+		"signatures": {},
+	}))
+	.expect("event serializes")
+}
+
+fn event_id(pdu: &RawJsonValue) -> OwnedEventId {
+	gen_event_id_canonical_json(pdu, &ROOM_VERSION)
+		.expect("event ID is generated")
+		.0
+}
+
+#[test]
+fn records_round_trip() {
+	let lines = [
+		json!({"header": {
+			"format": 1,
+			"room_id": "!room:example.com",
+			"room_version": "11",
+			"origin": "example.com",
+			"exported_at": 1_700_000_000_000_u64,
+		}}),
+		json!({"pdu": {"type": "m.room.message", "content": {"body": "hi"}}}),
+		json!({"state": {"event_id": "$a", "state": ["$b", "$c"]}}),
+		json!({"media": {
+			"mxc": "mxc://example.com/abc",
+			"content_type": "text/plain",
+			"content_disposition": null,
+			"content": "aGk=",
+		}}),
+	];
+
+	for line in lines {
+		let record: Record = serde_json::from_str(&line.to_string()).expect("record parses");
+		let written = serde_json::to_value(&record).expect("record serializes");
+
+		assert_eq!(written, line);
+	}
+}
+
+#[test]
+fn events_are_kept_verbatim() {
+	let line = r#"{"outlier":{"type":"m.room.create","content":{"room_version":"11"}}}"#;
+
+	let Record::Outlier(pdu) = serde_json::from_str(line).expect("record parses") else {
+		panic!("an outlier record");
+	};
+
+	assert_eq!(pdu.get(), r#"{"type":"m.room.create","content":{"room_version":"11"}}"#);
+}
+
+#[test]
+fn seed_is_the_latest_event_with_a_snapshot() {
+	let create = event("m.room.create", 1);
+	let name = event("m.room.name", 2);
+	let pdus = vec![
+		event("m.room.message", 3),
+		event("m.room.message", 4),
+		event("m.room.message", 5),
+	];
+
+	let archive = Archive {
+		snapshots: vec![
+			Snapshot {
+				event_id: event_id(&pdus[0]),
+				state: vec![event_id(&create)],
+			},
+			Snapshot {
+				event_id: event_id(&pdus[1]),
+				state: vec![event_id(&create), event_id(&name)],
+			},
+		],
+		outliers: vec![create.clone(), name.clone()],
+		pdus,
+		..Default::default()
+	};
+
+	let (seed, state) = seed(&archive, &ROOM_VERSION).expect("a seed is found");
+	let state: Vec<_> = state.iter().map(|pdu| event_id(pdu)).collect();
+
+	assert_eq!(seed, 1);
+	assert_eq!(state, [event_id(&create), event_id(&name)]);
+}
+
+#[test]
+fn seed_requires_a_timeline_event_with_a_snapshot() {
+	let create = event("m.room.create", 1);
+	let message = event("m.room.message", 2);
+
+	let archive = Archive {
+		snapshots: vec![Snapshot {
+			event_id: event_id(&create),
+			state: vec![event_id(&create)],
+		}],
+		outliers: vec![create],
+		pdus: vec![message],
+		..Default::default()
+	};
+
+	let error = seed(&archive, &ROOM_VERSION).expect_err("no seed is found");
+
+	assert!(
+		error
+			.message()
+			.contains("no timeline event with a state snapshot")
+	);
+}
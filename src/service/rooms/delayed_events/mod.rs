@@ -0,0 +1,345 @@
+//! Delayed events (MSC4140).
+//!
+//! A client may have a message or state event sent on its behalf after a
+//! delay. The event is persisted with its deadline and the worker sends it
+//! through the ordinary timeline path once the deadline passes, unless the
+//! client restarts the delay or cancels the event first. The schedule
+//! survives restarts; events which fell due while the server was down are sent
+//! at startup.
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::{FutureExt, Stream, StreamExt, future::pending};
+use loole::{Receiver, Sender};
+use ruma::{OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue as RawJsonValue;
+use tokio::time::sleep;
+use tuwunel_core::{
+	Err, Result, debug, debug_warn, err, implement,
+	matrix::pdu::PduBuilder,
+	utils::{
+		self, ReadyExt,
+		stream::{IterStream, TryIgnore},
+		time::now_millis,
+	},
+};
+use tuwunel_database::{Deserialized, Interfix, Json, Map};
+
+use crate::services::OnceServices;
+
+/// Length of the random identifier handed to the client for each delayed
+/// event. It is the only handle on the event, so it must not be guessable.
+const DELAY_ID_LENGTH: usize = 32;
+
+pub struct Service {
+	services: Arc<OnceServices>,
+	channel: (Sender<()>, Receiver<()>),
+	db: Data,
+}
+
+struct Data {
+	delayid_delayedevent: Arc<Map>,
+	timedue_delayid: Arc<Map>,
+	userid_delayid: Arc<Map>,
+}
+
+/// An event waiting to be sent.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DelayedEvent {
+	pub delay_id: String,
+
+	pub sender: OwnedUserId,
+
+	pub room_id: OwnedRoomId,
+
+	#[serde(rename = "type")]
+	pub event_type: String,
+
+	/// Present for state events.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub state_key: Option<String>,
+
+	pub content: Box<RawJsonValue>,
+
+	/// The delay in milliseconds.
+	pub delay: u64,
+
+	/// When the delay last (re)started, in milliseconds since the epoch.
+	pub running_since: u64,
+}
+
+impl DelayedEvent {
+	/// When the event is due to be sent, in milliseconds since the epoch.
+	#[inline]
+	#[must_use]
+	pub fn due(&self) -> u64 { self.running_since.saturating_add(self.delay) }
+}
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: args.services.clone(),
+			channel: loole::unbounded(),
+			db: Data {
+				delayid_delayedevent: args.db["delayid_delayedevent"].clone(),
+				timedue_delayid: args.db["timedue_delayid"].clone(),
+				userid_delayid: args.db["userid_delayid"].clone(),
+			},
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let receiver = self.channel.1.clone();
+
+		while !receiver.is_closed() && self.services.server.is_running() {
+			let next_due = self.next_due().await;
+			let deadline = async {
+				match next_due {
+					| Some(due) =>
+						sleep(Duration::from_millis(due.saturating_sub(now_millis()))).await,
+					| None => pending().await,
+				}
+			};
+
+			// Any change to the schedule wakes the worker to recompute its deadline.
+			tokio::select! {
+				() = deadline => self.send_due().await,
+				changed = receiver.recv_async() => if changed.is_err() {
+					break;
+				},
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn interrupt(&self) {
+		let (sender, _) = &self.channel;
+		if !sender.is_closed() {
+			sender.close();
+		}
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Sends every event whose deadline has passed.
+#[implement(Service)]
+async fn send_due(&self) {
+	let now = now_millis();
+	let due: Vec<String> = self
+		.db
+		.timedue_delayid
+		.keys::<(u64, &str)>()
+		.ignore_err()
+		.ready_take_while(|&(due, _)| due <= now)
+		.map(|(_, delay_id)| delay_id.to_owned())
+		.collect()
+		.await;
+
+	due.iter()
+		.stream()
+		.for_each(async |delay_id| {
+			if let Err(e) = self.send(delay_id).await {
+				debug_warn!(%delay_id, "Failed to send delayed event: {e}");
+			}
+		})
+		.await;
+}
+
+#[implement(Service)]
+async fn next_due(&self) -> Option<u64> {
+	self.db
+		.timedue_delayid
+		.keys::<(u64, &str)>()
+		.ignore_err()
+		.map(|(due, _)| due)
+		.boxed()
+		.next()
+		.await
+}
+
+/// Persists an event to be sent after `delay`, returning its delay_id.
+#[implement(Service)]
+pub fn schedule(
+	&self,
+	sender: &UserId,
+	room_id: &RoomId,
+	event_type: &str,
+	state_key: Option<&str>,
+	content: Box<RawJsonValue>,
+	delay: Duration,
+) -> Result<String> {
+	let max_delay = Duration::from_secs(self.services.config.max_event_delay_s);
+	if max_delay.is_zero() {
+		return Err!(Request(Forbidden("Delayed events are disabled on this server.")));
+	}
+
+	if delay > max_delay {
+		let max_delay = max_delay.as_millis();
+		return Err!(Request(InvalidParam(
+			"Delay exceeds the maximum of {max_delay}ms allowed by this server."
+		)));
+	}
+
+	let event = DelayedEvent {
+		delay_id: utils::random_string(DELAY_ID_LENGTH),
+		sender: sender.to_owned(),
+		room_id: room_id.to_owned(),
+		event_type: event_type.to_owned(),
+		state_key: state_key.map(ToOwned::to_owned),
+		content,
+		delay: delay.as_millis().try_into()?,
+		running_since: now_millis(),
+	};
+
+	debug!(
+		delay_id = %event.delay_id,
+		%room_id,
+		%event_type,
+		delay = event.delay,
+		"Scheduling delayed event"
+	);
+	self.insert(&event);
+
+	Ok(event.delay_id)
+}
+
+/// Restarts the delay from now, postponing the event.
+#[implement(Service)]
+pub async fn restart(&self, delay_id: &str) -> Result {
+	let mut event = self.get(delay_id).await?;
+
+	self.db
+		.timedue_delayid
+		.del((event.due(), delay_id));
+
+	event.running_since = now_millis();
+	self.insert(&event);
+
+	Ok(())
+}
+
+/// Discards the event without sending it.
+#[implement(Service)]
+pub async fn cancel(&self, delay_id: &str) -> Result {
+	let event = self.get(delay_id).await?;
+	self.remove(&event);
+
+	Ok(())
+}
+
+/// Sends the event now, ahead of its deadline.
+#[implement(Service)]
+pub async fn send(&self, delay_id: &str) -> Result<OwnedEventId> {
+	let event = self.get(delay_id).await?;
+
+	// Removed before sending; an event the room rejects is dropped rather than
+	// retried.
+	self.remove(&event);
+
+	let pdu_builder = PduBuilder {
+		event_type: event.event_type.into(),
+		content: serde_json::from_str(event.content.get())?,
+		state_key: event.state_key.as_deref().map(Into::into),
+		..Default::default()
+	};
+
+	self.services
+		.timeline
+		.check_pdu_for_spam(&pdu_builder, &event.sender, &event.room_id)
+		.await?;
+
+	let state_lock = self
+		.services
+		.state
+		.mutex
+		.lock(&event.room_id)
+		.await;
+	self.services
+		.timeline
+		.build_and_append_pdu(pdu_builder, &event.sender, &event.room_id, &state_lock)
+		.boxed()
+		.await
+}
+
+#[implement(Service)]
+pub async fn get(&self, delay_id: &str) -> Result<DelayedEvent> {
+	self.db
+		.delayid_delayedevent
+		.get(delay_id)
+		.await
+		.deserialized::<Json<_>>()
+		.map(|Json(event)| event)
+		.map_err(|_| err!(Request(NotFound("No delayed event with this delay_id."))))
+}
+
+/// The user's pending delayed events.
+#[implement(Service)]
+pub fn user_delayed_events<'a>(
+	&'a self,
+	user_id: &'a UserId,
+) -> impl Stream<Item = DelayedEvent> + Send + 'a {
+	self.db
+		.userid_delayid
+		.keys_prefix::<(&UserId, &str), _>(&(user_id, Interfix))
+		.ignore_err()
+		.map(|(_, delay_id)| delay_id.to_owned())
+		.then(async |delay_id| self.get(&delay_id).await)
+		.ready_filter_map(Result::ok)
+}
+
+/// Cancels all of the user's pending delayed events.
+#[implement(Service)]
+pub async fn cancel_user(&self, user_id: &UserId) {
+	let events: Vec<_> = self.user_delayed_events(user_id).collect().await;
+
+	for event in &events {
+		self.remove(event);
+	}
+}
+
+#[implement(Service)]
+fn insert(&self, event: &DelayedEvent) {
+	let delay_id = event.delay_id.as_str();
+
+	self.db
+		.delayid_delayedevent
+		.raw_put(delay_id, Json(event));
+
+	self.db
+		.timedue_delayid
+		.put_raw((event.due(), delay_id), []);
+
+	self.db
+		.userid_delayid
+		.put_raw((&event.sender, delay_id), []);
+
+	self.wake();
+}
+
+#[implement(Service)]
+fn remove(&self, event: &DelayedEvent) {
+	let delay_id = event.delay_id.as_str();
+
+	self.db.delayid_delayedevent.remove(delay_id);
+
+	self.db
+		.timedue_delayid
+		.del((event.due(), delay_id));
+
+	self.db
+		.userid_delayid
+		.del((&event.sender, delay_id));
+
+	self.wake();
+}
+
+#[implement(Service)]
+fn wake(&self) {
+	let (sender, _) = &self.channel;
+	sender.send(()).ok();
+}
@@ -146,7 +146,7 @@ pub async fn shutdown_room(
 
 	/// Wipes the room's storage. `force` widens the erasure of local users'
 	/// left-state (it is not Synapse's `force_purge`).
-	async fn purge_room(&self, room_id: &RoomId, force: bool, state_lock: &RoomMutexGuard) {
+	pub async fn purge_room(&self, room_id: &RoomId, force: bool, state_lock: &RoomMutexGuard) {
 		debug!("Deleting room's threads from database");
 		self.services
 			.threads
@@ -211,6 +211,12 @@ async fn purge_room(&self, room_id: &RoomId, force: bool, state_lock: &RoomMutex
 			.log_err()
 			.ok();
 
+		debug!("Deleting the room's partial-state record");
+		self.services
+			.partial_state
+			.delete_room(room_id)
+			.await;
+
 		debug!("Deleting room state hash from our database");
 		self.services
 			.state
@@ -19,7 +19,7 @@
 	signatures::{to_canonical_json_string_for_signing, verify_canonical_json_bytes},
 };
 use serde::{Deserialize, Serialize};
-use serde_json::value::to_raw_value;
+use serde_json::value::{RawValue as RawJsonValue, to_raw_value};
 use tuwunel_core::{
 	Err, Result, at, debug, implement,
 	matrix::{Event, pdu::into_outgoing_federation, room_version::rules as room_version_rules},
@@ -29,6 +29,8 @@
 };
 use tuwunel_database::{Cbor, Deserialized};
 
+use crate::policy_server::Decision;
+
 #[cfg(test)]
 mod tests;
 
@@ -309,6 +311,10 @@ async fn fetch_policy_signature(
 		return FetchOutcome::FailOpen;
 	};
 
+	if self.services.globals.server_is_ours(&policy.via) {
+		return self.local_policy_signature(&raw).await;
+	}
+
 	let timeout = Duration::from_secs(
 		self.services
 			.server
@@ -381,6 +387,35 @@ async fn fetch_policy_signature(
 		)
 }
 
+/// Asks this server's own policy server, for rooms naming it, in place of a
+/// request to ourselves. Failures fail open as a remote policy server's do.
+#[implement(super::Service)]
+async fn local_policy_signature(&self, pdu: &RawJsonValue) -> FetchOutcome {
+	let decision = match self.parse_incoming_pdu(pdu).await {
+		| Ok((room_id, event_id, value)) =>
+			self.services
+				.policy_server
+				.sign_pdu(&room_id, &event_id, value)
+				.await,
+		| Err(e) => Err(e),
+	};
+
+	match decision {
+		| Ok(Decision::Signed(signature)) => FetchOutcome::Signed(signature),
+		| Ok(Decision::Refused(reason)) => {
+			debug!("local policy server refused event: {reason}");
+			FetchOutcome::Refused {
+				status: StatusCode::BAD_REQUEST,
+				errcode: Some(ErrorKind::forbidden()),
+			}
+		},
+		| Err(e) => {
+			warn!("local policy server failed; failing open: {e}");
+			FetchOutcome::FailOpen
+		},
+	}
+}
+
 fn classify_fetch_error(
 	status: StatusCode,
 	errcode: &ErrorKind,
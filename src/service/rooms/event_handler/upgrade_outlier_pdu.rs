@@ -3,10 +3,11 @@
 use futures::{FutureExt, StreamExt, TryFutureExt};
 use ruma::{
 	CanonicalJsonObject, EventId, OwnedEventId, RoomId, RoomVersionId, ServerName,
-	events::StateEventType, room_version_rules::RoomVersionRules,
+	events::{StateEventType, TimelineEventType},
+	room_version_rules::RoomVersionRules,
 };
 use tuwunel_core::{
-	Result, debug, debug_info, err, implement, is_equal_to,
+	Result, debug, debug_info, debug_warn, err, implement, is_equal_to,
 	matrix::{Event, EventTypeExt, PduEvent, StateKey, pdu::check_rules, room_version},
 	trace,
 	utils::{
@@ -108,8 +109,35 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 		)
 		.await?;
 
-	self.auth_check_outlier_pdu(room_id, &incoming_pdu, &room_rules, &state_at_incoming_event)
-		.await?;
+	// MSC3706: partial state lacks the remote memberships positional and
+	// current-state auth may depend on. A failure is only put down to the
+	// partial state when a membership the check needs is missing from it and
+	// the sender's server is one send_join reported in the room; the event is
+	// then accepted provisionally and checked again once the full state is in.
+	let partial_state = self
+		.services
+		.partial_state
+		.is_partial_server(room_id, incoming_pdu.sender().server_name())
+		.await;
+
+	let mut provisional = false;
+
+	let positional_auth = self
+		.auth_check_outlier_pdu(room_id, &incoming_pdu, &room_rules, &state_at_incoming_event)
+		.await;
+
+	if let Err(error) = positional_auth {
+		if !partial_state
+			|| !self
+				.membership_missing_at(&incoming_pdu, &state_at_incoming_event)
+				.await
+		{
+			return Err(error);
+		}
+
+		debug_warn!(%error, "Accepting event whose membership is missing from partial state.");
+		provisional = true;
+	}
 
 	let soft_fail_pre = !cleared
 		&& self
@@ -122,10 +150,20 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 	let state_lock = self.services.state.mutex.lock(room_id).await;
 
 	// 14. Check if the event passes auth based on the current room state.
-	let soft_fail_current_state = !self
+	let mut soft_fail_current_state = !self
 		.current_state_auth_passes(room_id, &incoming_pdu, &room_rules)
 		.await;
 
+	if soft_fail_current_state
+		&& partial_state
+		&& self
+			.membership_missing_current(room_id, &incoming_pdu)
+			.await
+	{
+		soft_fail_current_state = false;
+		provisional = true;
+	}
+
 	let soft_fail = soft_fail_pre || soft_fail_current_state;
 
 	let mut extremities = self
@@ -236,6 +274,12 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 		return Ok(None);
 	}
 
+	if provisional {
+		self.services
+			.partial_state
+			.mark_provisional(room_id, incoming_pdu.event_id());
+	}
+
 	drop(state_lock);
 
 	if cleared {
@@ -319,6 +363,62 @@ async fn current_state_auth_passes(
 		.is_ok()
 }
 
+/// Whether the state at the event lacks a membership its auth depends on, as
+/// partial state may.
+#[implement(super::Service)]
+async fn membership_missing_at(
+	&self,
+	incoming_pdu: &PduEvent,
+	state_at_incoming_event: &HashMap<u64, OwnedEventId>,
+) -> bool {
+	for user_id in membership_subjects(incoming_pdu) {
+		let Ok(shortstatekey) = self
+			.services
+			.short
+			.get_shortstatekey(&StateEventType::RoomMember, user_id)
+			.await
+		else {
+			return true;
+		};
+
+		if !state_at_incoming_event.contains_key(&shortstatekey) {
+			return true;
+		}
+	}
+
+	false
+}
+
+/// Whether the room's current state lacks a membership the event's auth
+/// depends on, as partial state may.
+#[implement(super::Service)]
+async fn membership_missing_current(&self, room_id: &RoomId, incoming_pdu: &PduEvent) -> bool {
+	for user_id in membership_subjects(incoming_pdu) {
+		if self
+			.services
+			.state_accessor
+			.room_state_get_id(room_id, &StateEventType::RoomMember, user_id)
+			.await
+			.is_err()
+		{
+			return true;
+		}
+	}
+
+	false
+}
+
+/// The users whose membership decides the event's auth: the sender, and the
+/// target of a membership event.
+fn membership_subjects(pdu: &PduEvent) -> impl Iterator<Item = &str> {
+	let target = (*pdu.kind() == TimelineEventType::RoomMember)
+		.then(|| pdu.state_key())
+		.flatten()
+		.filter(|target| *target != pdu.sender().as_str());
+
+	once(pdu.sender().as_str()).chain(target)
+}
+
 /// Re-examines an event that already carries a soft-fail marker.
 ///
 /// The marker is a standing verdict rather than a permanent rejection, so it
@@ -450,8 +550,10 @@ async fn resolve_state_at_incoming_event(
 	Ok((state, ResolvedVia::Fetch))
 }
 
+/// Checks the event's auth against the state given for it, keyed by
+/// shortstatekey.
 #[implement(super::Service)]
-async fn auth_check_outlier_pdu(
+pub(crate) async fn auth_check_outlier_pdu(
 	&self,
 	room_id: &RoomId,
 	incoming_pdu: &PduEvent,
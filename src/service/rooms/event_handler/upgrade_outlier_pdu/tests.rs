@@ -1,8 +1,32 @@
 use std::{ops::Range, time::Duration};
 
-use tuwunel_core::utils::continue_exponential_backoff;
+use ruma::{UserId, events::TimelineEventType, owned_event_id, owned_room_id, uint, user_id};
+use serde_json::{json, value::to_raw_value};
+use tuwunel_core::{
+	matrix::{EventHash, PduEvent},
+	utils::continue_exponential_backoff,
+};
 
-use super::UPGRADE_RETRY;
+use super::{UPGRADE_RETRY, membership_subjects};
+
+fn pdu(sender: &UserId, kind: TimelineEventType, state_key: Option<&str>) -> PduEvent {
+	PduEvent {
+		event_id: owned_event_id!("$event:example.com"),
+		room_id: owned_room_id!("!room:example.com"),
+		sender: sender.to_owned(),
+		origin: None,
+		origin_server_ts: uint!(1),
+		state_key: state_key.map(Into::into),
+		kind,
+		content: to_raw_value(&json!({})).unwrap().into(),
+		redacts: None,
+		unsigned: None,
+		auth_events: Default::default(),
+		prev_events: Default::default(),
+		depth: uint!(1),
+		hashes: EventHash::default(),
+	}
+}
 
 #[test]
 fn upgrade_retry_releases_after_the_window() {
@@ -19,3 +43,31 @@ fn upgrade_retry_widens_but_stays_capped() {
 	assert!(continue_exponential_backoff(start, end, Duration::from_mins(6), 2));
 	assert!(!continue_exponential_backoff(start, end, end, 1_000));
 }
+
+#[test]
+fn partial_state_auth_depends_on_the_sender() {
+	let alice = user_id!("@alice:example.com");
+	let event = pdu(alice, TimelineEventType::RoomMessage, None);
+
+	assert_eq!(membership_subjects(&event).collect::<Vec<_>>(), [alice.as_str()]);
+}
+
+#[test]
+fn partial_state_auth_depends_on_the_membership_target() {
+	let alice = user_id!("@alice:example.com");
+	let bob = "@bob:remote.example.org";
+
+	let kick = pdu(alice, TimelineEventType::RoomMember, Some(bob));
+	assert_eq!(membership_subjects(&kick).collect::<Vec<_>>(), [alice.as_str(), bob]);
+
+	let join = pdu(alice, TimelineEventType::RoomMember, Some(alice.as_str()));
+	assert_eq!(membership_subjects(&join).collect::<Vec<_>>(), [alice.as_str()]);
+}
+
+#[test]
+fn partial_state_auth_ignores_other_state_keys() {
+	let alice = user_id!("@alice:example.com");
+	let topic = pdu(alice, TimelineEventType::RoomTopic, Some(""));
+
+	assert_eq!(membership_subjects(&topic).collect::<Vec<_>>(), [alice.as_str()]);
+}
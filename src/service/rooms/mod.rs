@@ -1,10 +1,13 @@
 pub mod alias;
+pub mod archive;
 pub mod auth_chain;
+pub mod delayed_events;
 pub mod delete;
 pub mod directory;
 pub mod event_handler;
 pub mod lazy_loading;
 pub mod metadata;
+pub mod partial_state;
 pub mod pdu_metadata;
 pub mod read_receipt;
 pub mod retention;
@@ -0,0 +1,267 @@
+//! Rooms joined with partial state through MSC3706 "faster joins".
+//!
+//! A remote join whose `send_join` response omitted the membership events is
+//! recorded here with the join event and the servers able to supply the rest.
+//! The join returns to the client at once; the worker resyncs the full state
+//! in the background and clears the record when done. Until then the room's
+//! state lacks remote memberships and consumers treat it accordingly. Events
+//! accepted only because a membership was missing are recorded, and checked
+//! again once the full state is in.
+
+#[cfg(test)]
+mod tests;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use loole::{Receiver, Sender};
+use ruma::{EventId, OwnedEventId, OwnedRoomId, OwnedServerName, RoomId, ServerName};
+use serde::{Deserialize, Serialize};
+use tokio::time::{MissedTickBehavior, interval, timeout};
+use tuwunel_core::{
+	Result, debug, debug_warn, implement, info,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+use tuwunel_database::{Deserialized, Interfix, Json, Map};
+
+use crate::services::OnceServices;
+
+/// Interval between sweeps re-queueing rooms whose resync has not finished.
+const RESYNC_RETRY_INTERVAL: Duration = Duration::from_mins(5);
+
+pub struct Service {
+	services: Arc<OnceServices>,
+	channel: (Sender<OwnedRoomId>, Receiver<OwnedRoomId>),
+	db: Data,
+}
+
+struct Data {
+	roomid_partialstate: Arc<Map>,
+	roomid_fullstatecount: Arc<Map>,
+	roomid_provisionaleventid: Arc<Map>,
+}
+
+/// The record kept for a room until its full state has been resynced.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PartialState {
+	/// Our join event; the resync requests the room state at this event.
+	pub event_id: OwnedEventId,
+
+	/// Servers in the room according to `send_join`, tried in order.
+	pub servers: Vec<OwnedServerName>,
+}
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: args.services.clone(),
+			channel: loole::unbounded(),
+			db: Data {
+				roomid_partialstate: args.db["roomid_partialstate"].clone(),
+				roomid_fullstatecount: args.db["roomid_fullstatecount"].clone(),
+				roomid_provisionaleventid: args.db["roomid_provisionaleventid"].clone(),
+			},
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let receiver = self.channel.1.clone();
+
+		// The first tick fires immediately, picking up rooms left partial by a
+		// restart.
+		let mut retry = interval(RESYNC_RETRY_INTERVAL);
+		retry.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+		while !receiver.is_closed() && self.services.server.is_running() {
+			tokio::select! {
+				_ = retry.tick() => self.requeue().await,
+				room_id = receiver.recv_async() => match room_id {
+					| Ok(room_id) => self.resync(&room_id).await,
+					| Err(_) => break,
+				},
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn interrupt(&self) {
+		let (sender, _) = &self.channel;
+		if !sender.is_closed() {
+			sender.close();
+		}
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+#[implement(Service)]
+async fn requeue(&self) {
+	let (sender, _) = &self.channel;
+	self.rooms()
+		.map(ToOwned::to_owned)
+		.ready_for_each(|room_id| {
+			sender.send(room_id).ok();
+		})
+		.await;
+}
+
+#[implement(Service)]
+#[tracing::instrument(name = "resync", level = "info", skip(self))]
+async fn resync(&self, room_id: &RoomId) {
+	if !self.is_partial(room_id).await {
+		return;
+	}
+
+	match self
+		.services
+		.membership
+		.resync_partial_state(room_id)
+		.await
+	{
+		| Ok(()) => info!("Resynced full state after partial-state join."),
+		| Err(e) => debug_warn!("Partial state resync failed; will retry: {e}"),
+	}
+}
+
+/// Records `room_id` as joined with partial state and queues its resync.
+#[implement(Service)]
+pub fn mark_partial(&self, room_id: &RoomId, state: &PartialState) {
+	debug!(%room_id, servers = state.servers.len(), "Room joined with partial state.");
+	self.db
+		.roomid_partialstate
+		.raw_put(room_id, Json(state));
+
+	let (sender, _) = &self.channel;
+	sender.send(room_id.to_owned()).ok();
+}
+
+/// Clears the partial-state record once the full state is installed, noting
+/// the count at which it happened so sync can deliver the room afresh.
+#[implement(Service)]
+pub fn mark_full(&self, room_id: &RoomId) {
+	let count = self.services.globals.next_count();
+
+	self.db
+		.roomid_fullstatecount
+		.raw_put(room_id, *count);
+
+	self.db.roomid_partialstate.remove(room_id);
+}
+
+/// Whether the room still awaits its full state after a faster join.
+#[implement(Service)]
+pub async fn is_partial(&self, room_id: &RoomId) -> bool {
+	self.db
+		.roomid_partialstate
+		.exists(room_id)
+		.await
+		.is_ok()
+}
+
+/// The partial-state record for the room, if it has one.
+#[implement(Service)]
+pub async fn get(&self, room_id: &RoomId) -> Result<PartialState> {
+	self.db
+		.roomid_partialstate
+		.get(room_id)
+		.await
+		.deserialized::<Json<_>>()
+		.map(|Json(state)| state)
+}
+
+/// Whether `server` was reported in the room by the `send_join` which left it
+/// with partial state. Memberships for such servers may be missing locally.
+#[implement(Service)]
+pub async fn is_partial_server(&self, room_id: &RoomId, server: &ServerName) -> bool {
+	self.get(room_id)
+		.await
+		.is_ok_and(|state| state.servers.iter().any(|s| s == server))
+}
+
+/// The count at which the room's full state was installed after a faster
+/// join, if it ever had partial state.
+#[implement(Service)]
+pub async fn full_state_count(&self, room_id: &RoomId) -> Option<u64> {
+	self.db
+		.roomid_fullstatecount
+		.get(room_id)
+		.await
+		.deserialized()
+		.ok()
+}
+
+/// Resolves once the room no longer has partial state, or when `wait` has
+/// elapsed; true when the full state is present.
+#[implement(Service)]
+pub async fn wait_full_state(&self, room_id: &RoomId, wait: Duration) -> bool {
+	let until_full = async {
+		loop {
+			// Register before checking so a concurrent completion is not missed.
+			let watch = self
+				.db
+				.roomid_partialstate
+				.watch_raw_prefix_once(room_id);
+
+			if !self.is_partial(room_id).await {
+				return;
+			}
+
+			watch.await;
+		}
+	};
+
+	timeout(wait, until_full).await.is_ok()
+}
+
+/// Records an event accepted while the room has partial state although its
+/// auth failed for lack of a membership, to be checked again against the full
+/// state.
+#[implement(Service)]
+pub fn mark_provisional(&self, room_id: &RoomId, event_id: &EventId) {
+	debug!(%room_id, %event_id, "Event accepted against partial state.");
+	self.db
+		.roomid_provisionaleventid
+		.put_raw((room_id, event_id), []);
+}
+
+/// The events of the room accepted against partial state, not yet checked
+/// against the full state.
+#[implement(Service)]
+pub fn provisional_events<'a>(
+	&'a self,
+	room_id: &'a RoomId,
+) -> impl Stream<Item = OwnedEventId> + Send + 'a {
+	self.db
+		.roomid_provisionaleventid
+		.keys_prefix::<(&RoomId, &EventId), _>(&(room_id, Interfix))
+		.ignore_err()
+		.map(|(_, event_id)| event_id.to_owned())
+}
+
+/// Drops the room's provisional events once they have been checked.
+#[implement(Service)]
+pub async fn clear_provisional(&self, room_id: &RoomId) {
+	self.db
+		.roomid_provisionaleventid
+		.keys_prefix_raw(&(room_id, Interfix))
+		.ignore_err()
+		.ready_for_each(|key| self.db.roomid_provisionaleventid.remove(key))
+		.await;
+}
+
+/// Every room currently awaiting its full state.
+#[implement(Service)]
+pub fn rooms(&self) -> impl Stream<Item = &RoomId> + Send + '_ {
+	self.db.roomid_partialstate.keys().ignore_err()
+}
+
+/// Drops all partial-state bookkeeping for a room being deleted.
+#[implement(Service)]
+pub async fn delete_room(&self, room_id: &RoomId) {
+	self.db.roomid_partialstate.remove(room_id);
+	self.db.roomid_fullstatecount.remove(room_id);
+	self.clear_provisional(room_id).await;
+}
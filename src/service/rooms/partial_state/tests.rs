@@ -0,0 +1,22 @@
+use ruma::{owned_event_id, owned_server_name};
+use tuwunel_database::{Json, deserialize_from_slice, serialize_to_vec};
+
+use super::PartialState;
+
+#[test]
+fn partial_state_record_round_trips() {
+	let record = PartialState {
+		event_id: owned_event_id!("$join:example.com"),
+		servers: vec![
+			owned_server_name!("remote.example.org"),
+			owned_server_name!("other.example.net"),
+		],
+	};
+
+	let bytes = serialize_to_vec(Json(&record)).expect("serialize record");
+	let Json(decoded): Json<PartialState> =
+		deserialize_from_slice(&bytes).expect("deserialize record");
+
+	assert_eq!(decoded.event_id, record.event_id);
+	assert_eq!(decoded.servers, record.servers, "resync tries servers in order");
+}
@@ -0,0 +1,136 @@
+//! Room history retention (MSC1763).
+//!
+//! A room's `m.room.retention` state sets the `max_lifetime` of its events in
+//! milliseconds, bounded by the configured allowed range; rooms without one
+//! fall back to the configured default. Expired history is purged through the
+//! timeline's purge, which keeps state events.
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use ruma::{MilliSecondsSinceUnixEpoch, RoomId, UInt, api::Direction, events::StateEventType};
+use serde::Deserialize;
+use tuwunel_core::{Result, debug, debug_info, implement, utils::time::now, warn};
+
+/// The parts of `m.room.retention` content this server acts on.
+#[derive(Deserialize)]
+struct RoomRetentionEventContent {
+	max_lifetime: Option<u64>,
+}
+
+/// Purges the expired history of every room, returning the events removed.
+#[implement(super::Service)]
+pub(super) async fn purge_expired_history(&self) -> usize {
+	let room_ids: Vec<_> = self
+		.services
+		.metadata
+		.iter_ids()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut purged = 0_usize;
+	for room_id in &room_ids {
+		match self.purge_expired_room(room_id).await {
+			| Ok(count) => purged = purged.saturating_add(count),
+			| Err(e) => warn!(%room_id, "Failed to purge expired history: {e}"),
+		}
+	}
+
+	purged
+}
+
+#[implement(super::Service)]
+async fn purge_expired_room(&self, room_id: &RoomId) -> Result<usize> {
+	let Some(lifetime) = self.max_lifetime(room_id).await else {
+		return Ok(0);
+	};
+
+	let cutoff = now().saturating_sub(lifetime).as_millis();
+	let cutoff = UInt::new_saturating(u64::try_from(cutoff).unwrap_or(u64::MAX));
+	let cutoff = MilliSecondsSinceUnixEpoch(cutoff);
+
+	// Everything before the first unexpired event goes; when all have expired
+	// the latest event stays behind so the room keeps a timeline to extend.
+	let timeline = &self.services.timeline;
+	let boundary = match timeline
+		.get_pdu_id_near_ts(room_id, cutoff, Direction::Forward)
+		.await
+	{
+		| Ok(unexpired) => Ok(unexpired),
+		| Err(_) =>
+			timeline
+				.get_pdu_id_near_ts(
+					room_id,
+					MilliSecondsSinceUnixEpoch::now(),
+					Direction::Backward,
+				)
+				.await,
+	};
+
+	let Ok((_, boundary)) = boundary else {
+		return Ok(0);
+	};
+
+	let purged = timeline
+		.purge_expired_history(room_id, boundary.count)
+		.await?;
+
+	if purged > 0 {
+		debug!(%room_id, purged, ?lifetime, "Purged expired history");
+	}
+
+	Ok(purged)
+}
+
+/// The lifetime of events in the room, or `None` to keep them forever.
+#[implement(super::Service)]
+pub async fn max_lifetime(&self, room_id: &RoomId) -> Option<Duration> {
+	let config = &self.services.server.config;
+	let policy = self
+		.services
+		.state_accessor
+		.room_state_get_content(room_id, &StateEventType::from("m.room.retention"), "")
+		.await
+		.ok()
+		.and_then(|content: RoomRetentionEventContent| content.max_lifetime)
+		.map(Duration::from_millis);
+
+	effective_max_lifetime(
+		policy,
+		config
+			.retention_default_max_lifetime_seconds
+			.map(Duration::from_secs),
+		config
+			.retention_allowed_lifetime_min_seconds
+			.map(Duration::from_secs),
+		config
+			.retention_allowed_lifetime_max_seconds
+			.map(Duration::from_secs),
+	)
+}
+
+/// Runs one retention pass when enabled.
+#[implement(super::Service)]
+pub(super) async fn purge_history_if_enabled(&self) {
+	if !self.services.config.retention_enabled {
+		return;
+	}
+
+	debug_info!("Purging expired room history");
+	let purged = self.purge_expired_history().await;
+	debug_info!(?purged, "Finished purging expired room history");
+}
+
+/// Applies the configured default and bounds to a room's policy.
+pub(super) fn effective_max_lifetime(
+	policy: Option<Duration>,
+	default: Option<Duration>,
+	min: Option<Duration>,
+	max: Option<Duration>,
+) -> Option<Duration> {
+	let lifetime = policy.or(default)?;
+	let lifetime = min.map_or(lifetime, |min| lifetime.max(min));
+
+	Some(max.map_or(lifetime, |max| lifetime.min(max)))
+}
@@ -1,3 +1,7 @@
+mod history;
+#[cfg(test)]
+mod tests;
+
 use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
@@ -54,6 +58,8 @@ async fn worker(self: Arc<Self>) -> Result {
 				debug_info!(?count, "Finished cleaning up retained events");
 			}
 
+			self.purge_history_if_enabled().await;
+
 			tokio::select! {
 				() = tokio::time::sleep(Duration::from_hours(1)) => {},
 				() = self.services.server.until_shutdown() => return Ok(())
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use super::history::effective_max_lifetime;
+
+const DAY: Duration = Duration::from_secs(86_400);
+
+#[test]
+fn room_policy_overrides_default() {
+	assert_eq!(effective_max_lifetime(Some(DAY), Some(DAY * 30), None, None), Some(DAY));
+	assert_eq!(effective_max_lifetime(None, Some(DAY * 30), None, None), Some(DAY * 30));
+	assert_eq!(effective_max_lifetime(None, None, Some(DAY), Some(DAY * 30)), None);
+}
+
+#[test]
+fn room_policy_is_clamped_to_allowed_range() {
+	let min = Some(DAY);
+	let max = Some(DAY * 365);
+
+	assert_eq!(effective_max_lifetime(Some(DAY / 24), None, min, max), Some(DAY));
+	assert_eq!(effective_max_lifetime(Some(DAY * 1000), None, min, max), Some(DAY * 365));
+	assert_eq!(effective_max_lifetime(Some(DAY * 7), None, min, max), Some(DAY * 7));
+}
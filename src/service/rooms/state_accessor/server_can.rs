@@ -34,6 +34,18 @@ pub async fn server_can_see_event(
 			c.history_visibility
 		});
 
+	// MSC3706: a partial-state room may lack the server's memberships; trust
+	// the servers `send_join` reported until the resync completes.
+	if matches!(history_visibility, HistoryVisibility::Invited | HistoryVisibility::Joined)
+		&& self
+			.services
+			.partial_state
+			.is_partial_server(room_id, origin)
+			.await
+	{
+		return true;
+	}
+
 	let current_server_members = self
 		.services
 		.state_cache
@@ -44,6 +44,7 @@ struct Data {
 	roomserverids: Arc<Map>,
 	roomuserid_invitecount: Arc<Map>,
 	roomuserid_joinedcount: Arc<Map>,
+	roomuserid_joinedts: Arc<Map>,
 	roomuserid_leftcount: Arc<Map>,
 	roomuserid_knockedcount: Arc<Map>,
 	roomuseroncejoinedids: Arc<Map>,
@@ -71,6 +72,7 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				roomserverids: args.db["roomserverids"].clone(),
 				roomuserid_invitecount: args.db["roomuserid_invitecount"].clone(),
 				roomuserid_joinedcount: args.db["roomuserid_joined"].clone(),
+				roomuserid_joinedts: args.db["roomuserid_joinedts"].clone(),
 				roomuserid_leftcount: args.db["roomuserid_leftcount"].clone(),
 				roomuserid_knockedcount: args.db["roomuserid_knockedcount"].clone(),
 				roomuseroncejoinedids: args.db["roomuseroncejoinedids"].clone(),
@@ -413,6 +415,19 @@ pub async fn get_joined_count(&self, room_id: &RoomId, user_id: &UserId) -> Resu
 		.deserialized()
 }
 
+/// When this server took the user as joined to the room, in milliseconds since
+/// the epoch. Joins from before this was recorded have none.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "trace")]
+pub async fn get_joined_ts(&self, room_id: &RoomId, user_id: &UserId) -> Result<u64> {
+	let key = (room_id, user_id);
+	self.db
+		.roomuserid_joinedts
+		.qry(&key)
+		.await
+		.deserialized()
+}
+
 /// Returns an iterator over all memberships for a user.
 #[implement(Service)]
 #[inline]
@@ -17,7 +17,7 @@
 use tuwunel_core::{
 	Result, implement, is_not_empty,
 	matrix::PduCount,
-	utils::{ReadyExt, result::LogErr},
+	utils::{ReadyExt, result::LogErr, time::now_millis},
 	warn,
 };
 use tuwunel_database::{Json, serialize_key, serialize_val};
@@ -106,6 +106,11 @@ pub async fn update_membership(
 	match membership {
 		| MembershipState::Join => {
 			self.handle_join(room_id, user_id, count).await?;
+
+			self.services
+				.user_directory
+				.update_member(user_id, membership_event.displayname.as_deref())
+				.await;
 		},
 		| MembershipState::Invite => {
 			if self
@@ -123,6 +128,11 @@ pub async fn update_membership(
 		| MembershipState::Leave | MembershipState::Ban => {
 			self.handle_leave(room_id, user_id, count).await;
 
+			self.services
+				.user_directory
+				.update_departed(user_id)
+				.await;
+
 			// A departure drops the room from the account-wide badge total.
 			if self.services.globals.user_is_local(user_id) {
 				self.services
@@ -397,6 +407,13 @@ async fn handle_join(&self, room_id: &RoomId, user_id: &UserId, count: PduCount)
 			.await?;
 	}
 
+	// a membership change while joined is not a new join
+	if !self.is_joined(user_id, room_id).await {
+		self.db
+			.roomuserid_joinedts
+			.put((room_id, user_id), now_millis());
+	}
+
 	self.mark_as_joined(user_id, room_id, count);
 
 	Ok(())
@@ -2,7 +2,7 @@
 
 use futures::{Stream, StreamExt, TryFutureExt, future::join3};
 use ruma::{
-	CanonicalJsonValue, EventId, OwnedEventId, OwnedUserId, RoomId, UserId,
+	CanonicalJsonValue, EventId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
 	api::{Direction, client::threads::get_threads::v1::IncludeThreads},
 	events::{
 		TimelineEventType,
@@ -16,15 +16,18 @@
 	Event, Result, err,
 	matrix::pdu::{PduCount, PduEvent, PduId, RawPduId},
 	utils::{
-		ReadyExt,
+		MutexMap, ReadyExt,
 		stream::{TryIgnore, WidebandExt, automatic_width},
 	},
 };
 use tuwunel_database::{Deserialized, Map, Txn};
 
+mod subscriptions;
 #[cfg(test)]
 mod tests;
 
+pub use self::subscriptions::{Subscription, SubscriptionChange};
+
 /// Maximum relation hops walked when resolving thread membership, per
 /// the Matrix v1.4 spec recommendation (also MSC3771/MSC3773).
 const MAX_THREAD_HOPS: usize = 3;
@@ -44,12 +47,17 @@ struct ThreadRelation {
 pub struct Service {
 	db: Data,
 	services: Arc<crate::services::OnceServices>,
+
+	/// Serializes changes to a user's thread subscriptions in a room.
+	subscription_mutex: MutexMap<(OwnedUserId, OwnedRoomId), ()>,
 }
 
 pub(super) struct Data {
 	threadid_userids: Arc<Map>,
 	threadactivityid_rootid: Arc<Map>,
 	threadrootid_latestcount: Arc<Map>,
+	useridcount_threadsubscription: Arc<Map>,
+	userroomthreadid_subscription: Arc<Map>,
 }
 
 impl crate::Service for Service {
@@ -59,8 +67,11 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				threadid_userids: args.db["threadid_userids"].clone(),
 				threadactivityid_rootid: args.db["threadactivityid_rootid"].clone(),
 				threadrootid_latestcount: args.db["threadrootid_latestcount"].clone(),
+				useridcount_threadsubscription: args.db["useridcount_threadsubscription"].clone(),
+				userroomthreadid_subscription: args.db["userroomthreadid_subscription"].clone(),
 			},
 			services: args.services.clone(),
+			subscription_mutex: MutexMap::new(),
 		}))
 	}
 
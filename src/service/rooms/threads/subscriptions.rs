@@ -0,0 +1,264 @@
+//! Thread subscriptions (MSC4306).
+//!
+//! A user subscribes to a thread explicitly, or automatically on behalf of an
+//! event of theirs in it; the server does the latter itself for local users
+//! taking part in a thread. Unsubscribing is remembered, so an automatic
+//! subscription caused by an event older than the unsubscription is refused
+//! as conflicting. Every change takes a fresh stream position, its bump stamp,
+//! under which it is also indexed for the changes stream (MSC4308).
+
+use futures::{Stream, StreamExt};
+use ruma::{EventId, OwnedEventId, OwnedRoomId, RoomId, UserId, events::relation::RelationType};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	Err, Result, debug, implement,
+	matrix::Event,
+	utils::stream::{ReadyExt, TryIgnore},
+};
+use tuwunel_database::{Deserialized, Json};
+
+use super::ExtractThreadRelation;
+
+/// A user's standing in one thread.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Subscription {
+	/// `false` once the user unsubscribed.
+	pub subscribed: bool,
+
+	/// Subscribed on behalf of an event rather than by request.
+	pub automatic: bool,
+
+	/// Stream position of the change.
+	pub bump_stamp: u64,
+}
+
+/// One entry of the changes stream.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SubscriptionChange {
+	pub room_id: OwnedRoomId,
+	pub thread_root: OwnedEventId,
+	pub subscribed: bool,
+	pub automatic: bool,
+}
+
+#[implement(super::Service)]
+pub async fn get_subscription(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_root: &EventId,
+) -> Option<Subscription> {
+	self.db
+		.userroomthreadid_subscription
+		.qry(&(user_id, room_id, thread_root))
+		.await
+		.deserialized::<Json<_>>()
+		.map(|Json(subscription)| subscription)
+		.ok()
+}
+
+#[implement(super::Service)]
+pub async fn is_subscribed(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_root: &EventId,
+) -> bool {
+	self.get_subscription(user_id, room_id, thread_root)
+		.await
+		.is_some_and(|subscription| subscription.subscribed)
+}
+
+/// Subscribes the user to the thread. An automatic subscription names the
+/// event causing it; it never replaces an existing subscription and is refused
+/// when the user unsubscribed after that event.
+#[implement(super::Service)]
+pub async fn subscribe(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_root: &EventId,
+	automatic: Option<&EventId>,
+) -> Result {
+	let key = (user_id.to_owned(), room_id.to_owned());
+	let _lock = self.subscription_mutex.lock(&key).await;
+
+	let existing = self
+		.get_subscription(user_id, room_id, thread_root)
+		.await;
+
+	if let Some(cause) = automatic {
+		let cause = self
+			.services
+			.timeline
+			.get_pdu_count(cause)
+			.await?
+			.into_normal()
+			.into_unsigned();
+
+		if existing.is_some_and(|existing| existing.subscribed) {
+			return Ok(());
+		}
+
+		if unsubscribed_since(existing.as_ref(), cause) {
+			return Err!(Request(ConflictingUnsubscription(
+				"The thread was unsubscribed from after the automatic subscription's event."
+			)));
+		}
+	} else if existing.is_some_and(|existing| existing.subscribed && !existing.automatic) {
+		return Ok(());
+	}
+
+	let subscription = (true, automatic.is_some());
+	self.set_subscription(user_id, room_id, thread_root, existing, subscription);
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+pub async fn unsubscribe(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_root: &EventId,
+) -> Result {
+	let key = (user_id.to_owned(), room_id.to_owned());
+	let _lock = self.subscription_mutex.lock(&key).await;
+
+	let existing = self
+		.get_subscription(user_id, room_id, thread_root)
+		.await;
+
+	if !existing.is_some_and(|existing| existing.subscribed) {
+		return Err!(Request(NotFound("Not subscribed to this thread.")));
+	}
+
+	self.set_subscription(user_id, room_id, thread_root, existing, (false, false));
+
+	Ok(())
+}
+
+/// Subscribes the participants of a threaded event to its thread: the
+/// event's sender and the sender of the thread's root, where local.
+#[implement(super::Service)]
+pub async fn subscribe_participants<E>(&self, event: &E)
+where
+	E: Event,
+{
+	let Ok(ExtractThreadRelation { relates_to }) = event.get_content() else {
+		return;
+	};
+
+	if relates_to.rel_type != RelationType::Thread {
+		return;
+	}
+
+	let thread_root = &relates_to.event_id;
+	self.subscribe_participant(event.sender(), thread_root, event)
+		.await;
+
+	if let Ok(root) = self.services.timeline.get_pdu(thread_root).await {
+		self.subscribe_participant(root.sender(), thread_root, &root)
+			.await;
+	}
+}
+
+#[implement(super::Service)]
+async fn subscribe_participant<E>(&self, user_id: &UserId, thread_root: &EventId, event: &E)
+where
+	E: Event,
+{
+	if !self.services.globals.user_is_local(user_id) {
+		return;
+	}
+
+	if let Err(e) = self
+		.subscribe(user_id, event.room_id(), thread_root, Some(event.event_id()))
+		.await
+	{
+		debug!(%user_id, %thread_root, "Not subscribing participant: {e}");
+	}
+}
+
+/// The user's subscription changes after stream position `since`, through
+/// `until`, oldest first.
+#[implement(super::Service)]
+pub fn subscription_changes<'a>(
+	&'a self,
+	user_id: &'a UserId,
+	since: u64,
+	until: u64,
+) -> impl Stream<Item = (u64, SubscriptionChange)> + Send + 'a {
+	self.db
+		.useridcount_threadsubscription
+		.stream_from(&(user_id, since.saturating_add(1)))
+		.ignore_err()
+		.ready_take_while(
+			move |((user, count), _): &((&UserId, u64), Json<SubscriptionChange>)| {
+				*user == user_id && *count <= until
+			},
+		)
+		.map(|((_, count), Json(change))| (count, change))
+}
+
+/// The user's subscription changes before stream position `before`, after
+/// `since`, newest first.
+#[implement(super::Service)]
+pub fn subscription_changes_rev<'a>(
+	&'a self,
+	user_id: &'a UserId,
+	since: u64,
+	before: u64,
+) -> impl Stream<Item = (u64, SubscriptionChange)> + Send + 'a {
+	self.db
+		.useridcount_threadsubscription
+		.rev_stream_from(&(user_id, before.saturating_sub(1)))
+		.ignore_err()
+		.ready_take_while(
+			move |((user, count), _): &((&UserId, u64), Json<SubscriptionChange>)| {
+				*user == user_id && *count > since
+			},
+		)
+		.map(|((_, count), Json(change))| (count, change))
+}
+
+/// Records a change from the `previous` subscription, read under the
+/// subscription lock of the user and room which the caller holds.
+#[implement(super::Service)]
+fn set_subscription(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_root: &EventId,
+	previous: Option<Subscription>,
+	(subscribed, automatic): (bool, bool),
+) {
+	let key = (user_id, room_id, thread_root);
+	let next_count = self.services.globals.next_count();
+	let bump_stamp = *next_count;
+	let subscription = Subscription { subscribed, automatic, bump_stamp };
+	let change = SubscriptionChange {
+		room_id: room_id.to_owned(),
+		thread_root: thread_root.to_owned(),
+		subscribed,
+		automatic,
+	};
+
+	// The changes stream holds only the latest change to each thread.
+	let mut txn = self.services.db.txn();
+	if let Some(previous) = previous {
+		txn.del(&self.db.useridcount_threadsubscription, (user_id, previous.bump_stamp));
+	}
+
+	txn.put(&self.db.userroomthreadid_subscription, key, Json(subscription));
+	txn.put(&self.db.useridcount_threadsubscription, (user_id, bump_stamp), Json(change));
+	txn.execute();
+
+	drop(next_count);
+}
+
+/// Whether an unsubscription newer than the event at stream position `cause`
+/// stands.
+pub(super) fn unsubscribed_since(existing: Option<&Subscription>, cause: u64) -> bool {
+	existing.is_some_and(|existing| !existing.subscribed && existing.bump_stamp >= cause)
+}
@@ -34,3 +34,21 @@ fn latest_count_value_round_trips() {
 		assert_eq!(read, count);
 	}
 }
+
+#[test]
+fn automatic_subscription_conflicts_only_with_a_later_unsubscription() {
+	use super::{Subscription, subscriptions::unsubscribed_since};
+
+	let unsubscribed = Subscription {
+		subscribed: false,
+		automatic: false,
+		bump_stamp: 10,
+	};
+
+	assert!(unsubscribed_since(Some(&unsubscribed), 9));
+	assert!(!unsubscribed_since(Some(&unsubscribed), 11));
+	assert!(!unsubscribed_since(None, 9));
+
+	let subscribed = Subscription { subscribed: true, ..unsubscribed };
+	assert!(!unsubscribed_since(Some(&subscribed), 9));
+}
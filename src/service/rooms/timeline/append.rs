@@ -221,6 +221,12 @@ pub async fn append_pdu<'a, Leafs>(
 			.ok();
 	}
 
+	// MSC4306: participants follow the thread before its push rules run.
+	self.services
+		.threads
+		.subscribe_participants(pdu)
+		.await;
+
 	self.services
 		.pusher
 		.append_pdu(pdu_id, pdu)
@@ -325,7 +331,11 @@ async fn append_pdu_effects(
 				{
 					self.services
 						.admin
-						.command(body, Some((pdu.event_id()).into()))
+						.command(
+							body,
+							Some((pdu.event_id()).into()),
+							Some(pdu.sender().to_owned()),
+						)
 						.await?;
 				}
 			}
@@ -149,6 +149,27 @@ pub async fn build_and_append_pdu(
 	Ok(pdu.event_id().to_owned())
 }
 
+/// Asks the spam checkers whether a local user may send an event. Callers
+/// check before taking the room's state lock, so a slow checker does not hold
+/// up the room.
+#[implement(super::Service)]
+#[tracing::instrument(skip_all, level = "debug")]
+pub async fn check_pdu_for_spam(
+	&self,
+	pdu_builder: &PduBuilder,
+	sender: &UserId,
+	room_id: &RoomId,
+) -> Result {
+	if sender == self.services.globals.server_user {
+		return Ok(());
+	}
+
+	self.services
+		.spam_checker
+		.check_event_for_spam(sender, room_id, pdu_builder)
+		.await
+}
+
 #[implement(super::Service)]
 #[tracing::instrument(skip_all, level = "debug")]
 async fn sanitize_member_authorisation(
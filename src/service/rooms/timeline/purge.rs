@@ -1,16 +1,26 @@
-use futures::TryStreamExt;
-use ruma::{RoomId, api::Direction, events::TimelineEventType};
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet, hash_map::Entry},
+	iter::once,
+};
+
+use futures::{StreamExt, TryStreamExt};
+use ruma::{
+	EventId, OwnedMxcUri, OwnedRoomId, OwnedUserId, RoomId, api::Direction,
+	events::TimelineEventType,
+};
+use serde_json::Value;
 use tuwunel_core::{
-	Result, implement,
+	Result, debug_warn, implement,
 	matrix::{
 		Event,
 		pdu::{PduCount, PduEvent},
 	},
 	trace,
-	utils::stream::TryReadyExt,
+	utils::{ReadyExt, stream::TryReadyExt, u64_from_u8},
 };
 
 use super::{ExtractBody, RawPduId, bias_count};
+use crate::rooms::short::ShortRoomId;
 
 /// Selectively purges room history strictly before `until` in stream order,
 /// returning the number of events removed. State events are always preserved,
@@ -24,6 +34,26 @@ pub async fn purge_history(
 	room_id: &RoomId,
 	until: PduCount,
 	delete_local_events: bool,
+) -> Result<usize> {
+	self.purge(room_id, until, delete_local_events, false)
+		.await
+}
+
+/// Purges every non-state event strictly before `until` together with the
+/// media its senders uploaded into it, as required once history outlives a
+/// retention policy.
+#[implement(super::Service)]
+pub async fn purge_expired_history(&self, room_id: &RoomId, until: PduCount) -> Result<usize> {
+	self.purge(room_id, until, true, true).await
+}
+
+#[implement(super::Service)]
+async fn purge(
+	&self,
+	room_id: &RoomId,
+	until: PduCount,
+	delete_local_events: bool,
+	delete_media: bool,
 ) -> Result<usize> {
 	let shortroomid = self
 		.services
@@ -37,56 +67,182 @@ pub async fn purge_history(
 
 	let prefix = start.shortroomid();
 
-	self.db
+	let (purged, media) = self
+		.db
 		.pduid_pdu
 		.raw_stream_from(&start)
 		.ready_try_take_while(move |kv| {
 			let (key, _) = *kv;
 			Ok(key.starts_with(&prefix) && RawPduId::from(key).pdu_count() < until)
 		})
-		.try_fold(0_usize, async |purged, (key, value)| {
+		.try_fold((0_usize, Vec::new()), async |(purged, mut media), (key, value)| {
 			let pdu = serde_json::from_slice::<PduEvent>(value)?;
 
 			if pdu.state_key.is_some()
 				|| (!delete_local_events && self.services.globals.user_is_local(&pdu.sender))
 			{
-				return Ok(purged);
+				return Ok((purged, media));
 			}
 
-			let mut txn = self.db.db.txn();
-
 			let raw_id = RawPduId::from(key);
-			let count = raw_id.pdu_count();
-			let event_id = pdu.event_id.clone();
-			let ts: u64 = pdu.origin_server_ts.into();
+			self.delete_pdu(shortroomid, room_id, raw_id, &pdu)
+				.await;
 
-			txn.del_raw(&self.db.pduid_pdu, key);
-			txn.del_raw(&self.db.eventid_pduid, &event_id);
-			txn.del_raw(&self.db.eventid_outlierpdu, &event_id);
+			if delete_media && let Ok(content) = pdu.get_content::<Value>() {
+				media.extend(
+					media_urls(&content).map(|url| (pdu.sender.clone(), OwnedMxcUri::from(url))),
+				);
+			}
 
-			let room_id_ts_id = (room_id, ts, bias_count(raw_id.count()));
-			txn.del(&self.db.roomid_tscount_pducount, room_id_ts_id);
+			trace!(event_id = ?pdu.event_id, ?room_id, "Purged");
 
-			txn.execute();
+			Ok((purged.saturating_add(1), media))
+		})
+		.await?;
 
-			if pdu.kind == TimelineEventType::RoomMessage
-				&& let Ok(ExtractBody { body: Some(body) }) = pdu.get_content()
-			{
-				self.services
-					.search
-					.deindex_pdu(shortroomid, &raw_id, &body);
+	self.purge_media(room_id, media).await;
+
+	Ok(purged)
+}
+
+/// Withdraws an event from the room's timeline, keeping it as an outlier so it
+/// still resolves as an event other events reference.
+#[implement(super::Service)]
+pub async fn withdraw_pdu(&self, event_id: &EventId) -> Result {
+	let pdu_id = self.get_pdu_id(event_id).await?;
+	let pdu_json = self.get_pdu_json_from_id(&pdu_id).await?;
+	let pdu = self.get_pdu_from_id(&pdu_id).await?;
+	let shortroomid = u64_from_u8(&pdu_id.shortroomid());
+
+	self.delete_pdu(shortroomid, pdu.room_id(), pdu_id, &pdu)
+		.await;
+
+	self.add_pdu_outlier(event_id, &pdu_json);
+
+	Ok(())
+}
+
+/// Removes one event from the timeline and the indexes derived from it.
+#[implement(super::Service)]
+async fn delete_pdu(
+	&self,
+	shortroomid: ShortRoomId,
+	room_id: &RoomId,
+	raw_id: RawPduId,
+	pdu: &PduEvent,
+) {
+	let mut txn = self.db.db.txn();
+
+	let count = raw_id.pdu_count();
+	let event_id = pdu.event_id();
+	let ts: u64 = pdu.origin_server_ts.into();
+
+	txn.del_raw(&self.db.pduid_pdu, raw_id.as_bytes());
+	txn.del_raw(&self.db.eventid_pduid, event_id);
+	txn.del_raw(&self.db.eventid_outlierpdu, event_id);
+
+	let room_id_ts_id = (room_id, ts, bias_count(raw_id.count()));
+	txn.del(&self.db.roomid_tscount_pducount, room_id_ts_id);
+
+	txn.execute();
+
+	if pdu.kind == TimelineEventType::RoomMessage
+		&& let Ok(ExtractBody { body: Some(body) }) = pdu.get_content()
+	{
+		self.services
+			.search
+			.deindex_pdu(shortroomid, &raw_id, &body);
+	}
+
+	self.services
+		.pdu_metadata
+		.purge_event_relations(shortroomid, count, room_id, event_id)
+		.await;
+
+	self.services.retention.purge_original(event_id);
+}
+
+/// Deletes the media referenced by purged events when the event's sender
+/// uploaded it. Protected media is kept, as is media still referenced by the
+/// remaining history of this room or of any other room the uploader joined or
+/// left, so a file shared again elsewhere survives the purge.
+#[implement(super::Service)]
+async fn purge_media(&self, room_id: &RoomId, referenced: Vec<(OwnedUserId, OwnedMxcUri)>) {
+	let mut uploaded: BTreeMap<OwnedUserId, BTreeSet<OwnedMxcUri>> = BTreeMap::new();
+	for (sender, mxc) in referenced {
+		let Ok(parts) = mxc.parts() else {
+			continue;
+		};
+
+		let media = &self.services.media;
+		if media.uploader(&parts).await.as_ref() != Some(&sender)
+			|| media
+				.quarantine_state(&parts)
+				.await
+				.safe_from_quarantine
+		{
+			continue;
+		}
+
+		uploaded.entry(sender).or_default().insert(mxc);
+	}
+
+	// The media each room still refers to, scanned at most once per purge
+	// however many uploaders share the room.
+	let mut room_media: HashMap<OwnedRoomId, HashSet<OwnedMxcUri>> = HashMap::new();
+	for (uploader, mut mxcs) in uploaded {
+		let state_cache = &self.services.state_cache;
+		let rooms: Vec<OwnedRoomId> = state_cache
+			.rooms_joined(&uploader)
+			.chain(state_cache.rooms_left(&uploader))
+			.ready_filter(|other| *other != room_id)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		for room in once(room_id).chain(rooms.iter().map(AsRef::as_ref)) {
+			if mxcs.is_empty() {
+				break;
 			}
 
-			self.services
-				.pdu_metadata
-				.purge_event_relations(shortroomid, count, room_id, &event_id)
-				.await;
+			let local = match room_media.entry(room.to_owned()) {
+				| Entry::Occupied(entry) => entry.into_mut(),
+				| Entry::Vacant(entry) => {
+					let (local, _) = self.services.media.room_media(room).await;
+					entry.insert(local.into_iter().collect())
+				},
+			};
 
-			self.services.retention.purge_original(&event_id);
+			mxcs.retain(|mxc| !local.contains(mxc));
+		}
 
-			trace!(?event_id, ?room_id, "Purged");
+		for mxc in mxcs {
+			let Ok(parts) = mxc.parts() else {
+				continue;
+			};
 
-			Ok(purged.saturating_add(1))
-		})
-		.await
+			if let Err(e) = self.services.media.delete(&parts).await {
+				debug_warn!(%mxc, %room_id, "Failed to delete purged media: {e}");
+			}
+		}
+	}
+}
+
+/// MXC URIs of the main file, thumbnail and encrypted attachments of a message.
+fn media_urls(content: &Value) -> impl Iterator<Item = &str> {
+	let info = content.get("info");
+
+	[
+		content.get("url"),
+		content
+			.get("file")
+			.and_then(|file| file.get("url")),
+		info.and_then(|info| info.get("thumbnail_url")),
+		info.and_then(|info| info.get("thumbnail_file"))
+			.and_then(|file| file.get("url")),
+	]
+	.into_iter()
+	.flatten()
+	.filter_map(Value::as_str)
+	.filter(|url| url.starts_with("mxc://"))
 }
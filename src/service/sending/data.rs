@@ -1,4 +1,9 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+	collections::BTreeMap,
+	fmt::Debug,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
 
 use futures::{Stream, StreamExt, stream::iter};
 use ruma::{OwnedServerName, ServerName, UserId};
@@ -17,10 +22,17 @@
 pub(super) type QueueItem = (Key, SendingEvent);
 pub(super) type Key = Vec<u8>;
 
+type Depths = BTreeMap<Destination, usize>;
+
+/// How long queue depths are reused; computing them scans the whole queue.
+const DEPTHS_TTL: Duration = Duration::from_secs(30);
+
 pub struct Data {
 	servercurrentevent_data: Arc<Map>,
 	servernameevent_data: Arc<Map>,
 	servername_educount: Arc<Map>,
+	queued_depths: Mutex<Option<(Instant, Depths)>>,
+	active_depths: Mutex<Option<(Instant, Depths)>>,
 	pub(super) db: Arc<Database>,
 	services: Arc<crate::services::OnceServices>,
 }
@@ -32,6 +44,8 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 			servercurrentevent_data: db["servercurrentevent_data"].clone(),
 			servernameevent_data: db["servernameevent_data"].clone(),
 			servername_educount: db["servername_educount"].clone(),
+			queued_depths: Mutex::default(),
+			active_depths: Mutex::default(),
 			db: args.db.clone(),
 			services: args.services.clone(),
 		}
@@ -201,6 +215,18 @@ pub fn queued_requests(
 			})
 	}
 
+	/// Number of items waiting in each destination's queue, as of at most
+	/// `DEPTHS_TTL` ago.
+	pub async fn queued_depths(&self) -> Depths {
+		cached_depths(&self.queued_depths, &self.servernameevent_data).await
+	}
+
+	/// Number of items in flight to each destination, as of at most
+	/// `DEPTHS_TTL` ago.
+	pub async fn active_depths(&self) -> Depths {
+		cached_depths(&self.active_depths, &self.servercurrentevent_data).await
+	}
+
 	/// Streams queued push destinations with a pending badge refresh.
 	///
 	/// Returned destinations are owned and may safely cross cursor advances.
@@ -234,6 +260,39 @@ pub async fn get_latest_educount(&self, server_name: &ServerName) -> u64 {
 	}
 }
 
+async fn cached_depths(cache: &Mutex<Option<(Instant, Depths)>>, map: &Arc<Map>) -> Depths {
+	let cached = cache
+		.lock()
+		.expect("locked")
+		.as_ref()
+		.filter(|(computed, _)| computed.elapsed() < DEPTHS_TTL)
+		.map(|(_, depths)| depths.clone());
+
+	if let Some(depths) = cached {
+		return depths;
+	}
+
+	let depths = depths(map).await;
+	cache
+		.lock()
+		.expect("locked")
+		.replace((Instant::now(), depths.clone()));
+
+	depths
+}
+
+async fn depths(map: &Arc<Map>) -> Depths {
+	map.raw_stream()
+		.ignore_err()
+		.ready_filter_map(|(key, val)| parse_servercurrentevent(key, val).ok())
+		.ready_fold(BTreeMap::new(), |mut depths, (dest, _)| {
+			let depth = depths.entry(dest).or_insert(0_usize);
+			*depth = depth.saturating_add(1);
+			depths
+		})
+		.await
+}
+
 pub(super) fn parse_servercurrentevent(
 	key: &[u8],
 	value: &[u8],
@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
 use ruma::{api::federation::discovery::VerifyKey, serde::Base64, signatures::Ed25519KeyPair};
-use tuwunel_core::{Result, debug, debug_info, err, error, utils, utils::string_from_bytes};
+use tuwunel_core::{
+	Result, debug, debug_info, err, error, utils,
+	utils::{string_from_bytes, time::now_millis, u64_from_bytes},
+};
 use tuwunel_database::Database;
 
 use super::VerifyKeys;
@@ -22,6 +25,27 @@ pub(super) fn init(db: &Arc<Database>) -> Result<(Box<Ed25519KeyPair>, VerifyKey
 	Ok((keypair, verify_keys))
 }
 
+/// Replaces the stored keypair with a newly generated one.
+pub(super) fn rotate(db: &Arc<Database>) -> Result<(Box<Ed25519KeyPair>, VerifyKeys)> {
+	create(db)?;
+	init(db)
+}
+
+/// When the stored keypair was generated, in milliseconds. A keypair kept
+/// from before this was recorded counts as generated now.
+pub(super) fn created_at(db: &Arc<Database>) -> u64 {
+	let global = &db["global"];
+	global
+		.get_blocking(b"keypair_created")
+		.ok()
+		.and_then(|val| u64_from_bytes(&val).ok())
+		.unwrap_or_else(|| {
+			let now = now_millis();
+			global.insert(b"keypair_created", now.to_be_bytes());
+			now
+		})
+}
+
 fn load(db: &Arc<Database>) -> Result<Box<Ed25519KeyPair>> {
 	let (version, key) = db["global"]
 		.get_blocking(b"keypair")
@@ -53,6 +77,7 @@ fn create(db: &Arc<Database>) -> Result<(String, Vec<u8>)> {
 
 	let value: (String, Vec<u8>) = (id, keypair.to_vec());
 	db["global"].raw_put(b"keypair", &value);
+	db["global"].insert(b"keypair_created", now_millis().to_be_bytes());
 
 	Ok(value)
 }
@@ -61,4 +86,5 @@ fn create(db: &Arc<Database>) -> Result<(String, Vec<u8>)> {
 fn remove(db: &Arc<Database>) {
 	let global = &db["global"];
 	global.remove(b"keypair");
+	global.remove(b"keypair_created");
 }
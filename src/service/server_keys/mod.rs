@@ -1,16 +1,23 @@
 mod acquire;
 mod get;
 mod keypair;
+mod notary;
 mod request;
+mod rotate;
 mod sign;
 mod verify;
 
-use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::{Arc, Mutex, RwLock},
+	time::{Duration, Instant},
+};
 
+use async_trait::async_trait;
 use futures::StreamExt;
 use ruma::{
-	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedServerSigningKeyId, ServerName,
-	ServerSigningKeyId,
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedServerName, OwnedServerSigningKeyId,
+	ServerName, ServerSigningKeyId,
 	api::federation::discovery::{ServerSigningKeys, VerifyKey},
 	room_version_rules::RoomVersionRules,
 	serde::Raw,
@@ -24,14 +31,21 @@
 use tuwunel_database::{Deserialized, Json, Map};
 
 pub struct Service {
-	keypair: Box<Ed25519KeyPair>,
-	verify_keys: VerifyKeys,
+	active: RwLock<Active>,
 	minimum_valid: Duration,
+	notary_fetched: Mutex<HashMap<OwnedServerName, Instant>>,
 	services: Arc<crate::services::OnceServices>,
 	db: Data,
 }
 
+/// The keypair this server signs with, and its verify key.
+struct Active {
+	keypair: Arc<Ed25519KeyPair>,
+	verify_keys: VerifyKeys,
+}
+
 struct Data {
+	server_signedkeys: Arc<Map>,
 	server_signingkeys: Arc<Map>,
 }
 
@@ -39,6 +53,7 @@ struct Data {
 pub type PubKeyMap = PublicKeyMap;
 pub type PubKeys = PublicKeySet;
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		let minimum_valid = Duration::from_hours(1);
@@ -47,38 +62,49 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		debug_assert!(verify_keys.len() == 1, "only one active verify_key supported");
 
 		Ok(Arc::new(Self {
-			keypair,
-			verify_keys,
+			active: RwLock::new(Active { keypair: keypair.into(), verify_keys }),
 			minimum_valid,
+			notary_fetched: Mutex::default(),
 			services: args.services.clone(),
 			db: Data {
+				server_signedkeys: args.db["server_signedkeys"].clone(),
 				server_signingkeys: args.db["server_signingkeys"].clone(),
 			},
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result { self.rotate_on_schedule().await }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 #[implement(Service)]
 #[inline]
 #[must_use]
-pub fn keypair(&self) -> &Ed25519KeyPair { &self.keypair }
+pub fn keypair(&self) -> Arc<Ed25519KeyPair> {
+	self.active
+		.read()
+		.expect("locked for reading")
+		.keypair
+		.clone()
+}
 
 #[implement(Service)]
 #[inline]
 #[must_use]
-pub fn active_key_id(&self) -> &ServerSigningKeyId { self.active_verify_key().0 }
+pub fn active_key_id(&self) -> OwnedServerSigningKeyId { self.active_verify_key().0 }
 
 #[implement(Service)]
-#[inline]
 #[must_use]
-pub fn active_verify_key(&self) -> (&ServerSigningKeyId, &VerifyKey) {
-	debug_assert!(self.verify_keys.len() <= 1, "more than one active verify_key");
-	self.verify_keys
+pub fn active_verify_key(&self) -> (OwnedServerSigningKeyId, VerifyKey) {
+	let active = self.active.read().expect("locked for reading");
+
+	debug_assert!(active.verify_keys.len() <= 1, "more than one active verify_key");
+	active
+		.verify_keys
 		.iter()
 		.next()
-		.map(|(id, key)| (id.as_ref(), key))
+		.map(|(id, key)| (id.clone(), key.clone()))
 		.expect("missing active verify_key")
 }
 
@@ -165,7 +191,8 @@ pub async fn verify_keys_for(&self, origin: &ServerName) -> VerifyKeys {
 		.unwrap_or(BTreeMap::new());
 
 	if self.services.globals.server_is_ours(origin) {
-		keys.extend(self.verify_keys.clone());
+		let active = self.active.read().expect("locked for reading");
+		keys.extend(active.verify_keys.clone());
 	}
 
 	keys
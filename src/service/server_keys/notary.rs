@@ -0,0 +1,193 @@
+use std::{
+	collections::BTreeMap,
+	time::{Duration, Instant, SystemTime},
+};
+
+use ruma::{
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, ServerName, ServerSigningKeyId, Signatures,
+	api::federation::discovery::{OldVerifyKey, ServerSigningKeys},
+	canonical_json::to_canonical_object,
+	serde::Raw,
+	signatures::verify_json,
+};
+use serde_json::value::to_raw_value;
+use tuwunel_core::{Result, debug_warn, implement, utils::timepoint_from_now};
+use tuwunel_database::{Deserialized, Json};
+
+use super::{PubKeyMap, PubKeys, key_exists};
+
+#[cfg(test)]
+mod tests;
+
+/// How long a notary query waits before fetching an origin's keys again,
+/// whether the last fetch succeeded or failed.
+const REFETCH_INTERVAL: Duration = Duration::from_mins(5);
+
+/// This server's signing keys, unsigned: the active key, with earlier keys
+/// as expired when they were rotated out. Matrix does not support
+/// invalidating public keys, so the active key is renewed for as long as it
+/// is served.
+#[implement(super::Service)]
+pub async fn local_server_keys(&self) -> ServerSigningKeys {
+	let server_name = self.services.globals.server_name();
+	let (active_key_id, active_key) = self.active_verify_key();
+	let (stored_keys, rotated_keys) = self
+		.signing_keys_for(server_name)
+		.await
+		.map(|keys| (keys.verify_keys, keys.old_verify_keys))
+		.unwrap_or_default();
+
+	let mut old_verify_keys: BTreeMap<_, _> = stored_keys
+		.into_iter()
+		.map(|(id, key)| (id, OldVerifyKey::new(expires_ts(), key.key)))
+		.chain(rotated_keys)
+		.collect();
+
+	old_verify_keys.remove(&active_key_id);
+
+	ServerSigningKeys {
+		verify_keys: [(active_key_id, active_key)].into(),
+		old_verify_keys,
+		server_name: server_name.to_owned(),
+		valid_until_ts: valid_until_ts(),
+		signatures: Signatures::new(),
+	}
+}
+
+/// The keys of `origin` for a notary query, signed by the origin and by this
+/// server. The cached response is served unless it lacks one of `key_ids` or
+/// expires before `minimum_valid_until_ts`; it is then fetched again from the
+/// origin, unless it was fetched within the last few minutes, and served as it
+/// was when the origin cannot be reached.
+#[implement(super::Service)]
+pub async fn notary_keys<'a, I>(
+	&self,
+	origin: &ServerName,
+	key_ids: I,
+	minimum_valid_until_ts: MilliSecondsSinceUnixEpoch,
+) -> Result<Raw<ServerSigningKeys>>
+where
+	I: Iterator<Item = &'a ServerSigningKeyId> + Send,
+{
+	let mut keys = if self.services.globals.server_is_ours(origin) {
+		to_canonical_object(self.local_server_keys().await)?
+	} else {
+		self.origin_signed_keys(origin, key_ids, minimum_valid_until_ts)
+			.await?
+	};
+
+	self.sign_json(&mut keys)?;
+
+	Ok(Raw::from_json(to_raw_value(&keys)?))
+}
+
+#[implement(super::Service)]
+async fn origin_signed_keys<'a, I>(
+	&self,
+	origin: &ServerName,
+	key_ids: I,
+	minimum_valid_until_ts: MilliSecondsSinceUnixEpoch,
+) -> Result<CanonicalJsonObject>
+where
+	I: Iterator<Item = &'a ServerSigningKeyId> + Send,
+{
+	let cached: Result<ServerSigningKeys> = self
+		.db
+		.server_signedkeys
+		.get(origin)
+		.await
+		.deserialized();
+
+	let current = cached
+		.as_ref()
+		.is_ok_and(|keys| serves_query(keys, key_ids, minimum_valid_until_ts));
+
+	if !current && self.notary_may_fetch(origin) {
+		match self.server_request(origin).await {
+			| Ok(keys) => self.add_signing_keys(keys).await,
+			| Err(e) => debug_warn!(%origin, "Serving cached keys to notary query: {e}"),
+		}
+	}
+
+	self.db
+		.server_signedkeys
+		.get(origin)
+		.await
+		.deserialized()
+}
+
+/// Whether the origin's keys may be fetched for a notary query, recording the
+/// attempt when they may; each origin is fetched at most once per
+/// `REFETCH_INTERVAL`.
+#[implement(super::Service)]
+fn notary_may_fetch(&self, origin: &ServerName) -> bool {
+	let now = Instant::now();
+	let mut fetched = self.notary_fetched.lock().expect("locked");
+
+	fetched.retain(|_, last| now.duration_since(*last) < REFETCH_INTERVAL);
+	if fetched.contains_key(origin) {
+		return false;
+	}
+
+	fetched.insert(origin.to_owned(), now);
+
+	true
+}
+
+/// Keeps a key response as the origin sent it, for serving to notary
+/// queries, once it is verified to be signed by the origin.
+#[implement(super::Service)]
+pub(super) fn cache_signed_keys(
+	&self,
+	response: &Raw<ServerSigningKeys>,
+	keys: &ServerSigningKeys,
+) {
+	let origin = &keys.server_name;
+	if let Err(e) = verify_self_signed(response, keys) {
+		debug_warn!(%origin, "Keys are not signed by their origin: {e}");
+		return;
+	}
+
+	self.db
+		.server_signedkeys
+		.raw_put(origin, Json(response));
+}
+
+/// Whether the cached keys answer a notary query without fetching them again:
+/// they hold every one of `key_ids` and are valid until at least
+/// `minimum_valid_until_ts`.
+fn serves_query<'a, I>(
+	keys: &ServerSigningKeys,
+	mut key_ids: I,
+	minimum_valid_until_ts: MilliSecondsSinceUnixEpoch,
+) -> bool
+where
+	I: Iterator<Item = &'a ServerSigningKeyId>,
+{
+	keys.valid_until_ts >= minimum_valid_until_ts
+		&& key_ids.all(|key_id| key_exists(keys, key_id))
+}
+
+fn verify_self_signed(response: &Raw<ServerSigningKeys>, keys: &ServerSigningKeys) -> Result {
+	let object: CanonicalJsonObject = serde_json::from_str(response.json().get())?;
+	let pubkeys: PubKeys = keys
+		.verify_keys
+		.iter()
+		.map(|(key_id, key)| (key_id.to_string(), key.key.clone()))
+		.collect();
+
+	let pubkey_map: PubKeyMap = [(keys.server_name.to_string(), pubkeys)].into();
+
+	verify_json(&pubkey_map, &object).map_err(Into::into)
+}
+
+fn valid_until_ts() -> MilliSecondsSinceUnixEpoch {
+	let dur = Duration::from_hours(168);
+	let timepoint = timepoint_from_now(dur).expect("SystemTime should not overflow");
+	MilliSecondsSinceUnixEpoch::from_system_time(timepoint).expect("UInt should not overflow")
+}
+
+fn expires_ts() -> MilliSecondsSinceUnixEpoch {
+	let timepoint = SystemTime::now();
+	MilliSecondsSinceUnixEpoch::from_system_time(timepoint).expect("UInt should not overflow")
+}
@@ -0,0 +1,143 @@
+use ruma::{
+	CanonicalJsonObject, CanonicalJsonValue, MilliSecondsSinceUnixEpoch, OwnedServerSigningKeyId,
+	api::federation::discovery::{OldVerifyKey, ServerSigningKeys, VerifyKey},
+	canonical_json::to_canonical_object,
+	int,
+	serde::{Base64, Raw},
+	server_name,
+	signatures::{Ed25519KeyPair, KeyPair, sign_json},
+	uint,
+};
+use serde_json::value::to_raw_value;
+
+use super::{serves_query, verify_self_signed};
+
+const ORIGIN: &str = "origin.example";
+
+fn keypair(version: &str) -> Ed25519KeyPair {
+	let der = Ed25519KeyPair::generate();
+	Ed25519KeyPair::from_der(&der, version.to_owned()).expect("key pair should be generated")
+}
+
+fn key_id(version: &str) -> OwnedServerSigningKeyId {
+	format!("ed25519:{version}")
+		.try_into()
+		.expect("key ID should parse")
+}
+
+fn verify_key(keypair: &Ed25519KeyPair) -> VerifyKey {
+	VerifyKey {
+		key: Base64::new(keypair.public_key().to_vec()),
+	}
+}
+
+fn origin_keys(keypair: &Ed25519KeyPair) -> ServerSigningKeys {
+	let valid_until_ts = MilliSecondsSinceUnixEpoch(uint!(2_000));
+	let mut keys =
+		ServerSigningKeys::new(server_name!("origin.example").to_owned(), valid_until_ts);
+	keys.verify_keys
+		.insert(key_id(keypair.version()), verify_key(keypair));
+
+	keys
+}
+
+fn signed(
+	keys: &ServerSigningKeys,
+	signers: &[(&str, &Ed25519KeyPair)],
+) -> Raw<ServerSigningKeys> {
+	let mut object = to_canonical_object(keys).expect("keys should serialize");
+	for (entity, keypair) in signers {
+		sign_json(entity, *keypair, &mut object).expect("keys should sign");
+	}
+
+	Raw::from_json(to_raw_value(&object).expect("keys should serialize"))
+}
+
+#[test]
+fn accepts_keys_signed_by_origin() {
+	let origin = keypair("a");
+	let keys = origin_keys(&origin);
+	let notary = keypair("n");
+
+	verify_self_signed(&signed(&keys, &[(ORIGIN, &origin)]), &keys)
+		.expect("origin's own signature should verify");
+
+	verify_self_signed(&signed(&keys, &[(ORIGIN, &origin), ("notary.example", &notary)]), &keys)
+		.expect("another signature alongside the origin's should verify");
+}
+
+#[test]
+fn rejects_keys_the_origin_did_not_sign() {
+	let origin = keypair("a");
+	let keys = origin_keys(&origin);
+
+	verify_self_signed(&signed(&keys, &[]), &keys).expect_err("unsigned keys should be refused");
+
+	let notary = keypair("n");
+	verify_self_signed(&signed(&keys, &[("notary.example", &notary)]), &keys)
+		.expect_err("keys signed only by another server should be refused");
+
+	let forger = keypair("a");
+	verify_self_signed(&signed(&keys, &[(ORIGIN, &forger)]), &keys)
+		.expect_err("keys signed with another key of the same ID should be refused");
+}
+
+#[test]
+fn rejects_keys_altered_after_signing() {
+	let origin = keypair("a");
+	let keys = origin_keys(&origin);
+	let response = signed(&keys, &[(ORIGIN, &origin)]);
+
+	let mut object: CanonicalJsonObject =
+		serde_json::from_str(response.json().get()).expect("response should parse");
+	object.insert("valid_until_ts".to_owned(), CanonicalJsonValue::Integer(int!(9_000)));
+
+	let mut altered = keys;
+	altered.valid_until_ts = MilliSecondsSinceUnixEpoch(uint!(9_000));
+	let altered_response = Raw::from_json(to_raw_value(&object).expect("keys should serialize"));
+
+	verify_self_signed(&altered_response, &altered)
+		.expect_err("keys altered after the origin signed them should be refused");
+}
+
+#[test]
+fn cached_keys_serve_query_when_current() {
+	let origin = keypair("a");
+	let keys = origin_keys(&origin);
+	let active = key_id("a");
+
+	assert!(serves_query(
+		&keys,
+		[&*active].into_iter(),
+		MilliSecondsSinceUnixEpoch(uint!(2_000))
+	));
+	assert!(serves_query(&keys, [].into_iter(), MilliSecondsSinceUnixEpoch(uint!(1_000))));
+}
+
+#[test]
+fn expiring_cached_keys_are_fetched_again() {
+	let keys = origin_keys(&keypair("a"));
+	let active = key_id("a");
+
+	assert!(!serves_query(
+		&keys,
+		[&*active].into_iter(),
+		MilliSecondsSinceUnixEpoch(uint!(2_001))
+	));
+}
+
+#[test]
+fn cached_keys_missing_a_key_are_fetched_again() {
+	let mut keys = origin_keys(&keypair("a"));
+	let (active, old, missing) = (key_id("a"), key_id("b"), key_id("c"));
+	let minimum = MilliSecondsSinceUnixEpoch(uint!(1_000));
+
+	assert!(!serves_query(&keys, [&*active, &*old].into_iter(), minimum));
+
+	let expired_ts = MilliSecondsSinceUnixEpoch(uint!(500));
+	keys.old_verify_keys
+		.insert(old.clone(), OldVerifyKey::new(expired_ts, verify_key(&keypair("b")).key));
+
+	assert!(serves_query(&keys, [&*active, &*old].into_iter(), minimum));
+	assert!(!serves_query(&keys, [&*active, &*missing].into_iter(), minimum));
+}
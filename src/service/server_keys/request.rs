@@ -165,13 +165,14 @@ pub async fn notary_request(
 pub async fn server_request(&self, target: &ServerName) -> Result<ServerSigningKeys> {
 	use get_server_keys::v2::Request;
 
-	let server_signing_key = self
+	let server_key = self
 		.services
 		.federation
 		.execute(target, Request::new())
-		.await
-		.map(|response| response.server_key)
-		.and_then(|key| key.deserialize().map_err(Into::into))?;
+		.await?
+		.server_key;
+
+	let server_signing_key: ServerSigningKeys = server_key.deserialize()?;
 
 	if server_signing_key.server_name != target {
 		return Err!(BadServerResponse(debug_warn!(
@@ -181,5 +182,7 @@ pub async fn server_request(&self, target: &ServerName) -> Result<ServerSigningK
 		)));
 	}
 
+	self.cache_signed_keys(&server_key, &server_signing_key);
+
 	Ok(server_signing_key)
 }
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedServerSigningKeyId,
+	api::federation::discovery::{OldVerifyKey, ServerSigningKeys},
+};
+use tokio::time::sleep;
+use tuwunel_core::{Result, implement, info, utils::time::now_millis, warn};
+
+use super::{Active, keypair};
+
+/// How often the active key's age is checked for scheduled rotation.
+const CHECK_INTERVAL: Duration = Duration::from_hours(1);
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Generates a new signing key and makes it active. The previous key is kept
+/// as an old verify key which expired now, so events it signed still verify,
+/// here and for servers fetching this server's keys. An `m.room.policy` event
+/// naming the previous key no longer matches this server until it is updated.
+#[implement(super::Service)]
+pub async fn rotate_keypair(&self) -> Result<OwnedServerSigningKeyId> {
+	let (old_key_id, old_key) = self.active_verify_key();
+	let server_name = self.services.globals.server_name();
+	let now = MilliSecondsSinceUnixEpoch::now();
+
+	let mut retired = ServerSigningKeys::new(server_name.to_owned(), now);
+	retired
+		.old_verify_keys
+		.insert(old_key_id.clone(), OldVerifyKey::new(now, old_key.key));
+
+	self.add_signing_keys(retired).await;
+
+	let (keypair, verify_keys) = keypair::rotate(&self.services.db)?;
+	*self.active.write().expect("locked for writing") =
+		Active { keypair: keypair.into(), verify_keys };
+
+	let new_key_id = self.active_key_id();
+	info!(%old_key_id, %new_key_id, "Rotated server signing key");
+
+	Ok(new_key_id)
+}
+
+#[implement(super::Service)]
+pub(super) async fn rotate_on_schedule(&self) -> Result {
+	loop {
+		let rotation_days = self.services.config.signing_key_rotation_days;
+		if rotation_days > 0 {
+			let age = now_millis().saturating_sub(keypair::created_at(&self.services.db));
+			if age >= rotation_days.saturating_mul(DAY_MS)
+				&& let Err(e) = self.rotate_keypair().await
+			{
+				warn!("Scheduled signing key rotation failed: {e}");
+			}
+		}
+
+		tokio::select! {
+			() = sleep(CHECK_INTERVAL) => {},
+			() = self.services.server.until_shutdown() => return Ok(()),
+		}
+	}
+}
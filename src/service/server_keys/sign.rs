@@ -78,7 +78,7 @@ pub fn hash_and_sign_event(
 	};
 
 	add_content_hash_to_event(object).map_err(map_err)?;
-	sign_event(server_name.as_str(), self.keypair(), object, &room_version_rules.redaction)
+	sign_event(server_name.as_str(), &*self.keypair(), object, &room_version_rules.redaction)
 		.map_err(map_err)
 }
 
@@ -88,5 +88,5 @@ pub fn sign_json(&self, object: &mut CanonicalJsonObject) -> Result {
 
 	let server_name = self.services.globals.server_name().as_str();
 
-	sign_json(server_name, self.keypair(), object).map_err(Into::into)
+	sign_json(server_name, &*self.keypair(), object).map_err(Into::into)
 }
@@ -12,12 +12,13 @@
 	account_data, admin, appservice, client, config, deactivate, emergency, federation, fetcher,
 	globals, key_backups,
 	manager::Manager,
-	media, membership, oauth, presence, profile, pusher, registration_tokens, rendezvous,
-	resolver,
+	media, membership, oauth, policy_lists, policy_server, presence, profile, pusher, ratelimit,
+	registration_tokens, rendezvous, reports, resolver,
 	rooms::{self, retention},
 	sending, sendmail, server_keys,
 	service::{Args, Service},
-	storage, sync, tasks, threepid, transaction_ids, uiaa, users,
+	spam_checker, storage, synapse, sync, tasks, threepid, transaction_ids, uiaa, user_directory,
+	users,
 };
 
 pub struct Services {
@@ -31,16 +32,22 @@ pub struct Services {
 	pub globals: Arc<globals::Service>,
 	pub key_backups: Arc<key_backups::Service>,
 	pub media: Arc<media::Service>,
+	pub policy_lists: Arc<policy_lists::Service>,
+	pub policy_server: Arc<policy_server::Service>,
 	pub presence: Arc<presence::Service>,
 	pub pusher: Arc<pusher::Service>,
+	pub ratelimit: Arc<ratelimit::Service>,
 	pub resolver: Arc<resolver::Service>,
 	pub alias: Arc<rooms::alias::Service>,
+	pub archive: Arc<rooms::archive::Service>,
 	pub auth_chain: Arc<rooms::auth_chain::Service>,
+	pub delayed_events: Arc<rooms::delayed_events::Service>,
 	pub delete: Arc<rooms::delete::Service>,
 	pub directory: Arc<rooms::directory::Service>,
 	pub event_handler: Arc<rooms::event_handler::Service>,
 	pub lazy_loading: Arc<rooms::lazy_loading::Service>,
 	pub metadata: Arc<rooms::metadata::Service>,
+	pub partial_state: Arc<rooms::partial_state::Service>,
 	pub pdu_metadata: Arc<rooms::pdu_metadata::Service>,
 	pub read_receipt: Arc<rooms::read_receipt::Service>,
 	pub search: Arc<rooms::search::Service>,
@@ -57,16 +64,20 @@ pub struct Services {
 	pub federation: Arc<federation::Service>,
 	pub sending: Arc<sending::Service>,
 	pub server_keys: Arc<server_keys::Service>,
+	pub synapse: Arc<synapse::Service>,
 	pub sync: Arc<sync::Service>,
 	pub tasks: Arc<tasks::Service>,
 	pub transaction_ids: Arc<transaction_ids::Service>,
 	pub uiaa: Arc<uiaa::Service>,
 	pub users: Arc<users::Service>,
+	pub user_directory: Arc<user_directory::Service>,
 	pub membership: Arc<membership::Service>,
 	pub deactivate: Arc<deactivate::Service>,
 	pub oauth: Arc<oauth::Service>,
 	pub retention: Arc<retention::Service>,
 	pub registration_tokens: Arc<registration_tokens::Service>,
+	pub reports: Arc<reports::Service>,
+	pub spam_checker: Arc<spam_checker::Service>,
 	pub rendezvous: Arc<rendezvous::Service>,
 	pub sendmail: Arc<sendmail::Service>,
 	pub threepid: Arc<threepid::Service>,
@@ -99,15 +110,21 @@ pub async fn build(server: Arc<Server>) -> Result<Arc<Self>> {
 		globals: globals::Service::build(&args)?,
 		key_backups: key_backups::Service::build(&args)?,
 		media: media::Service::build(&args)?,
+		policy_lists: policy_lists::Service::build(&args)?,
+		policy_server: policy_server::Service::build(&args)?,
 		presence: presence::Service::build(&args)?,
 		pusher: pusher::Service::build(&args)?,
+		ratelimit: ratelimit::Service::build(&args)?,
 		alias: rooms::alias::Service::build(&args)?,
+		archive: rooms::archive::Service::build(&args)?,
 		auth_chain: rooms::auth_chain::Service::build(&args)?,
+		delayed_events: rooms::delayed_events::Service::build(&args)?,
 		delete: rooms::delete::Service::build(&args)?,
 		directory: rooms::directory::Service::build(&args)?,
 		event_handler: rooms::event_handler::Service::build(&args)?,
 		lazy_loading: rooms::lazy_loading::Service::build(&args)?,
 		metadata: rooms::metadata::Service::build(&args)?,
+		partial_state: rooms::partial_state::Service::build(&args)?,
 		pdu_metadata: rooms::pdu_metadata::Service::build(&args)?,
 		read_receipt: rooms::read_receipt::Service::build(&args)?,
 		search: rooms::search::Service::build(&args)?,
@@ -124,16 +141,20 @@ pub async fn build(server: Arc<Server>) -> Result<Arc<Self>> {
 		federation: federation::Service::build(&args)?,
 		sending: sending::Service::build(&args)?,
 		server_keys: server_keys::Service::build(&args)?,
+		synapse: synapse::Service::build(&args)?,
 		sync: sync::Service::build(&args)?,
 		tasks: tasks::Service::build(&args)?,
 		transaction_ids: transaction_ids::Service::build(&args)?,
 		uiaa: uiaa::Service::build(&args)?,
 		users: users::Service::build(&args)?,
+		user_directory: user_directory::Service::build(&args)?,
 		membership: membership::Service::build(&args)?,
 		deactivate: deactivate::Service::build(&args)?,
 		oauth: oauth::Service::build(&args)?,
 		retention: retention::Service::build(&args)?,
 		registration_tokens: registration_tokens::Service::build(&args)?,
+		reports: reports::Service::build(&args)?,
+		spam_checker: spam_checker::Service::build(&args)?,
 		rendezvous: rendezvous::Service::build(&args)?,
 		sendmail: sendmail::Service::build(&args)?,
 		threepid: threepid::Service::build(&args)?,
@@ -167,15 +188,21 @@ macro_rules! cast {
 		cast!(self.globals),
 		cast!(self.key_backups),
 		cast!(self.media),
+		cast!(self.policy_lists),
+		cast!(self.policy_server),
 		cast!(self.presence),
 		cast!(self.pusher),
+		cast!(self.ratelimit),
 		cast!(self.alias),
+		cast!(self.archive),
 		cast!(self.auth_chain),
+		cast!(self.delayed_events),
 		cast!(self.delete),
 		cast!(self.directory),
 		cast!(self.event_handler),
 		cast!(self.lazy_loading),
 		cast!(self.metadata),
+		cast!(self.partial_state),
 		cast!(self.pdu_metadata),
 		cast!(self.read_receipt),
 		cast!(self.search),
@@ -192,16 +219,20 @@ macro_rules! cast {
 		cast!(self.federation),
 		cast!(self.sending),
 		cast!(self.server_keys),
+		cast!(self.synapse),
 		cast!(self.sync),
 		cast!(self.tasks),
 		cast!(self.transaction_ids),
 		cast!(self.uiaa),
 		cast!(self.users),
+		cast!(self.user_directory),
 		cast!(self.membership),
 		cast!(self.deactivate),
 		cast!(self.oauth),
 		cast!(self.retention),
 		cast!(self.registration_tokens),
+		cast!(self.reports),
+		cast!(self.spam_checker),
 		cast!(self.rendezvous),
 		cast!(self.profile),
 	]
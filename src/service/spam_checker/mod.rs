@@ -0,0 +1,230 @@
+//! Spam-checker hooks.
+//!
+//! Checkers are consulted before the server performs an action on behalf of a
+//! user: sending an event, inviting, joining or creating a room, registering
+//! and uploading media. The first checker to deny an action refuses it with
+//! `M_FORBIDDEN` and the checker's reason. A checker may be registered
+//! in-process through [`Service::register`]; an HTTP webhook configured by
+//! `spam_checker_webhook_url` is always consulted first.
+
+#[cfg(test)]
+mod tests;
+mod webhook;
+
+use std::{
+	net::IpAddr,
+	sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use ruma::{RoomId, UserId};
+use tuwunel_core::{Err, Result, debug_info, implement, matrix::PduBuilder};
+
+use self::webhook::Webhook;
+
+pub struct Service {
+	checkers: RwLock<Vec<Arc<dyn Checker>>>,
+	webhook: Arc<Webhook>,
+}
+
+/// Answer of a checker to one action.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Verdict {
+	Allow,
+
+	/// Refuse the action; the reason is shown to the user.
+	Deny(String),
+}
+
+/// Callbacks of a spam checker. Each allows its action unless overridden.
+#[async_trait]
+pub trait Checker: Send + Sync + 'static {
+	/// A local user is about to send an event into a room. It is checked as
+	/// the user wrote it, before it is built on the room's state.
+	async fn check_event_for_spam(
+		&self,
+		_sender: &UserId,
+		_room_id: &RoomId,
+		_event: &PduBuilder,
+	) -> Verdict {
+		Verdict::Allow
+	}
+
+	async fn user_may_invite(
+		&self,
+		_inviter: &UserId,
+		_invitee: &UserId,
+		_room_id: &RoomId,
+	) -> Verdict {
+		Verdict::Allow
+	}
+
+	async fn user_may_join_room(
+		&self,
+		_user_id: &UserId,
+		_room_id: &RoomId,
+		_is_invited: bool,
+	) -> Verdict {
+		Verdict::Allow
+	}
+
+	async fn user_may_create_room(&self, _user_id: &UserId) -> Verdict { Verdict::Allow }
+
+	/// A new account is about to be registered from `client_ip`.
+	async fn check_registration_for_spam(
+		&self,
+		_user_id: &UserId,
+		_client_ip: Option<IpAddr>,
+	) -> Verdict {
+		Verdict::Allow
+	}
+
+	/// A file is about to be stored; `uploader` is `None` for remote media.
+	async fn check_media_file_for_spam(
+		&self,
+		_uploader: Option<&UserId>,
+		_content_type: Option<&str>,
+		_file: &[u8],
+	) -> Verdict {
+		Verdict::Allow
+	}
+}
+
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			checkers: RwLock::new(Vec::new()),
+			webhook: Arc::new(Webhook { services: args.services.clone() }),
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Adds a checker consulted after those already registered.
+#[implement(Service)]
+pub fn register(&self, checker: Arc<dyn Checker>) {
+	self.checkers
+		.write()
+		.expect("locked for writing")
+		.push(checker);
+}
+
+#[implement(Service)]
+pub async fn check_event_for_spam(
+	&self,
+	sender: &UserId,
+	room_id: &RoomId,
+	event: &PduBuilder,
+) -> Result {
+	for checker in self.checkers() {
+		verdict_result(
+			checker
+				.check_event_for_spam(sender, room_id, event)
+				.await,
+		)?;
+	}
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub async fn user_may_invite(
+	&self,
+	inviter: &UserId,
+	invitee: &UserId,
+	room_id: &RoomId,
+) -> Result {
+	for checker in self.checkers() {
+		verdict_result(
+			checker
+				.user_may_invite(inviter, invitee, room_id)
+				.await,
+		)?;
+	}
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub async fn user_may_join_room(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	is_invited: bool,
+) -> Result {
+	for checker in self.checkers() {
+		verdict_result(
+			checker
+				.user_may_join_room(user_id, room_id, is_invited)
+				.await,
+		)?;
+	}
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub async fn user_may_create_room(&self, user_id: &UserId) -> Result {
+	for checker in self.checkers() {
+		verdict_result(checker.user_may_create_room(user_id).await)?;
+	}
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub async fn check_registration_for_spam(
+	&self,
+	user_id: &UserId,
+	client_ip: Option<IpAddr>,
+) -> Result {
+	for checker in self.checkers() {
+		verdict_result(
+			checker
+				.check_registration_for_spam(user_id, client_ip)
+				.await,
+		)?;
+	}
+
+	Ok(())
+}
+
+#[implement(Service)]
+pub async fn check_media_file_for_spam(
+	&self,
+	uploader: Option<&UserId>,
+	content_type: Option<&str>,
+	file: &[u8],
+) -> Result {
+	for checker in self.checkers() {
+		verdict_result(
+			checker
+				.check_media_file_for_spam(uploader, content_type, file)
+				.await,
+		)?;
+	}
+
+	Ok(())
+}
+
+/// The webhook followed by the registered checkers, snapshotted so no lock is
+/// held across a check.
+#[implement(Service)]
+fn checkers(&self) -> Vec<Arc<dyn Checker>> {
+	let registered = self.checkers.read().expect("locked for reading");
+
+	std::iter::once(self.webhook.clone() as Arc<dyn Checker>)
+		.chain(registered.iter().cloned())
+		.collect()
+}
+
+fn verdict_result(verdict: Verdict) -> Result {
+	match verdict {
+		| Verdict::Allow => Ok(()),
+		| Verdict::Deny(reason) => {
+			debug_info!(%reason, "Action refused by a spam checker");
+			Err!(Request(Forbidden("{reason}")))
+		},
+	}
+}
@@ -0,0 +1,29 @@
+use super::{Verdict, webhook::verdict};
+
+#[test]
+fn webhook_allow() {
+	assert_eq!(verdict(br#"{"allow": true, "reason": "ignored"}"#).unwrap(), Verdict::Allow);
+}
+
+#[test]
+fn webhook_deny_carries_reason() {
+	assert_eq!(
+		verdict(br#"{"allow": false, "reason": "Too many links"}"#).unwrap(),
+		Verdict::Deny("Too many links".to_owned())
+	);
+}
+
+#[test]
+fn webhook_deny_without_reason_has_default() {
+	let Verdict::Deny(reason) = verdict(br#"{"allow": false}"#).unwrap() else {
+		panic!("expected a denial");
+	};
+
+	assert!(!reason.is_empty());
+}
+
+#[test]
+fn webhook_answer_without_allow_is_an_error() {
+	verdict(br#"{"reason": "missing"}"#).expect_err("allow is required");
+	verdict(b"not json").expect_err("body must be JSON");
+}
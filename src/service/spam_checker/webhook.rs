@@ -0,0 +1,175 @@
+//! HTTP webhook backend.
+//!
+//! Each check is POSTed as a JSON object naming its `callback` alongside the
+//! callback's arguments. The webhook answers `{"allow": true}`, or
+//! `{"allow": false, "reason": "..."}` to refuse the action. Media is
+//! described by its type, size and SHA-256 rather than sent whole.
+
+use std::{net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD_NO_PAD as b64};
+use ruma::{RoomId, UserId};
+use serde::Deserialize;
+use serde_json::{Value as JsonValue, json};
+use tuwunel_core::{Result, implement, matrix::PduBuilder, utils::hash::sha256, warn};
+use url::Url;
+
+use super::{Checker, Verdict};
+use crate::{SelfServices, client::read_response_capped};
+
+pub(super) struct Webhook {
+	pub(super) services: SelfServices,
+}
+
+/// A webhook's answer.
+#[derive(Deserialize)]
+struct Answer {
+	allow: bool,
+
+	#[serde(default)]
+	reason: Option<String>,
+}
+
+#[async_trait]
+impl Checker for Webhook {
+	async fn check_event_for_spam(
+		&self,
+		sender: &UserId,
+		room_id: &RoomId,
+		event: &PduBuilder,
+	) -> Verdict {
+		self.call(json!({
+			"callback": "check_event_for_spam",
+			"sender": sender,
+			"room_id": room_id,
+			"type": event.event_type,
+			"state_key": event.state_key,
+			"content": event.content,
+		}))
+		.await
+	}
+
+	async fn user_may_invite(
+		&self,
+		inviter: &UserId,
+		invitee: &UserId,
+		room_id: &RoomId,
+	) -> Verdict {
+		self.call(json!({
+			"callback": "user_may_invite",
+			"inviter": inviter,
+			"invitee": invitee,
+			"room_id": room_id,
+		}))
+		.await
+	}
+
+	async fn user_may_join_room(
+		&self,
+		user_id: &UserId,
+		room_id: &RoomId,
+		is_invited: bool,
+	) -> Verdict {
+		self.call(json!({
+			"callback": "user_may_join_room",
+			"user_id": user_id,
+			"room_id": room_id,
+			"is_invited": is_invited,
+		}))
+		.await
+	}
+
+	async fn user_may_create_room(&self, user_id: &UserId) -> Verdict {
+		self.call(json!({
+			"callback": "user_may_create_room",
+			"user_id": user_id,
+		}))
+		.await
+	}
+
+	async fn check_registration_for_spam(
+		&self,
+		user_id: &UserId,
+		client_ip: Option<IpAddr>,
+	) -> Verdict {
+		self.call(json!({
+			"callback": "check_registration_for_spam",
+			"user_id": user_id,
+			"client_ip": client_ip,
+		}))
+		.await
+	}
+
+	async fn check_media_file_for_spam(
+		&self,
+		uploader: Option<&UserId>,
+		content_type: Option<&str>,
+		file: &[u8],
+	) -> Verdict {
+		self.call(json!({
+			"callback": "check_media_file_for_spam",
+			"user_id": uploader,
+			"content_type": content_type,
+			"size": file.len(),
+			"sha256": b64.encode(sha256::hash(file)),
+		}))
+		.await
+	}
+}
+
+/// Asks the configured webhook, if any. Failures allow the action unless
+/// `spam_checker_webhook_fail_closed` is set.
+#[implement(Webhook)]
+async fn call(&self, check: JsonValue) -> Verdict {
+	let config = &self.services.config;
+	let Some(url) = config.spam_checker_webhook_url.clone() else {
+		return Verdict::Allow;
+	};
+
+	match self.request(url, &check).await {
+		| Ok(verdict) => verdict,
+		| Err(e) if config.spam_checker_webhook_fail_closed => {
+			warn!(callback = %check["callback"], "Spam-checker webhook failed; refusing: {e}");
+			Verdict::Deny("The spam checker is unavailable.".to_owned())
+		},
+		| Err(e) => {
+			warn!(callback = %check["callback"], "Spam-checker webhook failed; allowing: {e}");
+			Verdict::Allow
+		},
+	}
+}
+
+#[implement(Webhook)]
+async fn request(&self, url: Url, check: &JsonValue) -> Result<Verdict> {
+	let config = &self.services.config;
+	let response = self
+		.services
+		.client
+		.default
+		.post(url)
+		.json(check)
+		.timeout(Duration::from_secs(config.spam_checker_webhook_timeout))
+		.send()
+		.await?
+		.error_for_status()?;
+
+	let body = read_response_capped(response, config.max_response_size).await?;
+
+	verdict(&body)
+}
+
+/// Reads a webhook's answer.
+pub(super) fn verdict(body: &[u8]) -> Result<Verdict> {
+	let answer: Answer = serde_json::from_slice(body)?;
+	if answer.allow {
+		return Ok(Verdict::Allow);
+	}
+
+	let reason = answer
+		.reason
+		.filter(|reason| !reason.is_empty())
+		.unwrap_or_else(|| "This action was refused by the spam checker.".to_owned());
+
+	Ok(Verdict::Deny(reason))
+}
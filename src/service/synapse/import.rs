@@ -0,0 +1,470 @@
+#![cfg(feature = "synapse_import")]
+
+use std::{
+	path::{Path, PathBuf},
+	time::Duration,
+};
+
+use futures::FutureExt;
+use ruma::{
+	MilliSecondsSinceUnixEpoch, Mxc, MxcUri, OwnedDeviceId, OwnedServerName, RoomId, UInt,
+	UserId,
+	api::client::device::Device,
+	events::{
+		GlobalAccountDataEventType,
+		push_rules::{PushRulesEvent, PushRulesEventContent},
+	},
+	serde::Raw,
+};
+use serde_json::json;
+use tuwunel_core::{
+	Err, Result, debug_warn, implement, info,
+	utils::{content_disposition::make_content_disposition, time::now_millis},
+	warn,
+};
+
+use super::{
+	Import, Report, rules,
+	source::{Source, User},
+};
+use crate::{membership::Join, profile::Propagation, users::device::TOKEN_LENGTH};
+
+/// Imports the local users of the Synapse database which do not exist here
+/// yet. Guests are left behind. A user failing part way is counted and
+/// logged, and the import goes on with the next; their account stays, so a
+/// later run skips them.
+#[implement(super::Service)]
+pub async fn import(&self, import: &Import) -> Result<Report> {
+	let source = Source::open(&import.database)?;
+	let server_name = self.services.globals.server_name();
+
+	let mut report = Report::default();
+	for user in source.users().await? {
+		let user_id = match UserId::parse(&user.name) {
+			| Ok(user_id) => user_id,
+			| Err(e) => {
+				warn!(name = %user.name, "Could not import Synapse user: {e}");
+				report.users_failed = report.users_failed.saturating_add(1);
+				continue;
+			},
+		};
+
+		if user_id.server_name() != server_name {
+			return Err!(Request(InvalidParam(
+				"Synapse user {user_id} does not belong to {server_name}; the Synapse server \
+				 name must be this server's."
+			)));
+		}
+
+		if user.is_guest || self.services.users.exists(&user_id).await {
+			report.skipped_users = report.skipped_users.saturating_add(1);
+			continue;
+		}
+
+		if let Err(e) = self
+			.import_user(&source, import, &user_id, &user, &mut report)
+			.await
+		{
+			warn!(%user_id, "Synapse user left partially imported: {e}");
+			report.users_failed = report.users_failed.saturating_add(1);
+			continue;
+		}
+
+		report.users = report.users.saturating_add(1);
+	}
+
+	info!(?report, "Imported Synapse users");
+
+	Ok(report)
+}
+
+#[implement(super::Service)]
+async fn import_user(
+	&self,
+	source: &Source,
+	import: &Import,
+	user_id: &UserId,
+	user: &User,
+	report: &mut Report,
+) -> Result {
+	let password_hash = user
+		.password_hash
+		.as_deref()
+		.filter(|_| !user.deactivated);
+
+	self.services
+		.users
+		.create(user_id, None, Some("password"))
+		.await?;
+
+	if let Some(password_hash) = password_hash {
+		self.services
+			.users
+			.set_password_hash(user_id, password_hash);
+	}
+
+	self.import_profile(source, user_id).await?;
+	self.import_account_data(source, user_id, report)
+		.await?;
+	self.import_push_rules(source, user_id, report)
+		.await?;
+
+	if let Some(media_store) = &import.media_store {
+		self.import_media(source, media_store, user_id, report)
+			.await?;
+	}
+
+	if user.deactivated {
+		return Ok(());
+	}
+
+	self.import_devices(source, user_id, report)
+		.await?;
+	self.import_key_backups(source, user_id, report)
+		.await?;
+
+	if user.admin {
+		self.services
+			.admin
+			.make_user_admin(user_id)
+			.boxed()
+			.await?;
+	}
+
+	if import.rejoin {
+		self.rejoin_rooms(source, user_id, report).await?;
+	}
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+async fn import_profile(&self, source: &Source, user_id: &UserId) -> Result {
+	let Some((displayname, avatar_url)) = source.profile(user_id.localpart()).await? else {
+		return Ok(());
+	};
+
+	let propagation = Some(Propagation::None);
+	self.services
+		.profile
+		.set_displayname(user_id, displayname.as_deref(), propagation)
+		.await?;
+
+	if let Some(avatar_url) = avatar_url {
+		self.services
+			.profile
+			.set_avatar_url(user_id, Some(<&MxcUri>::from(avatar_url.as_str())), propagation)
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// Devices and the access tokens logged into them. A token past its expiry
+/// is left behind, as is one shorter than tokens issued here.
+#[implement(super::Service)]
+async fn import_devices(&self, source: &Source, user_id: &UserId, report: &mut Report) -> Result {
+	let user = user_id.as_str();
+	for device in source.devices(user).await? {
+		let last_seen_ts = device
+			.last_seen
+			.and_then(UInt::new)
+			.map(MilliSecondsSinceUnixEpoch);
+
+		self.services
+			.users
+			.put_device_metadata(user_id, true, &Device {
+				device_id: device.device_id.into(),
+				display_name: device.display_name.map(Into::into),
+				last_seen_ts,
+				last_seen_ip: device.ip.map(Into::into),
+			});
+
+		report.devices = report.devices.saturating_add(1);
+	}
+
+	let now = now_millis();
+	for token in source.access_tokens(user).await? {
+		let expires_in = token
+			.valid_until_ms
+			.map(|valid_until_ms| Duration::from_millis(valid_until_ms.saturating_sub(now)));
+
+		if expires_in.is_some_and(|expires_in| expires_in.is_zero())
+			|| token.token.len() < TOKEN_LENGTH
+		{
+			debug_warn!(%user_id, device_id = %token.device_id, "Skipping access token");
+			continue;
+		}
+
+		let device_id: OwnedDeviceId = token.device_id.into();
+		if !self
+			.services
+			.users
+			.device_exists(user_id, &device_id)
+			.await
+		{
+			continue;
+		}
+
+		self.services
+			.users
+			.set_access_token(
+				user_id,
+				&device_id,
+				&token.token,
+				expires_in,
+				token.refresh_token.as_deref(),
+			)
+			.await?;
+
+		report.access_tokens = report.access_tokens.saturating_add(1);
+	}
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+async fn import_account_data(
+	&self,
+	source: &Source,
+	user_id: &UserId,
+	report: &mut Report,
+) -> Result {
+	for data in source.account_data(user_id.as_str()).await? {
+		let room_id = data
+			.room_id
+			.as_deref()
+			.map(RoomId::parse)
+			.transpose()?;
+
+		let content: serde_json::Value = serde_json::from_str(&data.content)?;
+		let event = json!({ "type": data.kind, "content": content });
+
+		self.services
+			.account_data
+			.update(room_id.as_deref(), user_id, data.kind.into(), &event)
+			.await?;
+
+		report.account_data = report.account_data.saturating_add(1);
+	}
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+async fn import_push_rules(
+	&self,
+	source: &Source,
+	user_id: &UserId,
+	report: &mut Report,
+) -> Result {
+	let user = user_id.as_str();
+	let push_rules = source.push_rules(user).await?;
+	let enabled = source.push_rules_enabled(user).await?;
+
+	let (ruleset, failed) = rules::ruleset(user_id, &push_rules, &enabled);
+	for rule_id in &failed {
+		warn!(%user_id, %rule_id, "Could not import push rule");
+	}
+
+	self.services
+		.account_data
+		.update(
+			None,
+			user_id,
+			GlobalAccountDataEventType::PushRules
+				.to_string()
+				.into(),
+			&serde_json::to_value(PushRulesEvent {
+				content: PushRulesEventContent { global: ruleset },
+			})?,
+		)
+		.await?;
+
+	report.push_rules_failed = report
+		.push_rules_failed
+		.saturating_add(failed.len());
+
+	Ok(())
+}
+
+/// Each live backup version becomes a new version here, in the same order,
+/// holding the same keys; clients find the latest by asking for it.
+#[implement(super::Service)]
+async fn import_key_backups(
+	&self,
+	source: &Source,
+	user_id: &UserId,
+	report: &mut Report,
+) -> Result {
+	let user = user_id.as_str();
+	for backup in source.backup_versions(user).await? {
+		let auth_data: serde_json::Value = serde_json::from_str(&backup.auth_data)?;
+		let algorithm: Raw<_> = serde_json::from_value(json!({
+			"algorithm": backup.algorithm,
+			"auth_data": auth_data,
+		}))?;
+
+		let version = self
+			.services
+			.key_backups
+			.create_backup(user_id, &algorithm)?;
+
+		for key in source.room_keys(user, backup.version).await? {
+			let room_id = RoomId::parse(key.room_id)?;
+			let session_data: serde_json::Value = serde_json::from_str(&key.session_data)?;
+			let key_data: Raw<_> = serde_json::from_value(json!({
+				"first_message_index": key.first_message_index,
+				"forwarded_count": key.forwarded_count,
+				"is_verified": key.is_verified,
+				"session_data": session_data,
+			}))?;
+
+			self.services
+				.key_backups
+				.add_key(user_id, &version, &room_id, &key.session_id, &key_data)
+				.await?;
+
+			report.room_keys = report.room_keys.saturating_add(1);
+		}
+
+		report.key_backups = report.key_backups.saturating_add(1);
+	}
+
+	Ok(())
+}
+
+/// Re-creates the user's uploads from the media store. Thumbnails are made
+/// again on request. A file missing from the store, or refused by this
+/// server's checks, is counted and skipped.
+#[implement(super::Service)]
+async fn import_media(
+	&self,
+	source: &Source,
+	media_store: &Path,
+	user_id: &UserId,
+	report: &mut Report,
+) -> Result {
+	let server_name = self.services.globals.server_name();
+	for media in source.media(user_id.as_str()).await? {
+		let mxc = Mxc { server_name, media_id: &media.media_id };
+		let Some(path) = local_content_path(media_store, &media.media_id) else {
+			report.media_failed = report.media_failed.saturating_add(1);
+			continue;
+		};
+
+		let file = match tokio::fs::read(&path).await {
+			| Ok(file) => file,
+			| Err(e) => {
+				warn!(%mxc, ?path, "Could not read media file: {e}");
+				report.media_failed = report.media_failed.saturating_add(1);
+				continue;
+			},
+		};
+
+		let content_type = media.media_type.as_deref();
+		let content_disposition =
+			make_content_disposition(None, content_type, media.upload_name.as_deref());
+
+		let created = self
+			.services
+			.media
+			.create(&mxc, Some(user_id), Some(&content_disposition), content_type, &file)
+			.await;
+
+		if let Err(e) = created {
+			warn!(%mxc, "Could not import media: {e}");
+			report.media_failed = report.media_failed.saturating_add(1);
+			continue;
+		}
+
+		if media.safe_from_quarantine {
+			self.services
+				.media
+				.set_protected(&mxc, true)
+				.await;
+		}
+
+		if media.quarantined {
+			let admin = &self.services.globals.server_user;
+			self.services
+				.media
+				.set_quarantined(&mxc, Some(admin))
+				.await;
+		}
+
+		report.media = report.media.saturating_add(1);
+	}
+
+	Ok(())
+}
+
+/// Joins the user to each room they were joined to over federation, through
+/// the servers of the room's other members. Rooms no other server is in
+/// cannot be rejoined.
+#[implement(super::Service)]
+async fn rejoin_rooms(&self, source: &Source, user_id: &UserId, report: &mut Report) -> Result {
+	let server_name = self.services.globals.server_name();
+	for room_id in source.joined_rooms(user_id.as_str()).await? {
+		let room_id = RoomId::parse(room_id)?;
+		let mut servers: Vec<OwnedServerName> = source
+			.room_members(room_id.as_str())
+			.await?
+			.iter()
+			.filter_map(|member| UserId::parse(member).ok())
+			.map(|member| member.server_name().to_owned())
+			.filter(|server| server != server_name)
+			.collect();
+
+		servers.sort_unstable();
+		servers.dedup();
+
+		let joined = self
+			.services
+			.membership
+			.join(Join {
+				sender_user: user_id,
+				room_id: &room_id,
+				orig_room_id: None,
+				reason: None,
+				servers: &servers,
+				is_appservice: false,
+				extra_content: None,
+			})
+			.boxed()
+			.await;
+
+		match joined {
+			| Ok(()) => report.rooms_joined = report.rooms_joined.saturating_add(1),
+			| Err(e) => {
+				warn!(%user_id, %room_id, "Could not rejoin room: {e}");
+				report.rooms_failed = report.rooms_failed.saturating_add(1);
+			},
+		}
+	}
+
+	Ok(())
+}
+
+/// Where Synapse's media store keeps a local upload:
+/// `local_content/ab/cd/efgh…` for media ID `abcdefgh…`.
+pub(super) fn local_content_path(media_store: &Path, media_id: &str) -> Option<PathBuf> {
+	let safe = media_id
+		.chars()
+		.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'));
+
+	if !safe || media_id.len() <= 4 {
+		return None;
+	}
+
+	let (shard1, rest) = media_id.split_at(2);
+	let (shard2, rest) = rest.split_at(2);
+
+	Some(
+		media_store
+			.join("local_content")
+			.join(shard1)
+			.join(shard2)
+			.join(rest),
+	)
+}
@@ -0,0 +1,75 @@
+//! Synapse Import
+//!
+//! Imports the local accounts of a Synapse homeserver from its SQLite database
+//! and media store, for this server to take over its server name. Users keep
+//! their password hashes, devices and access tokens, so clients stay logged in
+//! across the switch, along with their profiles, account data, push rules, key
+//! backups and uploads. Room history is not imported: users are rejoined to
+//! their rooms over federation, which loses rooms no other server is in.
+
+mod import;
+mod rules;
+mod source;
+#[cfg(test)]
+mod tests;
+
+use std::{path::PathBuf, sync::Arc};
+
+#[cfg(not(feature = "synapse_import"))]
+use tuwunel_core::Err;
+use tuwunel_core::Result;
+
+pub struct Service {
+	#[cfg_attr(not(feature = "synapse_import"), expect(dead_code))]
+	services: Arc<crate::services::OnceServices>,
+}
+
+/// What to import.
+#[derive(Debug)]
+pub struct Import {
+	/// The Synapse SQLite database, usually `homeserver.db`.
+	pub database: PathBuf,
+
+	/// Synapse's `media_store_path`; uploads are not imported without it.
+	pub media_store: Option<PathBuf>,
+
+	/// Rejoin users to their rooms over federation.
+	pub rejoin: bool,
+}
+
+/// What an import brought over.
+#[derive(Debug, Default)]
+pub struct Report {
+	pub users: usize,
+	pub skipped_users: usize,
+
+	/// Users whose import failed, some part way through; see the log.
+	pub users_failed: usize,
+
+	pub devices: usize,
+	pub access_tokens: usize,
+	pub account_data: usize,
+	pub push_rules_failed: usize,
+	pub key_backups: usize,
+	pub room_keys: usize,
+	pub media: usize,
+	pub media_failed: usize,
+	pub rooms_joined: usize,
+	pub rooms_failed: usize,
+}
+
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self { services: args.services.clone() }))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	#[cfg(not(feature = "synapse_import"))]
+	#[expect(clippy::unused_async)]
+	pub async fn import(&self, _import: &Import) -> Result<Report> {
+		Err!(FeatureDisabled("synapse_import"))
+	}
+}
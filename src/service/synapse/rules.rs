@@ -0,0 +1,109 @@
+#![cfg(feature = "synapse_import")]
+
+//! Synapse keeps a user's push rules as rows scoped `global/<kind>/<rule_id>`:
+//! their own rules, ordered by priority class and priority, and new actions
+//! for the server-default rules under a negative class. Enabled flags are kept
+//! apart, for both. The rows are laid over the server-default ruleset.
+
+use ruma::{
+	RoomId, UserId,
+	push::{
+		Actions, NewConditionalPushRule, NewPatternedPushRule, NewPushRule, NewSimplePushRule,
+		PushCondition, RuleKind, Ruleset,
+	},
+};
+use serde::Deserialize;
+
+use super::source::PushRule;
+
+/// The one condition of a content rule, holding its pattern.
+#[derive(Deserialize)]
+struct PatternCondition {
+	pattern: String,
+}
+
+/// The user's ruleset, with the IDs of the rows which could not be applied.
+/// Rules are given lowest priority first; each is inserted ahead of the last.
+pub(super) fn ruleset(
+	user_id: &UserId,
+	rules: &[PushRule],
+	enabled: &[(String, bool)],
+) -> (Ruleset, Vec<String>) {
+	let mut ruleset = Ruleset::server_default(user_id);
+	let mut failed = Vec::new();
+	for rule in rules {
+		if apply_rule(&mut ruleset, rule).is_none() {
+			failed.push(rule.rule_id.clone());
+		}
+	}
+
+	for (rule_id, enabled) in enabled {
+		let set = parse_rule_id(rule_id)
+			.and_then(|(kind, id)| ruleset.set_enabled(kind, id, *enabled).ok());
+
+		if set.is_none() {
+			failed.push(rule_id.clone());
+		}
+	}
+
+	(ruleset, failed)
+}
+
+fn apply_rule(ruleset: &mut Ruleset, rule: &PushRule) -> Option<()> {
+	let (kind, rule_id) = parse_rule_id(&rule.rule_id)?;
+	let actions: Actions = serde_json::from_str(&rule.actions).ok()?;
+	if rule.priority_class < 0 {
+		return ruleset.set_actions(kind, rule_id, actions).ok();
+	}
+
+	let new_rule = match kind {
+		| RuleKind::Override => NewPushRule::Override(conditional(rule_id, rule, actions)?),
+		| RuleKind::Underride => NewPushRule::Underride(conditional(rule_id, rule, actions)?),
+		| RuleKind::Content => {
+			let conditions: Vec<PatternCondition> =
+				serde_json::from_str(&rule.conditions).ok()?;
+			let pattern = conditions.into_iter().next()?.pattern;
+
+			NewPushRule::Content(NewPatternedPushRule::new(rule_id.into(), pattern, actions))
+		},
+		| RuleKind::Room => {
+			let room_id = RoomId::parse(rule_id).ok()?;
+
+			NewPushRule::Room(NewSimplePushRule::new(room_id, actions))
+		},
+		| RuleKind::Sender => {
+			let user_id = UserId::parse(rule_id).ok()?;
+
+			NewPushRule::Sender(NewSimplePushRule::new(user_id, actions))
+		},
+		| _ => return None,
+	};
+
+	ruleset.insert(new_rule, None, None).ok()
+}
+
+fn conditional(
+	rule_id: &str,
+	rule: &PushRule,
+	actions: Actions,
+) -> Option<NewConditionalPushRule> {
+	let conditions: Vec<PushCondition> = serde_json::from_str(&rule.conditions).ok()?;
+
+	Some(NewConditionalPushRule::new(rule_id.into(), conditions, actions))
+}
+
+/// The kind and bare ID of a `global/<kind>/<rule_id>` rule ID.
+pub(super) fn parse_rule_id(rule_id: &str) -> Option<(RuleKind, &str)> {
+	let (kind, rule_id) = rule_id.strip_prefix("global/")?.split_once('/')?;
+
+	let kind = match kind {
+		| "override" => RuleKind::Override,
+		| "underride" => RuleKind::Underride,
+		| "content" => RuleKind::Content,
+		| "room" => RuleKind::Room,
+		| "sender" => RuleKind::Sender,
+		| _ => return None,
+	};
+
+	Some((kind, rule_id))
+}
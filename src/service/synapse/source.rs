@@ -0,0 +1,304 @@
+#![cfg(feature = "synapse_import")]
+
+//! Reads the Synapse SQLite database. The connection is opened read-only and
+//! every query runs on a blocking thread, one at a time.
+
+use std::{
+	path::Path,
+	sync::{Arc, Mutex},
+};
+
+use rusqlite::{Connection, OpenFlags, Params, Row};
+use tuwunel_core::{Result, err};
+
+pub(super) struct Source {
+	conn: Arc<Mutex<Connection>>,
+}
+
+/// A row of `users`.
+pub(super) struct User {
+	pub(super) name: String,
+	pub(super) password_hash: Option<String>,
+	pub(super) admin: bool,
+	pub(super) deactivated: bool,
+	pub(super) is_guest: bool,
+}
+
+/// A visible row of `devices`.
+pub(super) struct Device {
+	pub(super) device_id: String,
+	pub(super) display_name: Option<String>,
+	pub(super) last_seen: Option<u64>,
+	pub(super) ip: Option<String>,
+}
+
+/// A row of `access_tokens` with the refresh token it pairs with, if any.
+pub(super) struct AccessToken {
+	pub(super) device_id: String,
+	pub(super) token: String,
+	pub(super) valid_until_ms: Option<u64>,
+	pub(super) refresh_token: Option<String>,
+}
+
+/// A row of `account_data`, or of `room_account_data` when it has a room.
+pub(super) struct AccountData {
+	pub(super) room_id: Option<String>,
+	pub(super) kind: String,
+	pub(super) content: String,
+}
+
+/// A row of `push_rules`, its conditions and actions still JSON.
+pub(super) struct PushRule {
+	pub(super) rule_id: String,
+	pub(super) priority_class: i64,
+	pub(super) conditions: String,
+	pub(super) actions: String,
+}
+
+/// A live row of `e2e_room_keys_versions`.
+pub(super) struct BackupVersion {
+	pub(super) version: i64,
+	pub(super) algorithm: String,
+	pub(super) auth_data: String,
+}
+
+/// A row of `e2e_room_keys`.
+pub(super) struct RoomKey {
+	pub(super) room_id: String,
+	pub(super) session_id: String,
+	pub(super) first_message_index: i64,
+	pub(super) forwarded_count: i64,
+	pub(super) is_verified: bool,
+	pub(super) session_data: String,
+}
+
+/// A row of `local_media_repository`, excluding URL preview media.
+pub(super) struct Media {
+	pub(super) media_id: String,
+	pub(super) media_type: Option<String>,
+	pub(super) upload_name: Option<String>,
+	pub(super) quarantined: bool,
+	pub(super) safe_from_quarantine: bool,
+}
+
+impl Source {
+	pub(super) fn open(path: &Path) -> Result<Self> {
+		let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+		let conn = Connection::open_with_flags(path, flags)
+			.map_err(|e| err!(Database("Failed to open Synapse database {path:?}: {e}")))?;
+
+		Ok(Self::new(conn))
+	}
+
+	/// Reads from a connection opened elsewhere.
+	pub(super) fn new(conn: Connection) -> Self { Self { conn: Arc::new(Mutex::new(conn)) } }
+
+	pub(super) async fn users(&self) -> Result<Vec<User>> {
+		self.query(
+			"SELECT name, password_hash, admin, deactivated, is_guest FROM users ORDER BY name",
+			(),
+			|row| {
+				Ok(User {
+					name: row.get(0)?,
+					password_hash: row.get(1)?,
+					admin: row.get(2)?,
+					deactivated: row.get(3)?,
+					is_guest: row.get(4)?,
+				})
+			},
+		)
+		.await
+	}
+
+	/// The user's devices, without the hidden ones Synapse keeps for
+	/// cross-signing keys.
+	pub(super) async fn devices(&self, user_id: &str) -> Result<Vec<Device>> {
+		self.query(
+			"SELECT device_id, display_name, last_seen, ip FROM devices WHERE user_id = ?1 AND \
+			 COALESCE(hidden, 0) = 0",
+			[user_id.to_owned()],
+			|row| {
+				Ok(Device {
+					device_id: row.get(0)?,
+					display_name: row.get(1)?,
+					last_seen: row.get(2)?,
+					ip: row.get(3)?,
+				})
+			},
+		)
+		.await
+	}
+
+	/// The user's own access tokens bound to a device; admin puppeting tokens
+	/// are left behind.
+	pub(super) async fn access_tokens(&self, user_id: &str) -> Result<Vec<AccessToken>> {
+		self.query(
+			"SELECT a.device_id, a.token, a.valid_until_ms, r.token FROM access_tokens a LEFT \
+			 JOIN refresh_tokens r ON r.id = a.refresh_token_id WHERE a.user_id = ?1 AND \
+			 a.device_id IS NOT NULL AND a.puppets_user_id IS NULL",
+			[user_id.to_owned()],
+			|row| {
+				Ok(AccessToken {
+					device_id: row.get(0)?,
+					token: row.get(1)?,
+					valid_until_ms: row.get(2)?,
+					refresh_token: row.get(3)?,
+				})
+			},
+		)
+		.await
+	}
+
+	pub(super) async fn profile(
+		&self,
+		localpart: &str,
+	) -> Result<Option<(Option<String>, Option<String>)>> {
+		self.query(
+			"SELECT displayname, avatar_url FROM profiles WHERE user_id = ?1",
+			[localpart.to_owned()],
+			|row| Ok((row.get(0)?, row.get(1)?)),
+		)
+		.await
+		.map(|profiles| profiles.into_iter().next())
+	}
+
+	/// The user's global account data followed by their room account data.
+	pub(super) async fn account_data(&self, user_id: &str) -> Result<Vec<AccountData>> {
+		self.query(
+			"SELECT NULL, account_data_type, content FROM account_data WHERE user_id = ?1 UNION \
+			 ALL SELECT room_id, account_data_type, content FROM room_account_data WHERE \
+			 user_id = ?1",
+			[user_id.to_owned()],
+			|row| {
+				Ok(AccountData {
+					room_id: row.get(0)?,
+					kind: row.get(1)?,
+					content: row.get(2)?,
+				})
+			},
+		)
+		.await
+	}
+
+	/// The user's push rules, lowest priority first.
+	pub(super) async fn push_rules(&self, user_id: &str) -> Result<Vec<PushRule>> {
+		self.query(
+			"SELECT rule_id, priority_class, conditions, actions FROM push_rules WHERE \
+			 user_name = ?1 ORDER BY priority_class, priority",
+			[user_id.to_owned()],
+			|row| {
+				Ok(PushRule {
+					rule_id: row.get(0)?,
+					priority_class: row.get(1)?,
+					conditions: row.get(2)?,
+					actions: row.get(3)?,
+				})
+			},
+		)
+		.await
+	}
+
+	/// Rules the user switched on or off, by rule ID.
+	pub(super) async fn push_rules_enabled(&self, user_id: &str) -> Result<Vec<(String, bool)>> {
+		self.query(
+			"SELECT rule_id, enabled FROM push_rules_enable WHERE user_name = ?1",
+			[user_id.to_owned()],
+			|row| Ok((row.get(0)?, row.get(1)?)),
+		)
+		.await
+	}
+
+	/// The user's undeleted key backup versions, oldest first.
+	pub(super) async fn backup_versions(&self, user_id: &str) -> Result<Vec<BackupVersion>> {
+		self.query(
+			"SELECT version, algorithm, auth_data FROM e2e_room_keys_versions WHERE user_id = \
+			 ?1 AND deleted = 0 ORDER BY version",
+			[user_id.to_owned()],
+			|row| {
+				Ok(BackupVersion {
+					version: row.get(0)?,
+					algorithm: row.get(1)?,
+					auth_data: row.get(2)?,
+				})
+			},
+		)
+		.await
+	}
+
+	pub(super) async fn room_keys(&self, user_id: &str, version: i64) -> Result<Vec<RoomKey>> {
+		self.query(
+			"SELECT room_id, session_id, first_message_index, forwarded_count, is_verified, \
+			 session_data FROM e2e_room_keys WHERE user_id = ?1 AND version = ?2",
+			(user_id.to_owned(), version),
+			|row| {
+				Ok(RoomKey {
+					room_id: row.get(0)?,
+					session_id: row.get(1)?,
+					first_message_index: row.get(2)?,
+					forwarded_count: row.get(3)?,
+					is_verified: row.get(4)?,
+					session_data: row.get(5)?,
+				})
+			},
+		)
+		.await
+	}
+
+	pub(super) async fn media(&self, user_id: &str) -> Result<Vec<Media>> {
+		self.query(
+			"SELECT media_id, media_type, upload_name, quarantined_by IS NOT NULL, \
+			 safe_from_quarantine FROM local_media_repository WHERE user_id = ?1 AND url_cache \
+			 IS NULL",
+			[user_id.to_owned()],
+			|row| {
+				Ok(Media {
+					media_id: row.get(0)?,
+					media_type: row.get(1)?,
+					upload_name: row.get(2)?,
+					quarantined: row.get(3)?,
+					safe_from_quarantine: row.get(4)?,
+				})
+			},
+		)
+		.await
+	}
+
+	/// Rooms the user is joined to.
+	pub(super) async fn joined_rooms(&self, user_id: &str) -> Result<Vec<String>> {
+		self.query(
+			"SELECT room_id FROM local_current_membership WHERE user_id = ?1 AND membership = \
+			 'join'",
+			[user_id.to_owned()],
+			|row| row.get(0),
+		)
+		.await
+	}
+
+	/// Members joined to the room, by user ID, for the servers to rejoin it
+	/// through.
+	pub(super) async fn room_members(&self, room_id: &str) -> Result<Vec<String>> {
+		self.query(
+			"SELECT state_key FROM current_state_events WHERE room_id = ?1 AND type = \
+			 'm.room.member' AND membership = 'join'",
+			[room_id.to_owned()],
+			|row| row.get(0),
+		)
+		.await
+	}
+
+	async fn query<T, P, F>(&self, sql: &'static str, params: P, f: F) -> Result<Vec<T>>
+	where
+		T: Send + 'static,
+		P: Params + Send + 'static,
+		F: FnMut(&Row<'_>) -> rusqlite::Result<T> + Send + 'static,
+	{
+		let conn = self.conn.clone();
+		tokio::task::spawn_blocking(move || {
+			let conn = conn.lock().expect("locked");
+
+			conn.prepare(sql)?.query_map(params, f)?.collect()
+		})
+		.await?
+		.map_err(|e| err!(Database("Synapse database: {e}")))
+	}
+}
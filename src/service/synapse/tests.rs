@@ -0,0 +1,235 @@
+#![cfg(feature = "synapse_import")]
+
+use std::path::Path;
+
+use ruma::{
+	UserId,
+	push::{AnyPushRuleRef, RuleKind},
+};
+
+use super::{
+	import::local_content_path,
+	rules,
+	source::{PushRule, Source},
+};
+
+mod media {
+	use super::*;
+
+	#[test]
+	fn local_content_is_sharded_by_media_id() {
+		let path =
+			local_content_path(Path::new("/var/lib/synapse/media"), "GCmhgzMPRjqgpODLsNQzVuHZ")
+				.expect("a media ID maps to a path");
+
+		assert_eq!(
+			path,
+			Path::new("/var/lib/synapse/media/local_content/GC/mh/gzMPRjqgpODLsNQzVuHZ")
+		);
+	}
+
+	#[test]
+	fn unsafe_media_ids_have_no_path() {
+		let media_store = Path::new("/media");
+
+		assert_eq!(local_content_path(media_store, "abcd"), None);
+		assert_eq!(local_content_path(media_store, "../../etc/passwd"), None);
+		assert_eq!(local_content_path(media_store, "ab/cdefgh"), None);
+	}
+}
+
+mod push_rules {
+	use super::*;
+
+	fn row(rule_id: &str, priority_class: i64, conditions: &str, actions: &str) -> PushRule {
+		PushRule {
+			rule_id: rule_id.to_owned(),
+			priority_class,
+			conditions: conditions.to_owned(),
+			actions: actions.to_owned(),
+		}
+	}
+
+	fn user() -> &'static UserId { <&UserId>::try_from("@alice:example.com").expect("valid") }
+
+	#[test]
+	fn own_rules_are_laid_over_the_defaults() {
+		let rows = [
+			row("global/room/!quiet:example.com", 3, "[]", "[]"),
+			row(
+				"global/content/cats",
+				4,
+				r#"[{"kind":"event_match","key":"content.body","pattern":"cats"}]"#,
+				r#"["notify",{"set_tweak":"highlight"}]"#,
+			),
+			row(
+				"global/override/work",
+				5,
+				r#"[{"kind":"event_match","key":"room_id","pattern":"!work:example.com"}]"#,
+				r#"["notify"]"#,
+			),
+		];
+
+		let (ruleset, failed) = rules::ruleset(user(), &rows, &[]);
+
+		assert!(failed.is_empty(), "{failed:?}");
+		assert!(
+			ruleset
+				.get(RuleKind::Room, "!quiet:example.com")
+				.is_some()
+		);
+		assert!(ruleset.get(RuleKind::Override, "work").is_some());
+
+		let Some(AnyPushRuleRef::Content(cats)) = ruleset.get(RuleKind::Content, "cats") else {
+			panic!("the content rule is imported");
+		};
+
+		assert_eq!(cats.pattern, "cats");
+	}
+
+	#[test]
+	fn later_rows_take_priority() {
+		let rows = [
+			row("global/room/!low:example.com", 3, "[]", r#"["notify"]"#),
+			row("global/room/!high:example.com", 3, "[]", r#"["notify"]"#),
+		];
+
+		let (ruleset, _) = rules::ruleset(user(), &rows, &[]);
+		let rooms: Vec<_> = ruleset
+			.room
+			.iter()
+			.map(|rule| rule.rule_id.as_str())
+			.collect();
+
+		assert_eq!(rooms, ["!high:example.com", "!low:example.com"]);
+	}
+
+	#[test]
+	fn default_rules_take_new_actions_and_flags() {
+		let rows = [row("global/underride/.m.rule.message", -1, "[]", "[]")];
+		let enabled = [("global/override/.m.rule.master".to_owned(), true)];
+
+		let (ruleset, failed) = rules::ruleset(user(), &rows, &enabled);
+
+		assert!(failed.is_empty(), "{failed:?}");
+
+		let Some(message) = ruleset.get(RuleKind::Underride, ".m.rule.message") else {
+			panic!("the default rule is kept");
+		};
+
+		assert!(message.actions().is_empty());
+
+		let Some(master) = ruleset.get(RuleKind::Override, ".m.rule.master") else {
+			panic!("the default rule is kept");
+		};
+
+		assert!(master.enabled());
+	}
+
+	#[test]
+	fn unreadable_rows_are_reported() {
+		let rows = [
+			row("global/room/not-a-room", 3, "[]", r#"["notify"]"#),
+			row("device/abc/room/!r:example.com", 3, "[]", r#"["notify"]"#),
+		];
+		let enabled = [("global/override/missing".to_owned(), false)];
+
+		let (_, failed) = rules::ruleset(user(), &rows, &enabled);
+
+		assert_eq!(failed, [
+			"global/room/not-a-room",
+			"device/abc/room/!r:example.com",
+			"global/override/missing",
+		]);
+	}
+}
+
+mod source {
+	use rusqlite::Connection;
+
+	use super::*;
+
+	/// The tables and columns of a Synapse database the import reads, with a
+	/// user, their device, access token and upload, and rows it leaves behind.
+	const SCHEMA: &str = r#"
+		CREATE TABLE users (
+			name TEXT, password_hash TEXT, admin SMALLINT, deactivated SMALLINT,
+			is_guest SMALLINT
+		);
+		CREATE TABLE devices (
+			user_id TEXT, device_id TEXT, display_name TEXT, last_seen BIGINT, ip TEXT,
+			hidden BOOLEAN
+		);
+		CREATE TABLE refresh_tokens (id BIGINT, token TEXT);
+		CREATE TABLE access_tokens (
+			id BIGINT, user_id TEXT, device_id TEXT, token TEXT, valid_until_ms BIGINT,
+			puppets_user_id TEXT, refresh_token_id BIGINT
+		);
+		CREATE TABLE local_media_repository (
+			media_id TEXT, media_type TEXT, upload_name TEXT, user_id TEXT, url_cache TEXT,
+			quarantined_by TEXT, safe_from_quarantine BOOLEAN
+		);
+
+		INSERT INTO users VALUES
+			('@alice:example.com', '$2b$12$hash', 1, 0, 0),
+			('@guest:example.com', NULL, 0, 0, 1);
+		INSERT INTO devices VALUES
+			('@alice:example.com', 'PHONE', 'Alice''s phone', 1700000000000, '192.0.2.1', 0),
+			('@alice:example.com', 'MASTERKEY', NULL, NULL, NULL, 1);
+		INSERT INTO refresh_tokens VALUES (7, 'syr_refresh');
+		INSERT INTO access_tokens VALUES
+			(1, '@alice:example.com', 'PHONE', 'syt_phone', NULL, NULL, 7),
+			(2, '@alice:example.com', 'PHONE', 'syt_puppet', NULL, '@bob:example.com', NULL),
+			(3, '@alice:example.com', NULL, 'syt_deviceless', NULL, NULL, NULL);
+		INSERT INTO local_media_repository VALUES
+			('GCmhgzMPRjqgpODLsNQzVuHZ', 'image/png', 'cat.png', '@alice:example.com', NULL,
+			 NULL, 1),
+			('PreviewMediaFromAUrlxyz', 'image/png', NULL, '@alice:example.com',
+			 'https://example.org', NULL, 0);
+	"#;
+
+	fn source() -> Source {
+		let conn = Connection::open_in_memory().expect("an in-memory database");
+		conn.execute_batch(SCHEMA)
+			.expect("the Synapse tables are created");
+
+		Source::new(conn)
+	}
+
+	#[tokio::test]
+	async fn importable_rows_are_read() {
+		let source = source();
+		let alice = "@alice:example.com";
+
+		let users = source.users().await.expect("users");
+		assert_eq!(users.len(), 2);
+		assert_eq!(users[0].name, alice);
+		assert_eq!(users[0].password_hash.as_deref(), Some("$2b$12$hash"));
+		assert!(users[0].admin && !users[0].deactivated && !users[0].is_guest);
+		assert!(users[1].is_guest);
+
+		let devices = source.devices(alice).await.expect("devices");
+		assert_eq!(devices.len(), 1, "the hidden device is left behind");
+		assert_eq!(devices[0].device_id, "PHONE");
+		assert_eq!(devices[0].display_name.as_deref(), Some("Alice's phone"));
+		assert_eq!(devices[0].last_seen, Some(1_700_000_000_000));
+		assert_eq!(devices[0].ip.as_deref(), Some("192.0.2.1"));
+
+		let tokens = source
+			.access_tokens(alice)
+			.await
+			.expect("access tokens");
+		assert_eq!(tokens.len(), 1, "puppeting and deviceless tokens are left behind");
+		assert_eq!(tokens[0].device_id, "PHONE");
+		assert_eq!(tokens[0].token, "syt_phone");
+		assert_eq!(tokens[0].valid_until_ms, None);
+		assert_eq!(tokens[0].refresh_token.as_deref(), Some("syr_refresh"));
+
+		let media = source.media(alice).await.expect("media");
+		assert_eq!(media.len(), 1, "URL preview media is left behind");
+		assert_eq!(media[0].media_id, "GCmhgzMPRjqgpODLsNQzVuHZ");
+		assert_eq!(media[0].media_type.as_deref(), Some("image/png"));
+		assert_eq!(media[0].upload_name.as_deref(), Some("cat.png"));
+		assert!(!media[0].quarantined && media[0].safe_from_quarantine);
+	}
+}
@@ -13,7 +13,7 @@
 	DeviceId, OwnedDeviceId, OwnedRoomId, OwnedUserId, RoomId, UserId,
 	api::client::sync::sync_events::v5::{
 		ConnId as ConnectionId, ListId, Request, request,
-		request::{AccountData, E2EE, Receipts, ToDevice, Typing},
+		request::{AccountData, E2EE, Receipts, ThreadSubscriptions, ToDevice, Typing},
 	},
 };
 use serde::{Deserialize, Serialize};
@@ -43,6 +43,8 @@ struct Data {
 	readreceiptid_readreceipt: Arc<Map>,
 	userid_lastonetimekeyupdate: Arc<Map>,
 	roomuserid_lastnotificationread: Arc<Map>,
+	roomid_fullstatecount: Arc<Map>,
+	useridcount_threadsubscription: Arc<Map>,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -88,6 +90,8 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
 				roomuserid_lastnotificationread: args.db["roomuserid_lastnotificationread"]
 					.clone(),
+				roomid_fullstatecount: args.db["roomid_fullstatecount"].clone(),
+				useridcount_threadsubscription: args.db["useridcount_threadsubscription"].clone(),
 			},
 			services: args.services.clone(),
 			connections: Default::default(),
@@ -188,6 +192,10 @@ pub async fn get_loaded_connection(&self, key: &ConnectionKey) -> Result<Connect
 		.ok_or_else(|| err!(Request(NotFound("Connection not found."))))
 }
 
+/// Number of sliding-sync connections currently held in memory.
+#[implement(Service)]
+pub async fn loaded_connection_count(&self) -> usize { self.connections.lock().await.len() }
+
 #[implement(Service)]
 #[tracing::instrument(level = "trace", skip(self))]
 pub async fn list_loaded_connections(&self) -> Vec<ConnectionKey> {
@@ -329,6 +337,10 @@ fn update_cache_extensions(request: &Request, cached: &mut Self) {
 	Self::update_cache_typing(&request.typing, &mut cached.typing);
 	Self::update_cache_to_device(&request.to_device, &mut cached.to_device);
 	Self::update_cache_e2ee(&request.e2ee, &mut cached.e2ee);
+	Self::update_cache_thread_subscriptions(
+		&request.thread_subscriptions,
+		&mut cached.thread_subscriptions,
+	);
 }
 
 #[implement(Connection)]
@@ -363,6 +375,15 @@ fn update_cache_e2ee(request: &E2EE, cached: &mut E2EE) {
 	some_or_sticky(request.enabled.as_ref(), &mut cached.enabled);
 }
 
+#[implement(Connection)]
+fn update_cache_thread_subscriptions(
+	request: &ThreadSubscriptions,
+	cached: &mut ThreadSubscriptions,
+) {
+	some_or_sticky(request.enabled.as_ref(), &mut cached.enabled);
+	some_or_sticky(request.limit.as_ref(), &mut cached.limit);
+}
+
 fn list_or_sticky<T: Clone>(target: &Vec<T>, cached: &mut Vec<T>) {
 	if !target.is_empty() {
 		cached.clone_from(target);
@@ -74,6 +74,11 @@ pub async fn watch<'a, Rooms>(
 			.roomuserdataid_accountdata
 			.watch_prefix((Option::<&RoomId>::None, user_id, Interfix))
 			.boxed(),
+		// Thread subscription changes
+		self.db
+			.useridcount_threadsubscription
+			.watch_raw_prefix(&userid_prefix)
+			.boxed(),
 	]
 	.into_iter()
 	.collect();
@@ -126,6 +131,13 @@ pub async fn watch<'a, Rooms>(
 				.watch_prefix(short_roomid)
 				.boxed(),
 		);
+		// Partial-state resync completion
+		futures.push(
+			self.db
+				.roomid_fullstatecount
+				.watch_raw_prefix(room_id)
+				.boxed(),
+		);
 		// EDUs
 		futures.push(
 			self.db
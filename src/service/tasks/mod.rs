@@ -1,10 +1,16 @@
-//! In-memory background-task tracker for the Synapse admin API.
+//! Persistent background-task tracker for the Synapse admin API.
 //!
-//! Long-running admin actions (room deletion, history purge, bulk redaction)
-//! run detached on the runtime and are polled by their id or by the resource
-//! they act on. State is process-local: a restart drops history where Synapse
-//! persists it for seven days, which consumers tolerate (they poll right after
-//! issuing, and a post-restart miss reads as Synapse's post-retention 404).
+//! Long-running admin actions (room deletion, history purge, bulk redaction,
+//! media tiering) run detached on the runtime and are polled by their id or
+//! by the resource they act on. Every task is recorded in the database with
+//! its status, progress and outcome, and terminal tasks are kept for seven
+//! days as in Synapse. Tasks started with [`Params`] are re-run from the start
+//! on the next startup if a restart interrupted them; others are recorded as
+//! failed.
+
+mod resume;
+#[cfg(test)]
+mod tests;
 
 use std::{
 	collections::BTreeMap,
@@ -13,14 +19,20 @@
 };
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use tokio::{task::JoinHandle, time::sleep};
 use tuwunel_core::{
-	Result,
+	Err, Result,
 	arrayvec::ArrayString,
 	implement,
 	utils::{rand::string_array, time::now_millis},
 };
+use tuwunel_database::{Json, Map};
+
+pub use self::resume::{
+	DEDUPE_MEDIA, PURGE_HISTORY, Params, SHUTDOWN_AND_PURGE_ROOM, TIER_MEDIA,
+};
 
 /// Random task-id length, matching Synapse's `random_string(16)`.
 const TASK_ID_LEN: usize = 16;
@@ -40,9 +52,15 @@
 pub struct Service {
 	services: Arc<crate::services::OnceServices>,
 	tasks: StdMutex<BTreeMap<TaskId, Task>>,
+	db: Data,
+}
+
+struct Data {
+	taskid_task: Arc<Map>,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
 	Scheduled,
 	Active,
@@ -54,21 +72,26 @@ pub enum Status {
 #[derive(Clone, Debug)]
 pub struct TaskInfo {
 	pub id: TaskId,
-	pub action: &'static str,
+	pub action: String,
 	pub resource_id: String,
 	pub status: Status,
 	pub timestamp_ms: u64,
+	pub progress: Option<JsonValue>,
 	pub result: Option<JsonValue>,
 	pub error: Option<String>,
 }
 
+#[derive(Deserialize, Serialize)]
 struct Task {
-	action: &'static str,
+	action: String,
 	resource_id: String,
 	status: Status,
 	timestamp_ms: u64,
+	params: Option<Params>,
+	progress: Option<JsonValue>,
 	result: Option<JsonValue>,
 	error: Option<String>,
+	#[serde(skip)]
 	handle: Option<JoinHandle<()>>,
 }
 
@@ -78,10 +101,15 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			services: args.services.clone(),
 			tasks: StdMutex::new(BTreeMap::new()),
+			db: Data {
+				taskid_task: args.db["taskid_task"].clone(),
+			},
 		}))
 	}
 
 	async fn worker(self: Arc<Self>) -> Result {
+		self.resume().await;
+
 		loop {
 			self.prune();
 
@@ -99,14 +127,27 @@ fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 
 /// Spawn `work` on the runtime as a tracked task, returning its id. The record
 /// transitions Scheduled -> Active -> Complete/Failed; `work`'s `Ok` value is
-/// stored as the result, its `Err` as the error string.
+/// stored as the result, its `Err` as the error string. A restart before it
+/// finishes fails the task; use [`Service::spawn_resumable`] to re-run it.
 #[implement(Service)]
 pub fn spawn<F>(self: &Arc<Self>, action: &'static str, resource_id: String, work: F) -> TaskId
 where
 	F: Future<Output = Result<JsonValue>> + Send + 'static,
 {
 	let id = string_array::<TASK_ID_LEN>();
+	let task = Task::new(action.to_owned(), resource_id, None);
+
+	self.start(id, task, work);
+
+	id
+}
 
+/// Records the task and spawns its work.
+#[implement(Service)]
+fn start<F>(self: &Arc<Self>, id: TaskId, mut task: Task, work: F)
+where
+	F: Future<Output = Result<JsonValue>> + Send + 'static,
+{
 	// Hold the lock across the spawn+insert so the task cannot mark itself
 	// Active before its record exists.
 	let mut tasks = self.tasks.lock().expect("locked");
@@ -118,17 +159,9 @@ pub fn spawn<F>(self: &Arc<Self>, action: &'static str, resource_id: String, wor
 		this.finish(&task_id, outcome);
 	});
 
-	tasks.insert(id, Task {
-		action,
-		resource_id,
-		status: Status::Scheduled,
-		timestamp_ms: now_millis(),
-		result: None,
-		error: None,
-		handle: Some(handle),
-	});
-
-	id
+	task.handle = Some(handle);
+	self.save(id.as_str(), &task);
+	tasks.insert(id, task);
 }
 
 /// The task with this id, if it is still tracked.
@@ -178,10 +211,46 @@ pub fn list(&self) -> Vec<TaskInfo> {
 		.collect()
 }
 
+/// Stops a scheduled or active task and records it as failed. Work it already
+/// did is not undone.
+#[implement(Service)]
+pub fn cancel(&self, id: &str) -> Result {
+	let mut tasks = self.tasks.lock().expect("locked");
+	let Some(task) = tasks.get_mut(id) else {
+		return Err!(Request(NotFound("Unknown task")));
+	};
+
+	if task.status.is_terminal() {
+		return Err!(Request(InvalidParam("Task has already finished")));
+	}
+
+	if let Some(handle) = task.handle.take() {
+		handle.abort();
+	}
+
+	task.status = Status::Failed;
+	task.error = Some("Cancelled by an admin".to_owned());
+	self.save(id, task);
+
+	Ok(())
+}
+
+/// Records how far the task has got, shown alongside its status.
+#[implement(Service)]
+fn set_progress(&self, id: &str, progress: JsonValue) {
+	let mut tasks = self.tasks.lock().expect("locked");
+	if let Some(task) = tasks.get_mut(id) {
+		task.progress = Some(progress);
+		self.save(id, task);
+	}
+}
+
 #[implement(Service)]
 fn set_active(&self, id: &str) {
-	if let Some(task) = self.tasks.lock().expect("locked").get_mut(id) {
+	let mut tasks = self.tasks.lock().expect("locked");
+	if let Some(task) = tasks.get_mut(id) {
 		task.status = Status::Active;
+		self.save(id, task);
 	}
 }
 
@@ -192,6 +261,7 @@ fn finish(&self, id: &str, outcome: Result<JsonValue>) {
 		return;
 	};
 
+	task.handle = None;
 	match outcome {
 		| Ok(value) => {
 			task.status = Status::Complete;
@@ -202,15 +272,29 @@ fn finish(&self, id: &str, outcome: Result<JsonValue>) {
 			task.error = Some(error.to_string());
 		},
 	}
+
+	self.save(id, task);
 }
 
+#[implement(Service)]
+fn save(&self, id: &str, task: &Task) { self.db.taskid_task.raw_put(id, Json(task)); }
+
 #[implement(Service)]
 fn prune(&self) {
 	let now = now_millis();
+	let mut tasks = self.tasks.lock().expect("locked");
+	let before: Vec<TaskId> = tasks.keys().copied().collect();
+
+	prune_tasks(&mut tasks, now);
 
-	prune_tasks(&mut self.tasks.lock().expect("locked"), now);
+	before
+		.iter()
+		.filter(|id| !tasks.contains_key(*id))
+		.for_each(|id| self.db.taskid_task.remove(id.as_str()));
 }
 
+/// Stops the running tasks for shutdown. Their records stay nonterminal, so
+/// resumable tasks run again on the next startup.
 #[implement(Service)]
 fn abort_all(&self) {
 	self.tasks
@@ -237,13 +321,28 @@ pub fn as_str(self) -> &'static str {
 }
 
 impl Task {
+	fn new(action: String, resource_id: String, params: Option<Params>) -> Self {
+		Self {
+			action,
+			resource_id,
+			status: Status::Scheduled,
+			timestamp_ms: now_millis(),
+			params,
+			progress: None,
+			result: None,
+			error: None,
+			handle: None,
+		}
+	}
+
 	fn info(&self, id: &TaskId) -> TaskInfo {
 		TaskInfo {
 			id: *id,
-			action: self.action,
+			action: self.action.clone(),
 			resource_id: self.resource_id.clone(),
 			status: self.status,
 			timestamp_ms: self.timestamp_ms,
+			progress: self.progress.clone(),
 			result: self.result.clone(),
 			error: self.error.clone(),
 		}
@@ -272,6 +371,3 @@ fn prune_tasks(tasks: &mut BTreeMap<TaskId, Task>, now_ms: u64) {
 
 	tasks.retain(|_, task| !task.status.is_terminal() || task.timestamp_ms >= cutoff);
 }
-
-#[cfg(test)]
-mod tests;
@@ -0,0 +1,261 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+use ruma::{OwnedRoomId, OwnedUserId};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue, json};
+use tuwunel_core::{
+	Result, debug_info, implement,
+	matrix::pdu::PduCount,
+	utils::{
+		rand::string_array,
+		stream::{ReadyExt, TryIgnore},
+	},
+	warn,
+};
+use tuwunel_database::Json;
+
+use super::{Status, TASK_ID_LEN, Task, TaskId, matches_nonterminal};
+use crate::{media::migrations::dedupe_media, rooms::delete::ShutdownRoom};
+
+/// Action name of a history purge.
+pub const PURGE_HISTORY: &str = "purge_history";
+
+/// Action name of a room shutdown, mirroring Synapse's
+/// `SHUTDOWN_AND_PURGE_ROOM`.
+pub const SHUTDOWN_AND_PURGE_ROOM: &str = "shutdown_and_purge_room";
+
+/// Action name of a media tiering pass.
+pub const TIER_MEDIA: &str = "tier_media";
+
+/// Action name of the move of media files to content-addressed paths.
+pub const DEDUPE_MEDIA: &str = "dedupe_media";
+
+/// An action the tracker can run itself, and so re-run after a restart.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Params {
+	/// Purges the room's history strictly before the event at `until`.
+	PurgeHistory {
+		room_id: OwnedRoomId,
+		until: i64,
+		delete_local_events: bool,
+	},
+
+	/// Evicts the room's local users, then optionally purges its storage and
+	/// blocks it on behalf of `requester`.
+	ShutdownRoom {
+		room_id: OwnedRoomId,
+		requester: OwnedUserId,
+		block: bool,
+		purge: bool,
+	},
+
+	/// Moves the media due by the tiering policy to `provider`.
+	TierMedia {
+		provider: String,
+	},
+
+	/// Moves key-addressed media files to content-addressed paths.
+	DedupeMedia,
+}
+
+/// Spawn the action described by `params` as a tracked task, returning its id.
+#[implement(super::Service)]
+pub fn spawn_resumable(self: &Arc<Self>, params: Params) -> TaskId {
+	let id = string_array::<TASK_ID_LEN>();
+	let task = Task::new(params.action().to_owned(), params.resource_id(), Some(params.clone()));
+
+	self.start(id, task, Arc::clone(self).run(id, params, None));
+
+	id
+}
+
+/// Records the action described by `params` for the worker to run once it
+/// starts, unless an earlier record of it has yet to finish.
+#[implement(super::Service)]
+pub async fn schedule_resumable(&self, params: Params) {
+	let action = params.action();
+	let resource_id = params.resource_id();
+	let pending = self
+		.db
+		.taskid_task
+		.stream()
+		.ignore_err()
+		.ready_any(|(_, Json(task)): (&str, Json<Task>)| {
+			matches_nonterminal(&task, action, &resource_id)
+		})
+		.await;
+
+	if pending {
+		return;
+	}
+
+	let id = string_array::<TASK_ID_LEN>();
+	let task = Task::new(action.to_owned(), resource_id, Some(params));
+
+	self.save(&id, &task);
+}
+
+/// Loads the stored tasks. Those a restart interrupted are spawned again when
+/// they are resumable and failed otherwise.
+#[implement(super::Service)]
+pub(super) async fn resume(self: &Arc<Self>) {
+	let stored: Vec<(TaskId, Task)> = self
+		.db
+		.taskid_task
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|(id, Json(task)): (&str, Json<Task>)| {
+			Some((TaskId::from(id).ok()?, task))
+		})
+		.collect()
+		.await;
+
+	for (id, mut task) in stored {
+		if task.status.is_terminal() {
+			self.tasks
+				.lock()
+				.expect("locked")
+				.insert(id, task);
+			continue;
+		}
+
+		if let Some(params) = task.params.clone() {
+			debug_info!(%id, action = %task.action, "Resuming interrupted task");
+			let work = Arc::clone(self).run(id, params, task.progress.clone());
+			task.status = Status::Scheduled;
+			self.start(id, task, work);
+			continue;
+		}
+
+		task.status = Status::Failed;
+		task.error = Some("Interrupted by a server restart".to_owned());
+		self.save(&id, &task);
+		self.tasks
+			.lock()
+			.expect("locked")
+			.insert(id, task);
+	}
+}
+
+/// Runs a resumable action, continuing from `progress` recorded by an earlier
+/// attempt.
+#[implement(super::Service)]
+async fn run(
+	self: Arc<Self>,
+	id: TaskId,
+	params: Params,
+	progress: Option<JsonValue>,
+) -> Result<JsonValue> {
+	match params {
+		| Params::PurgeHistory { room_id, until, delete_local_events } => {
+			let purged = self
+				.services
+				.timeline
+				.purge_history(&room_id, PduCount::from_signed(until), delete_local_events)
+				.await?;
+
+			Ok(json!({ "purged": purged }))
+		},
+		| Params::ShutdownRoom { room_id, requester, block, purge } => {
+			let delete = &self.services.delete;
+			let state_lock = self.services.state.mutex.lock(&room_id).await;
+
+			// Users evicted before a restart are no longer in the room; keep the
+			// summary of the first attempt.
+			let recorded = progress
+				.and_then(|progress| progress.get("shutdown_room").cloned())
+				.and_then(|summary| serde_json::from_value::<ShutdownRoom>(summary).ok());
+
+			let summary = match recorded {
+				| Some(summary) => summary,
+				| None => {
+					let summary = delete.shutdown_room(&room_id, &state_lock).await;
+					self.set_progress(&id, json!({ "shutdown_room": summary }));
+					summary
+				},
+			};
+
+			if purge {
+				delete
+					.purge_room(&room_id, false, &state_lock)
+					.await;
+			}
+
+			if block {
+				self.services
+					.metadata
+					.block_room(&room_id, &requester);
+			}
+
+			Ok(serde_json::to_value(summary)?)
+		},
+		| Params::TierMedia { provider } => {
+			let media = &self.services.media;
+
+			// content moved before a restart is no longer a candidate; keep
+			// counting from the first attempt
+			let mut moved = progress
+				.and_then(|progress| progress.get("moved")?.as_u64())
+				.unwrap_or(0);
+
+			let candidates = media.tiering_candidates(&provider).await?;
+			let mut remaining = candidates.len();
+			let mut failed = 0_u64;
+			for digest in candidates {
+				match media.tier_content(&digest, &provider).await {
+					| Ok(()) => moved = moved.saturating_add(1),
+					| Err(e) => {
+						warn!(%provider, "Failed to move media content: {e}");
+						failed = failed.saturating_add(1);
+					},
+				}
+
+				remaining = remaining.saturating_sub(1);
+				self.set_progress(
+					&id,
+					json!({ "moved": moved, "failed": failed, "remaining": remaining }),
+				);
+			}
+
+			Ok(json!({ "moved": moved, "failed": failed }))
+		},
+		| Params::DedupeMedia => {
+			// files moved before a restart are skipped; keep counting from the
+			// first attempt
+			let counts = progress
+				.and_then(|progress| serde_json::from_value(progress).ok())
+				.unwrap_or_default();
+
+			let counts = dedupe_media(&self.services, counts, |counts| {
+				self.set_progress(&id, json!(counts));
+			})
+			.await;
+
+			Ok(json!(counts))
+		},
+	}
+}
+
+impl Params {
+	#[must_use]
+	pub fn action(&self) -> &'static str {
+		match self {
+			| Self::PurgeHistory { .. } => PURGE_HISTORY,
+			| Self::ShutdownRoom { .. } => SHUTDOWN_AND_PURGE_ROOM,
+			| Self::TierMedia { .. } => TIER_MEDIA,
+			| Self::DedupeMedia => DEDUPE_MEDIA,
+		}
+	}
+
+	#[must_use]
+	pub fn resource_id(&self) -> String {
+		match self {
+			| Self::PurgeHistory { room_id, .. } | Self::ShutdownRoom { room_id, .. } =>
+				room_id.to_string(),
+			| Self::TierMedia { provider } => provider.clone(),
+			| Self::DedupeMedia => String::new(),
+		}
+	}
+}
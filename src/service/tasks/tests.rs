@@ -1,6 +1,11 @@
 use std::collections::BTreeMap;
 
-use super::{CAPACITY, RETENTION_MS, Status, Task, TaskId, matches_nonterminal, prune_tasks};
+use ruma::owned_room_id;
+use serde_json::json;
+
+use super::{
+	CAPACITY, Params, RETENTION_MS, Status, Task, TaskId, matches_nonterminal, prune_tasks,
+};
 
 fn key(s: &str) -> TaskId { TaskId::from(s).expect("id fits") }
 
@@ -8,10 +13,12 @@ fn task(status: Status, timestamp_ms: u64) -> Task { task_for("test", "", status
 
 fn task_for(action: &'static str, resource_id: &str, status: Status, timestamp_ms: u64) -> Task {
 	Task {
-		action,
+		action: action.to_owned(),
 		resource_id: resource_id.to_owned(),
 		status,
 		timestamp_ms,
+		params: None,
+		progress: None,
 		result: None,
 		error: None,
 		handle: None,
@@ -73,3 +80,39 @@ fn prune_caps_terminal_tasks() {
 	assert!(tasks.len() <= CAPACITY, "terminal tasks capped");
 	assert!(tasks.contains_key("t0"), "the newest survivor is kept");
 }
+
+#[test]
+fn stored_task_round_trips_with_its_params() {
+	let params = Params::PurgeHistory {
+		room_id: owned_room_id!("!room:example.com"),
+		until: 42,
+		delete_local_events: true,
+	};
+
+	let mut stored = task_for(params.action(), &params.resource_id(), Status::Active, 7);
+	stored.params = Some(params);
+
+	let value = serde_json::to_value(&stored).expect("serializes");
+	assert_eq!(value["status"], json!("active"));
+	assert_eq!(value["params"]["kind"], json!("purge_history"));
+
+	let loaded: Task = serde_json::from_value(value).expect("deserializes");
+	assert_eq!(loaded.action, "purge_history");
+	assert_eq!(loaded.resource_id, "!room:example.com");
+	assert!(matches!(
+		loaded.params,
+		Some(Params::PurgeHistory { until: 42, delete_local_events: true, .. })
+	));
+	assert!(loaded.handle.is_none());
+}
+
+#[test]
+fn tiering_task_is_keyed_by_its_provider() {
+	let params = Params::TierMedia { provider: "media_on_s3".to_owned() };
+
+	assert_eq!(params.action(), "tier_media");
+	assert_eq!(params.resource_id(), "media_on_s3");
+
+	let value = serde_json::to_value(&params).expect("serializes");
+	assert_eq!(value, json!({ "kind": "tier_media", "provider": "media_on_s3" }));
+}
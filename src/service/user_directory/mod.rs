@@ -0,0 +1,355 @@
+//! Searchable index of the users known to this server.
+//!
+//! Every indexed user contributes the lowercased words of their localpart and
+//! displayname as tokens. A search matches each query word against token
+//! prefixes and intersects the results, so "ali smi" finds "Alice Smith". Local
+//! users are always indexed; remote users while they share a joined room with
+//! this server.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	cmp::Reverse,
+	collections::{BTreeSet, HashMap},
+	sync::Arc,
+};
+
+use futures::{FutureExt, StreamExt};
+use ruma::{OwnedUserId, UserId, events::room::join_rules::JoinRule};
+use tuwunel_core::{
+	Result, debug, implement,
+	utils::{
+		IterStream, ReadyExt,
+		stream::{BroadbandExt, TryIgnore},
+	},
+};
+use tuwunel_database::{Deserialized, Json, Map};
+
+/// Longest word indexed or searched for.
+const WORD_MAX_LEN: usize = 50;
+
+/// Bound on the users gathered for a single query word. Short prefixes of
+/// common words are truncated rather than scanned in full.
+const CANDIDATES_MAX: usize = 4096;
+
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+	db: Data,
+}
+
+struct Data {
+	userdirectory_tokenuserid: Arc<Map>,
+	userid_directorytokens: Arc<Map>,
+}
+
+/// Sort key of a match; smaller ranks first. Users sharing a room with the
+/// searcher lead, then local users, then users matching every query word in
+/// full.
+type Rank = (Reverse<bool>, Reverse<bool>, Reverse<bool>);
+
+/// Matched users, each flagged whether every query word matched a whole token.
+type Candidates = HashMap<OwnedUserId, bool>;
+
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: args.services.clone(),
+			db: Data {
+				userdirectory_tokenuserid: args.db["userdirectory_tokenuserid"].clone(),
+				userid_directorytokens: args.db["userid_directorytokens"].clone(),
+			},
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Searches the directory on behalf of `sender_user`, returning at most
+/// `limit` users in rank order and whether more matched.
+#[implement(Service)]
+pub async fn search(
+	&self,
+	sender_user: &UserId,
+	search_term: &str,
+	limit: usize,
+) -> (Vec<OwnedUserId>, bool) {
+	let candidates = query_words(search_term)
+		.stream()
+		.then(async |word| self.candidates(&word).await)
+		.ready_fold(None, |matched: Option<Candidates>, word_matched| {
+			Some(match matched {
+				| Some(matched) => intersect(matched, word_matched),
+				| None => word_matched,
+			})
+		})
+		.await
+		.unwrap_or_default();
+
+	let mut ranked: Vec<(Rank, OwnedUserId)> = candidates
+		.into_iter()
+		.filter(|(user_id, _)| user_id != sender_user)
+		.stream()
+		.broad_filter_map(async |(user_id, exact)| {
+			let rank = self.rank(sender_user, &user_id, exact).await?;
+
+			Some((rank, user_id))
+		})
+		.collect()
+		.await;
+
+	ranked.sort_unstable();
+
+	let limited = ranked.len() > limit;
+	let results = ranked
+		.into_iter()
+		.take(limit)
+		.map(|(_, user_id)| user_id)
+		.collect();
+
+	(results, limited)
+}
+
+/// Ranks a matched user, or hides them from the searcher.
+///
+/// Users in exclusive appservice namespaces are never shown. Others are shown
+/// when they share a room with the searcher or sit in a public room, unless
+/// `show_all_local_users_in_user_directory` lifts the restriction.
+#[implement(Service)]
+async fn rank(&self, sender_user: &UserId, user_id: &UserId, exact: bool) -> Option<Rank> {
+	if self
+		.services
+		.appservice
+		.is_exclusive_user_id(user_id)
+		.await
+	{
+		return None;
+	}
+
+	let local = self.services.globals.user_is_local(user_id);
+	let shared = self
+		.services
+		.state_cache
+		.user_sees_user(sender_user, user_id)
+		.await;
+
+	let visible = shared
+		|| self
+			.services
+			.server
+			.config
+			.show_all_local_users_in_user_directory
+		|| self.in_public_room(user_id).await;
+
+	visible.then_some(rank(shared, local, exact))
+}
+
+#[implement(Service)]
+async fn in_public_room(&self, user_id: &UserId) -> bool {
+	self.services
+		.state_cache
+		.rooms_joined(user_id)
+		.map(ToOwned::to_owned)
+		.broad_any(async |room_id| {
+			self.services
+				.state_accessor
+				.get_join_rules(&room_id)
+				.map(|rule| matches!(rule, JoinRule::Public))
+				.await
+		})
+		.await
+}
+
+/// Users with a token starting with `word`.
+#[implement(Service)]
+async fn candidates(&self, word: &str) -> Candidates {
+	self.db
+		.userdirectory_tokenuserid
+		.keys_raw_prefix(word)
+		.ignore_err()
+		.take(CANDIDATES_MAX)
+		.ready_fold(Candidates::new(), |mut candidates, (token, user_id): (&str, &UserId)| {
+			let exact = candidates.entry(user_id.to_owned()).or_default();
+			*exact |= token == word;
+			candidates
+		})
+		.await
+}
+
+/// Indexes a user who joined a room. A local user is indexed under their
+/// profile displayname; a remote user falls back to the one in their
+/// membership event when no profile has been fetched for them.
+#[implement(Service)]
+pub async fn update_member(&self, user_id: &UserId, member_displayname: Option<&str>) {
+	let displayname = self
+		.services
+		.profile
+		.displayname(user_id)
+		.await
+		.ok();
+	let displayname = if self.services.globals.user_is_local(user_id) {
+		displayname.as_deref()
+	} else {
+		displayname.as_deref().or(member_displayname)
+	};
+
+	self.update(user_id, displayname).await;
+}
+
+/// Drops a remote user who left a room once they are in no other joined room.
+#[implement(Service)]
+pub async fn update_departed(&self, user_id: &UserId) {
+	if self.services.globals.user_is_local(user_id) {
+		return;
+	}
+
+	let joined = self
+		.services
+		.state_cache
+		.rooms_joined(user_id)
+		.boxed()
+		.next()
+		.await;
+
+	if joined.is_some() {
+		return;
+	}
+
+	self.remove(user_id).await;
+}
+
+/// Replaces the user's tokens with those of their user ID and `displayname`.
+#[implement(Service)]
+pub async fn update(&self, user_id: &UserId, displayname: Option<&str>) {
+	let tokens = tokens(user_id, displayname);
+	let indexed = self.indexed_tokens(user_id).await;
+
+	for token in indexed.difference(&tokens) {
+		self.db
+			.userdirectory_tokenuserid
+			.del((token, user_id));
+	}
+
+	for token in tokens.difference(&indexed) {
+		self.db
+			.userdirectory_tokenuserid
+			.put_raw((token, user_id), []);
+	}
+
+	self.db
+		.userid_directorytokens
+		.raw_put(user_id, Json(tokens));
+}
+
+/// Removes the user from the directory.
+#[implement(Service)]
+pub async fn remove(&self, user_id: &UserId) {
+	for token in self.indexed_tokens(user_id).await {
+		self.db
+			.userdirectory_tokenuserid
+			.del((token, user_id));
+	}
+
+	self.db.userid_directorytokens.remove(user_id);
+}
+
+#[implement(Service)]
+async fn indexed_tokens(&self, user_id: &UserId) -> BTreeSet<String> {
+	self.db
+		.userid_directorytokens
+		.get(user_id)
+		.await
+		.deserialized::<Json<_>>()
+		.map(|Json(tokens)| tokens)
+		.unwrap_or_default()
+}
+
+/// Indexes every local user and every remote user in a joined room.
+#[implement(Service)]
+pub async fn rebuild(&self) -> Result {
+	let users: Vec<OwnedUserId> = self
+		.services
+		.users
+		.stream()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut indexed = 0_usize;
+	for user_id in &users {
+		let room_id = self
+			.services
+			.state_cache
+			.rooms_joined(user_id)
+			.map(ToOwned::to_owned)
+			.boxed()
+			.next()
+			.await;
+
+		let member_displayname = match &room_id {
+			| Some(room_id) => self
+				.services
+				.state_accessor
+				.get_member(room_id, user_id)
+				.await
+				.ok()
+				.and_then(|member| member.displayname),
+			| None if self.services.globals.user_is_local(user_id) => None,
+			| None => continue,
+		};
+
+		self.update_member(user_id, member_displayname.as_deref())
+			.await;
+
+		indexed = indexed.saturating_add(1);
+	}
+
+	debug!(indexed, "Rebuilt user directory");
+
+	Ok(())
+}
+
+/// Tokens indexed for a user: the words of their localpart and displayname,
+/// plus the whole localpart.
+fn tokens(user_id: &UserId, displayname: Option<&str>) -> BTreeSet<String> {
+	let localpart = user_id.localpart().to_lowercase();
+
+	words(&localpart)
+		.chain(displayname.into_iter().flat_map(words))
+		.chain((localpart.len() <= WORD_MAX_LEN).then_some(localpart.clone()))
+		.collect()
+}
+
+/// Words of a search term. A term spelled as a user ID searches its localpart.
+fn query_words(search_term: &str) -> BTreeSet<String> {
+	let term = search_term
+		.strip_prefix('@')
+		.map_or(search_term, |term| term.split(':').next().unwrap_or(term));
+
+	words(term).collect()
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+	text.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty() && word.len() <= WORD_MAX_LEN)
+		.map(str::to_lowercase)
+}
+
+/// Keeps the users matched by both words; a user matches exactly only if both
+/// words matched a whole token.
+fn intersect(mut matched: Candidates, word_matched: Candidates) -> Candidates {
+	matched.retain(|user_id, exact| {
+		word_matched
+			.get(user_id)
+			.is_some_and(|&word_exact| {
+				*exact &= word_exact;
+				true
+			})
+	});
+
+	matched
+}
+
+fn rank(shared: bool, local: bool, exact: bool) -> Rank {
+	(Reverse(shared), Reverse(local), Reverse(exact))
+}
@@ -0,0 +1,54 @@
+use ruma::{owned_user_id, user_id};
+
+use super::{Candidates, intersect, query_words, rank, tokens};
+
+#[test]
+fn tokens_cover_localpart_and_displayname_words() {
+	let tokens = tokens(user_id!("@alice.smith:example.com"), Some("Alice O'Brien"));
+
+	assert_eq!(tokens.into_iter().collect::<Vec<_>>(), [
+		"alice",
+		"alice.smith",
+		"brien",
+		"o",
+		"smith"
+	]);
+}
+
+#[test]
+fn query_spelled_as_user_id_searches_localpart() {
+	let words = query_words("@Bob_Jones:matrix.org");
+
+	assert_eq!(words.into_iter().collect::<Vec<_>>(), ["bob", "jones"]);
+}
+
+#[test]
+fn intersect_keeps_users_matching_every_word() {
+	let alice = owned_user_id!("@alice:example.com");
+	let bob = owned_user_id!("@bob:example.com");
+
+	let first: Candidates = [(alice.clone(), true), (bob.clone(), true)].into();
+	let second: Candidates = [(alice.clone(), false)].into();
+
+	let matched = intersect(first, second);
+	assert_eq!(matched.len(), 1);
+	assert_eq!(matched.get(&alice), Some(&false));
+}
+
+#[test]
+fn rank_orders_shared_then_local_then_exact() {
+	let mut ranks = [
+		rank(false, false, true),
+		rank(false, true, false),
+		rank(true, false, false),
+		rank(false, true, true),
+	];
+	ranks.sort_unstable();
+
+	assert_eq!(ranks, [
+		rank(true, false, false),
+		rank(false, true, true),
+		rank(false, true, false),
+		rank(false, false, true),
+	]);
+}
@@ -173,6 +173,12 @@ pub async fn deactivate_account(&self, user_id: &UserId) -> Result {
 			.for_each(|device_id| self.remove_device(user_id, device_id))
 			.await;
 
+		// Nothing may be sent on behalf of a deactivated account
+		self.services
+			.delayed_events
+			.cancel_user(user_id)
+			.await;
+
 		// Set the password to "" to indicate a deactivated account. Hashes will never
 		// result in an empty string, so the user will not be able to log in again.
 		// Systems like changing the password without logging in should check if the
@@ -423,6 +429,15 @@ pub async fn set_password(&self, user_id: &UserId, password: Option<&str>) -> Re
 		Ok(())
 	}
 
+	/// Sets a password hash carried over from another server as-is; login
+	/// verifies it in its own format.
+	pub fn set_password_hash(&self, user_id: &UserId, password_hash: &str) {
+		self.db
+			.userid_password
+			.insert(user_id, password_hash);
+		self.db.userid_origin.insert(user_id, "password");
+	}
+
 	/// Creates a new sync filter. Returns the filter id.
 	#[must_use]
 	pub fn create_filter(&self, user_id: &UserId, filter: &FilterDefinition) -> String {